@@ -0,0 +1,582 @@
+use color_eyre::Result;
+
+use crate::{
+    app::App,
+    config::{save_columns, save_view_option},
+    ui::{Tab, UI},
+    widgets::legend,
+    worker::{Modification, Outcome},
+};
+
+/// An operation triggered by the user (e.g. via key-presses or mouse events) or by
+/// other sources. Actions are dispatched centrally using [`Action::dispatch`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Quit the application
+    Quit,
+    /// Close the currently displayed popup
+    ClosePopup,
+    /// Force an update of the Slurm state
+    Refresh,
+    /// Suspend/resume automatic updates of the Slurm state
+    TogglePause,
+    /// Show/hide unavailable nodes
+    ToggleUnavailable,
+    /// Switch between organizing nodes by partition and by node group
+    ToggleNodeGroups,
+    /// Switch focus between the node and job lists
+    ToggleFocus,
+    /// Switch between showing the node and job lists stacked and side by side
+    ToggleLayout,
+    /// Copy the selected job ID, node name, or row to the clipboard
+    Copy,
+    /// Explain the colors used in utilization bars
+    ShowLegend,
+    /// List problems found in the Slurm state, e.g. jobs in unknown partitions
+    ShowWarnings,
+    /// List nodes that went down, drained, or stopped responding during this session
+    ShowNodeAlerts,
+    /// List nodes that are persistently overloaded or underutilized
+    ShowAnomalies,
+    /// Run the user-defined command bound to the function key with this number, with
+    /// the selected job, node, or partition substituted, and show its output
+    RunCommand(u8),
+    /// Switch between showing the utilization of nodes as bars and as numbers
+    ToggleNumbers,
+    /// Switch between showing the utilization of nodes as bars and as percentages
+    TogglePercentages,
+    /// Move the selection in the focused list by the specified number of rows
+    Scroll(isize),
+    /// Select the list item at the specified screen column and row
+    Click(u16, u16),
+    /// Scroll the list at the specified screen column and row by the specified number
+    /// of rows
+    ScrollAt(u16, u16, isize),
+    /// Drag the border between the node and job lists to the specified screen row
+    Drag(u16),
+    /// Stop dragging the border between the node and job lists
+    Release,
+    /// Mark/unmark the selected job
+    ToggleMark,
+    /// Start/stop watching the selected job for changes in state
+    ToggleWatch,
+    /// Export details of marked jobs (or the selected job) to a file
+    Export,
+    /// Move backward/forward through recorded snapshots
+    Seek(isize),
+    /// Show the state transitions of the user's jobs
+    ShowHistory,
+    /// Show details of the selected node, the limits of the selected partition, or the
+    /// layout of the selected job
+    ShowInfo,
+    /// Show the list of batch scripts that can be submitted
+    ShowScripts,
+    /// Move the selection in the list of batch scripts
+    ScrollScripts(isize),
+    /// Submit the selected batch script and close the list of scripts
+    SubmitScript,
+    /// Close the list of batch scripts without submitting a script
+    CloseScripts,
+    /// Ask for confirmation before cancelling marked jobs (or the selected job)
+    CancelJob,
+    /// Cancel the specified jobs using `scancel`
+    CancelJobs(Vec<usize>),
+    /// Hold (true) or release (false) the selected pending job using `scontrol`
+    HoldJob(bool),
+    /// Move the selected pending job ahead of the user's other pending jobs
+    TopJob,
+    /// Ask for confirmation before requeuing the selected job
+    RequeueJob,
+    /// Requeue the specified job using `scontrol`
+    Requeue(usize),
+    /// Show a dialog for changing the time limit of the selected job
+    EditTimeLimit,
+    /// Change the time limit of the specified job using `scontrol`
+    SetTimeLimit(usize, String),
+    /// Show a dialog for entering the reason for draining the selected node
+    EditDrainReason,
+    /// Drain the specified node for the specified reason using `scontrol`
+    DrainNode(String, String),
+    /// Ask for confirmation before resuming the selected node
+    ResumeNode,
+    /// Resume the specified node using `scontrol`
+    Resume(String),
+    /// Show the output of the selected job in the log viewer
+    ShowOutput,
+    /// (Re-)read the output file shown in the log viewer
+    ReadOutput,
+    /// Switch between showing stdout and stderr in the log viewer
+    SwitchOutput,
+    /// Scroll the log viewer by the specified number of lines
+    ScrollOutput(isize),
+    /// Start/stop following the end of the output in the log viewer
+    FollowOutput,
+    /// Show a dialog for entering text to search for in the log viewer
+    EditOutputSearch,
+    /// Highlight the specified text in the log viewer and scroll to the first match
+    SearchOutput(String),
+    /// Scroll to the next (true) or previous (false) match in the log viewer
+    FindInOutput(bool),
+    /// Close the log viewer
+    CloseOutput,
+    /// Append a character to the text being entered
+    TypeInput(char),
+    /// Remove the last character of the text being entered
+    EraseInput,
+    /// Perform the action using the text entered, if valid, and close the dialog
+    SubmitInput,
+    /// Close the text input dialog without performing the action
+    CloseInput,
+    /// Start entering text used to filter the job list
+    StartFilter,
+    /// Append a character to the job filter
+    TypeFilter(char),
+    /// Remove the last character of the job filter
+    EraseFilter,
+    /// Stop entering text, while keeping the job list filtered
+    AcceptFilter,
+    /// Remove the job filter
+    ClearFilter,
+    /// Perform the action awaiting confirmation
+    Confirm,
+    /// Discard the action awaiting confirmation
+    Decline,
+    /// Select the previous/next column in the list that has focus; jobs are sorted by
+    /// the selected column
+    SelectColumn(isize),
+    /// Move the selected column left/right and save the order of columns
+    MoveColumn(isize),
+    /// Switch between sorting jobs in ascending and descending order
+    ToggleSortOrder,
+    /// Show the TRES minutes consumed by the user and their accounts, compared to the
+    /// limits of their associations
+    ShowAssociationUsage,
+    /// Show the given tab (see [`UI::tabs`])
+    SelectTab(Tab),
+    /// Show the report of the previous/next period in the usage tab
+    CycleReportPeriod(isize),
+    /// Show the list of partitions to restrict the node list to
+    ShowPartitions,
+    /// Move the selection in the list of partitions
+    ScrollPartitions(isize),
+    /// Show/hide the selected partition in the list of partitions
+    TogglePartition,
+    /// Only show nodes in the picked partitions and close the list of partitions
+    SelectPartitions,
+    /// Close the list of partitions without changing which partitions are shown
+    ClosePartitions,
+    /// Show the list of node features to filter by
+    ShowFeatures,
+    /// Move the selection in the list of node features
+    ScrollFeatures(isize),
+    /// Filter nodes by the selected feature and close the list of features
+    SelectFeature,
+    /// Close the list of node features without changing the filter
+    CloseFeatures,
+}
+
+impl Action {
+    /// Performs the action, returning true if the UI needs to be redrawn
+    pub fn dispatch(self, app: &mut App, ui: &mut UI) -> Result<bool> {
+        match self {
+            Action::Quit => app.quit(),
+            Action::ClosePopup => ui.close_popup(),
+            Action::Refresh if ui.tab() == Tab::Usage => show_usage(app, ui, true),
+            Action::Refresh => {
+                // The update is performed on the next tick, once the UI indicates that
+                // the update is in progress
+                if app.request_update() {
+                    ui.set_refreshing();
+                } else {
+                    return Ok(false);
+                }
+            }
+            Action::ToggleLayout => ui.toggle_layout(),
+            Action::Copy => ui.copy_selection(),
+            Action::ShowLegend => ui.show_message("Legend", legend()),
+            Action::ShowWarnings => ui.show_warnings(),
+            Action::ShowNodeAlerts => ui.show_node_alerts(&app.node_alerts),
+            Action::ShowAnomalies => ui.show_anomalies(&app.anomalies),
+            Action::RunCommand(key) => {
+                let Some(command) = app.commands.iter().find(|v| v.key == key) else {
+                    return Ok(false);
+                };
+
+                let result = command.command(&ui.selection_values()).and_then(|command| {
+                    app.run_command(command.clone())?;
+                    Ok(command)
+                });
+
+                match result {
+                    Ok(command) => ui.show_status(format!("Running {:?} ...", command)),
+                    Err(error) => ui.show_error("Cannot run command", error),
+                }
+            }
+            Action::ToggleNumbers => ui.toggle_numbers(),
+            Action::TogglePercentages => ui.toggle_percentages(),
+            Action::TogglePause => {
+                let paused = app.toggle_pause();
+                ui.set_paused(paused);
+                ui.show_status(if paused {
+                    "Automatic updates paused; press <R> to update manually"
+                } else {
+                    "Automatic updates resumed"
+                });
+            }
+            Action::ToggleUnavailable => ui.toggle_unavailable(),
+            Action::ToggleNodeGroups => ui.toggle_node_groups(app),
+            Action::ToggleFocus => ui.toggle_focus(),
+            Action::Scroll(delta) => ui.scroll(delta),
+            Action::Click(column, row) => ui.mouse_click(column, row),
+            Action::ScrollAt(column, row, delta) => ui.mouse_wheel(column, row, delta),
+            Action::Drag(row) => ui.mouse_drag(row),
+            Action::Release => {
+                let Some(split) = ui.mouse_release() else {
+                    return Ok(false);
+                };
+
+                let path = app.config.path.as_deref();
+                match save_view_option(path, "split", toml_edit::value(split as i64)) {
+                    Ok(path) => {
+                        ui.show_status(format!("Saved size of the node list to {:?}", path))
+                    }
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::ToggleMark => ui.toggle_mark(),
+            Action::StartFilter => ui.start_job_filter(),
+            Action::TypeFilter(c) => ui.type_job_filter(c),
+            Action::EraseFilter => ui.erase_job_filter(),
+            Action::AcceptFilter => ui.accept_job_filter(),
+            Action::ClearFilter => ui.clear_job_filter(),
+            Action::ToggleWatch => {
+                // Jobs can only be watched while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                match app.toggle_watch(id) {
+                    Ok(watched) => {
+                        ui.set_watched(app.watchlist.ids());
+                        ui.show_status(if watched {
+                            format!("Watching job {} for changes in state", id)
+                        } else {
+                            format!("No longer watching job {}", id)
+                        });
+                    }
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::Export => {
+                let jobs = ui.marked_jobs();
+                if jobs.is_empty() {
+                    return Ok(false);
+                }
+
+                match app.export_jobs(&jobs) {
+                    Ok(()) => ui.show_status(format!("Exporting {} job(s) ...", jobs.len())),
+                    Err(error) => ui.show_error("Export failed", error),
+                }
+            }
+            Action::Seek(delta) => {
+                if app.seek(delta)? {
+                    ui.update(app);
+                } else {
+                    return Ok(false);
+                }
+            }
+            Action::ShowAssociationUsage => {
+                ui.show_status(format!(
+                    "Collecting usage and limits of {} ...",
+                    app.tracked_user()
+                ));
+                app.association_usage();
+            }
+            Action::ShowHistory => ui.select_tab(Tab::History),
+            Action::SelectTab(Tab::Usage) => {
+                ui.select_tab(Tab::Usage);
+                show_usage(app, ui, false);
+            }
+            Action::SelectTab(tab) => ui.select_tab(tab),
+            Action::CycleReportPeriod(delta) => {
+                ui.cycle_report_period(delta);
+                show_usage(app, ui, false);
+            }
+            Action::ShowInfo => match ui.selected_job() {
+                Some(id) => {
+                    ui.show_status(format!("Looking up the layout of job {} ...", id));
+                    app.job_layout(id);
+                }
+                None => ui.show_info(&app.partition_limits, &app.load_history),
+            },
+            Action::ShowScripts => match app.scripts() {
+                Ok(scripts) => ui.show_scripts(scripts),
+                Err(error) => ui.show_error("Cannot submit job", error),
+            },
+            Action::ScrollScripts(delta) => ui.scroll_scripts(delta),
+            Action::SubmitScript => {
+                if let Some(script) = ui.take_script() {
+                    match app.submit_job(&script) {
+                        Ok(()) => ui.show_status(format!("Submitting {:?} ...", script)),
+                        Err(error) => ui.show_error("Job submission failed", error),
+                    }
+                }
+            }
+            Action::CloseScripts => ui.close_scripts(),
+            Action::CancelJob => {
+                // Jobs can only be cancelled while the job list has focus
+                if ui.selected_job().is_none() {
+                    return Ok(false);
+                }
+
+                match app.jobs_to_cancel(&ui.marked_jobs()) {
+                    Ok(jobs) => {
+                        let description = jobs
+                            .iter()
+                            .map(|(id, user)| format!("{} ({})", id, user))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        let question = match jobs.len() {
+                            1 => format!("Cancel job {}?", description),
+                            n => format!("Cancel {} jobs: {}?", n, description),
+                        };
+                        let ids = jobs.into_iter().map(|(id, _)| id).collect();
+
+                        ui.confirm("Cancel jobs", question, Action::CancelJobs(ids));
+                    }
+                    Err(error) => ui.show_error("Cannot cancel job", error),
+                }
+            }
+            Action::CancelJobs(ids) => match app.cancel_jobs(&ids) {
+                Ok(()) => ui.show_status(format!("Cancelling {} job(s) ...", ids.len())),
+                Err(error) => ui.show_error("Job cancellation failed", error),
+            },
+            Action::HoldJob(hold) => {
+                // Jobs can only be held/released while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                match app.hold_job(id, hold) {
+                    Ok(()) => ui.show_status(format!(
+                        "{} job {} ...",
+                        if hold { "Holding" } else { "Releasing" },
+                        id
+                    )),
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::TopJob => {
+                // Jobs can only be prioritized while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                match app.top_job(id) {
+                    Ok(()) => ui.show_status(format!("Prioritizing job {} ...", id)),
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::RequeueJob => {
+                // Jobs can only be requeued while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                match app.job_owner(id, "requeue") {
+                    Ok(user) => {
+                        let question = format!("Requeue job {} ({})?", id, user);
+                        ui.confirm("Requeue job", question, Action::Requeue(id));
+                    }
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::Requeue(id) => match app.requeue_job(id) {
+                Ok(()) => ui.show_status(format!("Requeuing job {} ...", id)),
+                Err(error) => ui.show_status_error(error),
+            },
+            Action::EditTimeLimit => {
+                // Time limits can only be changed while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                match app.job_owner(id, "modify the time limit of") {
+                    Ok(_) => ui.edit_time_limit(id),
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::SetTimeLimit(id, limit) => match app.set_time_limit(id, &limit) {
+                Ok(()) => ui.show_status(format!("Changing the time limit of job {} ...", id)),
+                Err(error) => ui.show_status_error(error),
+            },
+            Action::EditDrainReason => {
+                // Nodes can only be drained while the node list has focus
+                let Some(node) = ui.selected_node() else {
+                    return Ok(false);
+                };
+
+                match app.check_node_action("drain") {
+                    Ok(()) => ui.edit_drain_reason(&node),
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::DrainNode(node, reason) => match app.drain_node(&node, &reason) {
+                Ok(()) => ui.show_status(format!("Draining node {} ...", node)),
+                Err(error) => ui.show_status_error(error),
+            },
+            Action::ResumeNode => {
+                // Nodes can only be resumed while the node list has focus
+                let Some(node) = ui.selected_node() else {
+                    return Ok(false);
+                };
+
+                match app.check_node_action("resume") {
+                    Ok(()) => {
+                        let question = format!("Resume node {}?", node);
+                        ui.confirm("Resume node", question, Action::Resume(node));
+                    }
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::Resume(node) => match app.resume_node(&node) {
+                Ok(()) => ui.show_status(format!("Resuming node {} ...", node)),
+                Err(error) => ui.show_status_error(error),
+            },
+            Action::ShowOutput => {
+                // Output can only be shown while the job list has focus
+                let Some(id) = ui.selected_job() else {
+                    return Ok(false);
+                };
+
+                ui.show_status(format!("Looking up the output of job {} ...", id));
+                app.job_output(id);
+            }
+            Action::ReadOutput => {
+                // The output is shown once read; see [`report`]
+                if let Some(path) = ui.output_due() {
+                    app.read_output(path);
+                }
+
+                return Ok(false);
+            }
+            Action::SwitchOutput => {
+                ui.switch_output();
+                Action::ReadOutput.dispatch(app, ui)?;
+            }
+            Action::ScrollOutput(delta) => ui.scroll_output(delta),
+            Action::FollowOutput => ui.toggle_follow_output(),
+            Action::EditOutputSearch => ui.edit_output_search(),
+            Action::SearchOutput(text) => ui.search_output(text),
+            Action::FindInOutput(forward) => ui.find_in_output(forward),
+            Action::CloseOutput => ui.close_output(),
+            Action::TypeInput(c) => ui.type_input(c),
+            Action::EraseInput => ui.erase_input(),
+            Action::SubmitInput => {
+                if let Some(action) = ui.take_input() {
+                    return action.dispatch(app, ui);
+                }
+            }
+            Action::CloseInput => ui.close_input(),
+            Action::Confirm => {
+                if let Some(action) = ui.take_confirmation() {
+                    return action.dispatch(app, ui);
+                }
+            }
+            Action::Decline => ui.close_confirmation(),
+            Action::SelectColumn(delta) => ui.select_column(delta),
+            Action::MoveColumn(delta) => {
+                let Some((key, columns)) = ui.move_column(delta) else {
+                    return Ok(false);
+                };
+
+                match save_columns(app.config.path.as_deref(), key, &columns) {
+                    Ok(path) => ui.show_status(format!("Saved order of columns to {:?}", path)),
+                    Err(error) => ui.show_status_error(error),
+                }
+            }
+            Action::ToggleSortOrder => ui.toggle_sort_order(),
+            Action::ShowPartitions => ui.show_partitions(app),
+            Action::ScrollPartitions(delta) => ui.scroll_partitions(delta),
+            Action::TogglePartition => ui.toggle_partition(),
+            Action::SelectPartitions => ui.select_partitions(),
+            Action::ClosePartitions => ui.close_partitions(),
+            Action::ShowFeatures => ui.show_features(),
+            Action::ScrollFeatures(delta) => ui.scroll_features(delta),
+            Action::SelectFeature => ui.select_feature(),
+            Action::CloseFeatures => ui.close_features(),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Shows the report of the period selected in the usage tab, if recently collected, and
+/// otherwise collects the report in the background
+fn show_usage(app: &mut App, ui: &mut UI, refresh: bool) {
+    match app.usage_report(ui.report_period(), refresh) {
+        Some(report) => ui.show_usage(Ok(report)),
+        None => ui.collecting_usage(),
+    }
+}
+
+/// Shows the outcome of a task run in the background (see [`App::take_outcomes`]),
+/// returning true if the UI needs to be redrawn
+pub fn report(outcome: Outcome, app: &mut App, ui: &mut UI) -> Result<bool> {
+    // Messages indicating that the task is in progress are replaced by the outcome
+    if !matches!(outcome, Outcome::Output(..) | Outcome::UsageReport(..)) {
+        ui.clear_status();
+    }
+
+    match outcome {
+        Outcome::Modified(kind, Ok(message), _) => {
+            ui.update(app);
+            match kind {
+                Modification::Cancel => {
+                    ui.clear_marks();
+                    ui.show_message("Jobs cancelled", message);
+                }
+                Modification::Submit => ui.show_message("Job submitted", message),
+                Modification::Control => ui.show_status(message),
+            }
+        }
+        Outcome::Modified(Modification::Cancel, Err(error), _) => {
+            ui.show_error("Job cancellation failed", error)
+        }
+        Outcome::Modified(Modification::Submit, Err(error), _) => {
+            ui.show_error("Job submission failed", error)
+        }
+        Outcome::Modified(Modification::Control, Err(error), _) => ui.show_status_error(error),
+        Outcome::Exported(Ok(message)) => {
+            ui.clear_marks();
+            ui.show_message("Export", message);
+        }
+        Outcome::Exported(Err(error)) => ui.show_error("Export failed", error),
+        Outcome::JobLayout(Ok(layout)) => ui.show_job_layout(&layout),
+        Outcome::JobLayout(Err(error)) => ui.show_error("Cannot show job layout", error),
+        Outcome::JobOutput(Ok(output)) => {
+            ui.show_output(output);
+            Action::ReadOutput.dispatch(app, ui)?;
+        }
+        Outcome::JobOutput(Err(error)) => ui.show_error("Cannot show job output", error),
+        Outcome::Output(path, text) => ui.set_output(&path, text),
+        Outcome::Command(command, Ok(output)) => ui.show_command_output(&command, &output),
+        Outcome::Command(_, Err(error)) => ui.show_error("Cannot run command", error),
+        Outcome::UsageReport(period, report) => {
+            // Reports of other periods are kept for when those periods are selected
+            if period != ui.report_period() {
+                return Ok(false);
+            }
+
+            ui.show_usage(report);
+        }
+        Outcome::AssociationUsage(user, Ok(associations)) => {
+            ui.show_association_usage(&user, &associations)
+        }
+        Outcome::AssociationUsage(_, Err(error)) => {
+            ui.show_error("Cannot show usage and limits", error)
+        }
+    }
+
+    Ok(true)
+}
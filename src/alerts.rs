@@ -0,0 +1,94 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Local};
+
+use crate::slurm::{NodeState, Partition, SlurmState};
+
+/// Maximum number of alerts kept in the log of recent alerts
+const MAX_ALERTS: usize = 100;
+
+/// A node that went down, drained, or stopped responding, as observed between two updates
+#[derive(Clone, Debug)]
+pub struct NodeAlert {
+    pub time: DateTime<Local>,
+    pub node: String,
+    pub from: NodeState,
+    pub to: NodeState,
+}
+
+impl std::fmt::Display for NodeAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {} → {}",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.node,
+            self.from,
+            self.to,
+        )
+    }
+}
+
+/// Tracks the state of nodes between updates, in order to alert the user when nodes
+/// become unavailable
+#[derive(Debug, Default)]
+pub struct NodeAlerts {
+    /// Most recently observed state of each node; empty until the first update
+    states: HashMap<String, NodeState>,
+    /// Recent alerts, oldest first
+    log: VecDeque<NodeAlert>,
+}
+
+impl NodeAlerts {
+    /// Returns the nodes that went down, drained, or stopped responding since the previous
+    /// update. Nodes that were already unavailable when first seen are not reported
+    pub fn update(&mut self, cluster: &[Partition]) -> Vec<NodeAlert> {
+        let time = Local::now();
+        let mut states = HashMap::new();
+        let mut alerts = Vec::new();
+        for node in cluster.iter().flat_map(|v| &v.nodes) {
+            if states.contains_key(&node.name) {
+                continue;
+            }
+
+            if let Some(from) = self.states.get(&node.name) {
+                if !is_failure(from) && is_failure(&node.state) {
+                    alerts.push(NodeAlert {
+                        time,
+                        node: node.name.clone(),
+                        from: from.clone(),
+                        to: node.state.clone(),
+                    });
+                }
+            }
+
+            states.insert(node.name.clone(), node.state.clone());
+        }
+
+        self.states = states;
+        self.log.extend(alerts.iter().cloned());
+        while self.log.len() > MAX_ALERTS {
+            self.log.pop_front();
+        }
+
+        alerts
+    }
+
+    /// Returns recent alerts, oldest first
+    pub fn log(&self) -> impl DoubleEndedIterator<Item = &NodeAlert> {
+        self.log.iter()
+    }
+}
+
+/// Returns true if the node is down, drained, failed, or not responding
+fn is_failure(state: &NodeState) -> bool {
+    !state.responds
+        || matches!(
+            state.state,
+            SlurmState::Down
+                | SlurmState::Drained
+                | SlurmState::Draining
+                | SlurmState::Fail
+                | SlurmState::Failing
+        )
+}
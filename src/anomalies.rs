@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+
+use crate::config::AnomalyConfig;
+use crate::slurm::{Node, Partition};
+
+/// CPU load not attributed to jobs, e.g. of system daemons, that is tolerated before a
+/// node is considered overloaded
+const LOAD_MARGIN: f64 = 2.0;
+
+/// Ways in which the CPU load of a node may deviate from the CPUs allocated to jobs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// The CPU load greatly exceeds the allocated CPUs, e.g. due to stray processes or
+    /// jobs using more threads than requested
+    Overloaded,
+    /// The CPU load is far below the allocated CPUs, e.g. due to idle interactive jobs
+    Underutilized,
+}
+
+impl std::fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            AnomalyKind::Overloaded => "overloaded",
+            AnomalyKind::Underutilized => "underutilized",
+        })
+    }
+}
+
+/// A node whose CPU load deviates from the CPUs allocated to jobs
+#[derive(Clone, Debug)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    /// Time of the first update in which the deviation was observed
+    pub since: DateTime<Local>,
+    /// CPU load and allocated CPUs in the most recent update
+    pub cpu_load: f64,
+    pub allocated: usize,
+    /// Has the deviation persisted for long enough to be reported?
+    pub active: bool,
+}
+
+/// Tracks the CPU load of nodes between updates, in order to flag nodes that are
+/// persistently overloaded or underutilized
+#[derive(Debug, Default)]
+pub struct Anomalies {
+    config: AnomalyConfig,
+    /// Nodes whose CPU load currently deviates from their allocated CPUs
+    nodes: HashMap<String, Anomaly>,
+}
+
+impl Anomalies {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Records the CPU load of each node. Deviations are reported once they have been
+    /// observed in consecutive updates for the configured duration
+    pub fn update(&mut self, cluster: &[Partition]) {
+        let time = Local::now();
+        let mut nodes = HashMap::new();
+        for node in cluster.iter().flat_map(|v| &v.nodes) {
+            if nodes.contains_key(&node.name) {
+                continue;
+            }
+
+            let (Some(kind), Some(cpu_load)) = (self.classify(node), node.cpu_load) else {
+                continue;
+            };
+
+            let since = match self.nodes.get(&node.name) {
+                Some(previous) if previous.kind == kind => previous.since,
+                _ => time,
+            };
+            let after = match kind {
+                AnomalyKind::Overloaded => self.config.overload_after,
+                AnomalyKind::Underutilized => self.config.idle_after,
+            };
+            let active = after.is_none_or(|v| (time - since).to_std().is_ok_and(|d| d >= v));
+
+            nodes.insert(
+                node.name.clone(),
+                Anomaly {
+                    kind,
+                    since,
+                    cpu_load,
+                    allocated: node.cpu_state.allocated,
+                    active,
+                },
+            );
+        }
+
+        self.nodes = nodes;
+    }
+
+    /// Returns the nodes whose deviations are currently reported, ordered by name
+    pub fn active(&self) -> Vec<(&str, &Anomaly)> {
+        let mut nodes = self
+            .nodes
+            .iter()
+            .filter(|(_, v)| v.active)
+            .map(|(name, anomaly)| (name.as_str(), anomaly))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|v| v.0);
+        nodes
+    }
+
+    /// Returns the thresholds used to flag nodes
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    /// Determines if the CPU load of a node deviates from its allocated CPUs; nodes
+    /// that do not report their load (e.g. nodes that are down) are never flagged
+    fn classify(&self, node: &Node) -> Option<AnomalyKind> {
+        let load = node.cpu_load?;
+        let allocated = node.cpu_state.allocated as f64;
+        let (overload, idle) = (self.config.overload, self.config.idle);
+
+        if overload > 0.0 && load > allocated * overload + LOAD_MARGIN {
+            Some(AnomalyKind::Overloaded)
+        } else if idle > 0.0 && allocated > 0.0 && load < allocated * idle {
+            Some(AnomalyKind::Underutilized)
+        } else {
+            None
+        }
+    }
+}
@@ -1,10 +1,50 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
-use color_eyre::Result;
+use color_eyre::{
+    eyre::{bail, eyre},
+    Report, Result,
+};
 
+use tracing::{info, warn};
+
+use crate::alerts::{NodeAlert, NodeAlerts};
+use crate::anomalies::Anomalies;
 use crate::args::Args;
-use crate::slurm::{Partition, Slurm};
+use crate::commands::UserCommand;
+use crate::config::{Config, Pane};
+use crate::export::export_jobs;
+use crate::history::{LoadHistory, NodeHistory, PartitionHistory, Retention};
+use crate::ledger::{Ledger, Transition};
+use crate::replay::Replay;
+use crate::slurm::{
+    cancel_jobs, control_job, group_nodes, list_scripts, parse_time_limit, submit_script,
+    tail_file, update_node_state, update_time_limit, AssociationUsage, BillingWeights,
+    CustomFields, DefaultMem, Health, Job, JobDetails, JobLayout, JobOutput, JobState, NodeGroup,
+    Partition, PartitionConfig, PartitionLimits, ReportPeriod, Reservation, SlurmConfig,
+    SlurmVersion, Source, UsageReport, Watchdog,
+};
+use crate::watch::Watchlist;
+use crate::widgets::{set_palette, validate_custom_columns, Palette};
+use crate::worker::{Modification, Outcome, Snapshot, UpdateRequest, Worker};
+
+/// Slurm commands running longer than this many refresh intervals are considered hung
+/// and are killed, so that the next update can be attempted
+const WATCHDOG_INTERVALS: u64 = 3;
+/// Minimum time before a Slurm command is considered hung; `ssh` may be slow to connect
+const WATCHDOG_MIN_SECS: u64 = 30;
+/// Updates running longer than this many command timeouts are considered stuck (e.g. on
+/// an unresponsive file system), in which case the worker is restarted
+const WORKER_STALL_TIMEOUTS: u32 = 4;
+/// Interval between refreshes of the configuration and limits of partitions
+const PARTITIONS_INTERVAL: Duration = Duration::from_secs(60);
+/// Maximum number of bytes read from the end of the output files of jobs
+pub const MAX_OUTPUT_BYTES: usize = 256 * 1024;
+/// Time for which reports of historical utilization are reused, as `sreport` queries the
+/// Slurm database and the reported utilization changes slowly
+const USAGE_REPORT_MAX_AGE: Duration = Duration::from_secs(10 * 60);
 
 #[derive(Debug)]
 pub struct App {
@@ -12,41 +52,373 @@ pub struct App {
     pub running: bool,
     /// Command-line args
     pub args: Args,
+    /// Settings from the configuration file, overridden by command-line args
+    pub config: Config,
+    /// Source of Slurm command output
+    pub source: Source,
     /// Slurm nodes organized by partition
     pub cluster: Rc<Vec<Partition>>,
+    /// Responsiveness of the Slurm daemons, unless disabled
+    pub health: Option<Health>,
+    /// Current and upcoming reservations of nodes
+    pub reservations: Vec<Reservation>,
+    /// Error encountered during the most recent update, if any
+    pub error: Option<String>,
+    /// Problems with the most recently collected state, such as jobs that could not be
+    /// assigned to a partition, indicating that the displayed state may be incomplete
+    pub warnings: Vec<String>,
+    /// Recorded snapshots when using `--replay`
+    pub replay: Option<Replay>,
+    /// User running the Slurm commands; only jobs of this user may be cancelled,
+    /// unless `--admin` is specified
+    pub user: String,
+    /// State transitions of the user's jobs during this session
+    pub ledger: Ledger,
+    /// Number of jobs on each node during recent updates
+    pub node_history: NodeHistory,
+    /// Utilization of each partition during recent updates
+    pub partition_history: PartitionHistory,
+    /// CPU load and free memory of each node during recent updates
+    pub load_history: LoadHistory,
+    /// Jobs pinned by the user, whose changes in state are reported
+    pub watchlist: Watchlist,
+    /// Changes in state of watched jobs not yet reported to the user
+    watch_events: Vec<Transition>,
+    /// Nodes that went down, drained, or stopped responding during this session
+    pub node_alerts: NodeAlerts,
+    /// Nodes that are persistently overloaded or underutilized
+    pub anomalies: Anomalies,
+    /// Alerts about nodes not yet reported to the user
+    node_events: Vec<NodeAlert>,
+    /// Default amount of memory allocated to jobs
+    pub default_mem: DefaultMem,
+    /// Partition-specific overrides of `default_mem`
+    pub partition_default_mem: HashMap<String, DefaultMem>,
+    /// Partition-specific TRESBillingWeights
+    pub partition_billing: HashMap<String, BillingWeights>,
+    /// Partition-specific limits on jobs
+    pub partition_limits: HashMap<String, PartitionLimits>,
+    /// Node groups defined in the configuration file
+    pub node_groups: Vec<NodeGroup>,
+    /// Shell commands bound to function keys in the configuration file
+    pub commands: Vec<UserCommand>,
+    /// Use the JSON output of sinfo/squeue; disabled if not supported by Slurm
+    json: bool,
+    /// Version of Slurm, unless reading from files or not reported by `sinfo --version`
+    pub version: Option<SlurmVersion>,
     /// Time since last automatic update
     last_update: Instant,
+    /// Time at which the Slurm state was last collected successfully, if ever
+    pub updated: Option<Instant>,
+    /// Time at which the configuration of partitions was last collected
+    partitions_collected: Instant,
+    /// Has the user requested an update that has not yet been performed?
+    update_requested: bool,
+    /// Are automatic updates suspended? Updates requested by the user are still performed
+    pub paused: bool,
+    /// Background worker performing updates, if started; see [`App::start_worker`]
+    worker: Option<Worker>,
+    /// Most recent report of historical utilization for each period, along with the
+    /// time at which it was collected; see [`App::usage_report`]
+    usage_reports: HashMap<ReportPeriod, (Instant, UsageReport)>,
+    /// Periods for which reports of historical utilization are being collected
+    pending_reports: HashSet<ReportPeriod>,
+    /// Is the output file of a job being read? See [`App::read_output`]
+    reading_output: bool,
+    /// Outcomes of tasks performed without a worker, not yet reported to the user
+    outcomes: Vec<Outcome>,
 }
 
 impl App {
     /// Constructs a new instance of [`App`].
     pub fn new(args: Args) -> Result<Self> {
-        let partitions = Slurm::collect(&args.sinfo, &args.squeue)?;
+        let source = match (&args.from_files, &args.ssh) {
+            (Some(root), _) => Source::Files(root.clone()),
+            (None, Some(host)) => Source::Ssh(host.clone()),
+            (None, None) => Source::Local,
+        };
+
+        Self::with_source(args, source)
+    }
+
+    /// Constructs a new instance of [`App`] that obtains the output of Slurm commands
+    /// from `source`, instead of the source selected by `--from-files` or `--ssh`
+    pub fn with_source(args: Args, source: Source) -> Result<Self> {
+        if args.record.is_some() && (args.from_files.is_some() || args.replay.is_some()) {
+            bail!("--record cannot be combined with --from-files or --replay");
+        } else if (args.once || args.output.is_some()) && args.grafana.is_some() {
+            bail!("--once and --output cannot be combined with --grafana");
+        } else if (args.once || args.output.is_some()) && args.serve.is_some() {
+            bail!("--once and --output cannot be combined with --serve");
+        } else if args.grafana.is_some() && args.serve.is_some() {
+            bail!("--grafana cannot be combined with --serve");
+        } else if args.from_files.is_some() && args.replay.is_some() {
+            bail!("--from-files cannot be combined with --replay");
+        } else if args.ssh.is_some() && (args.from_files.is_some() || args.replay.is_some()) {
+            bail!("--ssh cannot be combined with --from-files or --replay");
+        }
+
+        // Commands run without a worker (e.g. with --once) are also subject to the timeout
+        let source = source.with_watchdog(Watchdog::new(Some(command_timeout(&args))));
+
+        let replay = match &args.replay {
+            Some(root) => Some(Replay::open(root)?),
+            None => None,
+        };
+
+        let mut config = Config::load(args.config.as_deref())?;
+        config.view.focus = args.focus.or(config.view.focus);
+        config.view.partition = args.partition.clone().or(config.view.partition);
+        config.view.layout = args.layout.unwrap_or(config.view.layout);
+        config.view.hide_unavailable |= args.hide_unavailable;
+        config.view.billing |= args.billing;
+        config.view.limits |= args.limits;
+        config.view.features |= args.features;
+        config.view.history |= args.history;
+        config.view.gauges |= args.gauges;
+        config.view.usage |= args.usage;
+        config.view.bell |= args.bell;
+        // --read-only takes precedence, e.g. over actions enabled by site admins
+        config.view.allow_actions =
+            (config.view.allow_actions || args.allow_actions) && !args.read_only;
+        for (arg, value) in [
+            (&args.node_columns, &mut config.view.node_columns),
+            (&args.job_columns, &mut config.view.job_columns),
+        ] {
+            if let Some(arg) = arg {
+                *value = Some(arg.split(',').map(String::from).collect());
+            }
+        }
+        config.view.blocked_alert = args.blocked_alert.or(config.view.blocked_alert);
+        config.theme.preset = args.theme.clone().or(config.theme.preset);
+        // Must be set before any widgets are created, as the palette can only be set once
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        set_palette(if args.no_color || no_color {
+            Palette::monochrome()
+        } else {
+            config.theme.palette()?
+        })?;
+        validate_custom_columns(&config.columns)?;
+
+        let slurm = &mut config.slurm;
+        for (arg, value) in [
+            (&args.sinfo, &mut slurm.sinfo),
+            (&args.squeue, &mut slurm.squeue),
+            (&args.scontrol, &mut slurm.scontrol),
+            (&args.sacct, &mut slurm.sacct),
+            (&args.sbatch, &mut slurm.sbatch),
+            (&args.scancel, &mut slurm.scancel),
+            (&args.sdiag, &mut slurm.sdiag),
+            (&args.sreport, &mut slurm.sreport),
+            (&args.sshare, &mut slurm.sshare),
+            (&args.sacctmgr, &mut slurm.sacctmgr),
+        ] {
+            if let Some(arg) = arg {
+                value.clone_from(arg);
+            }
+        }
+        slurm.def_mem_per_cpu = args.def_mem_per_cpu.or(slurm.def_mem_per_cpu);
+
+        let node_groups = config
+            .groups
+            .iter()
+            .map(|v| NodeGroup::new(&v.name, v.nodes.as_deref(), v.pattern.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
+        let commands = UserCommand::parse_all(&config.commands)?;
 
-        Ok(Self {
+        let user = current_user(&source);
+
+        // The job history tracks the jobs of the user running slurmboard by default
+        let ledger_user = args.user.clone().unwrap_or_else(|| user.clone());
+
+        // Custom fields are only available in the delimited output of sinfo/squeue
+        let json = args.json && config.columns.is_empty();
+        if args.json && !json {
+            warn!("--json is ignored, since custom columns are configured");
+        }
+        let retention = Retention {
+            max_entries: config.retention.transitions,
+            max_age: config.retention.max_age,
+        };
+        let anomalies = Anomalies::new(config.anomalies.clone());
+        let mut app = Self {
             args,
+            config,
+            source,
             running: true,
-            cluster: Rc::new(partitions),
+            cluster: Rc::default(),
+            health: None,
+            reservations: Vec::new(),
+            error: None,
+            warnings: Vec::new(),
+            replay,
+            ledger: Ledger::new(ledger_user, retention),
+            user,
+            node_history: NodeHistory::default(),
+            partition_history: PartitionHistory::default(),
+            load_history: LoadHistory::default(),
+            watchlist: Watchlist::default(),
+            watch_events: Vec::new(),
+            node_alerts: NodeAlerts::default(),
+            anomalies,
+            node_events: Vec::new(),
+            default_mem: DefaultMem::None,
+            partition_default_mem: HashMap::new(),
+            partition_billing: HashMap::new(),
+            partition_limits: HashMap::new(),
+            node_groups,
+            commands,
+            json,
+            version: None,
             last_update: Instant::now(),
-        })
+            updated: None,
+            partitions_collected: Instant::now(),
+            update_requested: false,
+            paused: false,
+            worker: None,
+            usage_reports: HashMap::new(),
+            pending_reports: HashSet::new(),
+            reading_output: false,
+            outcomes: Vec::new(),
+        };
+
+        app.collect_config();
+
+        app.collect()?;
+
+        Ok(app)
+    }
+
+    /// Returns the nodes of the cluster organized by the configured node groups
+    pub fn grouped_cluster(&self) -> Rc<Vec<Partition>> {
+        Rc::new(group_nodes(&self.cluster, &self.node_groups))
+    }
+
+    /// Performs subsequent updates of the Slurm state on a background thread, instead
+    /// of blocking during [`App::tick`], along with tasks run on behalf of the user (e.g.
+    /// cancelling jobs); `notify` is called whenever an update or a task finishes.
+    /// Snapshots are read on demand when replaying, so no worker is used in that case
+    pub fn start_worker<F: Fn() + Send + Sync + 'static>(&mut self, notify: F) {
+        if self.replay.is_none() {
+            // Each worker kills its own commands when dropped, without affecting others
+            let watchdog = Watchdog::new(Some(command_timeout(&self.args)));
+            self.source = std::mem::take(&mut self.source).with_watchdog(watchdog.clone());
+            self.worker = Some(Worker::new(watchdog, notify));
+        }
+    }
+
+    /// Returns true if an update is being performed in the background
+    pub fn updating(&self) -> bool {
+        self.worker.as_ref().is_some_and(Worker::busy)
     }
 
-    /// Handles the tick event of the terminal.
+    /// Handles the tick event of the terminal. Due updates are started in the background
+    /// if a worker has been started, and otherwise performed immediately. Returns true if
+    /// the Slurm state was updated
     pub fn tick(&mut self) -> Result<bool> {
-        if self.args.interval > 0 {
-            self.update(self.args.interval)
-        } else {
-            Ok(false)
+        if self.worker.is_none() {
+            if self.update_due() {
+                self.force_update();
+                return Ok(true);
+            }
+
+            return Ok(false);
+        }
+
+        self.recover_worker();
+
+        // Automatic updates are not queued behind slow updates, unlike those requested
+        if self.update_due() && (self.update_requested || !self.updating()) {
+            self.update_requested = false;
+            let request = self.update_request();
+            if let Some(worker) = &mut self.worker {
+                worker.submit(request);
+            }
+        }
+
+        match self.worker.as_mut().and_then(Worker::try_recv) {
+            Some(snapshot) => {
+                self.error = update_error(self.apply(snapshot));
+                self.last_update = Instant::now();
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
-    /// Force update of Slurm state
+    /// Restarts the worker if the current update has been running for much longer than
+    /// commands are allowed to run, since the worker thread is then stuck on something
+    /// other than a Slurm command. Tasks in progress are abandoned along with the update
+    fn recover_worker(&mut self) {
+        let Some(worker) = &mut self.worker else {
+            return;
+        };
+
+        let limit = command_timeout(&self.args) * WORKER_STALL_TIMEOUTS;
+        let Some(elapsed) = worker.busy_for().filter(|v| *v >= limit) else {
+            return;
+        };
+
+        warn!(
+            elapsed_ms = elapsed.as_millis() as u64,
+            "update did not finish in time; restarting worker"
+        );
+        // Tasks that had not finished are reported as failed by `take_outcomes`
+        let watchdog = worker.restart();
+        self.source = std::mem::take(&mut self.source).with_watchdog(watchdog);
+        self.error = Some(format!(
+            "update did not finish within {}s; restarted the background worker",
+            elapsed.as_secs()
+        ));
+        self.update_requested = true;
+    }
+
+    /// Returns true if the next tick will update the Slurm state, either because the
+    /// user requested an update or because the refresh interval has passed
+    pub fn update_due(&self) -> bool {
+        let elapsed = self.last_update.elapsed();
+        if self.update_requested && elapsed >= Duration::from_secs(1) {
+            return true;
+        }
+
+        // Snapshots are only changed on request when replaying
+        self.args.interval > 0
+            && !self.paused
+            && self.replay.is_none()
+            && elapsed >= Duration::from_secs(self.args.interval)
+    }
+
+    /// Returns the time until the next automatic update is due, or `None` if automatic
+    /// updates are disabled or suspended
+    pub fn until_update_due(&self) -> Option<Duration> {
+        if self.args.interval == 0 || self.paused || self.replay.is_some() {
+            return None;
+        }
+
+        Some(Duration::from_secs(self.args.interval).saturating_sub(self.last_update.elapsed()))
+    }
+
+    /// Suspends or resumes automatic updates; returns true if updates are now suspended
+    pub fn toggle_pause(&mut self) -> bool {
+        self.paused = !self.paused;
+        self.paused
+    }
+
+    /// Requests an update of the Slurm state during the next tick, so that the UI can
+    /// indicate that the update is in progress. Returns false if the state was updated
+    /// less than a second ago, to prevent the user just holding `r`
+    pub fn request_update(&mut self) -> bool {
+        self.update_requested = self.last_update.elapsed() >= Duration::from_secs(1);
+        self.update_requested
+    }
+
+    /// Force update of Slurm state. Failures to collect the Slurm state are
+    /// recorded in `error`, in which case the previous state is kept
     pub fn update(&mut self, interval: u64) -> Result<bool> {
         // A minimum refresh rate is enforced to prevent the user just holding `r`
         let update_rate = Duration::from_secs(interval.max(1));
         if self.last_update.elapsed() >= update_rate {
-            self.cluster = Rc::new(Slurm::collect(&self.args.sinfo, &self.args.squeue)?);
-            self.last_update = Instant::now();
+            self.force_update();
 
             return Ok(true);
         }
@@ -54,8 +426,704 @@ impl App {
         Ok(false)
     }
 
+    /// Updates the Slurm state regardless of when the last update happened
+    fn force_update(&mut self) {
+        self.error = update_error(self.collect());
+        self.last_update = Instant::now();
+        self.update_requested = false;
+    }
+
+    /// Moves `delta` snapshots forward/backward when replaying recorded snapshots
+    pub fn seek(&mut self, delta: isize) -> Result<bool> {
+        if let Some(replay) = &mut self.replay {
+            if replay.seek(delta) {
+                self.error = update_error(self.collect());
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Collects the current Slurm state, or the current snapshot when replaying
+    fn collect(&mut self) -> Result<()> {
+        let snapshot = self.update_request().run();
+        self.apply(snapshot)
+    }
+
+    /// Describes the commands needed to collect the current Slurm state
+    fn update_request(&self) -> UpdateRequest {
+        let source = match &self.replay {
+            Some(replay) => Source::Files(replay.current().to_owned()),
+            None => self.source.clone(),
+        };
+
+        UpdateRequest {
+            source,
+            record: self.args.record.clone(),
+            slurm: self.config.slurm.clone(),
+            json: self.json,
+            version: self.version,
+            custom: self.custom_fields(),
+            health: !self.args.no_health,
+            watched: self.watchlist.ids().collect(),
+            partitions: self.partitions_collected.elapsed() >= PARTITIONS_INTERVAL,
+        }
+    }
+
+    /// Returns the fields requested from squeue and sinfo for the custom columns
+    fn custom_fields(&self) -> CustomFields {
+        let fields = |list| {
+            self.config
+                .columns
+                .iter()
+                .filter(|v| v.list == list)
+                .map(|v| v.field.clone())
+                .collect()
+        };
+
+        CustomFields {
+            squeue: fields(Pane::Jobs),
+            sinfo: fields(Pane::Nodes),
+        }
+    }
+
+    /// Replaces the current Slurm state with a newly collected snapshot. The health of
+    /// the Slurm daemons is updated even if collecting nodes and jobs failed
+    fn apply(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.health = snapshot.health;
+        self.reservations = snapshot.reservations;
+        self.json = snapshot.json;
+        if let Some(partitions) = snapshot.partitions {
+            self.partitions_collected = Instant::now();
+            match partitions {
+                Ok(partitions) => self.apply_partitions(partitions),
+                Err(error) => warn!("failed to collect partitions: {:#}", error),
+            }
+        }
+
+        let (mut cluster, unassigned) = snapshot.cluster?;
+        for partition in &mut cluster {
+            if let Some(weights) = self.partition_billing.get(&partition.name.label) {
+                partition.update_billing(weights);
+            }
+        }
+
+        self.cluster = Rc::new(cluster);
+        self.warnings = unassigned_warnings(&unassigned);
+        self.ledger.update(&self.cluster);
+        self.node_history.update(&self.cluster);
+        self.partition_history.update(&self.cluster);
+        self.load_history.update(&self.cluster);
+        self.anomalies.update(&self.cluster);
+
+        let mut transitions = self.watchlist.update(&self.cluster);
+        for transition in transitions.iter_mut().filter(|v| v.to.is_none()) {
+            transition.to = snapshot.final_states.get(&transition.id).cloned();
+        }
+        self.watch_events.extend(transitions);
+        self.node_events
+            .extend(self.node_alerts.update(&self.cluster));
+        self.updated = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Starts or stops watching a job; returns true if the job is now being watched
+    pub fn toggle_watch(&mut self, id: usize) -> Result<bool> {
+        let job = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .find(|v| v.id == id)
+            .ok_or_else(|| eyre!("job {} is no longer listed by squeue", id))?;
+
+        Ok(self.watchlist.toggle(job))
+    }
+
+    /// Returns changes in the state of watched jobs observed since the last call
+    pub fn take_watch_events(&mut self) -> Vec<Transition> {
+        std::mem::take(&mut self.watch_events)
+    }
+
+    /// Returns nodes that became unavailable since the last call
+    pub fn take_node_events(&mut self) -> Vec<NodeAlert> {
+        std::mem::take(&mut self.node_events)
+    }
+
+    /// Collects cluster and partition configuration, and the version of Slurm. The value of
+    /// `--def-mem-per-cpu` is used for all partitions if specified, and otherwise the
+    /// DefMemPer* values reported by `scontrol show config` and by `scontrol show partition`
+    fn collect_config(&mut self) {
+        let source = match &self.replay {
+            Some(replay) => Source::Files(replay.current().to_owned()),
+            None => self.source.clone(),
+        };
+
+        // The fields listed by sinfo/squeue depend on the version of Slurm. The version is
+        // not known when reading from files, which are assumed to include all fields
+        if !matches!(source, Source::Files(_)) {
+            match SlurmVersion::collect(&source, &self.config.slurm.sinfo) {
+                Ok(version) => {
+                    info!(%version, "detected Slurm version");
+                    self.version = Some(version);
+                }
+                Err(error) => warn!("failed to determine Slurm version: {:#}", error),
+            }
+        }
+
+        // Failures are not fatal, since these values are only used to estimate blocked
+        // resources and billing, and to show limits, and may not be available when
+        // reading from files
+        let slurm = &self.config.slurm;
+        let partitions =
+            PartitionConfig::collect(&source, &slurm.scontrol, &slurm.sacctmgr).unwrap_or_default();
+        self.apply_partitions(partitions);
+        self.partitions_collected = Instant::now();
+
+        if let Some(value) = self.config.slurm.def_mem_per_cpu {
+            if value > 0 {
+                self.default_mem = DefaultMem::PerCPU(value);
+            }
+        } else {
+            self.default_mem = SlurmConfig::collect(&source, &self.config.slurm.scontrol)
+                .map(|v| v.default_mem())
+                .unwrap_or_default();
+        }
+    }
+
+    /// Replaces the limits, billing weights, and default amount of memory of partitions.
+    /// The partition-specific default amounts of memory are ignored if
+    /// `--def-mem-per-cpu` is specified
+    fn apply_partitions(&mut self, partitions: Vec<PartitionConfig>) {
+        self.partition_limits.clear();
+        self.partition_billing.clear();
+        self.partition_default_mem.clear();
+
+        for partition in partitions {
+            self.partition_limits
+                .insert(partition.name.clone(), partition.limits);
+
+            if let Some(weights) = partition.billing_weights {
+                self.partition_billing
+                    .insert(partition.name.clone(), weights);
+            }
+
+            if let Some(default_mem) = partition.default_mem {
+                if self.config.slurm.def_mem_per_cpu.is_none() {
+                    self.partition_default_mem
+                        .insert(partition.name, default_mem);
+                }
+            }
+        }
+    }
+
+    /// Returns the default amount of memory allocated to jobs in a partition
+    pub fn default_mem(&self, partition: &str) -> DefaultMem {
+        self.partition_default_mem
+            .get(partition)
+            .copied()
+            .unwrap_or(self.default_mem)
+    }
+
+    /// Exports details about the specified jobs to the file specified by `--export` in
+    /// the background; see [`App::take_outcomes`]
+    pub fn export_jobs(&mut self, ids: &[usize]) -> Result<()> {
+        let (source, slurm) = (self.source.clone(), self.config.slurm.clone());
+        let (path, ids) = (self.args.export.clone(), ids.to_vec());
+        let overwrite = self.args.overwrite_export;
+        let task = move || {
+            // Jobs whose details cannot be collected (e.g. jobs that have been purged by
+            // Slurm) are skipped, and reported along with the result of the export
+            let mut jobs = Vec::with_capacity(ids.len());
+            let mut errors = Vec::new();
+            for &id in &ids {
+                match JobDetails::collect(&source, &slurm.scontrol, &slurm.sacct, id) {
+                    Ok(job) => jobs.push(job),
+                    Err(error) => errors.push(format!("job {}: {:#}", id, error)),
+                }
+            }
+
+            let failures = if errors.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n\nFailed to collect details for {} job(s):\n{}",
+                    errors.len(),
+                    errors.join("\n")
+                )
+            };
+
+            let result = if jobs.is_empty() {
+                Err(eyre!("no jobs were exported{}", failures))
+            } else {
+                export_jobs(&path, &jobs, overwrite).map(|path| {
+                    format!(
+                        "Exported details for {} job(s) to {:?}{}",
+                        jobs.len(),
+                        path,
+                        failures
+                    )
+                })
+            };
+
+            Outcome::Exported(result)
+        };
+
+        self.spawn(task, |error| Outcome::Exported(Err(error)));
+
+        Ok(())
+    }
+
+    /// Looks up the distribution of the CPUs allocated to a running job across nodes in
+    /// the background
+    pub fn job_layout(&mut self, id: usize) {
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        self.spawn(
+            move || Outcome::JobLayout(JobLayout::collect(&source, &scontrol, id)),
+            |error| Outcome::JobLayout(Err(error)),
+        );
+    }
+
+    /// Returns the utilization of the cluster and the top users during `period`, as
+    /// reported by `sreport`. Recent reports are reused, unless `refresh` is true, and
+    /// otherwise the report is collected in the background and `None` is returned
+    pub fn usage_report(&mut self, period: ReportPeriod, refresh: bool) -> Option<UsageReport> {
+        if let Some((collected, report)) = self.usage_reports.get(&period) {
+            if !refresh && collected.elapsed() < USAGE_REPORT_MAX_AGE {
+                return Some(report.clone());
+            }
+        }
+
+        // Reports take a while to collect, so switching periods back and forth does not
+        // queue additional reports
+        if self.pending_reports.insert(period) {
+            let (source, sreport) = (self.source.clone(), self.config.slurm.sreport.clone());
+            self.spawn(
+                move || {
+                    Outcome::UsageReport(period, UsageReport::collect(&source, &sreport, period))
+                },
+                move |error| Outcome::UsageReport(period, Err(error)),
+            );
+        }
+
+        None
+    }
+
+    /// Looks up the usage of the associations of the tracked user and of their accounts,
+    /// compared to the limits on the TRES minutes that may be consumed, in the background
+    pub fn association_usage(&mut self) {
+        let (source, slurm) = (self.source.clone(), self.config.slurm.clone());
+        let user = self.tracked_user().to_string();
+        let abandoned_user = user.clone();
+        self.spawn(
+            move || {
+                let usage =
+                    AssociationUsage::collect(&source, &slurm.sshare, &slurm.sacctmgr, &user);
+                Outcome::AssociationUsage(user, usage)
+            },
+            move |error| Outcome::AssociationUsage(abandoned_user, Err(error)),
+        );
+    }
+
+    /// Returns the user whose jobs are tracked in the job history (see `--user`)
+    pub fn tracked_user(&self) -> &str {
+        self.args.user.as_deref().unwrap_or(&self.user)
+    }
+
+    /// Returns the sbatch scripts in the directory specified by `--scripts`
+    pub fn scripts(&self) -> Result<Vec<PathBuf>> {
+        match &self.args.scripts {
+            Some(root) => {
+                let scripts = list_scripts(root)?;
+                if scripts.is_empty() {
+                    bail!("no scripts found in {:?}", root);
+                }
+
+                Ok(scripts)
+            }
+            None => bail!("no script directory specified; use --scripts to enable job submission"),
+        }
+    }
+
+    /// Runs a user-defined command in the background. The output includes anything
+    /// written to stderr and, if the command failed, its exit status. Commands may modify
+    /// jobs or nodes, and are therefore not run in read-only mode
+    pub fn run_command(&mut self, command: String) -> Result<()> {
+        if !self.config.view.allow_actions {
+            bail!("custom commands cannot be run in read-only mode (see --allow-actions)");
+        }
+
+        info!(command, "running user-defined command");
+        let source = self.source.clone();
+        let abandoned_command = command.clone();
+        let task = move || {
+            let output = source.run_shell(&command).map(|output| {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                if !output.status.success() {
+                    text.push_str(&format!("\n[{}]", output.status));
+                }
+
+                text
+            });
+
+            Outcome::Command(command, output)
+        };
+
+        self.spawn(task, move |error| {
+            Outcome::Command(abandoned_command, Err(error))
+        });
+
+        Ok(())
+    }
+
+    /// Looks up the locations of the files to which a job writes its output in the
+    /// background
+    pub fn job_output(&mut self, id: usize) {
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        self.spawn(
+            move || Outcome::JobOutput(JobOutput::collect(&source, &scontrol, id)),
+            |error| Outcome::JobOutput(Err(error)),
+        );
+    }
+
+    /// Reads the last (at most) [`MAX_OUTPUT_BYTES`] bytes of a job output file in the
+    /// background, unless the output is already being read
+    pub fn read_output(&mut self, path: String) {
+        if !self.reading_output {
+            self.reading_output = true;
+            let source = self.source.clone();
+            let abandoned_path = path.clone();
+            self.spawn(
+                move || {
+                    let text = tail_file(&source, &path, MAX_OUTPUT_BYTES);
+                    Outcome::Output(path, text)
+                },
+                move |error| Outcome::Output(abandoned_path, Err(error)),
+            );
+        }
+    }
+
+    /// Submits a batch script using `sbatch` in the background, after which the Slurm
+    /// state is updated, so that the new job is shown
+    pub fn submit_job(&mut self, script: &Path) -> Result<()> {
+        if !self.config.view.allow_actions {
+            bail!("jobs cannot be submitted in read-only mode (see --allow-actions)");
+        } else if self.replay.is_some() {
+            bail!("jobs cannot be submitted while replaying recorded snapshots");
+        }
+
+        let (source, sbatch) = (self.source.clone(), self.config.slurm.sbatch.clone());
+        let script = script.to_owned();
+        self.spawn_modification(Modification::Submit, move || {
+            submit_script(&source, &sbatch, &script)
+        });
+
+        Ok(())
+    }
+
+    /// Returns the IDs and owners of jobs that are about to be cancelled. Fails if any
+    /// of the jobs belong to other users, unless `--admin` was specified
+    pub fn jobs_to_cancel(&self, ids: &[usize]) -> Result<Vec<(usize, String)>> {
+        let mut jobs = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let job = self.owned_job(id, "cancel")?;
+            jobs.push((id, job.user.to_string()));
+        }
+
+        Ok(jobs)
+    }
+
+    /// Cancels jobs using `scancel` in the background and updates the Slurm state
+    pub fn cancel_jobs(&mut self, ids: &[usize]) -> Result<()> {
+        // Ownership is checked again, in case the jobs were re-assigned since prompting
+        self.jobs_to_cancel(ids)?;
+
+        let (source, scancel) = (self.source.clone(), self.config.slurm.scancel.clone());
+        let ids = ids.to_vec();
+        self.spawn_modification(Modification::Cancel, move || {
+            cancel_jobs(&source, &scancel, &ids)?;
+
+            let ids = ids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            Ok(format!("Cancelled job(s) {}", ids.join(", ")))
+        });
+
+        Ok(())
+    }
+
+    /// Holds (or releases) a pending job using `scontrol` in the background and updates
+    /// the Slurm state
+    pub fn hold_job(&mut self, id: usize, hold: bool) -> Result<()> {
+        let verb = if hold { "hold" } else { "release" };
+        self.pending_job(id, verb)?;
+        self.control_job(verb, id, move || {
+            format!("{} job {}", if hold { "Held" } else { "Released" }, id)
+        });
+
+        Ok(())
+    }
+
+    /// Moves a pending job ahead of the other pending jobs of the same user using
+    /// `scontrol top` in the background and updates the Slurm state
+    pub fn top_job(&mut self, id: usize) -> Result<()> {
+        let user = self.pending_job(id, "prioritize")?.user.clone();
+        self.control_job("top", id, move || {
+            format!(
+                "Moved job {} ahead of the other pending jobs of {}",
+                id, user
+            )
+        });
+
+        Ok(())
+    }
+
+    /// Returns the owner of a job that is about to be modified. Fails if the job belongs
+    /// to another user, unless `--admin` was specified. `verb` describes the action in
+    /// error messages
+    pub fn job_owner(&self, id: usize, verb: &str) -> Result<String> {
+        Ok(self.owned_job(id, verb)?.user.to_string())
+    }
+
+    /// Requeues a job using `scontrol` in the background and updates the Slurm state
+    pub fn requeue_job(&mut self, id: usize) -> Result<()> {
+        // Ownership is checked again, in case the job was re-assigned since prompting
+        self.owned_job(id, "requeue")?;
+        self.control_job("requeue", id, move || format!("Requeued job {}", id));
+
+        Ok(())
+    }
+
+    /// Changes the time limit of a job using `scontrol` in the background and updates the
+    /// Slurm state. The time limit is validated using [`parse_time_limit`] beforehand
+    pub fn set_time_limit(&mut self, id: usize, limit: &str) -> Result<()> {
+        let limit = parse_time_limit(limit)?;
+        self.owned_job(id, "modify the time limit of")?;
+
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        self.spawn_modification(Modification::Control, move || {
+            update_time_limit(&source, &scontrol, id, &limit)?;
+            Ok(format!("Set time limit of job {} to {}", id, limit))
+        });
+
+        Ok(())
+    }
+
+    /// Fails unless nodes can be drained/resumed, which requires `--admin`. `verb`
+    /// describes the action in error messages
+    pub fn check_node_action(&self, verb: &str) -> Result<()> {
+        if !self.config.view.allow_actions {
+            bail!(
+                "cannot {} nodes in read-only mode (see --allow-actions)",
+                verb
+            );
+        } else if !self.args.admin {
+            bail!("--admin is required to {} nodes", verb);
+        } else if self.replay.is_some() || self.args.from_files.is_some() {
+            bail!("cannot {} nodes when reading output from files", verb);
+        }
+
+        Ok(())
+    }
+
+    /// Drains a node using `scontrol` in the background and updates the Slurm state
+    pub fn drain_node(&mut self, node: &str, reason: &str) -> Result<()> {
+        self.check_node_action("drain")?;
+
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        let (node, reason) = (node.to_string(), reason.to_string());
+        self.spawn_modification(Modification::Control, move || {
+            update_node_state(&source, &scontrol, &node, Some(&reason))?;
+            Ok(format!("Draining node {}", node))
+        });
+
+        Ok(())
+    }
+
+    /// Resumes a (drained) node using `scontrol` in the background and updates the Slurm
+    /// state
+    pub fn resume_node(&mut self, node: &str) -> Result<()> {
+        self.check_node_action("resume")?;
+
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        let node = node.to_string();
+        self.spawn_modification(Modification::Control, move || {
+            update_node_state(&source, &scontrol, &node, None)?;
+            Ok(format!("Resumed node {}", node))
+        });
+
+        Ok(())
+    }
+
+    /// Runs `scontrol <verb> <id>` in the background and updates the Slurm state;
+    /// `message` describes the change on success
+    fn control_job<F>(&mut self, verb: &'static str, id: usize, message: F)
+    where
+        F: FnOnce() -> String + Send + 'static,
+    {
+        let (source, scontrol) = (self.source.clone(), self.config.slurm.scontrol.clone());
+        self.spawn_modification(Modification::Control, move || {
+            control_job(&source, &scontrol, verb, id)?;
+            Ok(message())
+        });
+    }
+
+    /// Runs a command that modifies jobs or nodes in the background. If the command
+    /// succeeds, the Slurm state is collected again on the same thread, so that the
+    /// change is shown together with the message returned by the command
+    fn spawn_modification<F>(&mut self, kind: Modification, command: F)
+    where
+        F: FnOnce() -> Result<String> + Send + 'static,
+    {
+        info!(?kind, "modifying Slurm state");
+        let request = self.update_request();
+        self.spawn(
+            move || {
+                let result = command();
+                let snapshot = result.is_ok().then(|| Box::new(request.run()));
+
+                Outcome::Modified(kind, result, snapshot)
+            },
+            // The command may still have been carried out before the worker was restarted
+            move |error| Outcome::Modified(kind, Err(error), None),
+        );
+    }
+
+    /// Runs a task on the task thread of the worker, if started, and otherwise
+    /// immediately. Outcomes are returned by [`App::take_outcomes`]; `abandoned` builds
+    /// the outcome reported if the task is abandoned when the worker is restarted
+    fn spawn<F, A>(&mut self, task: F, abandoned: A)
+    where
+        F: FnOnce() -> Outcome + Send + 'static,
+        A: FnOnce(Report) -> Outcome + 'static,
+    {
+        match &mut self.worker {
+            Some(worker) => worker.spawn(Box::new(task), Box::new(abandoned)),
+            None => self.outcomes.push(task()),
+        }
+    }
+
+    /// Returns the outcomes of finished tasks not yet reported to the user. The Slurm
+    /// state collected after modifying jobs or nodes is applied, and reports of
+    /// historical utilization are kept for reuse
+    pub fn take_outcomes(&mut self) -> Vec<Outcome> {
+        let mut outcomes = std::mem::take(&mut self.outcomes);
+        if let Some(worker) = &mut self.worker {
+            outcomes.extend(std::iter::from_fn(|| worker.try_outcome()));
+        }
+
+        for outcome in &mut outcomes {
+            match outcome {
+                Outcome::Modified(_, _, snapshot) => {
+                    if let Some(snapshot) = snapshot.take() {
+                        self.error = update_error(self.apply(*snapshot));
+                        self.last_update = Instant::now();
+                    }
+                }
+                Outcome::Output(..) => self.reading_output = false,
+                Outcome::UsageReport(period, report) => {
+                    self.pending_reports.remove(period);
+                    if let Ok(report) = report {
+                        self.usage_reports
+                            .insert(*period, (Instant::now(), report.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        outcomes
+    }
+
+    /// Like `owned_job`, but also fails if the job is not pending
+    fn pending_job(&self, id: usize, verb: &str) -> Result<&Job> {
+        let job = self.owned_job(id, verb)?;
+        if job.state != JobState::Pending {
+            bail!("cannot {} job {}, as it is not pending", verb, id);
+        }
+
+        Ok(job)
+    }
+
+    /// Returns the job with the given ID, provided that it belongs to the current user
+    /// or that `--admin` was specified. `verb` describes the action in error messages
+    fn owned_job(&self, id: usize, verb: &str) -> Result<&Job> {
+        if !self.config.view.allow_actions {
+            bail!(
+                "cannot {} jobs in read-only mode (see --allow-actions)",
+                verb
+            );
+        } else if self.replay.is_some() || self.args.from_files.is_some() {
+            bail!("cannot {} jobs when reading output from files", verb);
+        }
+
+        let job = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .find(|v| v.id == id)
+            .ok_or_else(|| eyre!("job {} is no longer listed by squeue", id))?;
+
+        if *job.user != self.user && !self.args.admin {
+            bail!(
+                "job {} belongs to user {:?}; use --admin to {} jobs of other users",
+                id,
+                job.user,
+                verb
+            );
+        }
+
+        Ok(job)
+    }
+
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 }
+
+/// Formats the error encountered while updating the Slurm state, if any, which is also logged
+fn update_error(result: Result<()>) -> Option<String> {
+    let error = format!("{:#}", result.err()?);
+    warn!("failed to update Slurm state: {}", error);
+
+    Some(error)
+}
+
+/// Describes jobs listed by squeue in partitions not listed by sinfo, one line per partition
+fn unassigned_warnings(jobs: &[Job]) -> Vec<String> {
+    let mut partitions = BTreeMap::<&str, usize>::new();
+    for job in jobs {
+        *partitions.entry(&job.partition.label).or_default() += 1;
+    }
+
+    partitions
+        .into_iter()
+        .map(|(name, count)| {
+            let jobs = if count == 1 { "job" } else { "jobs" };
+            format!(
+                "{} {} in partition {:?}, which is not listed by sinfo",
+                count, jobs, name
+            )
+        })
+        .collect()
+}
+
+/// Returns the name of the user running the Slurm commands, locally or on the host
+/// specified via `--ssh`, which may differ from the local user (e.g. due to `User` in
+/// `~/.ssh/config`). Falls back to `$USER` if the user cannot be looked up, e.g. when
+/// reading output from files
+fn current_user(source: &Source) -> String {
+    match source.run("id", &["-un"]) {
+        Ok(stdout) => String::from_utf8_lossy(&stdout).trim().to_string(),
+        Err(error) => {
+            info!("failed to look up user; using $USER: {:#}", error);
+            std::env::var("USER").unwrap_or_default()
+        }
+    }
+}
+
+/// Returns the time after which Slurm commands are considered hung and are killed
+fn command_timeout(args: &Args) -> Duration {
+    Duration::from_secs((args.interval * WATCHDOG_INTERVALS).max(WATCHDOG_MIN_SECS))
+}
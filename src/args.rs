@@ -1,25 +1,283 @@
+use std::{path::PathBuf, str::FromStr};
+
 use argh::FromArgs;
 
+use crate::config::{ListLayout, Pane};
+
 /// Text-based dashboard for Slurm
 #[derive(FromArgs, Debug)]
 pub struct Args {
-    /// value of DefMemPerCPU from /etc/slurm/slurm.conf; 0 to disable
-    #[argh(option, default = "15948")]
-    pub def_mem_per_cpu: u64,
+    /// override the value of DefMemPerCPU reported by `scontrol show config`;
+    /// 0 to disable
+    #[argh(option)]
+    pub def_mem_per_cpu: Option<u64>,
 
     /// refresh frequency in seconds; a value of zero disables automatic updates
     #[argh(option, default = "5")]
     pub interval: u64,
 
+    /// read settings from this configuration file instead of
+    /// `~/.config/slurmboard/config.toml`; system-wide defaults are still read first
+    #[argh(option)]
+    pub config: Option<PathBuf>,
+
     /// location of `sinfo` executable
-    #[argh(option, default = "\"sinfo\".to_string()")]
-    pub sinfo: String,
+    #[argh(option)]
+    pub sinfo: Option<String>,
 
     /// location of `squeue` executable
-    #[argh(option, default = "\"squeue\".to_string()")]
-    pub squeue: String,
+    #[argh(option)]
+    pub squeue: Option<String>,
+
+    /// location of `scontrol` executable
+    #[argh(option)]
+    pub scontrol: Option<String>,
+
+    /// location of `sacct` executable
+    #[argh(option)]
+    pub sacct: Option<String>,
+
+    /// location of `sbatch` executable
+    #[argh(option)]
+    pub sbatch: Option<String>,
+
+    /// location of `scancel` executable
+    #[argh(option)]
+    pub scancel: Option<String>,
+
+    /// location of `sdiag` executable
+    #[argh(option)]
+    pub sdiag: Option<String>,
+
+    /// location of `sreport` executable
+    #[argh(option)]
+    pub sreport: Option<String>,
+
+    /// location of `sshare` executable
+    #[argh(option)]
+    pub sshare: Option<String>,
+
+    /// location of `sacctmgr` executable
+    #[argh(option)]
+    pub sacctmgr: Option<String>,
+
+    /// run Slurm commands on this host (`[user@]host`) using `ssh`; requires
+    /// non-interactive (e.g. key-based) authentication
+    #[argh(option)]
+    pub ssh: Option<String>,
+
+    /// read the output of Slurm commands from files in this directory instead of
+    /// running the commands, e.g. `sinfo.txt` and `squeue.txt`
+    #[argh(option)]
+    pub from_files: Option<PathBuf>,
+
+    /// save the output of Slurm commands to timestamped directories in this directory
+    #[argh(option)]
+    pub record: Option<PathBuf>,
+
+    /// replay output of Slurm commands previously saved using `--record`
+    #[argh(option)]
+    pub replay: Option<PathBuf>,
+
+    /// directory containing sbatch scripts that can be submitted from slurmboard
+    #[argh(option)]
+    pub scripts: Option<PathBuf>,
+
+    /// user whose jobs are tracked in the job history; defaults to the user
+    /// specified using `--ssh` or to the current user
+    #[argh(option)]
+    pub user: Option<String>,
+
+    /// pane that has focus at startup ('nodes' or 'jobs')
+    #[argh(option)]
+    pub focus: Option<Pane>,
+
+    /// arrangement of the node and job lists ('vertical' or 'horizontal'); the lists
+    /// are shown side by side in the horizontal layout
+    #[argh(option)]
+    pub layout: Option<ListLayout>,
+
+    /// partition initially selected in the node list
+    #[argh(option)]
+    pub partition: Option<String>,
+
+    /// hide unavailable nodes at startup
+    #[argh(switch)]
+    pub hide_unavailable: bool,
+
+    /// show the billable TRES of jobs and partitions
+    #[argh(switch)]
+    pub billing: bool,
+
+    /// show the time, node, and per-user CPU limits of partitions
+    #[argh(switch)]
+    pub limits: bool,
+
+    /// show the features available on each node
+    #[argh(switch)]
+    pub features: bool,
+
+    /// show the number of jobs on each node during recent updates
+    #[argh(switch)]
+    pub history: bool,
+
+    /// show gauges of the CPUs, memory, and GPUs allocated across the whole cluster
+    #[argh(switch)]
+    pub gauges: bool,
+
+    /// show a tab with the utilization of the cluster and the top users during the last
+    /// day, week, or month, as reported by `sreport`
+    #[argh(switch)]
+    pub usage: bool,
+
+    /// color palette: 'dark' (default), 'light', 'solarized', or 'colorblind'
+    #[argh(option)]
+    pub theme: Option<String>,
+
+    /// do not use colors; also enabled by setting the NO_COLOR environment variable
+    #[argh(switch)]
+    pub no_color: bool,
+
+    /// ring the terminal bell when watched jobs change state or nodes become unavailable
+    #[argh(switch)]
+    pub bell: bool,
+
+    /// comma-separated list of columns shown in the node list, e.g.
+    /// 'Node,State,CPUs,Memory'
+    #[argh(option)]
+    pub node_columns: Option<String>,
+
+    /// comma-separated list of columns shown in the job list, e.g.
+    /// 'JobID,User,State,Runtime,Name'
+    #[argh(option)]
+    pub job_columns: Option<String>,
+
+    /// highlight partitions in which more than this percentage of CPUs are blocked
+    /// due to jobs allocating more than the default amount of memory per CPU
+    #[argh(option)]
+    pub blocked_alert: Option<f64>,
+
+    /// do not show the responsiveness of the Slurm daemons
+    #[argh(switch)]
+    pub no_health: bool,
+
+    /// serve metrics for the Grafana JSON datasource plugin at this address (e.g.
+    /// '127.0.0.1:9800') instead of showing the user interface
+    #[argh(option)]
+    pub grafana: Option<String>,
+
+    /// serve a read-only web dashboard of partitions, nodes, and jobs at this address
+    /// (e.g. '0.0.0.0:8080') instead of showing the user interface
+    #[argh(option)]
+    pub serve: Option<String>,
+
+    /// print the node and job lists as plain text and exit, instead of showing the
+    /// user interface
+    #[argh(switch)]
+    pub once: bool,
+
+    /// print the state of the cluster in this format ('text' or 'json') and exit,
+    /// instead of showing the user interface
+    #[argh(option)]
+    pub output: Option<OutputFormat>,
+
+    /// allow cancelling and modifying jobs belonging to other users, and allow
+    /// draining and resuming nodes (requires --allow-actions)
+    #[argh(switch)]
+    pub admin: bool,
+
+    /// allow submitting, cancelling, and modifying jobs, running custom commands, and
+    /// draining and resuming nodes with --admin; slurmboard is read-only by default
+    #[argh(switch)]
+    pub allow_actions: bool,
+
+    /// disable all actions, even if enabled by --allow-actions or by the configuration
+    /// file
+    #[argh(switch)]
+    pub read_only: bool,
+
+    /// use the JSON output of sinfo and squeue, if supported, instead of the delimited
+    /// output; ignored if custom columns are configured
+    #[argh(switch)]
+    pub json: bool,
+
+    /// file to which details of selected jobs are exported; CSV if the extension is
+    /// '.csv', otherwise JSON
+    #[argh(option, default = "\"slurmboard_jobs.json\".into()")]
+    pub export: PathBuf,
+
+    /// overwrite the file specified by --export if it exists, instead of adding a
+    /// numeric suffix to the name of the new file (e.g. 'slurmboard_jobs-1.json')
+    #[argh(switch)]
+    pub overwrite_export: bool,
+
+    /// URL to which job IDs are linked in terminals supporting hyperlinks, with
+    /// '{{jobid}}' and '{{user}}' replaced by the job ID and owner of the job
+    #[argh(option)]
+    pub job_url: Option<String>,
+
+    /// URL to which node names are linked in terminals supporting hyperlinks, with
+    /// '{{node}}' and '{{partition}}' replaced by the node and partition names
+    #[argh(option)]
+    pub node_url: Option<String>,
+
+    /// terminal on which the user interface is drawn: 'stdout', 'stderr', or the path
+    /// of a terminal device; defaults to `/dev/tty` if available, otherwise stderr
+    #[argh(option)]
+    pub tty: Option<TerminalOutput>,
+
+    /// append log messages to this file, e.g. the Slurm commands run and how long they
+    /// took, problems parsing their output, and the actions performed
+    #[argh(option)]
+    pub log_file: Option<PathBuf>,
 
     /// print version information
     #[argh(switch, short = 'v')]
     pub version: bool,
 }
+
+/// Format in which the state of the cluster is printed by `--output`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The node and job lists as plain text, as printed by `--once`
+    Text,
+    /// Partitions, nodes, and jobs, including derived utilization
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "invalid output format {:?}; expected 'text' or 'json'",
+                s
+            )),
+        }
+    }
+}
+
+/// Terminal on which the user interface is drawn, as selected by `--tty`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalOutput {
+    Stdout,
+    Stderr,
+    /// A terminal device such as `/dev/tty` or `/dev/pts/3`
+    Device(PathBuf),
+}
+
+impl FromStr for TerminalOutput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdout" => Ok(TerminalOutput::Stdout),
+            "stderr" => Ok(TerminalOutput::Stderr),
+            "" => Err("expected 'stdout', 'stderr', or the path of a terminal".into()),
+            _ => Ok(TerminalOutput::Device(s.into())),
+        }
+    }
+}
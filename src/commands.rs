@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use color_eyre::{eyre::bail, Result};
+
+use crate::slurm::quote;
+
+/// Placeholders that may be used in commands, replaced by the values of the selected job,
+/// node, or partition
+pub const PLACEHOLDERS: [&str; 6] = ["jobid", "user", "account", "partition", "node", "nodelist"];
+
+/// A shell command defined in the `[commands]` section of the configuration file and
+/// bound to a function key, e.g. `F5 = "seff {jobid}"`
+#[derive(Clone, Debug)]
+pub struct UserCommand {
+    /// Number of the function key, e.g. 5 for `F5`
+    pub key: u8,
+    /// Command with placeholders such as `{jobid}` or `{node}`
+    pub template: String,
+}
+
+impl UserCommand {
+    /// Parses the commands of the configuration file, keyed by function key (e.g. "F5")
+    pub fn parse_all(commands: &BTreeMap<String, String>) -> Result<Vec<UserCommand>> {
+        let mut parsed = Vec::new();
+        for (key, template) in commands {
+            let number = key
+                .strip_prefix(['F', 'f'])
+                .and_then(|v| v.parse::<u8>().ok())
+                .filter(|v| (1..=12).contains(v));
+
+            match number {
+                Some(key) if !template.trim().is_empty() => parsed.push(UserCommand {
+                    key,
+                    template: template.clone(),
+                }),
+                Some(_) => bail!("no command specified for key {:?}", key),
+                None => bail!("invalid key {:?} for command; expected F1 to F12", key),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Returns the command with placeholders replaced by the given values. Text enclosed
+    /// in braces that is not a placeholder (e.g. in `awk '{print $1}'`) is kept as is
+    pub fn command(&self, values: &[(&str, String)]) -> Result<String> {
+        let mut command = String::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            command.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let name = rest[1..].split_once('}').map(|v| v.0);
+            match name.filter(|v| PLACEHOLDERS.contains(v)) {
+                Some(name) => {
+                    let Some((_, value)) = values.iter().find(|v| v.0 == name) else {
+                        bail!("{{{}}} is not available for the current selection", name);
+                    };
+
+                    command.push_str(&quote(value));
+                    rest = &rest[name.len() + 2..];
+                }
+                None => {
+                    command.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        command.push_str(rest);
+        Ok(command)
+    }
+}
@@ -0,0 +1,534 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use color_eyre::{
+    eyre::{bail, eyre, Context},
+    Result,
+};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+
+use crate::widgets::{palette, Palette, RowStyle};
+
+/// Name of the configuration file, relative to the user's configuration directory
+const CONFIG_FILE: &str = "slurmboard/config.toml";
+/// Directory containing system-wide defaults, e.g. installed by site admins. Settings
+/// are read from `config.toml` and from `conf.d/*.toml` in this directory
+const SYSTEM_CONFIG_DIR: &str = "/etc/slurmboard";
+
+/// A pane in the user interface
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Pane {
+    Nodes,
+    Jobs,
+}
+
+impl FromStr for Pane {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nodes" => Ok(Pane::Nodes),
+            "jobs" => Ok(Pane::Jobs),
+            _ => Err(format!("invalid pane {:?}; expected 'nodes' or 'jobs'", s)),
+        }
+    }
+}
+
+/// Arrangement of the node and job lists in the cluster tab
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListLayout {
+    /// The job list is shown below the node list
+    #[default]
+    Vertical,
+    /// The job list is shown to the right of the node list
+    Horizontal,
+}
+
+impl FromStr for ListLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertical" => Ok(ListLayout::Vertical),
+            "horizontal" => Ok(ListLayout::Horizontal),
+            _ => Err(format!(
+                "invalid layout {:?}; expected 'vertical' or 'horizontal'",
+                s
+            )),
+        }
+    }
+}
+
+/// What is copied to the clipboard when pressing `y`
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyMode {
+    /// The ID of the selected (or marked) jobs, or the name of the selected node
+    #[default]
+    Id,
+    /// The text of the selected row, with columns separated by tabs
+    Row,
+}
+
+/// Alignment of the values of a custom column
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    #[default]
+    Left,
+    Right,
+}
+
+/// A column showing a field of `squeue --Format` (job list) or `sinfo --Format` (node
+/// list) that is not otherwise shown, e.g. the comment of jobs or the licenses they use
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnConfig {
+    /// Name of the column, as shown in the header and as used in `job_columns` and
+    /// `node_columns`
+    pub name: String,
+    /// List in which the column is shown
+    pub list: Pane,
+    /// Name of the field, e.g. "Comment" or "Licenses"
+    pub field: String,
+    #[serde(default)]
+    pub align: Alignment,
+    /// Width of the column, truncating longer values; fit to the values by default
+    pub width: Option<u16>,
+    /// Sort jobs by the numeric value of the field, rather than alphabetically
+    #[serde(default)]
+    pub numeric: bool,
+}
+
+/// Initial state of the user interface
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewConfig {
+    /// Pane that has focus at startup; defaults to the node list
+    pub focus: Option<Pane>,
+    /// Partition initially selected in the node list
+    pub partition: Option<String>,
+    /// Hide unavailable nodes at startup
+    pub hide_unavailable: bool,
+    /// Show the billable TRES of jobs and partitions
+    pub billing: bool,
+    /// Show the time, node, and per-user CPU limits of partitions
+    pub limits: bool,
+    /// Show the features available on each node
+    pub features: bool,
+    /// Show the number of jobs on each node during recent updates
+    pub history: bool,
+    /// Show gauges of the resources allocated across the whole cluster above the tabs
+    pub gauges: bool,
+    /// Show a tab with the historical utilization of the cluster reported by `sreport`
+    pub usage: bool,
+    /// Highlight partitions in which more than this percentage of CPUs are blocked
+    pub blocked_alert: Option<f64>,
+    /// Organize nodes by the node groups defined in the configuration file, instead of
+    /// by partition
+    pub node_groups: bool,
+    /// Partitions that are not shown in the node list
+    pub hidden_partitions: Vec<String>,
+    /// Ring the terminal bell when watched jobs change state or nodes become unavailable
+    pub bell: bool,
+    /// Enable actions that (may) modify jobs or nodes, including custom commands; all
+    /// such actions are disabled by default, even with `--admin`
+    pub allow_actions: bool,
+    /// Columns shown in the node list, in order, instead of the default columns
+    pub node_columns: Option<Vec<String>>,
+    /// Columns shown in the job list, in order, instead of the default columns
+    pub job_columns: Option<Vec<String>>,
+    /// Percentage of the height of the cluster tab used by the node list, instead of
+    /// fitting the node list to its contents; saved when resized using the mouse
+    pub split: Option<u16>,
+    /// Show the node and job lists side by side, rather than stacked
+    pub layout: ListLayout,
+    /// Copy the IDs of jobs and names of nodes, or the full row, to the clipboard
+    pub copy: CopyMode,
+    /// Highlight running jobs whose runtime exceeds this duration, e.g. "24h"
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub age_warning: Option<Duration>,
+    /// Highlight running jobs whose runtime exceeds this duration using the color for
+    /// errors, e.g. "7d"
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub age_alert: Option<Duration>,
+    /// Highlight running jobs with less than this much time left before reaching their
+    /// time limit, e.g. "1h"
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub time_left_alert: Option<Duration>,
+}
+
+/// Locations of Slurm executables and overrides of the Slurm configuration
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlurmOptions {
+    pub sinfo: String,
+    pub squeue: String,
+    pub scontrol: String,
+    pub sacct: String,
+    pub sbatch: String,
+    pub scancel: String,
+    pub sdiag: String,
+    pub sreport: String,
+    pub sshare: String,
+    pub sacctmgr: String,
+    /// Overrides the value of DefMemPerCPU reported by `scontrol show config`; 0 to
+    /// disable
+    pub def_mem_per_cpu: Option<u64>,
+    /// Shell command reporting the utilization of the GPUs of a node, with `{node}`
+    /// replaced by the name of the node (see [`crate::slurm::GpuUsage::parse`]); run for
+    /// nodes with allocated GPUs on every update. Disabled by default
+    pub gpu_probe: Option<String>,
+}
+
+impl Default for SlurmOptions {
+    fn default() -> Self {
+        Self {
+            sinfo: "sinfo".into(),
+            squeue: "squeue".into(),
+            scontrol: "scontrol".into(),
+            sacct: "sacct".into(),
+            sbatch: "sbatch".into(),
+            scancel: "scancel".into(),
+            sdiag: "sdiag".into(),
+            sreport: "sreport".into(),
+            sshare: "sshare".into(),
+            sacctmgr: "sacctmgr".into(),
+            def_mem_per_cpu: None,
+            gpu_probe: None,
+        }
+    }
+}
+
+/// Colors and styles of the user interface. Colors may be specified by name (e.g.
+/// "dark gray"), as RGB values (e.g. "#303030"), or as indexed colors (e.g. "236")
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// Built-in palette (see [`crate::widgets::PALETTES`]); defaults to "dark"
+    pub preset: Option<String>,
+    /// Background color of every other row in the node and job lists; disabled by default
+    pub stripes: Option<Color>,
+    /// Background color of the selected row in the list that has focus
+    pub selection: Option<Color>,
+    /// Background color of the selected row in the list that does not have focus
+    pub inactive_selection: Option<Color>,
+    /// Draw the text of the selected row in bold
+    pub bold_selection: bool,
+    /// Overrides of individual colors of the palette, by name (see [`Palette::set`])
+    pub colors: HashMap<String, Color>,
+}
+
+impl ThemeConfig {
+    /// Returns the selected built-in palette, with any colors overridden
+    pub fn palette(&self) -> Result<Palette> {
+        let mut palette = Palette::named(self.preset.as_deref().unwrap_or("dark"))?;
+        for (name, color) in &self.colors {
+            palette.set(name, *color)?;
+        }
+
+        palette.selection = self.selection.unwrap_or(palette.selection);
+        palette.inactive_selection = self
+            .inactive_selection
+            .unwrap_or(palette.inactive_selection);
+
+        Ok(palette)
+    }
+}
+
+impl From<&ThemeConfig> for RowStyle {
+    /// Selection colors are taken from the palette used by all widgets, which includes
+    /// those set in the theme (see [`ThemeConfig::palette`]). Stripes are disabled
+    /// without colors
+    fn from(theme: &ThemeConfig) -> Self {
+        let palette = palette();
+        RowStyle {
+            stripes: theme.stripes.filter(|_| !palette.monochrome),
+            selection: palette.selection,
+            inactive_selection: palette.inactive_selection,
+            bold_selection: theme.bold_selection,
+        }
+    }
+}
+
+/// Limits on the amount of history kept in memory, so that long-running sessions
+/// (e.g. on wall-mounted displays) use a bounded amount of memory
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Maximum number of job state transitions kept in the job history
+    pub transitions: usize,
+    /// Maximum number of updates for which metrics served via `--grafana` are kept
+    pub samples: usize,
+    /// Discard history older than this, e.g. "12h" or "7d"; unlimited by default
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub max_age: Option<Duration>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            transitions: 10_000,
+            // One day at the default interval of 5 seconds
+            samples: 24 * 60 * 60 / 5,
+            max_age: None,
+        }
+    }
+}
+
+/// Thresholds used to flag nodes whose CPU load persistently deviates from the CPUs
+/// allocated to jobs (see [`crate::anomalies`])
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnomalyConfig {
+    /// Nodes are overloaded if their CPU load exceeds this multiple of the allocated
+    /// CPUs (plus a small margin); 0 to disable
+    pub overload: f64,
+    /// Nodes running jobs are underutilized if their CPU load is below this fraction of
+    /// the allocated CPUs; 0 to disable
+    pub idle: f64,
+    /// Duration for which a node must be overloaded before it is flagged, e.g. "5m"
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub overload_after: Option<Duration>,
+    /// Duration for which a node must be underutilized before it is flagged
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub idle_after: Option<Duration>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            overload: 1.5,
+            idle: 0.25,
+            overload_after: Some(Duration::from_secs(5 * 60)),
+            idle_after: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+/// A named group of nodes, specified using a hostlist expression and/or a regular
+/// expression matching node names
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupConfig {
+    pub name: String,
+    /// Hostlist expression, e.g. "node[01-16]"
+    pub nodes: Option<String>,
+    /// Regular expression matching node names, e.g. "^bigmem"
+    pub pattern: Option<String>,
+}
+
+/// Settings read from `/etc/slurmboard/config.toml`, `/etc/slurmboard/conf.d/*.toml`,
+/// and `~/.config/slurmboard/config.toml` (or the file specified using `--config`), in
+/// that order, with later files taking precedence. Command-line options take precedence
+/// over settings in configuration files
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub view: ViewConfig,
+    pub slurm: SlurmOptions,
+    pub theme: ThemeConfig,
+    pub retention: RetentionConfig,
+    pub anomalies: AnomalyConfig,
+    pub groups: Vec<GroupConfig>,
+    /// Additional columns of the node and job lists, showing site-specific fields
+    pub columns: Vec<ColumnConfig>,
+    /// Shell commands bound to function keys, by key (e.g. "F5"); see [`crate::commands`]
+    pub commands: BTreeMap<String, String>,
+    /// Location of the user's configuration file, to which changes to the view are
+    /// saved, if it could be determined
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Returns the location of the user's configuration file, if it could be determined
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(root) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+            Some(PathBuf::from(root).join(CONFIG_FILE))
+        } else {
+            std::env::var_os("HOME").map(|v| PathBuf::from(v).join(".config").join(CONFIG_FILE))
+        }
+    }
+
+    /// Returns the locations of configuration files, from lowest to highest precedence;
+    /// these files may not exist. The user's configuration file is replaced by `user`,
+    /// if specified
+    pub fn paths(user: Option<&Path>) -> Result<Vec<PathBuf>> {
+        let root = Path::new(SYSTEM_CONFIG_DIR);
+        let mut paths = vec![root.join("config.toml")];
+
+        let fragments = root.join("conf.d");
+        match fs::read_dir(&fragments) {
+            Ok(entries) => {
+                let mut entries = entries
+                    .map(|v| v.map(|v| v.path()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .wrap_err_with(|| format!("failed to read {:?}", fragments))?;
+                entries.retain(|v| v.extension().is_some_and(|v| v == "toml"));
+                entries.sort();
+                paths.extend(entries);
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(error).wrap_err_with(|| format!("failed to read {:?}", fragments));
+            }
+        }
+
+        paths.extend(user.map(PathBuf::from).or_else(Config::default_path));
+        Ok(paths)
+    }
+
+    /// Loads and merges the system-wide and user configuration files that exist. The
+    /// user's configuration file is replaced by `user`, which must exist, if specified
+    pub fn load(user: Option<&Path>) -> Result<Config> {
+        if let Some(path) = user.filter(|v| !v.is_file()) {
+            bail!("configuration file {:?} not found", path);
+        }
+
+        let mut merged = toml::Table::new();
+        for path in Config::paths(user)? {
+            if let Some(table) = read_table(&path)? {
+                merge_tables(&mut merged, table);
+            }
+        }
+
+        let mut config: Config = toml::Value::Table(merged)
+            .try_into()
+            .wrap_err("failed to parse configuration")?;
+        config.path = user.map(PathBuf::from).or_else(Config::default_path);
+
+        Ok(config)
+    }
+}
+
+/// Stores a list of columns (`node_columns` or `job_columns`) in the `[view]` section of
+/// the user's configuration file. Returns the location of the configuration file
+pub fn save_columns(path: Option<&Path>, key: &str, columns: &[String]) -> Result<PathBuf> {
+    save_view_option(
+        path,
+        key,
+        toml_edit::value(columns.iter().collect::<toml_edit::Array>()),
+    )
+}
+
+/// Stores a value in the `[view]` section of the user's configuration file (`path`; see
+/// [`Config::path`]), keeping the formatting and comments of the file. Returns the
+/// location of the configuration file
+pub fn save_view_option(path: Option<&Path>, key: &str, value: toml_edit::Item) -> Result<PathBuf> {
+    let path = path
+        .map(PathBuf::from)
+        .ok_or_else(|| eyre!("could not determine location of configuration file"))?;
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+        Err(error) => {
+            return Err(error).wrap_err_with(|| format!("failed to read {:?}", path));
+        }
+    };
+
+    let mut document = text
+        .parse::<toml_edit::DocumentMut>()
+        .wrap_err_with(|| format!("failed to parse {:?}", path))?;
+    let view = document
+        .entry("view")
+        .or_insert_with(toml_edit::table)
+        .as_table_mut()
+        .ok_or_else(|| eyre!("[view] in {:?} is not a table", path))?;
+    view[key] = value;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).wrap_err_with(|| format!("failed to create {:?}", parent))?;
+    }
+
+    fs::write(&path, document.to_string())
+        .wrap_err_with(|| format!("failed to write {:?}", path))?;
+
+    Ok(path)
+}
+
+/// Reads a configuration file, returning `None` if it does not exist. Each file is
+/// validated on its own, so that errors can be attributed to the offending file
+fn read_table(path: &Path) -> Result<Option<toml::Table>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(error).wrap_err_with(|| format!("failed to read {:?}", path));
+        }
+    };
+
+    let table: toml::Table =
+        toml::from_str(&text).wrap_err_with(|| format!("failed to parse {:?}", path))?;
+    toml::Value::Table(table.clone())
+        .try_into::<Config>()
+        .wrap_err_with(|| format!("failed to parse {:?}", path))?;
+
+    Ok(Some(table))
+}
+
+/// Merges `other` into `table`; nested tables are merged, while other values (including
+/// arrays such as `groups`) in `other` replace those in `table`
+fn merge_tables(table: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(other)) => {
+                merge_tables(table, other)
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Parses a duration such as "90s", "30m", "12h", or "7d"
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let number: u64 = number.parse().map_err(|_| {
+        format!(
+            "invalid duration {:?}; expected e.g. \"12h\" or \"7d\"",
+            value
+        )
+    })?;
+
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in {:?}; expected s, m, h, or d",
+                value
+            ))
+        }
+    };
+
+    Ok(Duration::from_secs(number * seconds))
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_duration(&value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
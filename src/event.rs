@@ -1,9 +1,14 @@
-use std::sync::mpsc;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use color_eyre::Result;
-use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
+use color_eyre::{eyre::eyre, Result};
+use crossterm::event::{Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent};
+use futures_util::StreamExt;
+use tokio::sync::{mpsc, watch};
+use tokio::task;
+use tokio::time::{self, Instant, MissedTickBehavior};
+
+/// Time without further resizes before a resize of the terminal is reported
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(100);
 
 /// Terminal events.
 #[derive(Clone, Copy, Debug)]
@@ -16,71 +21,120 @@ pub enum Event {
     Mouse(MouseEvent),
     /// Terminal resize.
     Resize(u16, u16),
+    /// Update of the Slurm state finished in the background.
+    Updated,
 }
 
 /// Terminal event handler.
+///
+/// Terminal events and ticks are awaited concurrently by a task running on the async
+/// runtime, and forwarded to the main loop via a channel. Other components may send
+/// events to the main loop using the [`EventSender`] returned by [`EventHandler::sender`].
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct EventHandler {
     /// Event sender channel.
-    sender: mpsc::Sender<Event>,
+    sender: EventSender,
+    /// Current tick rate of the handler task.
+    tick_rate: watch::Sender<Duration>,
     /// Event receiver channel.
-    receiver: mpsc::Receiver<Event>,
-    /// Event handler thread.
-    handler: thread::JoinHandle<()>,
+    receiver: mpsc::UnboundedReceiver<Event>,
+    /// Event handler task.
+    handler: task::JoinHandle<()>,
 }
 
+/// Sends events to the main loop
+pub type EventSender = mpsc::UnboundedSender<Event>;
+
 impl EventHandler {
-    /// Constructs a new instance of [`EventHandler`].
+    /// Constructs a new instance of [`EventHandler`]; must be called from within the
+    /// async runtime.
     pub fn new(tick_rate: u64) -> Self {
-        let tick_rate = Duration::from_millis(tick_rate);
-        let (sender, receiver) = mpsc::channel();
+        let (tick_rate, mut tick_rates) = watch::channel(Duration::from_millis(tick_rate));
+        let (sender, receiver) = mpsc::unbounded_channel();
         let handler = {
             let sender = sender.clone();
-            thread::spawn(move || {
-                let mut last_tick = Instant::now();
-                loop {
-                    let timeout = tick_rate
-                        .checked_sub(last_tick.elapsed())
-                        .unwrap_or(tick_rate);
+            tokio::spawn(async move {
+                let mut reader = EventStream::new();
+                let mut tick = time::interval(*tick_rates.borrow_and_update());
+                // Ticks are skipped rather than bursted if the main loop is blocked
+                tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                // Resizes are only reported once the terminal has not been resized for a
+                // while, as dragging the border of a window produces a stream of events
+                let mut resize = None;
+                let debounce = time::sleep(Duration::ZERO);
+                tokio::pin!(debounce);
 
-                    if event::poll(timeout).expect("failed to poll new events") {
-                        match event::read().expect("unable to read event") {
-                            CrosstermEvent::Key(e) => {
-                                if e.kind == KeyEventKind::Press {
-                                    sender.send(Event::Key(e))
-                                } else {
-                                    Ok(())
-                                }
-                            }
-                            CrosstermEvent::Mouse(e) => sender.send(Event::Mouse(e)),
-                            CrosstermEvent::Resize(w, h) => sender.send(Event::Resize(w, h)),
-                            CrosstermEvent::FocusGained => Ok(()),
-                            CrosstermEvent::FocusLost => Ok(()),
-                            CrosstermEvent::Paste(_) => unimplemented!(),
+                loop {
+                    let event = tokio::select! {
+                        _ = sender.closed() => break,
+                        _ = tick.tick() => Event::Tick,
+                        Ok(()) = tick_rates.changed() => {
+                            let rate = *tick_rates.borrow_and_update();
+                            tick = time::interval_at(Instant::now() + rate, rate);
+                            tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                            continue;
                         }
-                        .expect("failed to send terminal event")
-                    }
+                        _ = &mut debounce, if resize.is_some() => match resize.take() {
+                            Some((w, h)) => Event::Resize(w, h),
+                            None => continue,
+                        },
+                        event = reader.next() => match event {
+                            Some(Ok(CrosstermEvent::Key(e))) if e.kind == KeyEventKind::Press => {
+                                Event::Key(e)
+                            }
+                            Some(Ok(CrosstermEvent::Mouse(e))) => Event::Mouse(e),
+                            Some(Ok(CrosstermEvent::Resize(w, h))) => {
+                                resize = Some((w, h));
+                                debounce.as_mut().reset(Instant::now() + RESIZE_DEBOUNCE);
+                                continue;
+                            }
+                            Some(Ok(_)) => continue,
+                            Some(Err(error)) => panic!("unable to read event: {}", error),
+                            None => break,
+                        },
+                    };
 
-                    if last_tick.elapsed() >= tick_rate {
-                        sender.send(Event::Tick).expect("failed to send tick event");
-                        last_tick = Instant::now();
+                    if sender.send(event).is_err() {
+                        break;
                     }
                 }
             })
         };
         Self {
             sender,
+            tick_rate,
             receiver,
             handler,
         }
     }
 
-    /// Receive the next event from the handler thread.
+    /// Changes the interval between ticks; the next tick happens one interval from now.
+    pub fn set_tick_rate(&self, rate: Duration) {
+        self.tick_rate.send_if_modified(|current| {
+            let modified = *current != rate;
+            *current = rate;
+            modified
+        });
+    }
+
+    /// Returns a sender that may be used to deliver events to the main loop.
+    pub fn sender(&self) -> EventSender {
+        self.sender.clone()
+    }
+
+    /// Returns the next event if one is already available, without waiting.
+    pub fn try_next(&mut self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Receive the next event from the handler task.
     ///
-    /// This function will always block the current thread if
-    /// there is no data available and it's possible for more data to be sent.
-    pub fn next(&self) -> Result<Event> {
-        Ok(self.receiver.recv()?)
+    /// This function waits until an event is available.
+    pub async fn next(&mut self) -> Result<Event> {
+        self.receiver
+            .recv()
+            .await
+            .ok_or_else(|| eyre!("event handler stopped unexpectedly"))
     }
 }
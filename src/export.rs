@@ -0,0 +1,221 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::Context, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    app::App,
+    slurm::{Job, JobDetails, Node, Time, Utilization},
+};
+
+/// Writes job details to a CSV file if the extension is `.csv`, and to a JSON file otherwise.
+/// Unless `overwrite` is true, existing files are kept and a numeric suffix is added to the
+/// name of the new file (e.g. `jobs-1.csv`). Returns the path of the file written
+pub fn export_jobs(path: &Path, jobs: &[JobDetails], overwrite: bool) -> Result<PathBuf> {
+    let (file, path) = if overwrite {
+        let file = File::create(path).wrap_err_with(|| format!("failed to create {:?}", path))?;
+        (file, path.to_owned())
+    } else {
+        create_new(path)?
+    };
+    let writer = BufWriter::new(file);
+
+    let is_csv = path
+        .extension()
+        .is_some_and(|v| v.eq_ignore_ascii_case("csv"));
+
+    if is_csv {
+        export_csv(writer, jobs)
+    } else {
+        export_json(writer, jobs)
+    }
+    .wrap_err_with(|| format!("failed to write {:?}", path))?;
+
+    Ok(path)
+}
+
+/// Creates a file that does not already exist, adding the first free numeric suffix to
+/// the name of the file if `path` exists
+fn create_new(path: &Path) -> Result<(File, PathBuf)> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|v| v.to_string_lossy());
+    for suffix in 0.. {
+        let candidate = match (suffix, &extension) {
+            (0, _) => path.to_owned(),
+            (_, Some(extension)) => {
+                path.with_file_name(format!("{}-{}.{}", stem, suffix, extension))
+            }
+            (_, None) => path.with_file_name(format!("{}-{}", stem, suffix)),
+        };
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(file) => return Ok((file, candidate)),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+            Err(error) => {
+                return Err(error).wrap_err_with(|| format!("failed to create {:?}", candidate))
+            }
+        }
+    }
+
+    unreachable!("no free file name for {:?}", path)
+}
+
+fn export_json<W: std::io::Write>(writer: W, jobs: &[JobDetails]) -> Result<()> {
+    let records = jobs
+        .iter()
+        .map(|job| {
+            job.fields
+                .iter()
+                .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+                .collect::<serde_json::Map<_, _>>()
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer_pretty(writer, &records)?;
+
+    Ok(())
+}
+
+fn export_csv<W: std::io::Write>(writer: W, jobs: &[JobDetails]) -> Result<()> {
+    // Different jobs may report different fields, so the header is the union of all keys
+    let mut header: Vec<&str> = Vec::new();
+    for job in jobs {
+        for (key, _) in &job.fields {
+            if !header.contains(&key.as_str()) {
+                header.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(&header)?;
+    for job in jobs {
+        writer.write_record(header.iter().map(|key| job.get(key).unwrap_or_default()))?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Serializes the partitions, nodes, and jobs of the cluster, including the utilization
+/// of CPUs, memory, and GPUs derived from these (see `--output json`)
+pub fn cluster_to_json(app: &App) -> Value {
+    let default_mem = |node: &Node| app.default_mem(&node.partition.label);
+
+    let partitions = app
+        .cluster
+        .iter()
+        .map(|partition| {
+            let nodes = &partition.nodes;
+            json!({
+                "name": partition.name.label,
+                "default": partition.name.default,
+                "nodes": nodes.iter().map(|v| &v.name).collect::<Vec<_>>(),
+                "jobs": partition.jobs.iter().map(|v| v.id).collect::<Vec<_>>(),
+                "cpus": utilization_to_json(
+                    nodes.iter().map(|v| v.cpu_utilization(default_mem(v))).sum()
+                ),
+                "memory": utilization_to_json(nodes.iter().map(|v| v.mem_utilization()).sum()),
+                "gpus": utilization_to_json(
+                    nodes.iter().map(|v| v.gpu_utilization(default_mem(v))).sum()
+                ),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Nodes and pending jobs may be listed in multiple partitions, but are only listed once
+    let mut seen = HashSet::new();
+    let nodes = app
+        .cluster
+        .iter()
+        .flat_map(|v| &v.nodes)
+        .filter(|v| seen.insert(v.name.as_str()))
+        .map(|node| {
+            json!({
+                "name": node.name,
+                "partitions": app
+                    .cluster
+                    .iter()
+                    .filter(|v| v.nodes.iter().any(|v| v.name == node.name))
+                    .map(|v| &v.name.label)
+                    .collect::<Vec<_>>(),
+                "state": node.state.to_string(),
+                "available": node.state.is_available(),
+                "cpu_load": node.cpu_load,
+                "memory_free": node.mem_free,
+                "features": node.features,
+                "jobs": node.jobs.iter().map(|v| v.id).collect::<Vec<_>>(),
+                "cpus": utilization_to_json(node.cpu_utilization(default_mem(node))),
+                "memory": utilization_to_json(node.mem_utilization()),
+                "gpus": utilization_to_json(node.gpu_utilization(default_mem(node))),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    let jobs = app
+        .cluster
+        .iter()
+        .flat_map(|v| &v.jobs)
+        .filter(|v| seen.insert(v.id))
+        .map(job_to_json)
+        .collect::<Vec<_>>();
+
+    json!({
+        "partitions": partitions,
+        "nodes": nodes,
+        "jobs": jobs,
+    })
+}
+
+fn job_to_json(job: &Job) -> Value {
+    let seconds = |v: &Time| v.as_duration().map(|v| v.as_secs());
+
+    json!({
+        "id": job.id,
+        "display_id": job.display_id(),
+        "array_job_id": job.array_job_id,
+        "array_task_id": job.array_task_id,
+        "name": job.name,
+        "user": job.user,
+        "account": job.account,
+        "qos": job.qos,
+        "partition": job.partition.label,
+        "partitions": job.partitions,
+        "state": job.state.to_string(),
+        "priority": job.priority,
+        "runtime": seconds(&job.time),
+        "time_limit": seconds(&job.time_limit),
+        "time_left": seconds(&job.time_left),
+        "nodes": job.nodes,
+        "nodelist": job.nodelist,
+        "tasks": job.tasks,
+        "cpus": job.cpus,
+        "memory": job.mem,
+        "gpus": job.gpus,
+        "billing": job.billing,
+    })
+}
+
+/// Memory is reported in MB
+fn utilization_to_json(utilization: Utilization) -> Value {
+    json!({
+        "utilized": utilization.utilized,
+        "allocated": utilization.allocated,
+        "blocked": utilization.blocked,
+        "cached": utilization.cached,
+        "unavailable": utilization.unavailable,
+        "available": utilization.available(),
+        "capacity": utilization.capacity,
+    })
+}
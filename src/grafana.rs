@@ -0,0 +1,199 @@
+use std::{
+    collections::VecDeque,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use color_eyre::{eyre::Context, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    app::App,
+    history::Retention,
+    server::{serve, Request, Response},
+    slurm::{JobState, Node, Partition},
+};
+
+/// Metrics reported for each partition
+const METRICS: [(&str, &str); 11] = [
+    ("cpus_allocated", "Allocated CPUs"),
+    ("cpus_idle", "Idle CPUs"),
+    ("cpus_total", "Total CPUs"),
+    ("memory_allocated", "Allocated memory (MB)"),
+    ("memory_total", "Total memory (MB)"),
+    ("gpus_allocated", "Allocated GPUs"),
+    ("gpus_total", "Total GPUs"),
+    ("nodes_available", "Available nodes"),
+    ("nodes_total", "Total nodes"),
+    ("jobs_running", "Running jobs"),
+    ("jobs_pending", "Pending jobs"),
+];
+
+/// Values of [`METRICS`] for each partition at a point in time
+#[derive(Clone, Debug)]
+struct Sample {
+    time: DateTime<Utc>,
+    partitions: Vec<(String, [f64; METRICS.len()])>,
+}
+
+impl Sample {
+    fn new(cluster: &[Partition]) -> Sample {
+        let partitions = cluster
+            .iter()
+            .map(|partition| {
+                let nodes = &partition.nodes;
+                let sum = |f: fn(&Node) -> usize| nodes.iter().map(f).sum::<usize>();
+                let jobs =
+                    |state: JobState| partition.jobs.iter().filter(|v| v.state == state).count();
+
+                let values = [
+                    sum(|v| v.cpu_state.allocated),
+                    sum(|v| v.cpu_state.idle),
+                    sum(|v| v.cpu_state.total),
+                    sum(|v| v.mem_alloc),
+                    sum(|v| v.mem),
+                    sum(|v| v.gpus_used),
+                    sum(|v| v.gpus),
+                    sum(|v| v.state.is_available() as usize),
+                    nodes.len(),
+                    jobs(JobState::Running),
+                    jobs(JobState::Pending),
+                ];
+
+                (partition.name.label.clone(), values.map(|v| v as f64))
+            })
+            .collect();
+
+        Sample {
+            time: Utc::now(),
+            partitions,
+        }
+    }
+}
+
+/// Serves metrics about partitions to the Grafana JSON datasource plugin
+/// (<https://grafana.com/grafana/plugins/simpod-json-datasource/>) instead of
+/// running the user interface. Metrics are collected every `--interval` seconds
+/// and kept in memory as specified in the `[retention]` section of the configuration
+pub fn run(app: &mut App, addr: &str) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).wrap_err_with(|| format!("failed to bind {:?}", addr))?;
+    eprintln!(
+        "Serving Grafana JSON datasource at http://{}",
+        listener.local_addr()?
+    );
+
+    let samples = Arc::new(Mutex::new(VecDeque::from([Sample::new(&app.cluster)])));
+    {
+        let samples = samples.clone();
+        thread::spawn(move || {
+            serve(listener, move |request| {
+                let samples = samples.lock().unwrap_or_else(|v| v.into_inner());
+                handle(request, &samples)
+            })
+        });
+    }
+
+    let retention = Retention {
+        max_entries: app.config.retention.samples,
+        max_age: app.config.retention.max_age,
+    };
+
+    let interval = app.args.interval.max(1);
+    while app.running {
+        thread::sleep(Duration::from_secs(interval));
+        if !app.update(interval)? {
+            continue;
+        }
+
+        // Metrics are not recorded while the Slurm state cannot be collected
+        if let Some(error) = &app.error {
+            eprintln!("Error while updating Slurm state: {}", error);
+            continue;
+        }
+
+        let sample = Sample::new(&app.cluster);
+        let now = sample.time.timestamp();
+
+        let mut samples = samples.lock().unwrap_or_else(|v| v.into_inner());
+        samples.push_back(sample);
+        retention.apply(&mut samples, now, |v| v.time.timestamp());
+    }
+
+    Ok(())
+}
+
+fn handle(request: &Request, samples: &VecDeque<Sample>) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        // Used by Grafana to test the connection to the datasource
+        ("GET", "/") => Response::text(200, "OK"),
+        ("POST", "/metrics") => Response::json(
+            &METRICS
+                .iter()
+                .map(|(value, label)| json!({"label": label, "value": value}))
+                .collect(),
+        ),
+        ("POST", "/metric-payload-options") => Response::json(&json!([])),
+        ("POST", "/query") => match serde_json::from_slice::<Value>(&request.body) {
+            Ok(query) => Response::json(&timeseries(&query, samples)),
+            Err(error) => Response::text(400, &format!("invalid query: {}", error)),
+        },
+        (_, "/" | "/metrics" | "/metric-payload-options" | "/query") => {
+            Response::text(405, "method not allowed")
+        }
+        _ => Response::text(404, "not found"),
+    }
+}
+
+/// Returns one time series per partition for each metric targeted by the query,
+/// limited to the time range of the query
+fn timeseries(query: &Value, samples: &VecDeque<Sample>) -> Value {
+    let time = |key: &str| {
+        query["range"][key]
+            .as_str()
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|v| v.with_timezone(&Utc))
+    };
+    let (from, to) = (time("from"), time("to"));
+    let samples = samples
+        .iter()
+        .filter(|v| from.is_none_or(|from| v.time >= from))
+        .filter(|v| to.is_none_or(|to| v.time <= to))
+        .collect::<Vec<_>>();
+
+    let mut series = Vec::new();
+    for target in query["targets"].as_array().into_iter().flatten() {
+        let target = target["target"].as_str().unwrap_or_default();
+        let Some(metric) = METRICS.iter().position(|(name, _)| *name == target) else {
+            continue;
+        };
+
+        // Partitions may come and go, so these are collected from all samples
+        let mut partitions = Vec::new();
+        for (name, _) in samples.iter().flat_map(|v| &v.partitions) {
+            if !partitions.contains(&name) {
+                partitions.push(name);
+            }
+        }
+
+        for partition in partitions {
+            let datapoints = samples
+                .iter()
+                .filter_map(|sample| {
+                    let (_, values) = sample.partitions.iter().find(|(v, _)| v == partition)?;
+                    Some(json!([values[metric], sample.time.timestamp_millis()]))
+                })
+                .collect::<Vec<_>>();
+
+            series.push(json!({
+                "target": format!("{} {}", partition, target),
+                "datapoints": datapoints,
+            }));
+        }
+    }
+
+    Value::Array(series)
+}
@@ -1,57 +1,289 @@
 use color_eyre::Result;
 
-use crate::{app::App, ui::UI};
+use crate::{
+    action::Action,
+    app::App,
+    ui::{Tab, UI},
+};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tracing::{debug, trace};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App, ui: &mut UI) -> Result<bool> {
-    let mut processed = true;
+    match key_action(key_event, ui) {
+        Some(action) => {
+            debug!(key = ?key_event.code, ?action, "key pressed");
+            action.dispatch(app, ui)
+        }
+        None => Ok(false),
+    }
+}
 
-    match key_event.code {
-        // Exit application on `ESC` or `q`
-        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-            app.quit();
+/// Handles the mouse events and updates the state of [`App`].
+pub fn handle_mouse_events(event: MouseEvent, app: &mut App, ui: &mut UI) -> Result<bool> {
+    let Some(action) = mouse_action(event) else {
+        return Ok(false);
+    };
+
+    // Scrolling and dragging produce many events, which are only traced
+    trace!(?action, "mouse event");
+
+    let redraw = action.clone().dispatch(app, ui)?;
+    // Clicking the same row twice in quick succession shows details, like `Enter`
+    if let Action::Click(column, row) = action {
+        if ui.double_click(column, row) {
+            return Ok(Action::ShowInfo.dispatch(app, ui)? || redraw);
         }
+    }
+
+    Ok(redraw)
+}
+
+/// Maps a key-press to an [`Action`], depending on what is currently displayed
+pub fn key_action(key_event: KeyEvent, ui: &UI) -> Option<Action> {
+    let action = match key_event.code {
+        // Close popups on `ESC` or `Enter`
+        KeyCode::Esc | KeyCode::Enter if ui.has_popup() => Action::ClosePopup,
+        // Actions awaiting confirmation are performed on `y` and discarded on `n`/`ESC`
+        _ if ui.has_confirmation() => return confirmation_key_action(key_event),
+        // Keys are used to enter text while a text input dialog is shown
+        _ if ui.has_input() => return input_key_action(key_event),
+        // Keys are used to navigate the output of a job while the log viewer is shown
+        _ if ui.has_output() => return output_key_action(key_event),
+        // Keys are used to pick a script while the list of scripts is shown
+        _ if ui.has_scripts() => return script_key_action(key_event),
+        // Keys are used to pick partitions while the list of partitions is shown
+        _ if ui.has_partitions() => return partition_key_action(key_event),
+        // Keys are used to pick a feature while the list of features is shown
+        _ if ui.has_features() => return feature_key_action(key_event),
+        // Keys are used to enter the job filter while it is being edited
+        _ if ui.is_filtering_jobs() => return filter_key_action(key_event),
+        // Remove the job filter on `ESC`, before exiting on a second `ESC`
+        KeyCode::Esc if ui.has_job_filter() => Action::ClearFilter,
+        // Exit application on `ESC` or `q`
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => Action::Quit,
         // Exit application on `Ctrl-C`
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
-            } else {
-                processed = false;
-            }
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            Action::Quit
         }
-        // Toggle show/hide unavailable nodes
-        KeyCode::Char('h') | KeyCode::Char('H') => {
-            ui.toggle_unavailable();
+        // Requeue the selected job using `scontrol`, after confirmation
+        KeyCode::Char('r') | KeyCode::Char('R') if key_event.modifiers == KeyModifiers::CONTROL => {
+            Action::RequeueJob
         }
-        // Force refresh of Slurm state
-        KeyCode::Char('r') | KeyCode::Char('R') => {
-            if app.update(1)? {
-                ui.update(app);
-            } else {
-                processed = false;
-            }
+        // Move the selected pending job ahead of the user's other pending jobs
+        KeyCode::Char('t') | KeyCode::Char('T') if key_event.modifiers == KeyModifiers::CONTROL => {
+            Action::TopJob
+        }
+        // Show the node and job lists stacked or side by side; `l` shows the job history
+        KeyCode::Char('l') | KeyCode::Char('L') if key_event.modifiers == KeyModifiers::CONTROL => {
+            Action::ToggleLayout
+        }
+        // Switch between tabs using the number keys
+        KeyCode::Char(c @ '1'..='9') => {
+            let idx = c.to_digit(10)? as usize - 1;
+            Action::SelectTab(ui.tabs().nth(idx)?)
         }
+        // Toggle show/hide unavailable nodes
+        KeyCode::Char('h') | KeyCode::Char('H') => Action::ToggleUnavailable,
+        // Toggle organizing nodes by partition or by node group
+        KeyCode::Char('g') | KeyCode::Char('G') => Action::ToggleNodeGroups,
+        // Force refresh of Slurm state
+        KeyCode::Char('r') | KeyCode::Char('R') => Action::Refresh,
+        // Suspend/resume automatic updates
+        KeyCode::Char('p') | KeyCode::Char('P') => Action::TogglePause,
+        // Filter jobs by job ID, user, account, QOS, or name
+        KeyCode::Char('/') => Action::StartFilter,
+        // Mark/unmark the selected job
+        KeyCode::Char(' ') => Action::ToggleMark,
+        // Watch the selected job for changes in state
+        KeyCode::Char('w') | KeyCode::Char('W') => Action::ToggleWatch,
+        // Export details of marked jobs (or the selected job) to a file
+        KeyCode::Char('e') | KeyCode::Char('E') => Action::Export,
+        // Cancel marked jobs (or the selected job) using `scancel`, after confirmation
+        KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Delete => Action::CancelJob,
+        // Hold ("freeze") or release the selected pending job using `scontrol`
+        KeyCode::Char('z') | KeyCode::Char('Z') => Action::HoldJob(true),
+        KeyCode::Char('u') | KeyCode::Char('U') => Action::HoldJob(false),
+        // Change the time limit of the selected job using `scontrol`
+        KeyCode::Char('t') | KeyCode::Char('T') => Action::EditTimeLimit,
+        // Drain the selected node (after entering a reason) or resume it using `scontrol`
+        KeyCode::Char('d') | KeyCode::Char('D') => Action::EditDrainReason,
+        KeyCode::Char('o') | KeyCode::Char('O') => Action::ResumeNode,
+        // Show the output of the selected job
+        KeyCode::Char('v') | KeyCode::Char('V') => Action::ShowOutput,
+        // Pick a batch script to submit using `sbatch`
+        KeyCode::Char('b') | KeyCode::Char('B') => Action::ShowScripts,
+        // Show state transitions of the user's jobs in the history tab
+        KeyCode::Char('l') | KeyCode::Char('L') => Action::ShowHistory,
+        // Show the TRES minutes consumed by the user and their accounts versus their limits
+        KeyCode::Char('m') | KeyCode::Char('M') => Action::ShowAssociationUsage,
+        // Show CPUs, memory, and GPUs as numbers rather than bars
+        KeyCode::Char('n') | KeyCode::Char('N') => Action::ToggleNumbers,
+        // Show CPUs, memory, and GPUs as percentages allocated rather than bars
+        KeyCode::Char('%') => Action::TogglePercentages,
+        // Explain the colors of utilization bars
+        KeyCode::Char('?') => Action::ShowLegend,
+        // List problems found in the Slurm state, e.g. jobs in unknown partitions
+        KeyCode::Char('!') => Action::ShowWarnings,
+        // List nodes that went down, drained, or stopped responding
+        KeyCode::Char('a') | KeyCode::Char('A') => Action::ShowNodeAlerts,
+        // List nodes whose CPU load greatly exceeds or falls short of their allocated CPUs
+        KeyCode::Char('x') | KeyCode::Char('X') => Action::ShowAnomalies,
+        // Run the user-defined command bound to a function key, if any
+        KeyCode::F(key) => Action::RunCommand(key),
+        // Copy ("yank") the selected job ID, node name, or row to the clipboard
+        KeyCode::Char('y') | KeyCode::Char('Y') => Action::Copy,
+        // Show details of the selected node or partition, or the layout of the selected job
+        KeyCode::Char('i') | KeyCode::Char('I') | KeyCode::Enter => Action::ShowInfo,
+        // Reverse the order of jobs while the job list has focus
+        KeyCode::Char('s') | KeyCode::Char('S') if ui.jobs_have_focus() => Action::ToggleSortOrder,
+        // Restrict the node list to one or more partitions
+        KeyCode::Char('s') | KeyCode::Char('S') => Action::ShowPartitions,
+        // Filter nodes by feature
+        KeyCode::Char('f') | KeyCode::Char('F') => Action::ShowFeatures,
+        // Move backward/forward through recorded snapshots
+        KeyCode::Char('[') => Action::Seek(-1),
+        KeyCode::Char(']') => Action::Seek(1),
+        KeyCode::Char('{') => Action::Seek(-10),
+        KeyCode::Char('}') => Action::Seek(10),
         // Scrolling
-        KeyCode::Up => ui.scroll(-1),
-        KeyCode::Down => ui.scroll(1),
-        KeyCode::PageUp => ui.scroll(-10),
-        KeyCode::PageDown => ui.scroll(10),
+        KeyCode::Up => Action::Scroll(-1),
+        KeyCode::Down => Action::Scroll(1),
+        KeyCode::PageUp => Action::Scroll(-10),
+        KeyCode::PageDown => Action::Scroll(10),
+        // Move the selected column (the sort column in the job list) left/right
+        KeyCode::Left if key_event.modifiers == KeyModifiers::SHIFT => Action::MoveColumn(-1),
+        KeyCode::Right if key_event.modifiers == KeyModifiers::SHIFT => Action::MoveColumn(1),
+        // Select the previous/next period of the report shown in the usage tab
+        KeyCode::Left if ui.tab() == Tab::Usage => Action::CycleReportPeriod(-1),
+        KeyCode::Right if ui.tab() == Tab::Usage => Action::CycleReportPeriod(1),
+        // Select the previous/next column; jobs are sorted by the selected column
+        KeyCode::Left => Action::SelectColumn(-1),
+        KeyCode::Right => Action::SelectColumn(1),
         // Switch focus between nodes / jobs
-        KeyCode::Tab | KeyCode::BackTab => ui.toggle_focus(),
-        _ => processed = false,
-    }
+        KeyCode::Tab | KeyCode::BackTab => Action::ToggleFocus,
+        _ => return None,
+    };
+
+    Some(action)
+}
 
-    Ok(processed)
+/// Maps key-presses to actions while the list of scripts is shown
+fn script_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => Action::CloseScripts,
+        KeyCode::Enter => Action::SubmitScript,
+        KeyCode::Up => Action::ScrollScripts(-1),
+        KeyCode::Down => Action::ScrollScripts(1),
+        KeyCode::PageUp => Action::ScrollScripts(-10),
+        KeyCode::PageDown => Action::ScrollScripts(10),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps key-presses to actions while the log viewer is shown
+fn output_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => Action::CloseOutput,
+        KeyCode::Tab | KeyCode::BackTab => Action::SwitchOutput,
+        KeyCode::Char('f') | KeyCode::Char('F') => Action::FollowOutput,
+        KeyCode::Char('/') => Action::EditOutputSearch,
+        KeyCode::Char('n') => Action::FindInOutput(true),
+        KeyCode::Char('N') | KeyCode::Char('p') | KeyCode::Char('P') => Action::FindInOutput(false),
+        KeyCode::Up => Action::ScrollOutput(-1),
+        KeyCode::Down => Action::ScrollOutput(1),
+        KeyCode::PageUp => Action::ScrollOutput(-10),
+        KeyCode::PageDown => Action::ScrollOutput(10),
+        KeyCode::Home => Action::ScrollOutput(isize::MIN),
+        KeyCode::End => Action::ScrollOutput(isize::MAX),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps key-presses to actions while the user is entering text
+fn input_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc => Action::CloseInput,
+        KeyCode::Enter => Action::SubmitInput,
+        KeyCode::Backspace => Action::EraseInput,
+        KeyCode::Char(c) if !c.is_control() => Action::TypeInput(c),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps key-presses to actions while the list of partitions is shown
+fn partition_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => Action::ClosePartitions,
+        KeyCode::Enter => Action::SelectPartitions,
+        KeyCode::Char(' ') => Action::TogglePartition,
+        KeyCode::Up => Action::ScrollPartitions(-1),
+        KeyCode::Down => Action::ScrollPartitions(1),
+        KeyCode::PageUp => Action::ScrollPartitions(-10),
+        KeyCode::PageDown => Action::ScrollPartitions(10),
+        _ => return None,
+    };
+
+    Some(action)
 }
 
-pub fn handle_mouse_events(event: MouseEvent, ui: &mut UI) -> Result<bool> {
+/// Maps key-presses to actions while the user is entering the job filter
+fn filter_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc => Action::ClearFilter,
+        KeyCode::Enter => Action::AcceptFilter,
+        KeyCode::Backspace => Action::EraseFilter,
+        KeyCode::Up => Action::Scroll(-1),
+        KeyCode::Down => Action::Scroll(1),
+        KeyCode::PageUp => Action::Scroll(-10),
+        KeyCode::PageDown => Action::Scroll(10),
+        KeyCode::Char(c) if !c.is_control() => Action::TypeFilter(c),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps key-presses to actions while asking the user to confirm an action
+fn confirmation_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => Action::Confirm,
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => Action::Decline,
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps key-presses to actions while the list of node features is shown
+fn feature_key_action(key_event: KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => Action::CloseFeatures,
+        KeyCode::Enter => Action::SelectFeature,
+        KeyCode::Up => Action::ScrollFeatures(-1),
+        KeyCode::Down => Action::ScrollFeatures(1),
+        KeyCode::PageUp => Action::ScrollFeatures(-10),
+        KeyCode::PageDown => Action::ScrollFeatures(10),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Maps a mouse event to an [`Action`]
+pub fn mouse_action(event: MouseEvent) -> Option<Action> {
     match event.kind {
-        MouseEventKind::Down(MouseButton::Left) => ui.mouse_click(event.row),
-        MouseEventKind::ScrollUp => ui.mouse_wheel(event.row, -1),
-        MouseEventKind::ScrollDown => ui.mouse_wheel(event.row, 1),
-        _ => return Ok(false),
+        MouseEventKind::Down(MouseButton::Left) => Some(Action::Click(event.column, event.row)),
+        MouseEventKind::Drag(MouseButton::Left) => Some(Action::Drag(event.row)),
+        MouseEventKind::Up(MouseButton::Left) => Some(Action::Release),
+        MouseEventKind::ScrollUp => Some(Action::ScrollAt(event.column, event.row, -1)),
+        MouseEventKind::ScrollDown => Some(Action::ScrollAt(event.column, event.row, 1)),
+        _ => None,
     }
-
-    Ok(true)
 }
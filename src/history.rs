@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use crate::slurm::{Node, Partition};
+
+/// Maximum number of updates for which the number of jobs on each node is kept
+pub const MAX_SAMPLES: usize = 16;
+/// Maximum number of updates for which the utilization of each partition is kept; five
+/// minutes at the default interval
+pub const MAX_PARTITION_SAMPLES: usize = 60;
+/// Maximum number of updates for which the CPU load and free memory of each node is kept
+pub const MAX_LOAD_SAMPLES: usize = 60;
+
+/// Limits on the number and age of entries kept in a history, so that long-running
+/// sessions use a bounded amount of memory
+#[derive(Clone, Copy, Debug)]
+pub struct Retention {
+    /// Maximum number of entries
+    pub max_entries: usize,
+    /// Maximum age of entries, if any
+    pub max_age: Option<Duration>,
+}
+
+impl Default for Retention {
+    /// No limits
+    fn default() -> Self {
+        Self {
+            max_entries: usize::MAX,
+            max_age: None,
+        }
+    }
+}
+
+impl Retention {
+    /// Removes the oldest entries until the limits are satisfied; entries must be
+    /// ordered by time, as returned by `timestamp` (in seconds)
+    pub fn apply<T, F>(&self, entries: &mut VecDeque<T>, now: i64, timestamp: F)
+    where
+        F: Fn(&T) -> i64,
+    {
+        while entries.len() > self.max_entries {
+            entries.pop_front();
+        }
+
+        if let Some(max_age) = self.max_age {
+            let cutoff = now.saturating_sub(max_age.as_secs() as i64);
+            while entries.front().is_some_and(|v| timestamp(v) < cutoff) {
+                entries.pop_front();
+            }
+        }
+    }
+}
+
+/// Number of jobs running on each node during the most recent updates, used to spot
+/// nodes on which jobs repeatedly start and fail
+#[derive(Clone, Debug, Default)]
+pub struct NodeHistory {
+    /// Number of jobs per node, oldest first
+    jobs: HashMap<String, VecDeque<usize>>,
+}
+
+impl NodeHistory {
+    /// Records the number of jobs on each node; nodes no longer reported are dropped
+    pub fn update(&mut self, cluster: &[Partition]) {
+        // Nodes may be listed in multiple partitions, each with the jobs of that partition
+        let mut jobs: HashMap<&str, HashSet<usize>> = HashMap::new();
+        for node in cluster.iter().flat_map(|v| &v.nodes) {
+            jobs.entry(&node.name)
+                .or_default()
+                .extend(node.jobs.iter().map(|v| v.id));
+        }
+
+        self.jobs.retain(|name, _| jobs.contains_key(name.as_str()));
+        for (name, ids) in jobs {
+            let samples = self.jobs.entry(name.to_string()).or_default();
+            if samples.len() >= MAX_SAMPLES {
+                samples.pop_front();
+            }
+
+            samples.push_back(ids.len());
+        }
+    }
+
+    /// Returns the approximate number of bytes used by this history
+    pub fn memory_usage(&self) -> usize {
+        self.jobs
+            .iter()
+            .map(|(name, samples)| name.capacity() + samples.capacity() * size_of::<usize>())
+            .sum()
+    }
+
+    /// Returns the number of jobs on a node during recent updates, oldest first
+    pub fn jobs(&self, node: &str) -> Option<&VecDeque<usize>> {
+        self.jobs.get(node)
+    }
+}
+
+/// Percentages of allocated CPUs and GPUs in a partition during recent updates, oldest
+/// first
+#[derive(Clone, Debug, Default)]
+pub struct PartitionSamples {
+    pub cpus: VecDeque<usize>,
+    /// Empty for partitions without GPUs
+    pub gpus: VecDeque<usize>,
+}
+
+/// Utilization of each partition during the most recent updates, used to show trends
+#[derive(Clone, Debug, Default)]
+pub struct PartitionHistory {
+    samples: HashMap<String, PartitionSamples>,
+}
+
+impl PartitionHistory {
+    /// Records the utilization of each partition; partitions no longer reported are
+    /// dropped
+    pub fn update(&mut self, cluster: &[Partition]) {
+        self.samples
+            .retain(|name, _| cluster.iter().any(|v| &v.name.label == name));
+
+        for partition in cluster {
+            let sum = |f: fn(&Node) -> usize| partition.nodes.iter().map(f).sum::<usize>();
+            let percentage = |used: usize, total: usize| (100 * used).checked_div(total);
+
+            let samples = self
+                .samples
+                .entry(partition.name.label.clone())
+                .or_default();
+            for (values, value) in [
+                (
+                    &mut samples.cpus,
+                    percentage(sum(|v| v.cpu_state.allocated), sum(|v| v.cpu_state.total)),
+                ),
+                (
+                    &mut samples.gpus,
+                    percentage(sum(|v| v.gpus_used), sum(|v| v.gpus)),
+                ),
+            ] {
+                if let Some(value) = value {
+                    if values.len() >= MAX_PARTITION_SAMPLES {
+                        values.pop_front();
+                    }
+
+                    values.push_back(value);
+                }
+            }
+        }
+    }
+
+    /// Returns the approximate number of bytes used by this history
+    pub fn memory_usage(&self) -> usize {
+        self.samples
+            .iter()
+            .map(|(name, samples)| {
+                name.capacity()
+                    + (samples.cpus.capacity() + samples.gpus.capacity()) * size_of::<usize>()
+            })
+            .sum()
+    }
+
+    /// Returns the utilization of a partition during recent updates
+    pub fn samples(&self, partition: &str) -> Option<&PartitionSamples> {
+        self.samples.get(partition)
+    }
+}
+
+/// CPU load and free memory (in MB) of a node in a single update, if reported by Slurm
+pub type LoadSample = Option<(f64, usize)>;
+
+/// CPU load and free memory of each node during the most recent updates, used to tell
+/// nodes that are persistently overloaded from nodes with short spikes in load
+#[derive(Clone, Debug, Default)]
+pub struct LoadHistory {
+    /// Samples per node, oldest first
+    samples: HashMap<String, VecDeque<LoadSample>>,
+}
+
+impl LoadHistory {
+    /// Records the load of each node; nodes no longer reported are dropped
+    pub fn update(&mut self, cluster: &[Partition]) {
+        let mut nodes: HashMap<&str, &Node> = HashMap::new();
+        for node in cluster.iter().flat_map(|v| &v.nodes) {
+            nodes.entry(&node.name).or_insert(node);
+        }
+
+        self.samples
+            .retain(|name, _| nodes.contains_key(name.as_str()));
+        for (name, node) in nodes {
+            let samples = self.samples.entry(name.to_string()).or_default();
+            if samples.len() >= MAX_LOAD_SAMPLES {
+                samples.pop_front();
+            }
+
+            samples.push_back(node.cpu_load.zip(node.mem_free));
+        }
+    }
+
+    /// Returns the approximate number of bytes used by this history
+    pub fn memory_usage(&self) -> usize {
+        self.samples
+            .iter()
+            .map(|(name, samples)| name.capacity() + samples.capacity() * size_of::<LoadSample>())
+            .sum()
+    }
+
+    /// Returns the load of a node during recent updates, oldest first
+    pub fn samples(&self, node: &str) -> Option<&VecDeque<LoadSample>> {
+        self.samples.get(node)
+    }
+}
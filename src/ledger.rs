@@ -0,0 +1,157 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    history::Retention,
+    slurm::{JobState, Partition},
+};
+
+/// A change in the state of a job, as observed between two updates
+#[derive(Clone, Debug)]
+pub struct Transition {
+    pub time: DateTime<Local>,
+    pub id: usize,
+    pub name: String,
+    /// Previous state, or `None` if the job was not seen before
+    pub from: Option<JobState>,
+    /// New state, or `None` if the job is no longer listed by `squeue` (and its final
+    /// state is not known)
+    pub to: Option<JobState>,
+}
+
+impl std::fmt::Display for Transition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = |v: &Option<JobState>, default| match v {
+            Some(state) => state.to_string(),
+            None => String::from(default),
+        };
+
+        write!(
+            f,
+            "{} {} ({}): {} → {}",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.id,
+            self.name,
+            state(&self.from, "New"),
+            state(&self.to, "Gone"),
+        )
+    }
+}
+
+/// Ledger of state transitions of a user's jobs during the current session
+#[derive(Debug, Default)]
+pub struct Ledger {
+    /// User whose jobs are tracked
+    user: String,
+    /// Most recently observed state and name of each job
+    jobs: HashMap<usize, (JobState, String)>,
+    /// Transitions in the order observed
+    transitions: VecDeque<Transition>,
+    /// Limits on the number and age of transitions kept
+    retention: Retention,
+}
+
+impl Ledger {
+    pub fn new(user: String, retention: Retention) -> Self {
+        Self {
+            user,
+            retention,
+            ..Default::default()
+        }
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Records transitions between the previous and the current state of the cluster
+    pub fn update(&mut self, cluster: &[Partition]) {
+        let time = Local::now();
+
+        // Jobs may be listed in multiple partitions, e.g. pending jobs
+        let mut jobs = HashMap::new();
+        for job in cluster.iter().flat_map(|v| &v.jobs) {
+            if *job.user == self.user {
+                jobs.insert(job.id, (job.state.clone(), job.name.clone()));
+            }
+        }
+
+        for (&id, (state, name)) in &jobs {
+            let from = self.jobs.get(&id).map(|(state, _)| state.clone());
+            if from.as_ref() != Some(state) {
+                self.transitions.push_back(Transition {
+                    time,
+                    id,
+                    name: name.clone(),
+                    from,
+                    to: Some(state.clone()),
+                });
+            }
+        }
+
+        for (&id, (state, name)) in &self.jobs {
+            if !jobs.contains_key(&id) {
+                self.transitions.push_back(Transition {
+                    time,
+                    id,
+                    name: name.clone(),
+                    from: Some(state.clone()),
+                    to: None,
+                });
+            }
+        }
+
+        // Transitions observed at the same time are ordered by job ID
+        let start = self
+            .transitions
+            .iter()
+            .rposition(|v| v.time != time)
+            .map_or(0, |v| v + 1);
+        self.transitions.make_contiguous()[start..].sort_by_key(|v| v.id);
+
+        self.retention
+            .apply(&mut self.transitions, time.timestamp(), |v| {
+                v.time.timestamp()
+            });
+        self.jobs = jobs;
+    }
+
+    /// Returns transitions in the order observed
+    pub fn transitions(&self) -> impl DoubleEndedIterator<Item = &Transition> {
+        self.transitions.iter()
+    }
+
+    /// Returns the number of transitions kept
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns true if no transitions have been observed (or kept)
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Returns the approximate number of bytes used by the ledger
+    pub fn memory_usage(&self) -> usize {
+        let transitions = self.transitions.capacity() * size_of::<Transition>()
+            + self
+                .transitions
+                .iter()
+                .map(|v| v.name.capacity())
+                .sum::<usize>();
+        let jobs = self.jobs.capacity() * size_of::<(usize, (JobState, String))>()
+            + self
+                .jobs
+                .values()
+                .map(|(_, name)| name.capacity())
+                .sum::<usize>();
+
+        transitions + jobs
+    }
+
+    /// Returns the limits on the number and age of transitions kept
+    pub fn retention(&self) -> &Retention {
+        &self.retention
+    }
+}
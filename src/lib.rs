@@ -1,16 +1,70 @@
+//! Terminal dashboard for Slurm clusters. The collection and parsing of Slurm state in
+//! [`slurm`] may be used on its own, by disabling the default `tui` feature.
+
+/// Actions triggered by the user
+#[cfg(feature = "tui")]
+pub mod action;
+/// Alerts about nodes that become unavailable
+#[cfg(feature = "tui")]
+pub mod alerts;
+/// Nodes whose CPU load deviates from the CPUs allocated to jobs
+#[cfg(feature = "tui")]
+pub mod anomalies;
 /// Application.
+#[cfg(feature = "tui")]
 pub mod app;
 /// Command-line arguments
+#[cfg(feature = "tui")]
 pub mod args;
+/// User-defined commands bound to function keys
+#[cfg(feature = "tui")]
+pub mod commands;
+/// Configuration file
+#[cfg(feature = "tui")]
+pub mod config;
 /// Terminal events handler
+#[cfg(feature = "tui")]
 pub mod event;
+/// Export of job details
+#[cfg(feature = "tui")]
+pub mod export;
+/// Metrics for the Grafana JSON datasource plugin
+#[cfg(feature = "tui")]
+pub mod grafana;
 /// Event handler.
+#[cfg(feature = "tui")]
 pub mod handler;
-/// Querying of Slurm state
+/// Per-session history of the number of jobs on each node
+#[cfg(feature = "tui")]
+pub mod history;
+/// Per-session history of job state transitions
+#[cfg(feature = "tui")]
+pub mod ledger;
+/// Logging to a file
+#[cfg(feature = "tui")]
+pub mod logging;
+/// Recording and replay of Slurm state
+#[cfg(feature = "tui")]
+pub mod replay;
+/// Minimal HTTP server
+#[cfg(feature = "tui")]
+pub mod server;
 pub mod slurm;
 /// Terminal user interface
+#[cfg(feature = "tui")]
 pub mod tui;
 /// Widget renderer
+#[cfg(feature = "tui")]
 pub mod ui;
+/// Jobs watched for changes in state
+#[cfg(feature = "tui")]
+pub mod watch;
+/// Read-only web dashboard
+#[cfg(feature = "tui")]
+pub mod web;
 /// Custom widgets
+#[cfg(feature = "tui")]
 pub mod widgets;
+/// Background updates of the Slurm state
+#[cfg(feature = "tui")]
+pub mod worker;
@@ -0,0 +1,23 @@
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+
+use color_eyre::{eyre::Context, Result};
+use tracing::Level;
+
+/// Appends log messages to a file (see `--log-file`), since the terminal is occupied by
+/// the user interface. Messages include the Slurm commands run and how long they took,
+/// problems parsing their output, and the actions performed by the user
+pub fn init(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("failed to open log file {:?}", path))?;
+
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_max_level(Level::DEBUG)
+        .with_target(true)
+        .init();
+
+    Ok(())
+}
@@ -1,55 +1,202 @@
 use color_eyre::Result;
-use ratatui::backend::CrosstermBackend;
+use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::Terminal;
-use std::io;
+use std::io::{self, ErrorKind, Write};
+use std::time::{Duration, Instant};
 
+use slurmboard::action::{report, Action};
 use slurmboard::app::App;
-use slurmboard::args::Args;
+use slurmboard::args::{Args, OutputFormat};
 use slurmboard::event::{Event, EventHandler};
+use slurmboard::export::cluster_to_json;
+use slurmboard::grafana;
 use slurmboard::handler::{handle_key_events, handle_mouse_events};
-use slurmboard::tui::Tui;
+use slurmboard::logging;
+use slurmboard::tui::{Output, Tui};
 use slurmboard::ui::UI;
+use slurmboard::web;
 
-fn main() -> Result<()> {
+/// Interval between ticks while the user is interacting with slurmboard or while the
+/// Slurm state is being updated, so that actions take effect and progress is shown promptly
+const ACTIVE_TICK: Duration = Duration::from_millis(50);
+/// Interval between ticks while idle, sufficient to keep the time since the last update
+/// current; ticks are also aligned to the next automatic update
+const IDLE_TICK: Duration = Duration::from_secs(1);
+/// Time since the last key press, mouse event, or resize after which slurmboard is idle
+const IDLE_AFTER: Duration = Duration::from_secs(5);
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<()> {
     let args: Args = argh::from_env();
+    Tui::<CrosstermBackend<Output>>::install_hooks(args.tty.clone())?;
+
     if args.version {
         println!("slurmboard v{}", env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
 
+    if let Some(path) = &args.log_file {
+        logging::init(path)?;
+        tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting slurmboard");
+    }
+
     let mut app = App::new(args)?;
+    if let Some(addr) = app.args.grafana.clone() {
+        return grafana::run(&mut app, &addr);
+    } else if let Some(addr) = app.args.serve.clone() {
+        return web::run(&mut app, &addr);
+    }
+
+    let output = app
+        .args
+        .output
+        .or(app.args.once.then_some(OutputFormat::Text));
+    if output == Some(OutputFormat::Json) {
+        let json = serde_json::to_string_pretty(&cluster_to_json(&app))?;
+        return print(&format!("{}\n", json));
+    }
+
     let mut ui = UI::new(&app);
+    if output == Some(OutputFormat::Text) {
+        return print(&ui.to_plain_text());
+    }
 
     // Initialize the terminal user interface
-    let backend = CrosstermBackend::new(io::stderr());
+    let backend = CrosstermBackend::new(Output::open(app.args.tty.as_ref())?);
     let terminal = Terminal::new(backend)?;
-    let events = EventHandler::new(50);
+    let events = EventHandler::new(ACTIVE_TICK.as_millis() as u64);
+    let sender = events.sender();
+    app.start_worker(move || {
+        let _ = sender.send(Event::Updated);
+    });
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
-    tui.draw(&mut ui)?;
+
+    // The terminal must be restored before errors are printed
+    let result = run(&mut app, &mut ui, &mut tui).await;
+    tui.exit()?;
+    result
+}
+
+async fn run<B: Backend + io::Write>(app: &mut App, ui: &mut UI, tui: &mut Tui<B>) -> Result<()> {
+    tui.draw(ui)?;
 
     // Main loop
+    let mut last_input = Instant::now();
     while app.running {
-        let redraw = match tui.events.next()? {
-            Event::Tick => {
-                if app.tick()? {
-                    ui.update(&app);
-                    true
-                } else {
-                    false
-                }
+        let mut event = tui.events.next().await?;
+        let mut redraw = false;
+        loop {
+            if !matches!(event, Event::Tick | Event::Updated) {
+                last_input = Instant::now();
+            }
+
+            redraw |= handle_event(event, app, ui, tui)?;
+
+            // Events that arrived in the meantime (e.g. keys being held) are handled before
+            // drawing, so that the interface is drawn once rather than after every event
+            match tui.events.try_next().filter(|_| app.running) {
+                Some(next) => event = next,
+                None => break,
             }
-            Event::Key(key_event) => handle_key_events(key_event, &mut app, &mut ui)?,
-            Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut ui)?,
-            Event::Resize(_, _) => true,
+        }
+
+        // Ticks are slowed down while idle, to reduce CPU usage on shared login nodes
+        let tick_rate = if app.updating() || last_input.elapsed() < IDLE_AFTER {
+            ACTIVE_TICK
+        } else {
+            app.until_update_due()
+                .map_or(IDLE_TICK, |v| v.min(IDLE_TICK))
+                .max(ACTIVE_TICK)
         };
+        tui.events.set_tick_rate(tick_rate);
 
         // FIXME: More fine-grained checks
         if redraw {
-            tui.draw(&mut ui)?;
+            tui.draw(ui)?;
+        }
+
+        if let Some(text) = ui.take_clipboard() {
+            tui.copy(&text)?;
         }
     }
 
-    tui.exit()?;
     Ok(())
 }
+
+/// Handles a single event; returns true if the interface needs to be redrawn
+fn handle_event<B: Backend + io::Write>(
+    event: Event,
+    app: &mut App,
+    ui: &mut UI,
+    tui: &mut Tui<B>,
+) -> Result<bool> {
+    let redraw = match event {
+        Event::Tick | Event::Updated => {
+            // Updates performed without a worker (i.e. when replaying) block the event
+            // loop, so the refresh indicator is drawn first
+            if app.update_due() && !app.updating() {
+                ui.set_refreshing();
+                tui.draw(ui)?;
+            }
+
+            let mut redraw = app.tick()?;
+            if redraw {
+                ui.update(app);
+            }
+
+            // Updates in progress in the background are indicated until finished
+            if app.updating() {
+                ui.set_refreshing();
+            }
+
+            // Outcomes of commands run on behalf of the user (e.g. cancelling jobs) are
+            // reported once finished
+            for outcome in app.take_outcomes() {
+                redraw |= report(outcome, app, ui)?;
+            }
+
+            // The output of jobs is re-read periodically while being followed
+            redraw |= Action::ReadOutput.dispatch(app, ui)?;
+
+            // Changes in state may also be observed when updating after user actions
+            let transitions = app.take_watch_events();
+            if !transitions.is_empty() {
+                ui.notify(&transitions);
+                if app.config.view.bell {
+                    tui.bell()?;
+                }
+
+                redraw = true;
+            }
+
+            // Nodes that became unavailable are reported without blocking input
+            let alerts = app.take_node_events();
+            if !alerts.is_empty() {
+                ui.alert(&alerts);
+                if app.config.view.bell {
+                    tui.bell()?;
+                }
+
+                redraw = true;
+            }
+
+            // The time since the last update is shown in the status bar
+            redraw | ui.alert_expired() | ui.update_label_changed()
+        }
+        Event::Key(key_event) => handle_key_events(key_event, app, ui)?,
+        Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, app, ui)?,
+        Event::Resize(_, _) => true,
+    };
+
+    Ok(redraw)
+}
+
+/// Writes text to stdout, ignoring errors due to the output being closed early, e.g.
+/// when piped to `head`
+fn print(text: &str) -> Result<()> {
+    match io::stdout().write_all(text.as_bytes()) {
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
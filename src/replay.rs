@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, NaiveDateTime};
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
+
+/// Format of the names of snapshot directories created by `--record`; milliseconds are
+/// included, since updates requested by the user may happen within the same second
+const SNAPSHOT_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+/// Format used to parse the names of snapshot directories, including those recorded by
+/// earlier versions without milliseconds
+const SNAPSHOT_PARSE_FORMAT: &str = "%Y%m%d-%H%M%S%.f";
+
+/// Creates a new, uniquely named directory for a snapshot recorded at the current time.
+/// A counter is appended to the name (e.g. "-1") if the directory already exists, so that
+/// existing snapshots are never overwritten
+pub fn create_snapshot(root: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(root).wrap_err_with(|| format!("failed to create {:?}", root))?;
+
+    let name = Local::now().format(SNAPSHOT_FORMAT).to_string();
+    for counter in 0.. {
+        let path = match counter {
+            0 => root.join(&name),
+            _ => root.join(format!("{}-{}", name, counter)),
+        };
+
+        match fs::create_dir(&path) {
+            Ok(()) => return Ok(path),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+            Err(error) => {
+                return Err(error).wrap_err_with(|| format!("failed to create {:?}", path))
+            }
+        }
+    }
+
+    unreachable!("no free snapshot name in {:?}", root)
+}
+
+/// Returns the time at which a snapshot was recorded, if named like a snapshot, along with
+/// the counter appended to names that already existed (see [`create_snapshot`])
+fn snapshot_time(path: &Path) -> Option<(NaiveDateTime, usize)> {
+    let name = path.file_name()?.to_str()?;
+    let (timestamp, counter) =
+        NaiveDateTime::parse_and_remainder(name, SNAPSHOT_PARSE_FORMAT).ok()?;
+    match counter {
+        "" => Some((timestamp, 0)),
+        counter => Some((timestamp, counter.strip_prefix('-')?.parse().ok()?)),
+    }
+}
+
+/// A sequence of snapshots previously recorded using `--record`
+#[derive(Debug)]
+pub struct Replay {
+    snapshots: Vec<PathBuf>,
+    index: usize,
+}
+
+impl Replay {
+    /// Lists snapshots in the root directory, ordered by the time they were recorded
+    pub fn open(root: &Path) -> Result<Self> {
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(root).wrap_err_with(|| format!("failed to read {:?}", root))? {
+            // Other directories (e.g. created by the user) are not snapshots
+            let path = entry?.path();
+            if let Some(time) = snapshot_time(&path).filter(|_| path.is_dir()) {
+                snapshots.push((time, path));
+            }
+        }
+
+        if snapshots.is_empty() {
+            bail!("no snapshots found in {:?}", root);
+        }
+
+        // Names are not compared as strings, since counters are not padded (e.g. "-10")
+        snapshots.sort();
+        let snapshots = snapshots.into_iter().map(|(_, path)| path).collect();
+
+        Ok(Self {
+            snapshots,
+            index: 0,
+        })
+    }
+
+    /// Returns the directory containing the current snapshot
+    pub fn current(&self) -> &Path {
+        &self.snapshots[self.index]
+    }
+
+    /// Moves `delta` snapshots forward/backward; returns true if the snapshot changed
+    pub fn seek(&mut self, delta: isize) -> bool {
+        let index = self
+            .index
+            .saturating_add_signed(delta)
+            .min(self.snapshots.len() - 1);
+
+        let changed = index != self.index;
+        self.index = index;
+        changed
+    }
+
+    /// Describes the current snapshot, e.g. "3/120: 2024-05-01 03:00:00"
+    pub fn label(&self) -> String {
+        // Counters appended to names that already existed are not shown
+        let timestamp = match snapshot_time(self.current()) {
+            Some((timestamp, _)) => timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => self.current().to_string_lossy().to_string(),
+        };
+
+        format!("{}/{}: {}", self.index + 1, self.snapshots.len(), timestamp)
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use color_eyre::{
+    eyre::{eyre, Context},
+    Result,
+};
+
+/// Maximum size of request bodies; larger requests are rejected
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Maximum length of the request line and of each header; longer requests are rejected
+const MAX_LINE_LENGTH: usize = 8192;
+/// Maximum number of headers; requests with more headers are rejected
+const MAX_HEADERS: usize = 100;
+/// Maximum number of connections handled concurrently; further connections are closed
+/// without a response
+const MAX_CONNECTIONS: usize = 64;
+/// Clients that do not send or receive data within this time are disconnected
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A minimal HTTP/1.1 request; only the method, path, and body are retained
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path without any query string
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// A response to a [`Request`]; connections are closed after each response
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(value: &serde_json::Value) -> Response {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    pub fn html(html: &str) -> Response {
+        Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: html.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn text(status: u16, text: &str) -> Response {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: text.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Serves requests to a listener indefinitely. Each connection is handled on its own
+/// thread, so that slow clients do not delay others
+pub fn serve<F>(listener: TcpListener, handler: F)
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming().flatten() {
+        if connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::Relaxed);
+            continue;
+        }
+
+        let (handler, connections) = (handler.clone(), connections.clone());
+        thread::spawn(move || {
+            // Failures affect only individual clients and are therefore ignored
+            let _ = handle_connection(stream, handler.as_ref());
+            connections.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+fn handle_connection<F>(mut stream: TcpStream, handler: &F) -> Result<()>
+where
+    F: Fn(&Request) -> Response,
+{
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let response = match read_request(&stream) {
+        Ok(request) => handler(&request),
+        Err(error) => Response::text(400, &format!("{:#}", error)),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let line = read_line(&mut reader).wrap_err("reading request")?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => return Err(eyre!("malformed request line {:?}", line.trim())),
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut content_length = 0;
+    for count in 0.. {
+        let header = read_line(&mut reader).wrap_err("reading headers")?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        } else if count == MAX_HEADERS {
+            return Err(eyre!("too many headers"));
+        }
+
+        if let Some((key, value)) = header.split_once(':') {
+            if key.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().wrap_err("invalid Content-Length")?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(eyre!("request body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).wrap_err("reading body")?;
+
+    Ok(Request { method, path, body })
+}
+
+/// Reads a line of at most [`MAX_LINE_LENGTH`] bytes, including the line break
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_line(&mut line)?;
+    if line.len() > MAX_LINE_LENGTH {
+        return Err(eyre!("line exceeds {} bytes", MAX_LINE_LENGTH));
+    }
+
+    Ok(line)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "",
+    }
+}
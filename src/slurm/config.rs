@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use color_eyre::Result;
+use tracing::debug;
+
+use super::{details::parse_key_values, misc::Table, shares::parse_tres, source::Source};
+
+/// Default amount of memory (in MB) allocated to jobs not explicitly requesting memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DefaultMem {
+    /// No default has been configured (or blocking is disabled)
+    #[default]
+    None,
+    /// Memory allocated per CPU (DefMemPerCPU)
+    PerCPU(u64),
+    /// Memory allocated per GPU (DefMemPerGPU); only applies to jobs requesting GPUs
+    PerGPU(u64),
+    /// Memory allocated per node (DefMemPerNode); 0 indicates all memory of the node
+    PerNode(u64),
+}
+
+impl DefaultMem {
+    /// Parses a `DefMemPer*` key/value pair, returning `None` for unrelated keys.
+    /// Slurm reports `DefMemPerNode = UNLIMITED` if no default has been configured
+    fn parse(key: &str, value: &str) -> Option<DefaultMem> {
+        let value = value.parse().ok();
+
+        match key {
+            "DefMemPerCPU" => value.map(DefaultMem::PerCPU),
+            "DefMemPerGPU" => value.map(DefaultMem::PerGPU),
+            "DefMemPerNode" => Some(value.map_or(DefaultMem::None, DefaultMem::PerNode)),
+            _ => None,
+        }
+    }
+}
+
+/// Weights used to calculate the billable TRES of jobs (TRESBillingWeights)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BillingWeights {
+    /// Weight per CPU
+    pub cpu: f64,
+    /// Weight per MB of memory
+    pub mem: f64,
+    /// Weight per GPU
+    pub gpu: f64,
+    /// Weight per node
+    pub node: f64,
+}
+
+impl BillingWeights {
+    /// Parses weights in the form "CPU=1.0,Mem=0.25G,GRES/gpu=2.0". The weight of
+    /// memory is per MB, unless a unit is specified (e.g. `G` for weight per GB)
+    fn parse(value: &str) -> BillingWeights {
+        let mut weights = BillingWeights::default();
+        for (key, value) in value.split(',').filter_map(|v| v.split_once('=')) {
+            let (value, divisor) = match value.char_indices().last() {
+                Some((idx, 'K')) => (&value[..idx], 1.0 / 1024.0),
+                Some((idx, 'M')) => (&value[..idx], 1.0),
+                Some((idx, 'G')) => (&value[..idx], 1024.0),
+                Some((idx, 'T')) => (&value[..idx], 1048576.0),
+                _ => (value, 1.0),
+            };
+
+            if let Ok(value) = value.parse::<f64>() {
+                match key.to_ascii_lowercase().as_str() {
+                    "cpu" => weights.cpu = value,
+                    "mem" => weights.mem = value / divisor,
+                    "gres/gpu" => weights.gpu = value,
+                    "node" => weights.node = value,
+                    _ => {}
+                }
+            }
+        }
+
+        weights
+    }
+
+    /// Returns the billable TRES for the given resources (summed, as in Slurm's default)
+    pub fn billing(&self, cpus: usize, mem: usize, gpus: usize, nodes: usize) -> f64 {
+        self.cpu * cpus as f64
+            + self.mem * mem as f64
+            + self.gpu * gpus as f64
+            + self.node * nodes as f64
+    }
+}
+
+/// Cluster-wide configuration as reported by `scontrol show config`
+#[derive(Clone, Debug, Default)]
+pub struct SlurmConfig {
+    /// Configuration parameters in the order reported, e.g. ("DefMemPerCPU", "15948")
+    pub values: Vec<(String, String)>,
+}
+
+impl SlurmConfig {
+    /// Collects the configuration using `scontrol show config`
+    pub fn collect(source: &Source, scontrol: &str) -> Result<SlurmConfig> {
+        let stdout = source.run(scontrol, &["show", "config"])?;
+
+        Ok(SlurmConfig::parse(&String::from_utf8_lossy(&stdout)))
+    }
+
+    /// Parses lines in the form "DefMemPerCPU            = 15948"
+    fn parse(text: &str) -> SlurmConfig {
+        let values = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace))
+            .collect();
+
+        SlurmConfig { values }
+    }
+
+    /// Returns the value of a configuration parameter, if set
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the default amount of memory allocated to jobs
+    pub fn default_mem(&self) -> DefaultMem {
+        ["DefMemPerCPU", "DefMemPerGPU", "DefMemPerNode"]
+            .into_iter()
+            .filter_map(|key| DefaultMem::parse(key, self.get(key)?))
+            .find(|v| *v != DefaultMem::None)
+            .unwrap_or_default()
+    }
+}
+
+/// Limits on jobs submitted to a partition; `None` if unlimited or not reported
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionLimits {
+    /// Maximum run time of jobs, e.g. "7-00:00:00"
+    pub max_time: Option<String>,
+    /// Time limit of jobs not explicitly requesting one
+    pub default_time: Option<String>,
+    /// Minimum number of nodes per job
+    pub min_nodes: Option<usize>,
+    /// Maximum number of nodes per job
+    pub max_nodes: Option<usize>,
+    /// Maximum number of CPUs per node available to jobs
+    pub max_cpus_per_node: Option<usize>,
+    /// Maximum memory (in MB) per node
+    pub max_mem_per_node: Option<usize>,
+    /// Maximum memory (in MB) per CPU
+    pub max_mem_per_cpu: Option<usize>,
+    /// Quality of service associated with the partition
+    pub qos: Option<String>,
+    /// Maximum number of CPUs allocated to the jobs of a single user; this limit is
+    /// configured via the QOS of the partition (`MaxTRESPerUser`)
+    pub max_cpus_per_user: Option<usize>,
+}
+
+impl PartitionLimits {
+    /// Parses a limit from `scontrol show partition`, returning `false` for unrelated keys
+    fn parse(&mut self, key: &str, value: &str) -> bool {
+        // Limits that are not set are reported as e.g. "UNLIMITED", "NONE", or "N/A"
+        let text = match value {
+            "UNLIMITED" | "NONE" | "N/A" | "(null)" => None,
+            value => Some(value.to_string()),
+        };
+        let number = text.as_deref().and_then(|v| v.parse().ok());
+
+        match key {
+            "MaxTime" => self.max_time = text,
+            "DefaultTime" => self.default_time = text,
+            "MinNodes" => self.min_nodes = number,
+            "MaxNodes" => self.max_nodes = number,
+            "MaxCPUsPerNode" => self.max_cpus_per_node = number,
+            "MaxMemPerNode" => self.max_mem_per_node = number,
+            "MaxMemPerCPU" => self.max_mem_per_cpu = number,
+            "QoS" => self.qos = text,
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/// Partition-specific configuration as reported by `scontrol show partition`
+#[derive(Clone, Debug, Default)]
+pub struct PartitionConfig {
+    /// Name of the partition
+    pub name: String,
+    /// Default amount of memory allocated to jobs, if overridden for this partition
+    pub default_mem: Option<DefaultMem>,
+    /// Weights used to calculate the billable TRES of jobs, if configured
+    pub billing_weights: Option<BillingWeights>,
+    /// Limits on jobs submitted to this partition
+    pub limits: PartitionLimits,
+}
+
+impl PartitionConfig {
+    /// Collects the configuration of all partitions using `scontrol show partition`,
+    /// along with the per-user CPU limits of their QOS using `sacctmgr show qos`. The
+    /// latter are left unset if `sacctmgr` fails, e.g. if accounting is not enabled
+    pub fn collect(
+        source: &Source,
+        scontrol: &str,
+        sacctmgr: &str,
+    ) -> Result<Vec<PartitionConfig>> {
+        let stdout = source.run(scontrol, &["show", "partition", "--oneliner"])?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let mut partitions = stdout
+            .lines()
+            .filter_map(PartitionConfig::parse)
+            .collect::<Vec<_>>();
+
+        if partitions.iter().any(|v| v.limits.qos.is_some()) {
+            match qos_cpu_limits(source, sacctmgr) {
+                Ok(limits) => {
+                    for partition in &mut partitions {
+                        let qos = partition.limits.qos.as_ref();
+                        partition.limits.max_cpus_per_user =
+                            qos.and_then(|v| limits.get(v)).copied();
+                    }
+                }
+                Err(error) => debug!("failed to collect QOS limits: {:#}", error),
+            }
+        }
+
+        Ok(partitions)
+    }
+
+    /// Parses a line in the form "PartitionName=standard ... DefMemPerCPU=15948 ..."
+    fn parse(line: &str) -> Option<PartitionConfig> {
+        let mut config = PartitionConfig::default();
+        for (key, value) in parse_key_values(line) {
+            match key.as_str() {
+                "PartitionName" => config.name = value,
+                "TRESBillingWeights" => {
+                    config.billing_weights = Some(BillingWeights::parse(&value))
+                }
+                key if config.limits.parse(key, &value) => {}
+                // Partitions without overrides report `DefMemPerNode=UNLIMITED`
+                key => {
+                    if let Some(mem) = DefaultMem::parse(key, &value) {
+                        if mem != DefaultMem::None {
+                            config.default_mem = Some(mem);
+                        }
+                    }
+                }
+            }
+        }
+
+        (!config.name.is_empty()).then_some(config)
+    }
+}
+
+/// Returns the maximum number of CPUs per user (`MaxTRESPerUser`) of each QOS with
+/// such a limit, using `sacctmgr show qos`
+fn qos_cpu_limits(source: &Source, sacctmgr: &str) -> Result<HashMap<String, usize>> {
+    let stdout = source.run(
+        sacctmgr,
+        &["show", "qos", "--parsable2", "format=Name,MaxTRESPerUser"],
+    )?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    let table = Table::parse(&stdout)?;
+
+    // The column is abbreviated in the header of the output
+    Ok(table
+        .rows(["Name", "MaxTRESPU"])
+        .filter_map(|[name, limits]| {
+            let (_, cpus) = parse_tres(limits).into_iter().find(|v| v.0 == "cpu")?;
+            Some((name.to_string(), cpus as usize))
+        })
+        .collect())
+}
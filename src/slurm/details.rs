@@ -0,0 +1,124 @@
+use color_eyre::{eyre::eyre, Result};
+use serde::{de::IntoDeserializer, Deserialize};
+
+use super::{jobs::JobState, source::Source};
+
+/// Fields reported by `sacct` when exporting job details
+const SACCT_FIELDS: &str = "JobID,JobName,User,Account,Partition,State,ExitCode,DerivedExitCode,\
+Submit,Start,End,Elapsed,AllocTRES,NodeList";
+
+/// Detailed description of a job as key/value pairs, as reported by `scontrol`
+/// for active jobs and by `sacct` for both active and completed jobs
+#[derive(Clone, Debug, Default)]
+pub struct JobDetails {
+    /// ID of the job
+    pub id: usize,
+    /// Fields in the order reported; fields from `sacct` are prefixed with `sacct:`
+    pub fields: Vec<(String, String)>,
+}
+
+impl JobDetails {
+    /// Collects the details of a job using `scontrol`, supplemented by `sacct` if available
+    pub fn collect(source: &Source, scontrol: &str, sacct: &str, id: usize) -> Result<JobDetails> {
+        let mut details = JobDetails {
+            id,
+            fields: Vec::new(),
+        };
+
+        // Jobs are purged from slurmctld shortly after finishing, so failures are expected
+        let scontrol_result = source.run(scontrol, &["show", "job", &id.to_string(), "--oneliner"]);
+        if let Ok(stdout) = &scontrol_result {
+            details
+                .fields
+                .extend(parse_key_values(&String::from_utf8_lossy(stdout)));
+        }
+
+        let sacct_result = source.run(
+            sacct,
+            &[
+                "--jobs",
+                &id.to_string(),
+                "--allocations",
+                "--parsable2",
+                "--format",
+                SACCT_FIELDS,
+            ],
+        );
+        if let Ok(stdout) = &sacct_result {
+            let stdout = String::from_utf8_lossy(stdout);
+            let mut lines = stdout.lines();
+            if let (Some(header), Some(values)) = (lines.next(), lines.next()) {
+                for (key, value) in header.split('|').zip(values.split('|')) {
+                    details
+                        .fields
+                        .push((format!("sacct:{}", key), value.to_string()));
+                }
+            }
+        }
+
+        if details.fields.is_empty() {
+            return Err(match sacct_result {
+                Err(error) => error.wrap_err(format!("no information found for job {}", id)),
+                Ok(_) => eyre!("no information found for job {}", id),
+            });
+        }
+
+        Ok(details)
+    }
+
+    /// Returns the (first) value for the given key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Returns the state of a job as recorded by `sacct`, e.g. once it is no longer listed
+/// by `squeue` after finishing
+pub fn final_state(source: &Source, sacct: &str, id: usize) -> Result<JobState> {
+    let stdout = source.run(
+        sacct,
+        &[
+            "--jobs",
+            &id.to_string(),
+            "--allocations",
+            "--noheader",
+            "--parsable2",
+            "--format",
+            "State",
+        ],
+    )?;
+
+    // States may include additional information, e.g. "CANCELLED by 1000"
+    let stdout = String::from_utf8_lossy(&stdout);
+    let state = stdout
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| eyre!("no state reported for job {}", id))?;
+
+    JobState::deserialize(state.into_deserializer())
+        .map_err(|error: serde::de::value::Error| eyre!("invalid state {:?}: {}", state, error))
+}
+
+/// Parses `Key=Value` pairs as printed by `scontrol show`. Values may contain
+/// whitespace, in which case words without a `=` are appended to the last value
+pub fn parse_key_values(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for word in text.split_whitespace() {
+        match word.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains(['/', ':']) => {
+                fields.push((key.to_string(), value.to_string()))
+            }
+            _ => {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push(' ');
+                    value.push_str(word);
+                }
+            }
+        }
+    }
+
+    fields
+}
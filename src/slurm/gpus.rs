@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use color_eyre::{eyre::bail, Result};
+
+use super::{misc::quote, source::Source};
+
+/// Utilization and memory usage of a GPU, as reported by a user-defined probe such as
+/// `nvidia-smi` or a DCGM exporter; Slurm itself only reports the allocation of GPUs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuUsage {
+    /// Percentage of time during which kernels were executing on the GPU
+    pub utilization: f64,
+    /// Memory used, in MB
+    pub mem_used: usize,
+    /// Total memory, in MB
+    pub mem_total: usize,
+}
+
+impl GpuUsage {
+    /// Runs the probe `command` for a node, with `{node}` replaced by the (quoted) name
+    /// of the node, and parses its output using [`GpuUsage::parse`]. The command is run
+    /// by the shell, locally or on the host specified using `--ssh`
+    pub fn probe(source: &Source, command: &str, node: &str) -> Result<Vec<GpuUsage>> {
+        let output = source.run_shell(&command.replace("{node}", &quote(node)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("GPU probe failed with {}: {}", output.status, stderr.trim());
+        }
+
+        GpuUsage::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parses the usage of each GPU of a node, ordered by index, in one of two formats:
+    ///
+    /// - The output of `nvidia-smi --query-gpu=utilization.gpu,memory.used,memory.total
+    ///   --format=csv,noheader,nounits`, i.e. one line per GPU such as "45, 10240, 81920"
+    /// - Metrics scraped from a DCGM exporter, i.e. `DCGM_FI_DEV_GPU_UTIL`,
+    ///   `DCGM_FI_DEV_FB_USED`, and `DCGM_FI_DEV_FB_FREE` labelled by `gpu="<index>"`
+    pub fn parse(text: &str) -> Result<Vec<GpuUsage>> {
+        if text.contains("DCGM_FI_DEV_") {
+            return Ok(parse_dcgm(text));
+        }
+
+        let mut gpus = Vec::new();
+        for line in text.lines().map(str::trim).filter(|v| !v.is_empty()) {
+            match parse_csv(line) {
+                Some(gpu) => gpus.push(gpu),
+                None => bail!("unexpected output of GPU probe: {:?}", line),
+            }
+        }
+
+        Ok(gpus)
+    }
+}
+
+/// Parses a line of the CSV output of `nvidia-smi`
+fn parse_csv(line: &str) -> Option<GpuUsage> {
+    let mut values = line.split(',').map(str::trim);
+    let gpu = GpuUsage {
+        utilization: values.next()?.parse().ok()?,
+        mem_used: values.next()?.parse().ok()?,
+        mem_total: values.next()?.parse().ok()?,
+    };
+
+    values.next().is_none().then_some(gpu)
+}
+
+/// Parses metrics in the Prometheus text format produced by a DCGM exporter
+fn parse_dcgm(text: &str) -> Vec<GpuUsage> {
+    let mut gpus = BTreeMap::<usize, GpuUsage>::new();
+    for line in text.lines().filter(|v| v.starts_with("DCGM_FI_DEV_")) {
+        let Some((metric, value)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        let (name, labels) = metric.split_once('{').unwrap_or((metric, ""));
+        let index = labels
+            .split(',')
+            .find_map(|v| v.trim().strip_prefix("gpu=\""))
+            .and_then(|v| v.trim_end_matches(['"', '}']).parse().ok());
+        let (Some(index), Ok(value)) = (index, value.trim().parse::<f64>()) else {
+            continue;
+        };
+
+        let gpu = gpus.entry(index).or_default();
+        match name {
+            "DCGM_FI_DEV_GPU_UTIL" => gpu.utilization = value,
+            "DCGM_FI_DEV_FB_USED" => gpu.mem_used = value as usize,
+            // Total memory is the sum of used and free memory
+            "DCGM_FI_DEV_FB_FREE" => gpu.mem_total = value as usize,
+            _ => {}
+        }
+    }
+
+    gpus.into_values()
+        .map(|mut v| {
+            v.mem_total += v.mem_used;
+            v
+        })
+        .collect()
+}
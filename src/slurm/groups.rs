@@ -0,0 +1,102 @@
+use color_eyre::{eyre::Context, Result};
+use regex::Regex;
+
+use super::{misc::expand_hostlist, nodes::PartitionName, Node, Partition};
+
+/// A named group of nodes, e.g. a rack or a set of nodes with large amounts of memory
+#[derive(Clone, Debug)]
+pub struct NodeGroup {
+    /// Name of the group, shown in place of a partition name
+    pub name: String,
+    /// Nodes explicitly listed using a hostlist expression
+    hosts: Vec<String>,
+    /// Regular expression matching node names
+    pattern: Option<Regex>,
+}
+
+impl NodeGroup {
+    /// Creates a group from a hostlist expression (e.g. "node[01-16]") and/or a regular
+    /// expression (e.g. "^bigmem"); nodes matching either belong to the group
+    pub fn new(name: &str, hostlist: Option<&str>, pattern: Option<&str>) -> Result<NodeGroup> {
+        let pattern = match pattern {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .wrap_err_with(|| format!("invalid pattern for node group {:?}", name))?,
+            ),
+            None => None,
+        };
+
+        Ok(NodeGroup {
+            name: name.to_string(),
+            hosts: hostlist.map(expand_hostlist).unwrap_or_default(),
+            pattern,
+        })
+    }
+
+    /// Returns true if a node belongs to this group
+    pub fn contains(&self, node: &str) -> bool {
+        self.hosts.iter().any(|v| v == node)
+            || self.pattern.as_ref().is_some_and(|v| v.is_match(node))
+    }
+}
+
+/// Organizes nodes by group instead of by partition. Nodes may belong to multiple
+/// groups, while nodes not belonging to any group are placed in a trailing "other"
+/// group. Jobs are assigned to a group if they are running on any of its nodes
+pub fn group_nodes(partitions: &[Partition], groups: &[NodeGroup]) -> Vec<Partition> {
+    let mut result: Vec<Partition> = groups
+        .iter()
+        .map(|v| new_group(&v.name))
+        .chain([new_group("other")])
+        .collect();
+
+    for node in partitions.iter().flat_map(|v| &v.nodes) {
+        let mut matched = false;
+        for (group, partition) in groups.iter().zip(result.iter_mut()) {
+            if group.contains(&node.name) {
+                add_node(partition, node);
+                matched = true;
+            }
+        }
+
+        if !matched {
+            if let Some(partition) = result.last_mut() {
+                add_node(partition, node);
+            }
+        }
+    }
+
+    result.retain(|v| !v.nodes.is_empty());
+    for partition in &mut result {
+        partition.update_users();
+    }
+
+    result
+}
+
+fn new_group(name: &str) -> Partition {
+    Partition {
+        name: PartitionName {
+            label: name.to_string(),
+            default: false,
+        },
+        jobs: Vec::new(),
+        nodes: Vec::new(),
+        users: 0,
+    }
+}
+
+/// Adds a node and its jobs to a group; nodes in multiple partitions are only added once
+fn add_node(group: &mut Partition, node: &Node) {
+    if group.nodes.iter().any(|v| v.name == node.name) {
+        return;
+    }
+
+    for job in &node.jobs {
+        if !group.jobs.iter().any(|v| v.id == job.id) {
+            group.jobs.push(job.clone());
+        }
+    }
+
+    group.nodes.push(node.clone());
+}
@@ -0,0 +1,91 @@
+use color_eyre::Result;
+
+use super::source::Source;
+
+/// State of a single `slurmctld` instance as reported by `scontrol ping`
+#[derive(Clone, Debug)]
+pub struct Controller {
+    /// Role of the controller, e.g. "primary" or "backup"
+    pub role: String,
+    /// Host on which the controller is running
+    pub host: String,
+    /// Indicates if the controller responded to the ping
+    pub up: bool,
+}
+
+/// Summarizes the responsiveness of the Slurm daemons
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    /// Controllers reported by `scontrol ping`
+    pub controllers: Vec<Controller>,
+    /// Number of active slurmctld server threads, as reported by `sdiag`
+    pub server_threads: Option<usize>,
+    /// Number of RPCs queued by slurmctld for delivery to slurmd, as reported by `sdiag`
+    pub agent_queue: Option<usize>,
+    /// Number of messages queued for slurmdbd; grows if slurmdbd is unresponsive
+    pub dbd_agent_queue: Option<usize>,
+}
+
+impl Health {
+    /// Collects the state of the controllers using `scontrol ping`, along with statistics
+    /// reported by `sdiag`, if available
+    pub fn collect(source: &Source, scontrol: &str, sdiag: &str) -> Result<Health> {
+        // `scontrol ping` terminates with a non-zero exit code if a controller is down
+        let stdout = source.run_unchecked(scontrol, &["ping"])?;
+        let mut health = Health {
+            controllers: parse_ping(&String::from_utf8_lossy(&stdout)),
+            ..Default::default()
+        };
+
+        // Statistics are not critical and may be unavailable to regular users
+        if let Ok(stdout) = source.run(sdiag, &[] as &[&str]) {
+            health.update_from_sdiag(&String::from_utf8_lossy(&stdout));
+        }
+
+        Ok(health)
+    }
+
+    /// Returns true if all known controllers are up
+    pub fn is_up(&self) -> bool {
+        !self.controllers.is_empty() && self.controllers.iter().all(|v| v.up)
+    }
+
+    fn update_from_sdiag(&mut self, text: &str) {
+        for line in text.lines() {
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim().parse().ok();
+
+                match key.trim() {
+                    "Server thread count" => self.server_threads = value,
+                    "Agent queue size" => self.agent_queue = value,
+                    "DBD Agent queue size" => self.dbd_agent_queue = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Parses lines in the form "Slurmctld(primary) at ctl01 is UP"
+fn parse_ping(text: &str) -> Vec<Controller> {
+    let mut controllers = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(line) = line.strip_prefix("Slurmctld(") {
+            if let Some((role, line)) = line.split_once(')') {
+                let mut fields = line.split_whitespace();
+                if let (Some("at"), Some(host), Some("is"), Some(state)) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                {
+                    controllers.push(Controller {
+                        role: role.to_string(),
+                        host: host.to_string(),
+                        up: state == "UP",
+                    });
+                }
+            }
+        }
+    }
+
+    controllers
+}
@@ -1,13 +1,25 @@
-use std::{fmt, process::Command};
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
 
 use color_eyre::{
     eyre::{bail, Context},
     Result,
 };
+use csv::StringRecord;
 use serde::{de, Deserialize, Deserializer};
+use tracing::{debug, warn};
+
+use super::{
+    config::BillingWeights,
+    json::{is_json, parse_squeue},
+    misc::{custom_fields, field_delimiter, format_string, split_custom_values},
+    nodes::PartitionName,
+    source::Source,
+    version::SlurmVersion,
+};
 
-use super::{misc::format_string, nodes::PartitionName};
-
+/// State of a job, as reported by `squeue` or `sacct`
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum JobState {
@@ -27,21 +39,37 @@ pub enum JobState {
     Failed,
     /// Terminated due to node failure
     NodeFail,
+    /// Terminated due to exceeding its memory limit
     OutOfMemory,
+    /// Waiting for resources or for a higher priority
     Pending,
+    /// Terminated due to preemption
     Preempted,
+    /// Requeued, e.g. following preemption or node failure
     Requeued,
+    /// Requeued due to a federated sibling job
     RequeueFed,
+    /// Requeued and held
     RequeueHold,
+    /// Changing size
     Resizing,
+    /// Held due to the deletion of its reservation
     ResvDelHold,
+    /// Revoked due to a federated sibling job
     Revoked,
+    /// Allocated resources and running
     Running,
+    /// Being signaled
     Signaling,
+    /// Requeued in a special state, e.g. by an epilog
     SpecialExit,
+    /// Staging out data
     StageOut,
+    /// Stopped using SIGSTOP; resources remain allocated
     Stopped,
+    /// Suspended; CPUs are released to other jobs
     Suspended,
+    /// Terminated due to reaching its time limit
     Timeout,
 }
 
@@ -51,12 +79,16 @@ impl fmt::Display for JobState {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Run time, time limit, or remaining time of a job
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Time {
-    /// Returned if the duration is invalid, e.g. due to clock skew
+    /// Returned if the duration is invalid, e.g. due to clock skew, or not reported
+    #[default]
     Invalid,
     /// A valid duration; may be inaccurate for suspended jobs
     Duration(JobDuration),
+    /// Time limits and remaining time of jobs without a time limit
+    Unlimited,
 }
 
 impl Time {
@@ -76,6 +108,7 @@ impl std::fmt::Display for Time {
         match self {
             Time::Invalid => fmt::Display::fmt("INVALID", f),
             Time::Duration(duration) => write!(f, "{}", duration),
+            Time::Unlimited => fmt::Display::fmt("UNLIMITED", f),
         }
     }
 }
@@ -104,14 +137,44 @@ impl std::fmt::Display for JobDuration {
     }
 }
 
+impl JobDuration {
+    pub fn as_duration(&self) -> std::time::Duration {
+        let hours = self.days * 24 + self.hours;
+        let minutes = hours * 60 + self.minutes;
+        std::time::Duration::from_secs((minutes * 60 + self.seconds) as u64)
+    }
+}
+
 impl Time {
+    /// Returns a valid duration of the given number of seconds
+    pub(super) fn from_secs(seconds: u64) -> Self {
+        let seconds = seconds as usize;
+        Time::Duration(JobDuration {
+            days: seconds / 86400,
+            hours: seconds / 3600 % 24,
+            minutes: seconds / 60 % 60,
+            seconds: seconds % 60,
+        })
+    }
+
+    /// Returns the duration as a [`std::time::Duration`], if valid and limited
+    pub fn as_duration(&self) -> Option<std::time::Duration> {
+        match self {
+            Time::Invalid | Time::Unlimited => None,
+            Time::Duration(duration) => Some(duration.as_duration()),
+        }
+    }
+
     fn from_str<'de, D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let value: &str = Deserialize::deserialize(deserializer)?;
-        if value == "INVALID" {
-            return Ok(Time::Invalid);
+        match value {
+            // Time limits may also be missing, e.g. "NOT_SET" for jobs being submitted
+            "INVALID" | "NOT_SET" | "N/A" | "" => return Ok(Time::Invalid),
+            "UNLIMITED" | "INFINITE" => return Ok(Time::Unlimited),
+            _ => {}
         }
 
         let (days, value) = if value.contains('-') {
@@ -141,6 +204,8 @@ impl Time {
     }
 }
 
+/// A job as listed by `squeue`; array jobs are listed once per running task, and once
+/// for all pending tasks
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct Job {
@@ -149,15 +214,28 @@ pub struct Job {
     pub id: usize,
     /// Zero or more nodes assigned to this job
     #[serde(deserialize_with = "nodelist_from_str")]
-    pub nodelist: Vec<String>,
+    pub nodelist: Vec<Arc<str>>,
 
-    /// Name of partition to which this job belongs
+    /// Name of partition to which this job belongs. For pending jobs submitted to multiple
+    /// partitions, this is the partition in which this copy of the job is listed
     #[serde(deserialize_with = "PartitionName::from_str")]
     pub partition: PartitionName,
+    /// Partitions to which a pending job was submitted, if more than one; squeue reports
+    /// these as a comma-separated list (e.g. `short,long`)
+    #[serde(skip_deserializing)]
+    pub partitions: Vec<String>,
     /// State of the job; typically Running since source is `squeue`
     pub state: JobState,
     /// Owner of the job
-    pub user: String,
+    pub user: Arc<str>,
+    /// Account charged for the job, if available; missing from output captured by
+    /// older versions of slurmboard
+    #[serde(default)]
+    pub account: Arc<str>,
+    /// Quality of service of the job, if available; missing from output captured by
+    /// older versions of slurmboard
+    #[serde(default)]
+    pub qos: Arc<str>,
 
     /// Number of tasks requested by/allocated to the job
     pub tasks: usize,
@@ -174,58 +252,157 @@ pub struct Job {
     /// Number of GPUs requested by/allocated to the job (via TRES)
     #[serde(skip_deserializing)]
     pub gpus: usize,
+    /// Billable TRES of the job (via GRES or TRESBillingWeights)
+    #[serde(skip_deserializing)]
+    pub billing: Option<f64>,
 
     /// Runtime if available
     #[serde(deserialize_with = "Time::from_str")]
     pub time: Time,
+    /// Time remaining until the job reaches its time limit, if available; missing
+    /// from output captured by older versions of slurmboard
+    #[serde(default, rename = "TIME_LEFT", deserialize_with = "Time::from_str")]
+    pub time_left: Time,
+    /// Time limit (walltime) of the job, if available; missing from output captured by
+    /// older versions of slurmboard
+    #[serde(default, rename = "TIME_LIMIT", deserialize_with = "Time::from_str")]
+    pub time_limit: Time,
     /// Full name of the job
     pub name: String,
+    /// Scheduling priority of the job, if available; missing from output captured by
+    /// older versions of slurmboard
+    #[serde(default)]
+    pub priority: Option<u64>,
 
     /// ID of job array, same as `id` if this is the job array
     pub array_job_id: usize,
     /// Description of job array, Nth task in array, or NA for non-array jobs
     pub array_task_id: String,
 
-    /// Generic resources requested (nodes, cpus, ram)
-    #[serde(rename = "TRES_ALLOC")]
-    gres: String,
-    /// Trackable resources requested (gpus)
-    #[serde(rename = "TRES_PER_NODE")]
-    tres: String,
+    /// Generic resources requested (nodes, cpus, ram); not supported by older versions
+    /// of Slurm
+    #[serde(default, rename = "TRES_ALLOC")]
+    pub(super) gres: String,
+    /// Trackable resources requested (gpus); not supported by older versions of Slurm
+    #[serde(default, rename = "TRES_PER_NODE")]
+    pub(super) tres: String,
+
+    /// Values of the custom fields requested from squeue (see [`CustomFields`]); empty if
+    /// not available
+    ///
+    /// [`CustomFields`]: crate::slurm::CustomFields
+    #[serde(skip_deserializing)]
+    pub custom: Vec<String>,
 }
 
 impl Job {
-    pub fn collect(exe: &str) -> Result<Vec<Job>> {
-        // FIXME: Generate parameters on demand
-        let output = Command::new(exe)
-            .args(["--Format", &squeue_format()])
-            .output()
-            .wrap_err_with(|| format!("failed to execute {:?}", exe))?;
-
-        if !output.status.success() {
-            panic!("{:?}", std::str::from_utf8(&output.stderr));
+    /// Returns the ID of the job as shown by `squeue`, i.e. `1234_17` for array tasks and
+    /// `1234_[18-100]` for pending tasks, instead of the unique ID of each task
+    pub fn display_id(&self) -> String {
+        if self.array_task_id == "N/A" || self.array_task_id.is_empty() {
+            self.id.to_string()
+        } else if self.array_task_id.bytes().all(|v| v.is_ascii_digit()) {
+            format!("{}_{}", self.array_job_id, self.array_task_id)
+        } else {
+            format!("{}_[{}]", self.array_job_id, self.array_task_id)
         }
+    }
 
-        // TODO: check output.status
-        Job::parse(std::io::Cursor::new(output.stdout))
+    /// Returns true if the job belongs to, or is pending in, the specified partition
+    pub fn in_partition(&self, name: &PartitionName) -> bool {
+        if self.partitions.is_empty() {
+            self.partition.same(name)
+        } else {
+            self.partitions.contains(&name.label)
+        }
     }
 
-    fn parse<R>(reader: R) -> Result<Vec<Job>>
-    where
-        R: std::io::Read,
-    {
+    /// Collects the jobs known to the controller using `squeue`, using the JSON output if
+    /// `json` is true, and otherwise the delimited output (see [`Job::squeue_args`])
+    pub fn collect(
+        source: &Source,
+        exe: &str,
+        json: bool,
+        version: Option<SlurmVersion>,
+        custom: &[String],
+    ) -> Result<Vec<Job>> {
+        let stdout = if json {
+            source.run(exe, &["--json"])?
+        } else {
+            source.run(exe, &Job::squeue_args(version, custom))?
+        };
+
+        Job::from_reader(stdout.as_slice(), version, custom)
+    }
+
+    /// Returns the arguments used to request the delimited output of `squeue`, i.e. the
+    /// fields supported by `version`, if known, followed by the `custom` fields
+    pub fn squeue_args(version: Option<SlurmVersion>, custom: &[String]) -> Vec<String> {
+        let format = squeue_fields(version)
+            .into_iter()
+            .chain(custom.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        vec!["--Format".to_string(), format_string(format.iter())]
+    }
+
+    /// Parses the output of `squeue --json`, or the delimited output of `squeue` run with
+    /// the arguments returned by [`Job::squeue_args`] for the same `version` and `custom`
+    /// fields. Custom fields are not available in the JSON output
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        version: Option<SlurmVersion>,
+        custom: &[String],
+    ) -> Result<Vec<Job>> {
+        let mut stdout = Vec::new();
+        reader
+            .read_to_end(&mut stdout)
+            .wrap_err("error while reading squeue output")?;
+
+        // Captured output may be in either format when reading from files
+        if is_json(&stdout) {
+            parse_squeue(&stdout)
+        } else {
+            let fields = squeue_fields(version).len();
+            Job::parse(&stdout, fields, custom.len())
+        }
+    }
+
+    /// Parses delimited squeue output, along with the values of `custom` fields requested
+    /// after the `fields` built-in fields
+    fn parse(stdout: &[u8], fields: usize, custom: usize) -> Result<Vec<Job>> {
+        // Job names may contain `|` (or quotes) as well as leading or trailing spaces, so
+        // rows are read as is; job names split into multiple fields are merged below and
+        // other fields are trimmed
         let mut reader = csv::ReaderBuilder::new()
-            .delimiter(b'|')
-            .trim(csv::Trim::All)
-            .from_reader(reader);
+            .delimiter(field_delimiter(stdout))
+            .quoting(false)
+            .flexible(true)
+            .trim(csv::Trim::Headers)
+            .from_reader(stdout);
+
+        let mut headers = reader.headers()?.clone();
+        let columns = headers.len();
+        let custom = custom_fields(&mut headers, fields, custom);
+        let name = headers.iter().position(|v| v == "NAME");
 
         let mut results = Vec::new();
-        for result in reader.deserialize() {
-            let mut job: Job = result?;
+        for record in reader.records() {
+            let record = record?;
+            // Custom fields may also contain `|`, in which case the values cannot be told apart
+            if record.len() > columns && custom.is_some() {
+                warn!(
+                    fields = record.len(),
+                    columns, "skipping job with values containing the delimiter"
+                );
+                continue;
+            }
 
-            // Update GPUs, nodes, CPUs, mem from `tres` and `gres` fields
-            job.update_from_gres()?;
-            job.update_from_tres()?;
+            let mut record = trim_fields(merge_name_fields(record, columns, name), name);
+            let values = split_custom_values(&mut record, custom);
+            let mut job: Job = record.deserialize(Some(&headers))?;
+            job.custom = values;
+            job.update_from_fields()?;
 
             results.push(job);
         }
@@ -233,6 +410,19 @@ impl Job {
         Ok(results)
     }
 
+    /// Updates the values derived from other fields, i.e. the partitions of pending jobs
+    /// and the GPUs, nodes, CPUs, and memory from the `tres` and `gres` fields
+    pub(super) fn update_from_fields(&mut self) -> Result<()> {
+        // Jobs submitted to multiple partitions are initially listed in the first
+        if self.partition.label.contains(',') {
+            self.partitions = self.partition.label.split(',').map(String::from).collect();
+            self.partition.label.clone_from(&self.partitions[0]);
+        }
+
+        self.update_from_gres()?;
+        self.update_from_tres()
+    }
+
     fn update_from_gres(&mut self) -> Result<()> {
         if !self.gres.is_empty() {
             for resource in self.gres.split(',') {
@@ -243,10 +433,20 @@ impl Job {
                                 .parse()
                                 .with_context(|| format!("parsing cpus in GRES: {:?}", self.gres))?
                         }
+                        "node" => {
+                            self.nodes = value.parse().with_context(|| {
+                                format!("parsing nodes in GRES: {:?}", self.gres)
+                            })?
+                        }
                         "mem" => {
                             self.mem = parse_memory(value)
                                 .with_context(|| format!("parsing mem in GRES: {:?}", self.gres))?;
                         }
+                        "billing" => {
+                            self.billing = Some(value.parse().with_context(|| {
+                                format!("parsing billing in GRES: {:?}", self.gres)
+                            })?)
+                        }
                         _ => {}
                     }
                 }
@@ -256,6 +456,14 @@ impl Job {
         Ok(())
     }
 
+    /// Calculates the billable TRES of the job if not reported by Slurm
+    pub fn update_billing(&mut self, weights: &BillingWeights) {
+        if self.billing.is_none() {
+            let nodes = self.nodelist.len().max(self.nodes);
+            self.billing = Some(weights.billing(self.cpus, self.mem, self.gpus, nodes));
+        }
+    }
+
     fn update_from_tres(&mut self) -> Result<()> {
         for resource in self.tres.split(',') {
             let fields: Vec<_> = resource.splitn(3, ':').collect();
@@ -272,28 +480,76 @@ impl Job {
     }
 }
 
-/// Generates parameter for the `-F` command-line option for `squeue`
-fn squeue_format() -> String {
-    format_string(
-        [
-            "ArrayJobID",
-            "ArrayTaskID",
-            "JobID",
-            "Name",
-            "NodeList",
-            "NumTasks",
-            "Partition",
-            "State",
-            "TimeUsed",
-            "Tres-Alloc",
-            "Tres-Per-Node",
-            "UserName",
-        ]
-        .iter(),
-    )
+/// Merges the fields of a row with more fields than the header into the job name, the
+/// only free-form value among the built-in fields, e.g. for a job named "a|b" in output
+/// delimited by `|`
+fn merge_name_fields(record: StringRecord, columns: usize, name: Option<usize>) -> StringRecord {
+    let Some(name) = name.filter(|_| record.len() > columns) else {
+        return record;
+    };
+
+    debug!(
+        fields = record.len(),
+        columns, "merging job name containing delimiter"
+    );
+
+    let extra = record.len() - columns;
+    let mut fields = record.iter().take(name).collect::<Vec<_>>();
+    let merged = record
+        .iter()
+        .skip(name)
+        .take(extra + 1)
+        .collect::<Vec<_>>()
+        .join("|");
+    fields.push(&merged);
+    fields.extend(record.iter().skip(name + extra + 1));
+
+    StringRecord::from(fields)
+}
+
+/// Trims leading and trailing whitespace from every field of a row except the job name
+fn trim_fields(record: StringRecord, name: Option<usize>) -> StringRecord {
+    record
+        .iter()
+        .enumerate()
+        .map(|(idx, v)| if Some(idx) == name { v } else { v.trim() })
+        .collect()
+}
+
+/// Fields of `squeue --Format` not supported by older releases of Slurm, along with the
+/// first release known to support them. The corresponding values default to empty values
+const SQUEUE_OPTIONAL: [(&str, SlurmVersion); 3] = [
+    ("PriorityLong", SlurmVersion::new(15, 8, 0)),
+    ("Tres-Alloc", SlurmVersion::new(16, 5, 0)),
+    ("Tres-Per-Node", SlurmVersion::new(18, 8, 0)),
+];
+
+/// Returns the fields requested using the `-F` command-line option for `squeue`
+fn squeue_fields(version: Option<SlurmVersion>) -> Vec<&'static str> {
+    let fields = [
+        "Account",
+        "ArrayJobID",
+        "ArrayTaskID",
+        "JobID",
+        "Name",
+        "NodeList",
+        "NumTasks",
+        "Partition",
+        "PriorityLong",
+        "QOS",
+        "State",
+        "TimeLeft",
+        "TimeLimit",
+        "TimeUsed",
+        "Tres-Alloc",
+        "Tres-Per-Node",
+        "UserName",
+    ];
+
+    SlurmVersion::supported_fields(version, &fields, &SQUEUE_OPTIONAL)
 }
 
-fn nodelist_from_str<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+fn nodelist_from_str<'de, D>(deserializer: D) -> Result<Vec<Arc<str>>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -301,7 +557,7 @@ where
     Ok(value
         .split(',')
         .filter(|v| !v.is_empty())
-        .map(|v| v.to_string())
+        .map(Arc::from)
         .collect::<Vec<_>>())
 }
 
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use color_eyre::{
+    eyre::{eyre, Context},
+    Result,
+};
+use serde::de::{self, IntoDeserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::jobs::{Job, JobState, Time};
+use super::misc::expand_hostlist;
+use super::nodes::{CPUState, Node, NodeState, PartitionName, SlurmState, Topology};
+
+/// Returns true if the output of a command appears to be JSON rather than delimited text
+pub fn is_json(stdout: &[u8]) -> bool {
+    stdout
+        .iter()
+        .find(|v| !v.is_ascii_whitespace())
+        .is_some_and(|&v| v == b'{')
+}
+
+/// Parses the output of `sinfo -N --json`. The JSON schema differs between Slurm
+/// versions, so values are looked up leniently and missing values are left empty
+pub fn parse_sinfo(stdout: &[u8]) -> Result<Vec<Node>> {
+    let root: Value = serde_json::from_slice(stdout).wrap_err("error while parsing sinfo JSON")?;
+
+    let mut nodes = Vec::new();
+    for entry in list(&root["sinfo"]) {
+        let cpus = &entry["cpus"];
+        let memory = &entry["memory"];
+        let topology = match (
+            maximum(&entry["sockets"]),
+            maximum(&entry["cores"]),
+            maximum(&entry["threads"]),
+        ) {
+            (Some(sockets), Some(cores), Some(threads)) => Some(Topology {
+                sockets: sockets as usize,
+                cores: cores as usize,
+                threads: threads as usize,
+            }),
+            _ => None,
+        };
+
+        let mut node = Node {
+            name: String::new(),
+            partition: PartitionName {
+                label: string(&entry["partition"]["name"]),
+                default: is_default_partition(&entry["partition"]),
+            },
+            state: node_state(&strings(&entry["node"]["state"])),
+            cpus: integer(&cpus["total"]),
+            // Load is reported in hundredths
+            cpu_load: maximum(&cpus["load"]).map(|v| v / 100.0),
+            cpu_state: CPUState {
+                allocated: integer(&cpus["allocated"]),
+                idle: integer(&cpus["idle"]),
+                other: integer(&cpus["other"]),
+                total: integer(&cpus["total"]),
+            },
+            topology,
+            mem: integer(&memory["minimum"]),
+            mem_alloc: integer(&memory["allocated"]),
+            mem_free: maximum(&memory["free"]).map(|v| v as usize),
+            gpus: 0,
+            gpus_used: 0,
+            gpus_in_use: Vec::new(),
+            gpu_usage: Vec::new(),
+            features: strings(&entry["features"]["total"])
+                .iter()
+                .flat_map(|v| v.split(','))
+                .filter(|v| !v.is_empty() && *v != "(null)")
+                .map(String::from)
+                .collect(),
+            gres: string(&entry["gres"]["total"]),
+            gres_used: string(&entry["gres"]["used"]),
+            jobs: Vec::new(),
+            users: 0,
+            custom: Vec::new(),
+        };
+        node.update_from_gres()?;
+
+        // Entries may cover multiple nodes with identical properties
+        for hostlist in strings(&entry["nodes"]["nodes"]) {
+            for name in expand_hostlist(&hostlist) {
+                nodes.push(Node {
+                    name,
+                    ..node.clone()
+                });
+            }
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Parses the output of `squeue --json`, analogous to [`parse_sinfo`]
+pub fn parse_squeue(stdout: &[u8]) -> Result<Vec<Job>> {
+    let root: Value = serde_json::from_slice(stdout).wrap_err("error while parsing squeue JSON")?;
+
+    let mut jobs = Vec::new();
+    for job in list(&root["jobs"]) {
+        let id = integer(&job["job_id"]);
+        let state = strings(&job["job_state"])
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        // Array jobs report the ID of the array and either the task ID or, for pending
+        // tasks, the range of task IDs
+        let array_job_id = integer(&job["array_job_id"]);
+        let array_task_id = match string(&job["array_task_string"]) {
+            tasks if !tasks.is_empty() => tasks,
+            _ => optional(number(&job["array_task_id"]).map(|v| (v as u64).to_string())),
+        };
+
+        let elapsed = elapsed_seconds(job, &state);
+        let limit = time_limit(job);
+        let time_left = limit.map(|v| v.map(|limit| limit.saturating_sub(elapsed)));
+
+        let mut job = Job {
+            id,
+            nodelist: expand_hostlist(&string(&job["nodes"]))
+                .into_iter()
+                .map(Arc::from)
+                .collect(),
+            partition: PartitionName {
+                label: string(&job["partition"]),
+                default: false,
+            },
+            partitions: Vec::new(),
+            state: JobState::deserialize(state.as_str().into_deserializer())
+                .map_err(|error: de::value::Error| eyre!("job {}: {}", id, error))?,
+            user: Arc::from(string(&job["user_name"])),
+            account: Arc::from(string(&job["account"])),
+            qos: Arc::from(string(&job["qos"])),
+            tasks: number(&job["tasks"])
+                .or_else(|| number(&job["num_tasks"]))
+                .map_or(1, |v| v as usize),
+            nodes: 0,
+            cpus: 0,
+            mem: 0,
+            gpus: 0,
+            billing: None,
+            time: Time::from_secs(elapsed),
+            time_left: time_from_limit(time_left),
+            time_limit: time_from_limit(limit),
+            // Names are shown on a single line
+            name: string(&job["name"]).replace(['\n', '\r'], " "),
+            priority: number(&job["priority"]).map(|v| v as u64),
+            array_job_id: if array_job_id > 0 { array_job_id } else { id },
+            array_task_id,
+            gres: string(&job["tres_alloc_str"]),
+            // The delimited output does not prefix GPUs with the type of resource
+            tres: string(&job["tres_per_node"]).replace("gres/", ""),
+            custom: Vec::new(),
+        };
+        job.update_from_fields()?;
+
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+/// Returns the number of seconds that a job has been running
+fn elapsed_seconds(job: &Value, state: &str) -> u64 {
+    let start = number(&job["start_time"]).unwrap_or_default() as i64;
+    let end = number(&job["end_time"]).unwrap_or_default() as i64;
+    let now = Utc::now().timestamp();
+
+    match state {
+        "RUNNING" | "SUSPENDED" | "COMPLETING" if start > 0 => {
+            let end = if end > start && end < now { end } else { now };
+            (end - start).max(0) as u64
+        }
+        _ => 0,
+    }
+}
+
+/// Returns the time limit of a job in seconds, `Some(None)` if the job is unlimited, or
+/// `None` if the time limit is not set
+fn time_limit(job: &Value) -> Option<Option<u64>> {
+    let limit = &job["time_limit"];
+    if limit["infinite"].as_bool() == Some(true) {
+        return Some(None);
+    }
+
+    // Time limits are reported in minutes
+    number(limit).map(|minutes| Some(minutes as u64 * 60))
+}
+
+/// Converts a time limit returned by [`time_limit`] into a [`Time`]
+fn time_from_limit(limit: Option<Option<u64>>) -> Time {
+    match limit {
+        Some(Some(seconds)) => Time::from_secs(seconds),
+        Some(None) => Time::Unlimited,
+        None => Time::Invalid,
+    }
+}
+
+/// Converts a list of node state flags (e.g. ["IDLE", "DRAIN"]) into the state
+/// reported by `sinfo` in the delimited output, e.g. "drained"
+fn node_state(flags: &[String]) -> NodeState {
+    let has = |flag: &str| flags.iter().any(|v| v == flag);
+    let base = flags.first().map(|v| v.as_str()).unwrap_or("UNKNOWN");
+    let busy = matches!(base, "ALLOCATED" | "MIXED") || has("COMPLETING");
+
+    let state = if has("DRAIN") {
+        if busy {
+            SlurmState::Draining
+        } else {
+            SlurmState::Drained
+        }
+    } else if has("FAIL") {
+        if busy {
+            SlurmState::Failing
+        } else {
+            SlurmState::Fail
+        }
+    } else if has("COMPLETING") {
+        SlurmState::Completing
+    } else if has("MAINTENANCE") {
+        SlurmState::Maintenance
+    } else if has("RESERVED") {
+        SlurmState::Reserved
+    } else if has("POWERED_DOWN") || has("POWER_DOWN") {
+        SlurmState::PowerDown
+    } else if has("POWERING_UP") || has("POWER_UP") {
+        SlurmState::PowerUp
+    } else if has("PERFCTRS") {
+        SlurmState::Perfctrs
+    } else {
+        match base {
+            "IDLE" => SlurmState::Idle,
+            "ALLOCATED" => SlurmState::Allocated,
+            "MIXED" => SlurmState::Mixed,
+            "DOWN" => SlurmState::Down,
+            "FUTURE" => SlurmState::Future,
+            _ => SlurmState::Unknown,
+        }
+    };
+
+    NodeState {
+        state,
+        responds: !has("NOT_RESPONDING"),
+    }
+}
+
+/// Returns true if a partition is flagged as the default partition
+fn is_default_partition(partition: &Value) -> bool {
+    [&partition["flags"], &partition["partition"]["flags"]]
+        .into_iter()
+        .any(|v| strings(v).iter().any(|v| v == "DEFAULT"))
+}
+
+fn list(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Returns a string value, or the values of a list of strings joined by commas
+fn string(value: &Value) -> String {
+    strings(value).join(",")
+}
+
+/// Returns a list of strings, treating single strings as a list of one value
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(value) => vec![value.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|v| v.as_str().map(ToString::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns a number, which may be wrapped in an object in the form
+/// `{"set": true, "infinite": false, "number": 1}`
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(value) => value.as_f64(),
+        Value::Object(object) => {
+            let set = object.get("set").and_then(Value::as_bool).unwrap_or(true);
+            let infinite = object.get("infinite").and_then(Value::as_bool);
+            if set && infinite != Some(true) {
+                object.get("number").and_then(number)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn integer(value: &Value) -> usize {
+    number(value).unwrap_or_default() as usize
+}
+
+/// Returns the maximum of values reported as a range (`{"minimum": 1, "maximum": 2}`)
+fn maximum(value: &Value) -> Option<f64> {
+    number(&value["maximum"]).or_else(|| number(value))
+}
+
+/// Formats an optional value, using "N/A" for missing values like Slurm
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| String::from("N/A"))
+}
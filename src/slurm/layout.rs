@@ -0,0 +1,140 @@
+use color_eyre::{eyre::eyre, Result};
+
+use super::{
+    details::parse_key_values,
+    misc::{expand_hostlist, parse_indices},
+    source::Source,
+};
+
+/// Resources allocated to a job on a single node
+#[derive(Clone, Debug)]
+pub struct NodeAllocation {
+    /// Name of the node
+    pub node: String,
+    /// CPUs allocated on this node, e.g. "0-15,32-47"
+    pub cpu_ids: String,
+    /// Number of CPUs allocated on this node
+    pub cpus: usize,
+    /// Memory allocated on this node in MB
+    pub mem: Option<usize>,
+    /// GRES allocated on this node, e.g. "gpu:a100:2(IDX:0-1)"
+    pub gres: String,
+}
+
+/// Distribution of the resources of a running job across nodes, as reported by
+/// `scontrol show job --details`
+#[derive(Clone, Debug)]
+pub struct JobLayout {
+    /// ID of the job
+    pub id: usize,
+    /// Total number of tasks in the job
+    pub tasks: usize,
+    /// Number of CPUs per task; one unless specified via `--cpus-per-task`
+    pub cpus_per_task: usize,
+    /// Resources allocated on each node
+    pub nodes: Vec<NodeAllocation>,
+    /// Script or command run by the job, if reported
+    pub command: Option<String>,
+    /// Working directory of the job, if reported
+    pub work_dir: Option<String>,
+    /// Location of the standard output of the job, if written to a file
+    pub stdout: Option<String>,
+}
+
+impl JobLayout {
+    /// Collects the layout of a job using `scontrol show job --details`
+    pub fn collect(source: &Source, scontrol: &str, id: usize) -> Result<JobLayout> {
+        let stdout = source.run(
+            scontrol,
+            &["show", "job", &id.to_string(), "--details", "--oneliner"],
+        )?;
+
+        JobLayout::parse(id, &String::from_utf8_lossy(&stdout))
+    }
+
+    /// Parses the detailed description of a job, in which each group of nodes with
+    /// identical allocations is described as "Nodes=node[01-02] CPU_IDs=0-15 Mem=.."
+    fn parse(id: usize, text: &str) -> Result<JobLayout> {
+        let mut layout = JobLayout {
+            id,
+            tasks: 1,
+            cpus_per_task: 1,
+            nodes: Vec::new(),
+            command: None,
+            work_dir: None,
+            stdout: None,
+        };
+
+        // `parse_key_values` treats keys containing a '/' as part of the previous value,
+        // so these are read directly
+        for word in text.split_whitespace() {
+            if let Some(Ok(value)) = word.strip_prefix("NumTasks=").map(str::parse) {
+                layout.tasks = value;
+            } else if let Some(Ok(value)) = word.strip_prefix("CPUs/Task=").map(str::parse) {
+                layout.cpus_per_task = value;
+            }
+        }
+
+        // Nodes are listed with the allocations that follow them
+        let mut nodes = Vec::new();
+        for (key, value) in parse_key_values(text) {
+            // Interactive jobs report an empty value or "(null)"
+            let known = || Some(value.clone()).filter(|v| !v.is_empty() && v != "(null)");
+
+            match key.as_str() {
+                "Command" => layout.command = known(),
+                "WorkDir" => layout.work_dir = known(),
+                "StdOut" => layout.stdout = known(),
+                "Nodes" => {
+                    layout.nodes.append(&mut nodes);
+                    nodes = expand_hostlist(&value)
+                        .into_iter()
+                        .map(|node| NodeAllocation {
+                            node,
+                            cpu_ids: String::new(),
+                            cpus: 0,
+                            mem: None,
+                            gres: String::new(),
+                        })
+                        .collect();
+                }
+                "CPU_IDs" => {
+                    for node in &mut nodes {
+                        node.cpus = parse_indices(&value).len();
+                        node.cpu_ids.clone_from(&value);
+                    }
+                }
+                "Mem" => {
+                    for node in &mut nodes {
+                        node.mem = value.parse().ok();
+                    }
+                }
+                "GRES" => {
+                    for node in &mut nodes {
+                        node.gres.clone_from(&value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        layout.nodes.append(&mut nodes);
+        if layout.nodes.is_empty() {
+            return Err(eyre!("no nodes are allocated to job {}", id));
+        }
+
+        Ok(layout)
+    }
+
+    /// Returns the (estimated) number of tasks running on a node, assuming that each
+    /// task is bound to `cpus_per_task` of the CPUs allocated on that node
+    pub fn tasks_on(&self, node: &NodeAllocation) -> usize {
+        node.cpus / self.cpus_per_task.max(1)
+    }
+
+    /// Returns true if the number of CPUs allocated differs between nodes
+    pub fn is_unbalanced(&self) -> bool {
+        let cpus = self.nodes.iter().map(|v| v.cpus);
+        cpus.clone().min() != cpus.max()
+    }
+}
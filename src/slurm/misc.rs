@@ -1,13 +1,38 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use color_eyre::{eyre::bail, Result};
+use csv::StringRecord;
+
 pub fn unique_values<'a, I>(iter: I) -> usize
 where
-    I: std::iter::Iterator<Item = &'a String>,
+    I: std::iter::Iterator<Item = &'a Arc<str>>,
 {
-    let mut usernames = iter.collect::<Vec<_>>();
-    usernames.sort_unstable();
-    usernames.dedup();
-    usernames.len()
+    iter.collect::<HashSet<_>>().len()
+}
+
+/// Deduplicates strings repeated across many jobs, such as usernames and node names,
+/// so that each distinct value is only allocated once per update and cheaply cloned
+#[derive(Debug, Default)]
+pub struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    /// Returns the shared copy of `value`
+    pub fn intern(&mut self, value: &Arc<str>) -> Arc<str> {
+        match self.0.get(value) {
+            Some(interned) => interned.clone(),
+            None => {
+                self.0.insert(value.clone());
+                value.clone()
+            }
+        }
+    }
 }
 
+/// Separator following each field requested using `--Format`. Unlike `|`, the ASCII unit
+/// separator cannot appear in job names, comments, or other free-form values
+pub const FIELD_SEPARATOR: u8 = 0x1f;
+
 /// Converts an iterator of &str to an  ``--Format`` argument
 pub fn format_string<'a, I, S>(iter: I) -> String
 where
@@ -19,5 +44,161 @@ where
         .map(|v| format!("{}:0", v.as_ref()))
         .collect::<Vec<_>>()
         // Join fields by a character that does not potentially appear in values
-        .join("|,")
+        .join(&format!("{},", FIELD_SEPARATOR as char))
+}
+
+/// Returns the delimiter of `--Format` output, i.e. [`FIELD_SEPARATOR`], or `|` for
+/// output captured by hand (see the README) or by older versions of slurmboard
+pub fn field_delimiter(output: &[u8]) -> u8 {
+    let header = output.split(|&v| v == b'\n').next().unwrap_or_default();
+    if header.contains(&FIELD_SEPARATOR) {
+        FIELD_SEPARATOR
+    } else {
+        b'|'
+    }
+}
+
+/// Returns the number of built-in fields in `--Format` output with the headers given, if
+/// the output includes the `custom` fields requested after the `fields` built-in fields.
+/// The custom fields are then removed from the headers. Output captured in files may
+/// have been produced using other fields, in which case the values are not available
+pub fn custom_fields(headers: &mut StringRecord, fields: usize, custom: usize) -> Option<usize> {
+    if custom > 0 && headers.len() == fields + custom {
+        headers.truncate(fields);
+        Some(fields)
+    } else {
+        None
+    }
+}
+
+/// Removes the values of custom fields following the `fields` built-in fields (see
+/// [`custom_fields`]) from a record, returning them in order
+pub fn split_custom_values(record: &mut StringRecord, fields: Option<usize>) -> Vec<String> {
+    let Some(fields) = fields else {
+        return Vec::new();
+    };
+
+    let values = record.iter().skip(fields).map(String::from).collect();
+    record.truncate(fields);
+    values
+}
+
+/// Expands a Slurm hostlist expression such as "node[01-03,07],gpu01" into individual
+/// host names. Malformed ranges are kept as is
+pub fn expand_hostlist(hostlist: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for item in split_hostlist(hostlist) {
+        expand_host(item, &mut hosts);
+    }
+
+    hosts
+}
+
+/// Splits a hostlist on commas that are not enclosed in brackets
+fn split_hostlist(hostlist: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (idx, c) in hostlist.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&hostlist[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    items.push(&hostlist[start..]);
+    items.retain(|v| !v.is_empty());
+    items
+}
+
+/// Expands the first bracketed range in a host name, recursing for any further ranges
+fn expand_host(host: &str, hosts: &mut Vec<String>) {
+    let (prefix, ranges, suffix) = match host.split_once('[') {
+        Some((prefix, rest)) => match rest.split_once(']') {
+            Some((ranges, suffix)) => (prefix, ranges, suffix),
+            None => return hosts.push(host.to_string()),
+        },
+        None => return hosts.push(host.to_string()),
+    };
+
+    for range in ranges.split(',') {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        match (first.parse::<usize>(), last.parse::<usize>()) {
+            (Ok(start), Ok(end)) if start <= end => {
+                // Zero-padding is determined by the first value, e.g. "01-10"
+                for value in start..=end {
+                    let host =
+                        format!("{}{:0width$}{}", prefix, value, suffix, width = first.len());
+                    expand_host(&host, hosts);
+                }
+            }
+            _ => hosts.push(format!("{}{}{}", prefix, range, suffix)),
+        }
+    }
+}
+
+/// Parses a list of indices and ranges of indices such as "0-3,8". Malformed values
+/// are skipped
+pub fn parse_indices(list: &str) -> Vec<usize> {
+    let mut indices = Vec::new();
+    for range in list.split(',') {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(start), Ok(end)) = (first.parse::<usize>(), last.parse::<usize>()) {
+            indices.extend(start..=end);
+        }
+    }
+
+    indices
+}
+
+/// A table in the `--parsable2` output of `sreport`, `sshare`, or `sacctmgr`
+pub struct Table<'a> {
+    header: Vec<&'a str>,
+    rows: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Table<'a> {
+    /// Parses a header and rows of values separated by `|`; preceding lines without
+    /// separators, such as the description of a report by `sreport`, are skipped
+    pub fn parse(text: &'a str) -> Result<Table<'a>> {
+        let mut lines = text.lines().filter(|v| v.contains('|'));
+        let Some(header) = lines.next() else {
+            bail!("output did not contain a table");
+        };
+
+        Ok(Table {
+            header: header.split('|').map(str::trim).collect(),
+            rows: lines
+                .map(|v| v.split('|').map(str::trim).collect())
+                .collect(),
+        })
+    }
+
+    /// Returns the values of the named columns of each row; missing values are empty
+    pub fn rows<const N: usize>(
+        &self,
+        names: [&str; N],
+    ) -> impl Iterator<Item = [&'a str; N]> + '_ {
+        let columns = names.map(|name| self.header.iter().position(|v| *v == name));
+        self.rows.iter().map(move |row| {
+            columns.map(|idx| idx.and_then(|idx| row.get(idx)).copied().unwrap_or(""))
+        })
+    }
+}
+
+/// Quotes values containing characters that may be interpreted by the shell, for use in
+/// shell commands defined by the user; job IDs, node names, and so on are inserted as is
+pub fn quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "._-,@:/+=".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
 }
@@ -1,30 +1,139 @@
+//! Querying of Slurm state
+//!
+//! Nodes, jobs, and partitions are collected by running `sinfo` and `squeue` via a
+//! [`Source`], e.g. locally, over SSH, or using output captured in files, and are then
+//! organized by partition using [`Slurm::collect`]. Output obtained by other means may
+//! be parsed using [`Node::from_reader`] and [`Job::from_reader`], and organized using
+//! [`Slurm::organize`]:
+//!
+//! ```
+//! use slurmboard::slurm::{DefaultMem, Job, Node, Slurm, Utilization};
+//!
+//! # fn main() -> color_eyre::Result<()> {
+//! // The output of `sinfo` and `squeue` run with `Node::sinfo_args(None, &[])` and
+//! // `Job::squeue_args(None, &[])`, or the output of `sinfo -N --json` and `squeue --json`
+//! let sinfo = "\
+//! CPUS|CPUS(A/I/O/T)|GRES|MEMORY|NODELIST|PARTITION|STATE
+//! 32|16/16/0/32|gpu:a100:4|256000|gpu01|gpu|mixed
+//! 64|0/64/0/64|(null)|512000|node01|standard*|idle
+//! ";
+//! let squeue = "\
+//! ACCOUNT|ARRAY_JOB_ID|ARRAY_TASK_ID|JOBID|NAME|NODELIST|TASKS|PARTITION|QOS|STATE|TIME_LEFT|TIME_LIMIT|TIME|USER
+//! lab|11|N/A|11|train|gpu01|1|gpu|normal|RUNNING|1:00:00|2:00:00|1:00:00|alice
+//! ";
+//!
+//! let nodes = Node::from_reader(sinfo.as_bytes(), None, &[])?;
+//! let jobs = Job::from_reader(squeue.as_bytes(), None, &[])?;
+//! let (partitions, _unassigned) = Slurm::organize(nodes, jobs);
+//! for partition in &partitions {
+//!     let cpus: Utilization = partition
+//!         .nodes
+//!         .iter()
+//!         .map(|v| v.cpu_utilization(DefaultMem::None))
+//!         .sum();
+//!     let cpus = cpus.to_numbers(|v| v.to_string());
+//!     println!("{}: {} jobs, {} CPUs", partition.name, partition.jobs.len(), cpus);
+//! }
+//!
+//! assert_eq!(partitions.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module is built without the terminal user interface when the default `tui`
+//! feature of the crate is disabled.
+#![warn(missing_docs)]
+mod config;
+mod details;
+mod gpus;
+mod groups;
+mod health;
 mod jobs;
+mod json;
+mod layout;
 mod misc;
 mod nodes;
+mod output;
 mod partitions;
+mod reservations;
+mod shares;
+mod source;
+mod submit;
+mod usage;
+mod utilization;
+mod version;
 
-pub use jobs::{Job, JobState};
-pub use nodes::{CPUState, Node, NodeState};
-pub use partitions::Partition;
+pub use config::{BillingWeights, DefaultMem, PartitionConfig, PartitionLimits, SlurmConfig};
+pub use details::{final_state, parse_key_values, JobDetails};
+pub use gpus::GpuUsage;
+pub use groups::{group_nodes, NodeGroup};
+pub use health::{Controller, Health};
+pub use jobs::{Job, JobState, Time};
+pub use layout::{JobLayout, NodeAllocation};
+pub use misc::{expand_hostlist, quote};
+pub use nodes::{CPUState, Node, NodeState, SlurmState, Topology};
+pub use output::{tail_file, JobOutput};
+pub use partitions::{count_node_states, Partition};
+pub use reservations::Reservation;
+pub use shares::AssociationUsage;
+pub use source::{file_name, Backend, Cancelled, Source, Timeout, Watchdog};
+pub use submit::{
+    cancel_jobs, control_job, list_scripts, parse_time_limit, submit_script, update_node_state,
+    update_time_limit,
+};
+pub use usage::{ClusterUsage, ReportPeriod, UsageReport, UserUsage};
+pub use utilization::Utilization;
+pub use version::SlurmVersion;
 
 use color_eyre::Result;
+use tracing::warn;
 
-pub enum Identifier {
-    Partition(String),
-    Node(String),
+use misc::Interner;
+
+/// Additional fields of `squeue --Format` and `sinfo --Format` requested for custom
+/// columns, e.g. "Comment" or "Licenses". The values are stored in `custom` of each
+/// [`Job`] and [`Node`], in the order given
+#[derive(Clone, Debug, Default)]
+pub struct CustomFields {
+    /// Fields of `squeue --Format`
+    pub squeue: Vec<String>,
+    /// Fields of `sinfo --Format`
+    pub sinfo: Vec<String>,
 }
 
+/// Collection of the nodes and jobs of a cluster
 pub struct Slurm {}
 
 impl Slurm {
-    pub fn collect(sinfo: &str, squeue: &str) -> Result<Vec<Partition>> {
-        let partitions = Slurm::collect_partitions(sinfo)?;
+    /// Collects nodes and jobs, using the JSON output of `sinfo` and `squeue` if `json`
+    /// is true, and otherwise their delimited output. Fields not supported by the specified
+    /// version of Slurm, if known, are not requested. Returns the partitions along with
+    /// jobs that could not be assigned to any partition listed by `sinfo`. The `custom`
+    /// fields are only available in the delimited output
+    pub fn collect(
+        source: &Source,
+        sinfo: &str,
+        squeue: &str,
+        json: bool,
+        version: Option<SlurmVersion>,
+        custom: &CustomFields,
+    ) -> Result<(Vec<Partition>, Vec<Job>)> {
+        let nodes = Node::collect(source, sinfo, json, version, &custom.sinfo)?;
+        let jobs = Job::collect(source, squeue, json, version, &custom.squeue)?;
 
-        Slurm::collect_jobs(squeue, partitions)
+        Ok(Slurm::organize(nodes, jobs))
     }
 
-    fn collect_partitions(sinfo: &str) -> Result<Vec<Partition>> {
-        let mut nodes = Node::collect(sinfo)?;
+    /// Organizes nodes and jobs by partition, e.g. as parsed using [`Node::from_reader`]
+    /// and [`Job::from_reader`]. Returns the partitions listed by `sinfo`, along with jobs
+    /// that could not be assigned to any of them
+    pub fn organize(nodes: Vec<Node>, jobs: Vec<Job>) -> (Vec<Partition>, Vec<Job>) {
+        let partitions = Slurm::partitions(nodes);
+
+        Slurm::assign_jobs(partitions, jobs)
+    }
+
+    fn partitions(mut nodes: Vec<Node>) -> Vec<Partition> {
         nodes.sort_by_key(|v| (v.partition.to_string(), v.name.clone()));
 
         let mut partitions: Vec<Partition> = Vec::new();
@@ -40,34 +149,65 @@ impl Slurm {
                 name: node.partition.clone(),
                 nodes: vec![node.clone()],
                 jobs: Vec::new(),
+                users: 0,
             });
         }
 
         // Sort by descending number of nodes
         partitions.sort_by_key(|v| -(v.nodes.len() as isize));
-        Ok(partitions)
+        partitions
     }
 
-    fn collect_jobs(squeue: &str, mut partitions: Vec<Partition>) -> Result<Vec<Partition>> {
-        // FIXME: Warn on unassigned jobs
-        for job in Job::collect(squeue)? {
+    fn assign_jobs(mut partitions: Vec<Partition>, jobs: Vec<Job>) -> (Vec<Partition>, Vec<Job>) {
+        // Usernames, accounts, and node names are shared by many jobs (and by the copies
+        // of jobs listed for each node), and are therefore only allocated once
+        let mut interner = Interner::default();
+
+        let mut unassigned = Vec::new();
+        for mut job in jobs {
+            for value in [&mut job.user, &mut job.account, &mut job.qos] {
+                *value = interner.intern(value);
+            }
+            for node in &mut job.nodelist {
+                *node = interner.intern(node);
+            }
+
+            // Pending jobs submitted to multiple partitions are listed in each of them
+            let mut assigned = false;
             for partition in &mut partitions {
-                if partition.name.same(&job.partition) {
-                    partition.jobs.push(job.clone());
-
-                    if !job.nodelist.is_empty() {
-                        for node in &mut partition.nodes {
-                            if job.nodelist.contains(&node.name) {
-                                node.jobs.push(job.clone());
-                            }
+                if !job.in_partition(&partition.name) {
+                    continue;
+                }
+
+                let mut job = job.clone();
+                if !job.partitions.is_empty() {
+                    job.partition = partition.name.clone();
+                }
+
+                if !job.nodelist.is_empty() {
+                    for node in &mut partition.nodes {
+                        if job.nodelist.iter().any(|v| **v == node.name) {
+                            node.jobs.push(job.clone());
                         }
                     }
-
-                    break;
                 }
+
+                partition.jobs.push(job);
+                assigned = true;
             }
+
+            // Jobs may belong to partitions hidden from the user or to partitions removed
+            // since sinfo was run; these are reported, so that missing jobs are noticed
+            if !assigned {
+                warn!(job = job.id, partition = %job.partition, "job in unknown partition");
+                unassigned.push(job);
+            }
+        }
+
+        for partition in &mut partitions {
+            partition.update_users();
         }
 
-        Ok(partitions)
+        (partitions, unassigned)
     }
 }
@@ -1,5 +1,5 @@
 use std::fmt;
-use std::process::Command;
+use std::io::Read;
 use std::str::{FromStr, Split};
 
 use color_eyre::eyre::Context;
@@ -7,11 +7,18 @@ use color_eyre::Result;
 use serde::{Deserialize, Deserializer};
 
 use serde::de::{self, IntoDeserializer, Visitor};
+use tracing::warn;
 
-use crate::widgets::Utilization;
-
+use super::config::DefaultMem;
+use super::gpus::GpuUsage;
 use super::jobs::Job;
-use super::misc::{format_string, unique_values};
+use super::json::{is_json, parse_sinfo};
+use super::misc::{
+    custom_fields, field_delimiter, format_string, parse_indices, split_custom_values,
+};
+use super::source::Source;
+use super::utilization::Utilization;
+use super::version::SlurmVersion;
 
 /// Summarizes the state of CPUs on a node
 #[derive(Clone, Debug, Default)]
@@ -26,6 +33,47 @@ pub struct CPUState {
     pub total: usize,
 }
 
+/// Number of sockets, cores per socket, and threads per core of a node
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Topology {
+    /// Number of sockets
+    pub sockets: usize,
+    /// Number of cores per socket
+    pub cores: usize,
+    /// Number of hardware threads per core
+    pub threads: usize,
+}
+
+impl Topology {
+    /// Parses a value in the form "S:C:T", e.g. "2:16:2"
+    fn parse(value: &str) -> Option<Topology> {
+        let mut values = value.split(':').map(|v| v.parse::<usize>().ok());
+        let topology = Topology {
+            sockets: values.next()??,
+            cores: values.next()??,
+            threads: values.next()??,
+        };
+
+        values.next().is_none().then_some(topology)
+    }
+
+    /// Total number of cores
+    pub fn total_cores(&self) -> usize {
+        self.sockets * self.cores
+    }
+
+    /// Total number of hardware threads
+    pub fn total_threads(&self) -> usize {
+        self.total_cores() * self.threads
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.sockets, self.cores, self.threads)
+    }
+}
+
 impl<'de> Deserialize<'de> for CPUState {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -71,36 +119,53 @@ impl<'de> Visitor<'de> for CPUStateVisitor {
     }
 }
 
+/// Base state of a node, as reported by `sinfo`
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SlurmState {
+    /// All CPUs are allocated to jobs
     #[serde(alias = "alloc")]
     Allocated,
+    /// Jobs on the node are completing
     #[serde(alias = "comp")]
     Completing,
+    /// Unavailable for use
     Down,
+    /// Unavailable for use at the request of an administrator
     #[serde(alias = "drain")]
     Drained,
+    /// Running jobs, but unavailable for new jobs at the request of an administrator
     #[serde(alias = "drng")]
     Draining,
+    /// Unavailable due to a failure
     Fail,
+    /// Running jobs, but expected to fail
     #[serde(alias = "failg")]
     Failing,
+    /// Not yet available, but defined for future use
     #[serde(alias = "futr")]
     Future,
+    /// No CPUs are allocated to jobs
     Idle,
+    /// Reserved for maintenance
     #[serde(alias = "maint")]
     Maintenance,
+    /// Some CPUs are allocated to jobs
     #[serde(alias = "mix")]
     Mixed,
+    /// Network performance counters are in use
     #[serde(alias = "npc")]
     Perfctrs,
+    /// Powered down
     #[serde(alias = "pow_dn")]
     PowerDown,
+    /// Being powered up
     #[serde(alias = "pow_up")]
     PowerUp,
+    /// Included in an advanced reservation
     #[serde(alias = "resv")]
     Reserved,
+    /// State is not (yet) known to the controller
     #[serde(alias = "unk")]
     Unknown,
 }
@@ -149,9 +214,12 @@ impl fmt::Display for PartitionName {
     }
 }
 
+/// State of a node, e.g. "mixed" or "down*"
 #[derive(Clone, Debug)]
 pub struct NodeState {
+    /// Base state of the node
     pub state: SlurmState,
+    /// False if the node is not responding, indicated by a `*` suffix
     pub responds: bool,
 }
 
@@ -170,6 +238,24 @@ impl NodeState {
         })
     }
 
+    /// Returns a short label used when summarizing the states of multiple nodes;
+    /// nodes not responding are counted as being down
+    pub fn summary_label(&self) -> &'static str {
+        if !self.responds {
+            return "down";
+        }
+
+        match self.state {
+            SlurmState::Idle => "idle",
+            SlurmState::Mixed => "mix",
+            SlurmState::Allocated | SlurmState::Completing => "alloc",
+            SlurmState::Reserved => "resv",
+            SlurmState::Drained | SlurmState::Draining => "drain",
+            SlurmState::Down | SlurmState::Fail | SlurmState::Failing => "down",
+            _ => "other",
+        }
+    }
+
     /// Returns true if the node is available for executing jobs
     pub fn is_available(&self) -> bool {
         self.responds
@@ -194,49 +280,122 @@ impl fmt::Display for NodeState {
     }
 }
 
+/// A node as listed by `sinfo -N`; nodes in multiple partitions are listed once per
+/// partition
 #[derive(Clone, Debug, Deserialize)]
 pub struct Node {
+    /// Name of the node
     #[serde(rename = "NODELIST")]
     pub name: String,
+    /// Partition in which the node is listed
     #[serde(rename = "PARTITION", deserialize_with = "PartitionName::from_str")]
     pub partition: PartitionName,
+    /// State of the node
     #[serde(rename = "STATE", deserialize_with = "NodeState::from_str")]
     pub state: NodeState,
 
+    /// Number of CPUs
     #[serde(rename = "CPUS")]
     pub cpus: usize,
-    #[serde(rename = "CPU_LOAD", deserialize_with = "parse_cpu_load")]
+    /// CPU load; not supported by older versions of Slurm
+    #[serde(rename = "CPU_LOAD", default, deserialize_with = "parse_cpu_load")]
     pub cpu_load: Option<f64>,
+    /// Number of allocated, idle, other, and total CPUs
     #[serde(rename = "CPUS(A/I/O/T)")]
     pub cpu_state: CPUState,
+    /// Sockets, cores, and threads; older captures of `sinfo` output may not include
+    /// this column
+    #[serde(rename = "S:C:T", default, deserialize_with = "parse_topology")]
+    pub topology: Option<Topology>,
 
+    /// Total memory in MB
     #[serde(rename = "MEMORY")]
     pub mem: usize,
-    #[serde(rename = "ALLOCMEM")]
+    /// Allocated memory; not supported by older versions of Slurm
+    #[serde(rename = "ALLOCMEM", default)]
     pub mem_alloc: usize,
-    #[serde(rename = "FREE_MEM", deserialize_with = "parse_free_mem")]
+    /// Free memory; not supported by older versions of Slurm
+    #[serde(rename = "FREE_MEM", default, deserialize_with = "parse_free_mem")]
     pub mem_free: Option<usize>,
 
+    /// Number of GPUs (via GRES)
     #[serde(skip_deserializing)]
     pub gpus: usize,
+    /// Number of GPUs allocated to jobs (via GRES)
     #[serde(skip_deserializing)]
     pub gpus_used: usize,
+    /// Indices of GPUs in use, if reported by `sinfo` (e.g. "gpu:a100:2(IDX:0-1)")
+    #[serde(skip_deserializing)]
+    pub gpus_in_use: Vec<usize>,
+    /// Utilization of each GPU, if collected using a probe (see [`GpuUsage::probe`]);
+    /// empty otherwise
+    #[serde(skip)]
+    pub gpu_usage: Vec<GpuUsage>,
+
+    /// Features available on this node, e.g. "avx512" or "ib"; older captures of
+    /// `sinfo` output may not include this column
+    #[serde(
+        rename = "AVAIL_FEATURES",
+        default,
+        deserialize_with = "parse_features"
+    )]
+    pub features: Vec<String>,
 
     #[serde(rename = "GRES")]
-    gres: String,
-    #[serde(rename = "GRES_USED")]
-    gres_used: String,
+    pub(super) gres: String,
+    /// GRES in use; not supported by older versions of Slurm
+    #[serde(rename = "GRES_USED", default)]
+    pub(super) gres_used: String,
 
+    /// Jobs running on this node; see [`Slurm::organize`](super::Slurm::organize)
     #[serde(skip)]
     pub jobs: Vec<Job>,
+    /// Number of distinct users with jobs on this node; see [`Partition::update_users`]
+    ///
+    /// [`Partition::update_users`]: crate::slurm::Partition::update_users
+    #[serde(skip)]
+    pub users: usize,
+
+    /// Values of the custom fields requested from sinfo (see [`CustomFields`]); empty if
+    /// not available
+    ///
+    /// [`CustomFields`]: crate::slurm::CustomFields
+    #[serde(skip)]
+    pub custom: Vec<String>,
 }
 
 impl Node {
-    pub fn users(&self) -> usize {
-        unique_values(self.jobs.iter().map(|v| &v.user))
+    /// Describes how CPUs are counted on nodes with multiple threads per core, or
+    /// if the number of CPUs does not match the number of cores or threads
+    pub fn topology_warning(&self) -> Option<String> {
+        let topology = self.topology?;
+        if self.cpus == topology.total_threads() && topology.threads > 1 {
+            Some(format!(
+                "CPUs are hardware threads: Each core provides {} CPUs, so tasks may be \
+                 placed on threads of the same core",
+                topology.threads
+            ))
+        } else if self.cpus == topology.total_cores() && topology.threads > 1 {
+            Some(format!(
+                "CPUs are cores (e.g. CR_Core): Each CPU allocated to a job includes {} \
+                 hardware threads",
+                topology.threads
+            ))
+        } else if self.cpus != topology.total_cores() && self.cpus != topology.total_threads() {
+            Some(format!(
+                "Slurm reports {} CPUs, but the node has {} cores and {} hardware threads",
+                self.cpus,
+                topology.total_cores(),
+                topology.total_threads()
+            ))
+        } else {
+            None
+        }
     }
 
-    pub fn cpu_utilization(&self, mem_per_cpu: u64) -> Utilization {
+    /// Returns the utilization of CPUs; idle CPUs are blocked if too little memory is free
+    /// for jobs requesting the default amount of memory, `default_mem`
+    pub fn cpu_utilization(&self, default_mem: DefaultMem) -> Utilization {
         // CPU load is refreshed at a slow pace, resulting in load frequently
         // exceeding the number of CPUs allocated; for this reason the value
         // is capped at the number of CPUs reserved.
@@ -246,32 +405,51 @@ impl Node {
             .min(self.cpu_state.allocated as f64);
 
         // Reserved RAM "blocks" the allocation of CPUs, unless the end-user
-        // explicitly requests less RAM per CPU for a job.
-        let blocked = if mem_per_cpu > 0 {
-            let free_mem = self.mem - self.mem_alloc;
-
+        // explicitly requests less RAM for a job.
+        let free_mem = self.mem.saturating_sub(self.mem_alloc);
+        let blocked = match default_mem {
             // The amount of RAM available may be greater than mem_per_cpu * self.cpus
-            self.cpus.saturating_sub(free_mem / mem_per_cpu as usize) as f64
-        } else {
-            0.0
+            DefaultMem::PerCPU(mem_per_cpu) if mem_per_cpu > 0 => {
+                self.cpus.saturating_sub(free_mem / mem_per_cpu as usize) as f64
+            }
+            // A job requesting the default amount of RAM cannot be started if less RAM is
+            // available; a value of 0 means that jobs are allocated all RAM on the node
+            DefaultMem::PerNode(mem_per_node) => {
+                let mem_per_node = if mem_per_node > 0 {
+                    mem_per_node as usize
+                } else {
+                    self.mem
+                };
+
+                if free_mem < mem_per_node {
+                    self.cpus as f64
+                } else {
+                    0.0
+                }
+            }
+            // DefMemPerGPU only applies to jobs requesting GPUs (see `gpu_utilization`)
+            _ => 0.0,
         };
 
         Utilization {
             utilized,
             allocated: self.cpu_state.allocated as f64,
             blocked: blocked.max(self.cpu_state.allocated as f64),
+            cached: 0.0,
             unavailable: self.cpu_state.other as f64,
             capacity: self.cpu_state.total as f64,
         }
     }
 
+    /// Returns the utilization of memory in MB
     pub fn mem_utilization(&self) -> Utilization {
         // See note regarding CPU load above
         // Free memory includes memory not allocated for Slurm
-        let utilized = self
-            .mem
-            .saturating_sub(self.mem_free.unwrap_or(self.mem))
-            .min(self.mem_alloc) as f64;
+        let used = self.mem.saturating_sub(self.mem_free.unwrap_or(self.mem));
+        let utilized = used.min(self.mem_alloc) as f64;
+        // Jobs are confined to the memory allocated to them, so memory in use beyond
+        // that is assumed to be page cache (or, to a lesser extent, system processes)
+        let cached = used.saturating_sub(self.mem_alloc) as f64;
 
         // Memory is considered "blocked" if there are no CPUs available for allocation
         let (blocked, unavailable) =
@@ -287,81 +465,179 @@ impl Node {
             utilized,
             allocated: self.mem_alloc as f64,
             blocked,
+            cached,
             unavailable,
             capacity: self.mem as f64,
         }
     }
 
-    pub fn gpu_utilization(&self, mem_per_cpu: u64) -> Utilization {
-        let cpu_utilization = self.cpu_utilization(mem_per_cpu);
+    /// Returns the utilization of GPUs; GPUs are blocked if the CPUs or memory of the node
+    /// are exhausted. GPUs are only utilized if their usage was collected using a probe
+    pub fn gpu_utilization(&self, default_mem: DefaultMem) -> Utilization {
+        let cpu_utilization = self.cpu_utilization(default_mem);
+        let free_gpus = self.gpus.saturating_sub(self.gpus_used);
 
         // GPUs are considered blocked if there are no available CPUs assuming default RAM allocations
         let blocked = if cpu_utilization.available() < 1.0 {
-            self.gpus - self.gpus_used
+            free_gpus
+        } else if let DefaultMem::PerGPU(mem_per_gpu @ 1..) = default_mem {
+            // Otherwise GPUs are blocked if there is insufficient RAM for default allocations
+            let free_mem = self.mem.saturating_sub(self.mem_alloc);
+
+            free_gpus.saturating_sub(free_mem / mem_per_gpu as usize)
         } else {
             0
         };
 
+        // GPUs are only considered utilized up to the number allocated to jobs
+        let utilized = self
+            .gpu_usage
+            .iter()
+            .map(|v| v.utilization / 100.0)
+            .sum::<f64>();
+
         Utilization {
-            utilized: 0.0,
+            utilized: utilized.min(self.gpus_used as f64),
             allocated: self.gpus_used as f64,
-            blocked: blocked as f64,
+            // Blocked GPUs are drawn following allocated GPUs
+            blocked: (self.gpus_used + blocked) as f64,
+            cached: 0.0,
             unavailable: 0.0,
             capacity: self.gpus as f64,
         }
     }
 
-    pub fn collect(exe: &str) -> Result<Vec<Node>> {
-        let output = Command::new(exe)
-            .args(["-N", "--Format", &sinfo_format()])
-            .output()
-            .wrap_err("failed to execute squeue")?;
+    /// Collects the nodes of all partitions using `sinfo`, using the JSON output if `json`
+    /// is true, and otherwise the delimited output (see [`Node::sinfo_args`])
+    pub fn collect(
+        source: &Source,
+        exe: &str,
+        json: bool,
+        version: Option<SlurmVersion>,
+        custom: &[String],
+    ) -> Result<Vec<Node>> {
+        let stdout = if json {
+            source.run(exe, &["-N", "--json"])?
+        } else {
+            source.run(exe, &Node::sinfo_args(version, custom))?
+        };
 
-        // TODO: check output.status
-        Self::parse(std::io::Cursor::new(output.stdout))
+        Node::from_reader(stdout.as_slice(), version, custom)
     }
 
-    /// Parses a CSV file into a vector of `Node`
-    fn parse<R>(reader: R) -> Result<Vec<Node>>
-    where
-        R: std::io::Read,
-    {
-        let mut nodes = Vec::new();
-        for node in csv::ReaderBuilder::new()
-            .delimiter(b'|')
+    /// Returns the arguments used to request the delimited output of `sinfo`, i.e. the
+    /// fields supported by `version`, if known, followed by the `custom` fields
+    pub fn sinfo_args(version: Option<SlurmVersion>, custom: &[String]) -> Vec<String> {
+        let format = sinfo_fields(version)
+            .into_iter()
+            .chain(custom.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        vec![
+            "-N".to_string(),
+            "--Format".to_string(),
+            format_string(format.iter()),
+        ]
+    }
+
+    /// Parses the output of `sinfo -N --json`, or the delimited output of `sinfo` run with
+    /// the arguments returned by [`Node::sinfo_args`] for the same `version` and `custom`
+    /// fields. Custom fields are not available in the JSON output
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        version: Option<SlurmVersion>,
+        custom: &[String],
+    ) -> Result<Vec<Node>> {
+        let mut stdout = Vec::new();
+        reader
+            .read_to_end(&mut stdout)
+            .wrap_err("error while reading sinfo output")?;
+
+        // Captured output may be in either format when reading from files
+        if is_json(&stdout) {
+            parse_sinfo(&stdout)
+        } else {
+            let fields = sinfo_fields(version).len();
+            Self::parse(&stdout, fields, custom.len())
+        }
+    }
+
+    /// Parses a CSV file into a vector of `Node`, along with the values of `custom`
+    /// fields requested after the `fields` built-in fields
+    fn parse(stdout: &[u8], fields: usize, custom: usize) -> Result<Vec<Node>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(field_delimiter(stdout))
+            .flexible(true)
             .trim(csv::Trim::All)
-            .from_reader(reader)
-            .deserialize::<Node>()
-        {
-            let mut node = node.wrap_err("error while parsing sinfo output")?;
-            node.gpus = parse_gpus(&node.gres).wrap_err("parsing GRES")?;
-            node.gpus_used = parse_gpus(&node.gres_used).wrap_err("parsing GRES_USED")?;
+            .from_reader(stdout);
+
+        let mut headers = reader.headers()?.clone();
+        let columns = headers.len();
+        let custom = custom_fields(&mut headers, fields, custom);
+
+        let mut nodes = Vec::new();
+        for record in reader.records() {
+            let mut record = record.wrap_err("error while parsing sinfo output")?;
+            // Custom fields (e.g. Reason) may contain `|` in output delimited by `|`
+            if record.len() > columns {
+                warn!(
+                    fields = record.len(),
+                    columns, "skipping node with values containing the delimiter"
+                );
+                continue;
+            }
+
+            let values = split_custom_values(&mut record, custom);
+            let mut node: Node = record
+                .deserialize(Some(&headers))
+                .wrap_err("error while parsing sinfo output")?;
+            node.custom = values;
+            node.update_from_gres()?;
 
             nodes.push(node);
         }
 
         Ok(nodes)
     }
+
+    /// Updates the number of GPUs, and of GPUs in use, from the `gres` fields
+    pub(super) fn update_from_gres(&mut self) -> Result<()> {
+        self.gpus = parse_gpus(&self.gres).wrap_err("parsing GRES")?;
+        self.gpus_used = parse_gpus(&self.gres_used).wrap_err("parsing GRES_USED")?;
+        self.gpus_in_use = parse_gpu_indices(&self.gres_used);
+
+        Ok(())
+    }
 }
 
-/// Generates parameter for the `-F` command-line option for `sinfo`
-fn sinfo_format() -> String {
-    format_string(
-        [
-            "AllocMem",
-            "CPUs",
-            "CPUsLoad",
-            "CPUsState",
-            "FreeMem",
-            "Gres",
-            "GresUsed",
-            "Memory",
-            "NodeList",
-            "Partition",
-            "StateLong",
-        ]
-        .iter(),
-    )
+/// Fields of `sinfo --Format` not supported by older releases of Slurm, along with the
+/// first release known to support them. The corresponding values default to empty values
+const SINFO_OPTIONAL: [(&str, SlurmVersion); 4] = [
+    ("AllocMem", SlurmVersion::new(15, 8, 0)),
+    ("CPUsLoad", SlurmVersion::new(14, 11, 0)),
+    ("FreeMem", SlurmVersion::new(15, 8, 0)),
+    ("GresUsed", SlurmVersion::new(16, 5, 0)),
+];
+
+/// Returns the fields requested using the `-F` command-line option for `sinfo`
+fn sinfo_fields(version: Option<SlurmVersion>) -> Vec<&'static str> {
+    let fields = [
+        "AllocMem",
+        "CPUs",
+        "CPUsLoad",
+        "CPUsState",
+        "Features",
+        "FreeMem",
+        "Gres",
+        "GresUsed",
+        "Memory",
+        "NodeList",
+        "Partition",
+        "SocketCoreThread",
+        "StateLong",
+    ];
+
+    SlurmVersion::supported_fields(version, &fields, &SINFO_OPTIONAL)
 }
 
 fn parse_optional_value<'de, D, T>(name: &str, deserializer: D) -> Result<Option<T>, D::Error>
@@ -393,6 +669,32 @@ where
     parse_optional_value("FREE_MEM", deserializer)
 }
 
+fn parse_topology<'de, D>(deserializer: D) -> Result<Option<Topology>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: &str = Deserialize::deserialize(deserializer)?;
+    if value == "N/A" {
+        return Ok(None);
+    }
+
+    Topology::parse(value)
+        .map(Some)
+        .ok_or_else(|| de::Error::custom(format!("invalid S:C:T: {:?}", value)))
+}
+
+fn parse_features<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: &str = Deserialize::deserialize(deserializer)?;
+    Ok(value
+        .split(',')
+        .filter(|v| !v.is_empty() && *v != "(null)")
+        .map(|v| v.to_string())
+        .collect())
+}
+
 fn parse_gpus(tres: &str) -> Result<usize> {
     for value in tres.split(',') {
         if value.starts_with("gpu:") {
@@ -407,3 +709,36 @@ fn parse_gpus(tres: &str) -> Result<usize> {
 
     Ok(0)
 }
+
+/// Parses the indices of GPUs in use from GRES such as "gpu:a100:1(IDX:0),gpu:v100:2(IDX:2-3)".
+/// Index lists may themselves contain commas, e.g. "gpu:2(IDX:0,2)"
+fn parse_gpu_indices(tres: &str) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (idx, c) in tres
+        .char_indices()
+        .chain(std::iter::once((tres.len(), ',')))
+    {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                let value = &tres[start..idx];
+                if value.starts_with("gpu:") {
+                    if let Some((_, list)) = value.split_once("(IDX:") {
+                        indices.extend(parse_indices(list.trim_end_matches(')')));
+                    }
+                }
+
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
@@ -0,0 +1,63 @@
+use color_eyre::{eyre::eyre, Result};
+
+use super::{details::parse_key_values, source::Source};
+
+/// Locations of the files to which a job writes its standard output and error, as
+/// reported by `scontrol show job`
+#[derive(Clone, Debug)]
+pub struct JobOutput {
+    /// ID of the job
+    pub id: usize,
+    /// Location of stdout, unless not written to a file
+    pub stdout: Option<String>,
+    /// Location of stderr, unless written to the same file as stdout
+    pub stderr: Option<String>,
+}
+
+impl JobOutput {
+    /// Collects the locations of the output of a job using `scontrol show job`
+    pub fn collect(source: &Source, scontrol: &str, id: usize) -> Result<JobOutput> {
+        let stdout = source.run(scontrol, &["show", "job", &id.to_string(), "--oneliner"])?;
+
+        JobOutput::parse(id, &String::from_utf8_lossy(&stdout))
+    }
+
+    fn parse(id: usize, text: &str) -> Result<JobOutput> {
+        let mut output = JobOutput {
+            id,
+            stdout: None,
+            stderr: None,
+        };
+
+        for (key, value) in parse_key_values(text) {
+            // Interactive jobs report an empty value or "(null)"
+            if value.is_empty() || value == "(null)" {
+                continue;
+            }
+
+            match key.as_str() {
+                "StdOut" => output.stdout = Some(value),
+                "StdErr" => output.stderr = Some(value),
+                _ => {}
+            }
+        }
+
+        if output.stderr.is_some() && output.stderr == output.stdout {
+            output.stderr = None;
+        }
+
+        if output.stdout.is_none() && output.stderr.is_none() {
+            return Err(eyre!("job {} does not write output to files", id));
+        }
+
+        Ok(output)
+    }
+}
+
+/// Returns the last (at most) `bytes` bytes of a file. The file is read using `tail`, so
+/// that the output of jobs can also be read on the host specified using `--ssh`
+pub fn tail_file(source: &Source, path: &str, bytes: usize) -> Result<String> {
+    let stdout = source.run_action("tail", &["-c", &bytes.to_string(), "--", path])?;
+
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
@@ -1,16 +1,74 @@
-use crate::slurm::Node;
+use crate::slurm::{BillingWeights, JobState, Node};
 
 use super::{jobs::Job, misc::unique_values, nodes::PartitionName};
 
+/// A partition along with its nodes and jobs
 #[derive(Clone, Debug)]
 pub struct Partition {
+    /// Name of the partition
     pub name: PartitionName,
+    /// Jobs in this partition, including pending jobs
     pub jobs: Vec<Job>,
+    /// Nodes in this partition
     pub nodes: Vec<Node>,
+    /// Number of distinct users with jobs in this partition; see [`Partition::update_users`]
+    pub users: usize,
 }
 
 impl Partition {
-    pub fn users(&self) -> usize {
-        unique_values(self.jobs.iter().map(|v| &v.user))
+    /// Returns the total billable TRES of running jobs, if known
+    pub fn billing(&self) -> Option<f64> {
+        self.jobs
+            .iter()
+            .filter(|v| v.state == JobState::Running)
+            .filter_map(|v| v.billing)
+            .reduce(|a, b| a + b)
     }
+
+    /// Calculates the billable TRES of jobs for which this is not reported by Slurm
+    pub fn update_billing(&mut self, weights: &BillingWeights) {
+        let jobs = self.nodes.iter_mut().flat_map(|v| v.jobs.iter_mut());
+        for job in self.jobs.iter_mut().chain(jobs) {
+            job.update_billing(weights);
+        }
+    }
+
+    /// Counts the distinct users of the jobs in this partition and on each of its nodes;
+    /// counted once when the jobs are collected, rather than whenever these are shown
+    pub fn update_users(&mut self) {
+        self.users = unique_values(self.jobs.iter().map(|v| &v.user));
+        for node in &mut self.nodes {
+            node.users = unique_values(node.jobs.iter().map(|v| &v.user));
+        }
+    }
+
+    /// Returns the number of nodes per summarized state (see [`count_node_states`])
+    pub fn node_states(&self) -> Vec<(&'static str, usize)> {
+        count_node_states(&self.nodes)
+    }
+}
+
+/// Returns the number of nodes per summarized state (see [`NodeState::summary_label`]),
+/// omitting states without any nodes
+///
+/// [`NodeState::summary_label`]: crate::slurm::NodeState::summary_label
+pub fn count_node_states<'a, I>(nodes: I) -> Vec<(&'static str, usize)>
+where
+    I: IntoIterator<Item = &'a Node>,
+{
+    let mut counts: Vec<(&'static str, usize)> =
+        ["idle", "mix", "alloc", "resv", "drain", "down", "other"]
+            .into_iter()
+            .map(|label| (label, 0))
+            .collect();
+
+    for node in nodes {
+        let label = node.state.summary_label();
+        if let Some((_, count)) = counts.iter_mut().find(|(v, _)| *v == label) {
+            *count += 1;
+        }
+    }
+
+    counts.retain(|(_, count)| *count > 0);
+    counts
 }
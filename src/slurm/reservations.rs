@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+
+use super::{details::parse_key_values, misc::expand_hostlist, source::Source};
+
+/// Format of timestamps reported by `scontrol`, e.g. "2026-10-20T08:00:00"
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A reservation of nodes as reported by `scontrol show reservation`
+#[derive(Clone, Debug)]
+pub struct Reservation {
+    /// Name of the reservation
+    pub name: String,
+    /// Start of the reservation in local time
+    pub start: NaiveDateTime,
+    /// End of the reservation in local time
+    pub end: NaiveDateTime,
+    /// Nodes included in the reservation
+    pub nodes: Vec<String>,
+    /// Flags such as "MAINT" or "SPEC_NODES"
+    pub flags: Vec<String>,
+}
+
+impl Reservation {
+    /// Collects reservations using `scontrol show reservation`
+    pub fn collect(source: &Source, scontrol: &str) -> Result<Vec<Reservation>> {
+        let stdout = source.run(scontrol, &["show", "reservation", "--oneliner"])?;
+        let stdout = String::from_utf8_lossy(&stdout);
+
+        Ok(stdout.lines().filter_map(Reservation::parse).collect())
+    }
+
+    /// Parses a line in the form "ReservationName=maint StartTime=... Nodes=node[01-03] ..."
+    fn parse(line: &str) -> Option<Reservation> {
+        let mut name = None;
+        let mut start = None;
+        let mut end = None;
+        let mut nodes = Vec::new();
+        let mut flags = Vec::new();
+
+        for (key, value) in parse_key_values(line) {
+            match key.as_str() {
+                "ReservationName" => name = Some(value),
+                "StartTime" => start = NaiveDateTime::parse_from_str(&value, TIME_FORMAT).ok(),
+                "EndTime" => end = NaiveDateTime::parse_from_str(&value, TIME_FORMAT).ok(),
+                "Nodes" if value != "(null)" => nodes = expand_hostlist(&value),
+                "Flags" => flags = value.split(',').map(|v| v.to_string()).collect(),
+                _ => {}
+            }
+        }
+
+        Some(Reservation {
+            name: name?,
+            start: start?,
+            end: end?,
+            nodes,
+            flags,
+        })
+    }
+
+    /// Returns true if this is a maintenance reservation
+    pub fn is_maintenance(&self) -> bool {
+        self.flags.iter().any(|v| v == "MAINT")
+    }
+}
@@ -0,0 +1,114 @@
+use color_eyre::Result;
+
+use super::{misc::Table, source::Source};
+
+/// Usage of an association (an account, or a user in an account) compared to the limits
+/// on the TRES minutes that may be consumed by the association (`GrpTRESMins`)
+#[derive(Clone, Debug, Default)]
+pub struct AssociationUsage {
+    /// Name of the cluster
+    pub cluster: String,
+    /// Name of the account
+    pub account: String,
+    /// Username, or an empty string if the association is the account itself, in which
+    /// case the usage and limits cover all users of the account
+    pub user: String,
+    /// TRES minutes consumed, e.g. `("cpu", 12000)`, as reported by `sshare`. The usage
+    /// decays over time if the cluster is configured with a `PriorityDecayHalfLife`
+    pub used: Vec<(String, u64)>,
+    /// Limits on the TRES minutes consumed, e.g. `("cpu", 100000)`, as reported by
+    /// `sacctmgr`; empty if the association is not limited
+    pub limits: Vec<(String, u64)>,
+}
+
+impl AssociationUsage {
+    /// Collects the usage and limits of the associations of `user`, as well as those of
+    /// the accounts to which the user belongs, using `sshare` and `sacctmgr`
+    pub fn collect(
+        source: &Source,
+        sshare: &str,
+        sacctmgr: &str,
+        user: &str,
+    ) -> Result<Vec<AssociationUsage>> {
+        let stdout = source.run(
+            sshare,
+            &[
+                "--parsable2",
+                "--users",
+                user,
+                "--format",
+                "Cluster,Account,User,GrpTRESRaw",
+            ],
+        )?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let rows = Table::parse(&stdout)?
+            .rows(["Cluster", "Account", "User", "GrpTRESRaw"])
+            .map(|[cluster, account, user, used]| AssociationUsage {
+                cluster: cluster.to_string(),
+                account: account.to_string(),
+                user: user.to_string(),
+                used: parse_tres(used),
+                limits: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        // `sshare` also lists the parents of the user's accounts, which are not shown
+        let accounts = rows
+            .iter()
+            .filter(|v| v.user == user)
+            .map(|v| (v.cluster.clone(), v.account.clone()))
+            .collect::<Vec<_>>();
+        let mut associations = rows
+            .into_iter()
+            .filter(|v| {
+                (v.user.is_empty() || v.user == user)
+                    && accounts.contains(&(v.cluster.clone(), v.account.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        // Account associations cannot be selected by user, so all associations are listed
+        let stdout = source.run(
+            sacctmgr,
+            &[
+                "show",
+                "associations",
+                "--parsable2",
+                "format=Cluster,Account,User,GrpTRESMins",
+            ],
+        )?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let table = Table::parse(&stdout)?;
+        for [cluster, account, user, limits] in
+            table.rows(["Cluster", "Account", "User", "GrpTRESMins"])
+        {
+            let association = associations
+                .iter_mut()
+                .find(|v| v.cluster == cluster && v.account == account && v.user == user);
+            if let Some(association) = association {
+                association.limits = parse_tres(limits);
+            }
+        }
+
+        // Accounts are listed before their users
+        associations.sort_by(|a, b| (&a.account, &a.user).cmp(&(&b.account, &b.user)));
+        Ok(associations)
+    }
+
+    /// Returns the TRES minutes of the given kind (e.g. "cpu") consumed by the association
+    pub fn used(&self, tres: &str) -> u64 {
+        self.used
+            .iter()
+            .find(|v| v.0 == tres)
+            .map(|v| v.1)
+            .unwrap_or_default()
+    }
+}
+
+/// Parses a list of TRES such as "cpu=12000,mem=4000,gres/gpu=10"; values that are not
+/// numbers (e.g. "N/A") are skipped
+pub(super) fn parse_tres(text: &str) -> Vec<(String, u64)> {
+    text.split(',')
+        .filter_map(|v| v.split_once('='))
+        .filter_map(|(name, value)| Some((name.trim().to_string(), value.trim().parse().ok()?)))
+        .collect()
+}
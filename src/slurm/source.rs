@@ -0,0 +1,430 @@
+use std::{
+    fmt::Debug,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{
+    eyre::{bail, eyre, Context},
+    Result,
+};
+use tracing::{debug, warn};
+
+/// Kills commands executed by a [`Source::Watched`] that exceed its timeout, or on
+/// request, e.g. when the worker running them is dropped. Clones share the same state,
+/// so that commands may be cancelled from another thread
+#[derive(Clone, Debug, Default)]
+pub struct Watchdog {
+    /// Set once running commands should be killed and no further commands started
+    cancelled: Arc<AtomicBool>,
+    /// Maximum run time of commands; commands may run indefinitely if `None`
+    timeout: Option<Duration>,
+}
+
+impl Watchdog {
+    /// Creates a watchdog that kills commands running longer than `timeout`, so that a
+    /// single hung command (e.g. due to an unresponsive controller) cannot stall updates
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            timeout,
+        }
+    }
+
+    /// Returns the maximum run time of commands, if limited
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Kills running commands and prevents further commands from being started, so that
+    /// slurmboard can exit without waiting for (or leaving behind) in-flight commands
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if commands have been cancelled
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Error reported when a command was killed by [`Watchdog::cancel`]
+#[derive(Clone, Copy, Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Error reported when a command was killed for exceeding the timeout
+#[derive(Clone, Copy, Debug)]
+pub struct Timeout(pub Duration);
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "killed after {}s without finishing",
+            self.0.as_secs_f64()
+        )
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Provides the output of Slurm commands without running them, e.g. canned output used
+/// to test the parsing of that output (see [`Source::Backend`])
+pub trait Backend: Debug + Send + Sync {
+    /// Returns the standard output of a command, or an error if the command failed
+    fn run(&self, exe: &str, args: &[&str]) -> Result<Vec<u8>>;
+}
+
+/// Determines how the output of Slurm commands is obtained
+#[derive(Clone, Debug, Default)]
+pub enum Source {
+    /// Slurm commands are executed on the local host
+    #[default]
+    Local,
+    /// Slurm commands are executed on a remote host (`[user@]host`) using `ssh`
+    Ssh(String),
+    /// Output is read from files previously captured in a directory. Files are named
+    /// after the command, e.g. `sinfo.txt` or `scontrol_ping.txt` (see [`file_name`])
+    Files(PathBuf),
+    /// Slurm commands are executed using another source and their output is written
+    /// to files in a directory, using the same naming scheme as [`Source::Files`]
+    Record(PathBuf, Box<Source>),
+    /// Output is provided by a [`Backend`]; commands are never executed
+    Backend(Arc<dyn Backend>),
+    /// Commands are executed using another source (see [`Source::with_watchdog`]), but
+    /// are killed if they exceed the timeout of the [`Watchdog`] or once it is cancelled
+    Watched(Watchdog, Box<Source>),
+}
+
+impl Source {
+    /// Returns a copy of the source whose commands are killed if they exceed the timeout
+    /// of `watchdog` or once it is cancelled, replacing any previous watchdog. Sources
+    /// that never execute commands (e.g. [`Source::Files`]) are returned as is
+    pub fn with_watchdog(self, watchdog: Watchdog) -> Source {
+        match self {
+            Source::Local | Source::Ssh(_) => Source::Watched(watchdog, Box::new(self)),
+            Source::Watched(_, source) => Source::Watched(watchdog, source),
+            Source::Record(root, source) => {
+                Source::Record(root, Box::new(source.with_watchdog(watchdog)))
+            }
+            Source::Files(_) | Source::Backend(_) => self,
+        }
+    }
+
+    /// Executes a Slurm command and returns its standard output. A non-zero exit
+    /// status is reported as an error that includes the output written to stderr
+    pub fn run<S: AsRef<str>>(&self, exe: &str, args: &[S]) -> Result<Vec<u8>> {
+        self.run_with(None, exe, args)
+    }
+
+    /// Executes a Slurm command and returns its standard output without checking the exit
+    /// status
+    pub fn run_unchecked<S: AsRef<str>>(&self, exe: &str, args: &[S]) -> Result<Vec<u8>> {
+        self.run_unchecked_with(None, exe, args)
+    }
+
+    /// Executes a command that modifies the state of the cluster, such as `sbatch`.
+    /// Such commands are never recorded and cannot be run when reading from files
+    pub fn run_action<S: AsRef<str>>(&self, exe: &str, args: &[S]) -> Result<Vec<u8>> {
+        self.run_action_with(None, exe, args)
+    }
+
+    /// Runs a user-defined shell command, locally or on the host specified via `--ssh`,
+    /// and returns its output regardless of the exit status. Like actions, such commands
+    /// are never recorded
+    pub fn run_shell(&self, command: &str) -> Result<Output> {
+        self.run_shell_with(None, command)
+    }
+
+    fn run_with<S: AsRef<str>>(
+        &self,
+        watchdog: Option<&Watchdog>,
+        exe: &str,
+        args: &[S],
+    ) -> Result<Vec<u8>> {
+        match self {
+            Source::Local => run_checked(watchdog, None, exe, args),
+            Source::Ssh(host) => run_checked(watchdog, Some(host), exe, args),
+            Source::Files(root) => read_file(root, exe, args),
+            Source::Record(root, source) => {
+                write_file(root, exe, args, source.run_with(watchdog, exe, args)?)
+            }
+            Source::Backend(backend) => backend.run(exe, &as_strs(args)),
+            Source::Watched(watchdog, source) => source.run_with(Some(watchdog), exe, args),
+        }
+    }
+
+    fn run_unchecked_with<S: AsRef<str>>(
+        &self,
+        watchdog: Option<&Watchdog>,
+        exe: &str,
+        args: &[S],
+    ) -> Result<Vec<u8>> {
+        match self {
+            Source::Local => Ok(execute(watchdog, None, exe, args)?.stdout),
+            Source::Ssh(host) => Ok(execute(watchdog, Some(host), exe, args)?.stdout),
+            Source::Files(root) => read_file(root, exe, args),
+            Source::Record(root, source) => write_file(
+                root,
+                exe,
+                args,
+                source.run_unchecked_with(watchdog, exe, args)?,
+            ),
+            Source::Backend(backend) => backend.run(exe, &as_strs(args)),
+            Source::Watched(watchdog, source) => {
+                source.run_unchecked_with(Some(watchdog), exe, args)
+            }
+        }
+    }
+
+    fn run_action_with<S: AsRef<str>>(
+        &self,
+        watchdog: Option<&Watchdog>,
+        exe: &str,
+        args: &[S],
+    ) -> Result<Vec<u8>> {
+        match self {
+            Source::Local => run_checked(watchdog, None, exe, args),
+            Source::Ssh(host) => run_checked(watchdog, Some(host), exe, args),
+            Source::Record(_, source) => source.run_action_with(watchdog, exe, args),
+            Source::Backend(backend) => backend.run(exe, &as_strs(args)),
+            Source::Files(_) => bail!("{:?} cannot be run when reading output from files", exe),
+            Source::Watched(watchdog, source) => source.run_action_with(Some(watchdog), exe, args),
+        }
+    }
+
+    fn run_shell_with(&self, watchdog: Option<&Watchdog>, command: &str) -> Result<Output> {
+        match self {
+            Source::Local => execute(watchdog, None, "sh", &["-c", command]),
+            Source::Ssh(host) => execute(watchdog, Some(host), "sh", &["-c", command]),
+            Source::Record(_, source) => source.run_shell_with(watchdog, command),
+            Source::Backend(backend) => Ok(Output {
+                status: ExitStatus::default(),
+                stdout: backend.run("sh", &["-c", command])?,
+                stderr: Vec::new(),
+            }),
+            Source::Files(_) => bail!("commands cannot be run when reading output from files"),
+            Source::Watched(watchdog, source) => source.run_shell_with(Some(watchdog), command),
+        }
+    }
+}
+
+/// Returns the name of the file used to store the output of a command; this
+/// consists of name of the executable and any leading, non-option arguments
+pub fn file_name<S: AsRef<str>>(exe: &str, args: &[S]) -> String {
+    let exe = Path::new(exe)
+        .file_name()
+        .map(|v| v.to_string_lossy())
+        .unwrap_or_else(|| exe.into());
+
+    let mut name = exe.to_string();
+    for arg in args.iter().map(AsRef::as_ref) {
+        if arg.starts_with('-') {
+            break;
+        }
+
+        name.push('_');
+        name.push_str(arg);
+    }
+
+    name.push_str(".txt");
+    name
+}
+
+fn as_strs<S: AsRef<str>>(args: &[S]) -> Vec<&str> {
+    args.iter().map(AsRef::as_ref).collect()
+}
+
+/// Quotes a string for use as a single argument in a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Executes a command locally or, if a host is specified, on that host using `ssh`
+fn execute<S: AsRef<str>>(
+    watchdog: Option<&Watchdog>,
+    host: Option<&str>,
+    exe: &str,
+    args: &[S],
+) -> Result<Output> {
+    let started = Instant::now();
+    let result = execute_command(watchdog, host, exe, args);
+    let command = || {
+        let args = args.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        format!("{} {}", exe, args.join(" "))
+    };
+
+    match &result {
+        Ok(output) => debug!(
+            command = command(),
+            host,
+            status = %output.status,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            stdout_bytes = output.stdout.len(),
+            "command finished"
+        ),
+        Err(error) => warn!(
+            command = command(),
+            host,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "command failed: {:#}",
+            error
+        ),
+    }
+
+    result
+}
+
+fn execute_command<S: AsRef<str>>(
+    watchdog: Option<&Watchdog>,
+    host: Option<&str>,
+    exe: &str,
+    args: &[S],
+) -> Result<Output> {
+    match host {
+        None => {
+            let mut command = Command::new(exe);
+            command.args(args.iter().map(AsRef::as_ref));
+            output(watchdog, command).wrap_err_with(|| format!("failed to execute {:?}", exe))
+        }
+        Some(host) => {
+            // The remote command is interpreted by the remote shell, so arguments
+            // must be quoted; format strings for sinfo/squeue for example contain `|`
+            let mut command = shell_quote(exe);
+            for arg in args {
+                command.push(' ');
+                command.push_str(&shell_quote(arg.as_ref()));
+            }
+
+            // BatchMode prevents ssh from prompting for passwords, which would
+            // interfere with the terminal user interface
+            let mut ssh = Command::new("ssh");
+            ssh.args(["-o", "BatchMode=yes", host, "--", &command]);
+            output(watchdog, ssh)
+                .wrap_err_with(|| format!("failed to execute {:?} on {:?} via ssh", exe, host))
+        }
+    }
+}
+
+/// Runs a command to completion and collects its output like [`Command::output`],
+/// but kills the command if it exceeds the timeout of the `watchdog`, or if the
+/// `watchdog` is cancelled
+fn output(watchdog: Option<&Watchdog>, mut command: Command) -> Result<Output> {
+    let timeout = watchdog.and_then(Watchdog::timeout);
+    let cancelled = || watchdog.is_some_and(Watchdog::cancelled);
+    if cancelled() {
+        return Err(Cancelled.into());
+    }
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Pipes are drained in the background, since commands may block on full pipes
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            buffer
+        })
+    };
+    let stdout = read_pipe(child.stdout.take().map(|v| Box::new(v) as _));
+    let stderr = read_pipe(child.stderr.take().map(|v| Box::new(v) as _));
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        let error = match timeout {
+            _ if cancelled() => Cancelled.into(),
+            Some(timeout) if start.elapsed() >= timeout => {
+                warn!(
+                    program = ?command.get_program(),
+                    timeout_ms = timeout.as_millis() as u64,
+                    "killing command that exceeded the timeout"
+                );
+                Timeout(timeout).into()
+            }
+            _ => {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+        };
+
+        // Reader threads are left to finish on their own, since processes spawned by
+        // the command may keep the pipes open after the command itself is killed
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(error);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn run_checked<S: AsRef<str>>(
+    watchdog: Option<&Watchdog>,
+    host: Option<&str>,
+    exe: &str,
+    args: &[S],
+) -> Result<Vec<u8>> {
+    let output = execute(watchdog, host, exe, args)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        warn!(exe, status = %output.status, stderr, "command terminated unsuccessfully");
+
+        return Err(if stderr.is_empty() {
+            eyre!("{:?} terminated with {}", exe, output.status)
+        } else {
+            eyre!("{:?} terminated with {}: {}", exe, output.status, stderr)
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+fn read_file<S: AsRef<str>>(root: &Path, exe: &str, args: &[S]) -> Result<Vec<u8>> {
+    let path = root.join(file_name(exe, args));
+    debug!(?path, "reading captured output");
+
+    fs::read(&path).wrap_err_with(|| format!("failed to read {:?}", path))
+}
+
+fn write_file<S: AsRef<str>>(
+    root: &Path,
+    exe: &str,
+    args: &[S],
+    stdout: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let path = root.join(file_name(exe, args));
+    fs::write(&path, &stdout).wrap_err_with(|| format!("failed to write {:?}", path))?;
+
+    Ok(stdout)
+}
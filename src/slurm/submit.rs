@@ -0,0 +1,134 @@
+use std::{fs, path::Path, path::PathBuf};
+
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
+
+use super::source::Source;
+
+/// Returns the (non-hidden) files in a directory of sbatch scripts, sorted by name
+pub fn list_scripts(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut scripts = Vec::new();
+    for entry in fs::read_dir(root).wrap_err_with(|| format!("failed to read {:?}", root))? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|v| v.to_string_lossy().starts_with('.'));
+
+        if path.is_file() && !hidden {
+            scripts.push(path);
+        }
+    }
+
+    scripts.sort();
+    Ok(scripts)
+}
+
+/// Submits a batch script using `sbatch` and returns the message printed by
+/// `sbatch`, e.g. "Submitted batch job 12345"
+pub fn submit_script(source: &Source, sbatch: &str, script: &Path) -> Result<String> {
+    let stdout = source.run_action(sbatch, &[script.to_string_lossy()])?;
+
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+/// Cancels jobs using `scancel`
+pub fn cancel_jobs(source: &Source, scancel: &str, ids: &[usize]) -> Result<()> {
+    let ids = ids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    source.run_action(scancel, &ids)?;
+
+    Ok(())
+}
+
+/// Runs `scontrol <command> <id>`, e.g. to hold, release, or requeue a job
+pub fn control_job(source: &Source, scontrol: &str, command: &str, id: usize) -> Result<()> {
+    source.run_action(scontrol, &[command.to_string(), id.to_string()])?;
+
+    Ok(())
+}
+
+/// Changes the time limit of a job using `scontrol update`. See [`parse_time_limit`]
+pub fn update_time_limit(source: &Source, scontrol: &str, id: usize, limit: &str) -> Result<()> {
+    let args = [
+        "update".to_string(),
+        "job".to_string(),
+        id.to_string(),
+        format!("TimeLimit={}", limit),
+    ];
+    source.run_action(scontrol, &args)?;
+
+    Ok(())
+}
+
+/// Drains a node for the given reason, or resumes the node if `reason` is `None`, using
+/// `scontrol update`
+pub fn update_node_state(
+    source: &Source,
+    scontrol: &str,
+    node: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    let mut args = vec!["update".to_string(), format!("NodeName={}", node)];
+    match reason {
+        Some(reason) => {
+            args.push("State=DRAIN".into());
+            args.push(format!("Reason={}", reason));
+        }
+        None => args.push("State=RESUME".into()),
+    }
+    source.run_action(scontrol, &args)?;
+
+    Ok(())
+}
+
+/// Validates a time limit accepted by `scontrol update`, i.e. "minutes",
+/// "minutes:seconds", "hours:minutes:seconds", "days-hours", "days-hours:minutes",
+/// "days-hours:minutes:seconds", or "UNLIMITED". Durations may be prefixed with '+' or
+/// '-' to extend or reduce the current time limit. Returns the trimmed time limit
+pub fn parse_time_limit(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("unlimited") || value.eq_ignore_ascii_case("infinite") {
+        return Ok(value.to_uppercase());
+    }
+
+    let duration = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let (days, time) = match duration.split_once('-') {
+        Some((days, time)) => (Some(days), time),
+        None => (None, duration),
+    };
+
+    let fields = time.split(':').collect::<Vec<_>>();
+    let valid = days.is_none_or(is_number)
+        && fields.iter().all(|v| is_number(v))
+        && match (days, fields.as_slice()) {
+            // Days may be followed by hours, hours:minutes, or hours:minutes:seconds
+            (Some(_), [hours, rest @ ..]) if rest.len() <= 2 => {
+                below(hours, 24) && rest.iter().all(|v| below(v, 60))
+            }
+            // Minutes, minutes:seconds, or hours:minutes:seconds
+            (None, [_]) => true,
+            (None, [_, seconds]) => below(seconds, 60),
+            (None, [_, minutes, seconds]) => below(minutes, 60) && below(seconds, 60),
+            _ => false,
+        };
+
+    if !valid {
+        bail!(
+            "invalid time limit {:?}; expected e.g. \"90\", \"12:00:00\", \"2-00:00:00\", \
+            \"+60\", or \"UNLIMITED\"",
+            value
+        );
+    }
+
+    Ok(value.to_string())
+}
+
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|v| v.is_ascii_digit())
+}
+
+/// Returns true if a (valid) number is smaller than `limit`
+fn below(value: &str, limit: u64) -> bool {
+    value.parse::<u64>().is_ok_and(|v| v < limit)
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use color_eyre::Result;
+
+use super::{misc::Table, source::Source};
+
+/// Maximum number of users listed by [`UsageReport::collect`]
+const TOP_USERS: usize = 10;
+
+/// Period covered by a [`UsageReport`], ending at the time the report was collected
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReportPeriod {
+    /// The last 24 hours
+    Day,
+    /// The last 7 days
+    #[default]
+    Week,
+    /// The last 30 days
+    Month,
+}
+
+impl ReportPeriod {
+    /// All periods, from shortest to longest
+    pub const ALL: [ReportPeriod; 3] = [ReportPeriod::Day, ReportPeriod::Week, ReportPeriod::Month];
+
+    /// Returns the start of the period as an argument of `sreport`, e.g. "start=now-7days"
+    fn start(self) -> &'static str {
+        match self {
+            ReportPeriod::Day => "start=now-1days",
+            ReportPeriod::Week => "start=now-7days",
+            ReportPeriod::Month => "start=now-30days",
+        }
+    }
+}
+
+impl fmt::Display for ReportPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportPeriod::Day => write!(f, "day"),
+            ReportPeriod::Week => write!(f, "week"),
+            ReportPeriod::Month => write!(f, "month"),
+        }
+    }
+}
+
+/// Utilization of a cluster during a [`ReportPeriod`], as reported by `sreport cluster
+/// utilization`, in percent of the total CPU time
+#[derive(Clone, Debug, Default)]
+pub struct ClusterUsage {
+    /// Name of the cluster
+    pub cluster: String,
+    /// CPU time allocated to jobs
+    pub allocated: f64,
+    /// CPU time of nodes that were down
+    pub down: f64,
+    /// CPU time of nodes that were powered down or otherwise planned to be unavailable
+    pub planned_down: f64,
+    /// CPU time that was neither allocated nor down
+    pub idle: f64,
+    /// CPU time that was reserved for pending jobs or by reservations; reported as
+    /// "Reserved" by older versions of Slurm
+    pub planned: f64,
+}
+
+/// A user with a large share of the CPU time used during a [`ReportPeriod`], as reported
+/// by `sreport user top`. Users are listed once for each of their accounts
+#[derive(Clone, Debug, Default)]
+pub struct UserUsage {
+    /// Name of the cluster
+    pub cluster: String,
+    /// Username
+    pub login: String,
+    /// Full name of the user, if known
+    pub name: String,
+    /// Account to which the CPU time was charged
+    pub account: String,
+    /// CPU time used, in percent of the total CPU time of the cluster
+    pub used: f64,
+}
+
+/// Historical utilization of the cluster and the users with the largest usage, as
+/// recorded by the Slurm database daemon and reported by `sreport`
+#[derive(Clone, Debug, Default)]
+pub struct UsageReport {
+    /// Period covered by the report
+    pub period: ReportPeriod,
+    /// Utilization of each cluster known to the database
+    pub clusters: Vec<ClusterUsage>,
+    /// Users with the largest usage, in descending order
+    pub top_users: Vec<UserUsage>,
+}
+
+impl UsageReport {
+    /// Collects the utilization of the cluster and the top users during `period` using
+    /// `sreport`, which requires the Slurm database daemon (slurmdbd)
+    pub fn collect(source: &Source, sreport: &str, period: ReportPeriod) -> Result<UsageReport> {
+        let stdout = source.run(
+            sreport,
+            &[
+                "cluster",
+                "utilization",
+                "--parsable2",
+                "-t",
+                "percent",
+                period.start(),
+                "end=now",
+            ],
+        )?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let columns = [
+            "Cluster",
+            "Allocated",
+            "Down",
+            "PLND Down",
+            "Idle",
+            "Planned",
+            "Reserved",
+        ];
+        let clusters = Table::parse(&stdout)?
+            .rows(columns)
+            .map(|row| ClusterUsage {
+                cluster: row[0].to_string(),
+                allocated: percent(row[1]),
+                down: percent(row[2]),
+                planned_down: percent(row[3]),
+                idle: percent(row[4]),
+                planned: percent(row[5]).max(percent(row[6])),
+            })
+            .collect();
+
+        let top = format!("TopCount={}", TOP_USERS);
+        let stdout = source.run(
+            sreport,
+            &[
+                "user",
+                "top",
+                "--parsable2",
+                "-t",
+                "percent",
+                period.start(),
+                "end=now",
+                &top,
+            ],
+        )?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        let columns = ["Cluster", "Login", "Proper Name", "Account", "Used"];
+        let mut top_users = Table::parse(&stdout)?
+            .rows(columns)
+            .map(|row| UserUsage {
+                cluster: row[0].to_string(),
+                login: row[1].to_string(),
+                name: row[2].to_string(),
+                account: row[3].to_string(),
+                used: percent(row[4]),
+            })
+            .collect::<Vec<_>>();
+        top_users.sort_by(|a, b| b.used.total_cmp(&a.used));
+
+        Ok(UsageReport {
+            period,
+            clusters,
+            top_users,
+        })
+    }
+}
+
+/// Parses a percentage such as "45.23%"; missing or invalid values are reported as 0
+fn percent(value: &str) -> f64 {
+    value.trim_end_matches('%').trim().parse().unwrap_or(0.0)
+}
@@ -0,0 +1,72 @@
+use std::iter::Sum;
+
+/// Resources (CPUs, memory, or GPUs) of a node or partition, broken down by their use
+#[derive(Debug, Default)]
+pub struct Utilization {
+    /// Actual utilization; should normally be less than reserved, but may exceed it
+    /// due to resource usage by system processes
+    pub utilized: f64,
+    /// Amount of resources allocated to users. Must be less or equal to capacity
+    pub allocated: f64,
+    /// Amount of resources "blocked" due to (over)allocation of linked resources;
+    /// CPUs may for example be "blocked" due to (over)allocation of RAM, unless a
+    /// user explicitly asks for less than the default amount of RAM per CPU.
+    pub blocked: f64,
+    /// Amount of unallocated resources used for caching, i.e. memory used by the page
+    /// cache; shown following allocated and blocked resources
+    pub cached: f64,
+    /// Amount of resources unavailable for other reasons (node down)
+    pub unavailable: f64,
+    /// Total amount of resources available
+    pub capacity: f64,
+}
+
+impl Utilization {
+    /// Returns the amount of resources that are neither allocated, blocked, nor unavailable
+    pub fn available(&self) -> f64 {
+        self.capacity - (self.allocated + self.blocked + self.unavailable)
+    }
+
+    /// Renders allocated and total resources, followed by the number of unallocated
+    /// resources that are blocked, if any, e.g. `37/64 (12 blk)`
+    pub fn to_numbers<F: Fn(f64) -> String>(&self, format: F) -> String {
+        if self.capacity <= 0.0 {
+            return String::new();
+        }
+
+        let mut text = format!("{}/{}", format(self.allocated), format(self.capacity));
+        // Blocked resources are drawn following allocated resources
+        let blocked = self.blocked - self.allocated;
+        if blocked > 0.0 {
+            text.push_str(&format!(" ({} blk)", format(blocked)));
+        }
+
+        text
+    }
+
+    /// Renders the percentage of allocated resources, e.g. `81%`
+    pub fn to_percentage(&self) -> String {
+        if self.capacity <= 0.0 {
+            return String::new();
+        }
+
+        format!("{:.0}%", 100.0 * self.allocated / self.capacity)
+    }
+}
+
+/// Implements the sum operator for Utilization objects
+/// This is used for generating partition overviews
+impl Sum for Utilization {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let mut result = Utilization::default();
+        for it in iter {
+            result.utilized += it.utilized;
+            result.allocated += it.allocated;
+            result.blocked += it.blocked;
+            result.cached += it.cached;
+            result.unavailable += it.unavailable;
+            result.capacity += it.capacity;
+        }
+        result
+    }
+}
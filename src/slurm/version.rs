@@ -0,0 +1,74 @@
+use std::fmt;
+
+use color_eyre::{eyre::eyre, Result};
+
+use super::source::Source;
+
+/// Version of Slurm as reported by `sinfo --version`, e.g. "slurm 23.02.7"
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SlurmVersion {
+    /// Major version, e.g. 23 (the year of the release)
+    pub major: u32,
+    /// Minor version, e.g. 2 (the month of the release)
+    pub minor: u32,
+    /// Patch level
+    pub patch: u32,
+}
+
+impl SlurmVersion {
+    /// Constructs a version from its components
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Determines the version of Slurm; the output cannot be captured using `--record`,
+    /// since it would overwrite the regular output of `sinfo`
+    pub fn collect(source: &Source, sinfo: &str) -> Result<SlurmVersion> {
+        let stdout = source.run(sinfo, &["--version"])?;
+        let stdout = String::from_utf8_lossy(&stdout);
+
+        Self::parse(stdout.trim()).ok_or_else(|| eyre!("unexpected version {:?}", stdout.trim()))
+    }
+
+    /// Parses versions such as "slurm 23.02.7", "slurm 24.05.0-0rc1", and "slurm-wlm 22.05.8"
+    fn parse(value: &str) -> Option<SlurmVersion> {
+        let (_, version) = value.rsplit_once(' ')?;
+        let mut values = version.split(['.', '-']).map(|v| v.parse::<u32>().ok());
+
+        Some(SlurmVersion {
+            major: values.next()??,
+            minor: values.next()??,
+            patch: values.next().flatten().unwrap_or(0),
+        })
+    }
+
+    /// Removes `--Format` fields that are not supported by this version of Slurm, given
+    /// the release in which each optional field was introduced. The version is unknown when
+    /// reading from files, in which case all fields are used
+    pub fn supported_fields<'a>(
+        version: Option<SlurmVersion>,
+        fields: &[&'a str],
+        optional: &[(&str, SlurmVersion)],
+    ) -> Vec<&'a str> {
+        fields
+            .iter()
+            .copied()
+            .filter(
+                |field| match (version, optional.iter().find(|v| v.0 == *field)) {
+                    (Some(version), Some((_, introduced))) => version >= *introduced,
+                    _ => true,
+                },
+            )
+            .collect()
+    }
+}
+
+impl fmt::Display for SlurmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:02}.{}", self.major, self.minor, self.patch)
+    }
+}
@@ -1,14 +1,69 @@
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 
-use color_eyre::{config::HookBuilder, eyre, Result};
+use color_eyre::{config::HookBuilder, eyre::eyre, Result};
 use ratatui::backend::Backend;
 use ratatui::Terminal;
-use std::io;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
 use std::panic;
+use std::path::Path;
+
+use crate::args::TerminalOutput;
 
 use crate::event::EventHandler;
 use crate::ui::UI;
+use crate::widgets::write_hyperlinks;
+
+/// Default terminal device, used unless another output is selected using `--tty`
+const DEFAULT_TTY: &str = "/dev/tty";
+
+/// Output to which the terminal interface is written
+#[derive(Debug)]
+pub enum Output {
+    Stdout(io::Stdout),
+    Stderr(io::Stderr),
+    Device(File),
+}
+
+impl Output {
+    /// Opens the selected output. By default `/dev/tty` is used, so that the interface
+    /// is unaffected by redirection of stdout and stderr, falling back to stderr if
+    /// there is no controlling terminal
+    pub fn open(output: Option<&TerminalOutput>) -> Result<Self> {
+        match output {
+            Some(TerminalOutput::Stdout) => Ok(Output::Stdout(io::stdout())),
+            Some(TerminalOutput::Stderr) => Ok(Output::Stderr(io::stderr())),
+            Some(TerminalOutput::Device(path)) => Self::open_device(path)
+                .map_err(|error| eyre!("could not open terminal {:?}: {}", path, error)),
+            None => Ok(Self::open_device(Path::new(DEFAULT_TTY))
+                .unwrap_or_else(|_| Output::Stderr(io::stderr()))),
+        }
+    }
+
+    fn open_device(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Output::Device(file))
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(out) => out.write(buf),
+            Output::Stderr(out) => out.write(buf),
+            Output::Device(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(out) => out.flush(),
+            Output::Stderr(out) => out.flush(),
+            Output::Device(out) => out.flush(),
+        }
+    }
+}
 
 /// Representation of a terminal user interface.
 ///
@@ -28,61 +83,129 @@ impl<B: Backend> Tui<B> {
         Self { terminal, events }
     }
 
-    /// Initializes the terminal interface.
+    /// Installs panic and error hooks.
     ///
-    /// It enables the raw mode and sets terminal properties.
-    pub fn init(&mut self) -> Result<()> {
+    /// This must be called before any errors are created, as the error hook
+    /// cannot be replaced once an error has been reported. On panics, the terminal
+    /// is reset by re-opening the `output` on which the interface is drawn.
+    pub fn install_hooks(output: Option<TerminalOutput>) -> Result<()> {
         // Define a custom panic hook to reset the terminal properties.
         // This way, you won't have your terminal messed up if an unexpected error happens.
         let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
         let panic_hook = panic_hook.into_panic_hook();
         panic::set_hook(Box::new(move |panic| {
-            Self::reset().expect("failed to reset the terminal");
+            tracing::error!("{}", panic);
+            Output::open(output.as_ref())
+                .and_then(|mut output| reset(&mut output))
+                .expect("failed to reset the terminal");
             panic_hook(panic);
         }));
 
-        let eyre_hook = eyre_hook.into_eyre_hook();
-        eyre::set_hook(Box::new(
-            move |error: &(dyn std::error::Error + 'static)| {
-                Self::reset().expect("failed to reset the terminal");
-                eyre_hook(error)
-            },
-        ))?;
+        // Errors may be recoverable (e.g. failure to run `sinfo`), so the terminal is
+        // not reset by the eyre hook. Instead the caller is responsible for calling `exit`.
+        eyre_hook.install()?;
 
+        Ok(())
+    }
+}
+
+impl<B: Backend + io::Write> Tui<B> {
+    /// Initializes the terminal interface.
+    ///
+    /// It enables the raw mode and sets terminal properties.
+    pub fn init(&mut self) -> Result<()> {
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+        crossterm::execute!(
+            self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture
+        )?;
 
         self.terminal.hide_cursor()?;
         self.terminal.clear()?;
         Ok(())
     }
 
+    /// Exits the terminal interface.
+    ///
+    /// It disables the raw mode and reverts back the terminal properties.
+    pub fn exit(&mut self) -> Result<()> {
+        reset(self.terminal.backend_mut())?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+
     /// [`Draw`] the terminal interface by [`rendering`] the widgets.
     ///
+    /// Hyperlinks are drawn on top of the rendered frame, since escape sequences
+    /// cannot be stored in the frame buffer itself.
+    ///
     /// [`Draw`]: ratatui::Terminal::draw
     pub fn draw(&mut self, ui: &mut UI) -> Result<()> {
-        self.terminal
+        let mut hyperlinks = Vec::new();
+        let frame = self
+            .terminal
             .draw(|frame| ui.render(frame.size(), frame.buffer_mut()))?;
+        write_hyperlinks(&mut hyperlinks, frame.buffer, &ui.hyperlinks())?;
+
+        let backend = self.terminal.backend_mut();
+        backend.write_all(&hyperlinks)?;
+        io::Write::flush(backend)?;
 
         Ok(())
     }
 
-    /// Resets the terminal interface.
-    ///
-    /// This function is also used for the panic hook to revert
-    /// the terminal properties if unexpected errors occur.
-    fn reset() -> Result<()> {
-        terminal::disable_raw_mode()?;
-        crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+    /// Copies text to the clipboard using an OSC 52 escape sequence, which is supported
+    /// by most terminals, including over SSH
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        let backend = self.terminal.backend_mut();
+        write!(backend, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+        io::Write::flush(backend)?;
+
         Ok(())
     }
 
-    /// Exits the terminal interface.
-    ///
-    /// It disables the raw mode and reverts back the terminal properties.
-    pub fn exit(&mut self) -> Result<()> {
-        Self::reset()?;
-        self.terminal.show_cursor()?;
+    /// Rings the terminal bell
+    pub fn bell(&mut self) -> Result<()> {
+        let backend = self.terminal.backend_mut();
+        backend.write_all(b"\x07")?;
+        io::Write::flush(backend)?;
+
         Ok(())
     }
 }
+
+/// Resets the terminal interface.
+///
+/// This function is also used for the panic hook to revert
+/// the terminal properties if unexpected errors occur.
+fn reset<W: Write>(output: &mut W) -> Result<()> {
+    terminal::disable_raw_mode()?;
+    crossterm::execute!(output, LeaveAlternateScreen, DisableMouseCapture)?;
+    Ok(())
+}
+
+/// Encodes bytes using standard base64 with padding
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let value = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                let sextet = (value >> (18 - 6 * idx)) & 0x3f;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
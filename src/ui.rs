@@ -1,14 +1,24 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::bail, Result};
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style},
     symbols,
-    widgets::{Block, StatefulWidgetRef, Widget},
+    widgets::{Block, Clear, Paragraph, StatefulWidget, StatefulWidgetRef, Tabs, Widget},
 };
+use regex::RegexBuilder;
 
 use ratatui::{
     prelude::Stylize,
     symbols::border,
-    text::Line,
+    text::{Line, Span, Text},
     widgets::{
         block::{Position, Title},
         Borders,
@@ -16,8 +26,22 @@ use ratatui::{
 };
 
 use crate::{
-    app::App,
-    widgets::{JobTable, JobTableState, NodeTable, NodeTableState, Selection},
+    action::Action,
+    alerts::{NodeAlert, NodeAlerts},
+    anomalies::Anomalies,
+    app::{App, MAX_OUTPUT_BYTES},
+    config::{CopyMode, ListLayout, Pane},
+    history::{LoadHistory, PartitionHistory, MAX_PARTITION_SAMPLES},
+    ledger::{Ledger, Transition},
+    slurm::{
+        parse_time_limit, AssociationUsage, Health, JobLayout, JobOutput, JobState, Node,
+        Partition, PartitionLimits, ReportPeriod, Reservation, UsageReport, Utilization,
+    },
+    widgets::{
+        mb_to_string, palette, sparkline, text_to_string, GaugeRow, HealthRow, Hyperlink, JobTable,
+        JobTableState, LoadChart, LogView, LogViewState, NodeTable, NodeTableState, Picker, Popup,
+        RowStyle, Selection, SummaryRow, Timeline, LOAD_CHART_HEIGHT,
+    },
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -27,110 +51,2235 @@ enum Focus {
     Nodes,
 }
 
+/// Views shown in separate tabs, selected using the number keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tab {
+    /// Nodes organized by partition (or node group) and the jobs on the selected node
+    #[default]
+    Cluster,
+    /// All jobs in the cluster
+    Jobs,
+    /// State transitions of the user's jobs
+    History,
+    /// Reservations, including maintenance windows
+    Reservations,
+    /// Utilization of each partition
+    Stats,
+    /// Running jobs on the nodes of the selected partition, along a time axis
+    Timeline,
+    /// Historical utilization of the cluster and the top users, as reported by
+    /// `sreport`; only shown if enabled (see `--usage`)
+    Usage,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 7] = [
+        Tab::Cluster,
+        Tab::Jobs,
+        Tab::History,
+        Tab::Reservations,
+        Tab::Stats,
+        Tab::Timeline,
+        Tab::Usage,
+    ];
+}
+
+impl std::fmt::Display for Tab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self, f)
+    }
+}
+
+/// A message displayed in a popup until dismissed by the user
+#[derive(Debug)]
+struct Message {
+    title: String,
+    text: Text<'static>,
+    /// Color of the border and title, e.g. red for errors
+    color: Color,
+    /// Is this an error reported by the most recent update of the Slurm state?
+    update_error: bool,
+    /// Chart of the recent load of a node, drawn below the text
+    chart: Option<LoadChart>,
+}
+
+/// The outcome of an action, shown below the tables until the next update
+#[derive(Debug)]
+struct Status {
+    text: String,
+    error: bool,
+}
+
+/// An action that is only performed once confirmed by the user
+#[derive(Debug)]
+struct Confirmation {
+    title: String,
+    question: String,
+    action: Action,
+}
+
+/// Batch scripts that the user can pick from for submission using `sbatch`
+#[derive(Debug)]
+struct ScriptPicker {
+    scripts: Vec<PathBuf>,
+    /// Names of scripts, relative to the scripts directory
+    names: Vec<String>,
+    selected: usize,
+}
+
+/// Frames of the indicator shown while the Slurm state is being updated
+const SPINNER: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+
+/// Title of the popup listing changes in the state of watched jobs
+const WATCH_TITLE: &str = "Watched jobs changed state";
+/// Time for which alerts about nodes are shown in the corner of the screen
+const ALERT_DURATION: Duration = Duration::from_secs(30);
+/// Maximum number of alerts about nodes shown in the corner of the screen
+const MAX_ALERT_LINES: usize = 5;
+
+/// Interval at which job output is re-read while following the end of the output
+const OUTPUT_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum time between the two clicks of a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// Smallest and largest percentage of the cluster tab that may be used by the node list
+const MIN_SPLIT: u16 = 10;
+const MAX_SPLIT: u16 = 90;
+/// Minimum width of the cluster tab for showing the node and job lists side by side
+const MIN_HORIZONTAL_WIDTH: u16 = 160;
+
+/// Output files of a job shown in the log viewer
+#[derive(Debug)]
+struct OutputViewer {
+    id: usize,
+    /// Label ("stdout", "stderr", or "stdout/stderr") and location of each file
+    files: Vec<(&'static str, String)>,
+    /// Index of the file currently shown
+    current: usize,
+    state: LogViewState,
+    /// Reason why the file could not be read, if any
+    error: Option<String>,
+    /// Time at which the file was last read; `None` if it has not yet been read
+    last_read: Option<Instant>,
+}
+
+/// Output of a user-defined command (see [`crate::commands`]) shown in the log viewer
+#[derive(Debug)]
+struct CommandOutput {
+    command: String,
+    state: LogViewState,
+}
+
+/// What the text entered by the user is used for
+#[derive(Debug)]
+enum InputTarget {
+    /// New time limit of the job with this ID
+    TimeLimit(usize),
+    /// Reason for draining the node with this name
+    DrainReason(String),
+    /// Text to search for in the output of a job
+    OutputSearch,
+}
+
+impl InputTarget {
+    /// Returns the action to perform using the text entered by the user, if valid
+    fn action(&self, value: &str) -> Result<Action> {
+        match self {
+            InputTarget::TimeLimit(id) => Ok(Action::SetTimeLimit(*id, parse_time_limit(value)?)),
+            InputTarget::DrainReason(node) => {
+                let reason = value.trim();
+                if reason.is_empty() {
+                    bail!("a reason is required when draining nodes");
+                }
+
+                Ok(Action::DrainNode(node.clone(), reason.to_string()))
+            }
+            InputTarget::OutputSearch => Ok(Action::SearchOutput(value.to_string())),
+        }
+    }
+}
+
+/// A dialog in which the user enters a line of text, e.g. a new time limit for a job
+#[derive(Debug)]
+struct TextInput {
+    title: String,
+    prompt: String,
+    value: String,
+    /// Reason why the value was rejected, if any
+    error: Option<String>,
+    target: InputTarget,
+}
+
+/// Partitions that the user can pick from to restrict the list of nodes
+#[derive(Debug)]
+struct PartitionPicker {
+    names: Vec<String>,
+    /// Which partitions are shown; nodes in all partitions are shown if none are
+    shown: Vec<bool>,
+    selected: usize,
+}
+
+/// Node features that the user can pick from to filter the list of nodes
+#[derive(Debug)]
+struct FeaturePicker {
+    /// Features available in the cluster, preceded by an entry for showing all nodes
+    names: Vec<String>,
+    selected: usize,
+}
+
+/// Text entered by the user to filter the job list by job ID, user, account, QOS, or name
+#[derive(Debug)]
+struct JobFilter {
+    text: String,
+    /// Is the user still entering the text?
+    editing: bool,
+}
+
 #[derive(Debug, Default)]
 pub struct UI {
+    /// Tab currently shown
+    tab: Tab,
     /// Indicates if the node list or job list has focus
     focus: Focus,
     nodes: NodeTable,
     node_state: NodeTableState,
     /// The last used layout; used to determine mouse-click targets
     node_layout: Rect,
+    job_layout: Rect,
+    /// Are the node and job lists shown side by side or stacked?
+    layout: ListLayout,
+    /// Area shared by the node and job lists, if both were shown; used to resize them
+    cluster_layout: Option<Rect>,
+    /// Percentage of the cluster tab used by the node list, if resized by the user
+    split: Option<u16>,
+    /// Is the border between the node and job lists being dragged using the mouse?
+    dragging: bool,
+    /// Time and position of the most recent click on a list, used to detect double-clicks
+    last_click: Option<(Instant, u16, u16)>,
+    /// Are IDs/names or full rows copied to the clipboard?
+    copy_mode: CopyMode,
+    /// Text to be copied to the clipboard once the frame has been drawn
+    clipboard: Option<String>,
     jobs: JobTable,
     job_state: JobTableState,
+    /// State of the list of all jobs shown in the jobs tab
+    all_jobs: JobTableState,
+    /// Text shown in the history, reservations, stats, and usage tabs
+    history: LogViewState,
+    reservations: LogViewState,
+    stats: LogViewState,
+    usage: LogViewState,
+    /// Is the usage tab shown (see `--usage`)?
+    usage_tab: bool,
+    /// Period covered by the report shown in the usage tab
+    report_period: ReportPeriod,
+    /// Index of the first line shown in the timeline tab
+    timeline_offset: usize,
+    /// Nodes and jobs organized by partition, summarized in the status bar
+    cluster: Rc<Vec<Partition>>,
+    /// Problems with the collected Slurm state; counted in the status bar
+    warnings: Vec<String>,
+    /// Time at which the Slurm state was last updated successfully, if ever
+    updated: Option<Instant>,
+    /// Time at which the current update started, if an update is in progress
+    refreshing: Option<Instant>,
+    /// Refresh interval in seconds; older states are highlighted as being stale
+    interval: u64,
+    /// Are automatic updates suspended?
+    paused: bool,
+    /// Most recently drawn description of when the Slurm state was updated
+    update_label: String,
+    /// Responsiveness of Slurm daemons, if enabled
+    health: Option<Health>,
+    /// Are gauges of the resources allocated across the cluster shown?
+    gauges: bool,
+    /// Description of the current snapshot when replaying recorded snapshots
+    replay: Option<String>,
+    /// Outcome of the most recent action not warranting a popup, if any
+    status: Option<Status>,
+    /// Action awaiting confirmation by the user
+    confirmation: Option<Confirmation>,
+    /// Message shown until dismissed by the user
+    popup: Option<Message>,
+    /// Recent alerts about nodes and the time of the latest alert; shown in the corner of
+    /// the screen without blocking input, until they expire
+    alert: Option<(Instant, Vec<String>)>,
+    /// Is job submission enabled (see `--scripts`)?
+    submit: bool,
+    /// Can nodes be drained and resumed (see `--admin`)?
+    admin: bool,
+    /// Are actions that modify jobs and nodes disabled (see `--allow-actions`)?
+    read_only: bool,
+    /// Scripts shown when the user is submitting a job
+    scripts: Option<ScriptPicker>,
+    /// Features shown when the user is filtering nodes
+    features: Option<FeaturePicker>,
+    /// Partitions shown when the user is restricting the list of nodes
+    partitions: Option<PartitionPicker>,
+    /// Text being entered by the user
+    input: Option<TextInput>,
+    /// Output of a job shown in the log viewer
+    output: Option<OutputViewer>,
+    /// Output of a user-defined command, shown on top of the output of a job, if any
+    command_output: Option<CommandOutput>,
+    /// Text used to filter the job list, if any
+    job_filter: Option<JobFilter>,
+    /// Are nodes organized by node groups rather than by partition?
+    node_groups: bool,
 }
 
 impl UI {
     pub fn new(app: &App) -> Self {
         let mut ui = Self::default();
-        // Set the amount of memory allocated per CPU by default
-        ui.node_state.set_def_mem_per_cpu(app.args.def_mem_per_cpu);
-        // Set initial focus on node list
-        ui.toggle_focus();
+        // Custom columns are added first, so that other optional columns are placed
+        // relative to them
+        ui.node_state.set_custom_columns(&app.config.columns);
+        ui.job_state.set_custom_columns(&app.config.columns);
+        ui.all_jobs.set_custom_columns(&app.config.columns);
+        // Set templates for clickable job IDs and node names
+        ui.node_state.set_node_url(app.args.node_url.clone());
+        ui.job_state.set_job_url(app.args.job_url.clone());
+        ui.all_jobs.set_job_url(app.args.job_url.clone());
+        ui.all_jobs.show_partition();
+        ui.submit = app.args.scripts.is_some();
+        ui.admin = app.args.admin;
+        ui.read_only = !app.config.view.allow_actions;
+        ui.interval = app.args.interval;
+
+        let rows = RowStyle::from(&app.config.theme);
+        ui.nodes.set_row_style(rows);
+        ui.jobs.set_row_style(rows);
+
+        let view = &app.config.view;
+        if view.hide_unavailable {
+            ui.toggle_unavailable();
+        }
+
+        if view.billing {
+            ui.node_state.show_billing();
+            ui.job_state.show_billing();
+            ui.all_jobs.show_billing();
+        }
+
+        ui.node_state.set_blocked_alert(view.blocked_alert);
+        ui.job_state
+            .set_age_thresholds(view.age_warning, view.age_alert);
+        ui.all_jobs
+            .set_age_thresholds(view.age_warning, view.age_alert);
+        ui.job_state.set_time_left_alert(view.time_left_alert);
+        ui.all_jobs.set_time_left_alert(view.time_left_alert);
+        ui.node_state
+            .set_hidden_partitions(view.hidden_partitions.clone());
+
+        if view.features {
+            ui.node_state.show_features();
+        }
+
+        if view.history {
+            ui.node_state.show_history();
+        }
+
+        ui.node_groups = view.node_groups && !app.node_groups.is_empty();
+        ui.gauges = view.gauges;
+        ui.usage_tab = view.usage;
+        ui.layout = view.layout;
+        ui.copy_mode = view.copy;
+        ui.split = view.split.map(|v| v.clamp(MIN_SPLIT, MAX_SPLIT));
+
+        if view.limits {
+            ui.node_state.show_limits();
+        }
+
+        // Custom columns replace the default columns, including those enabled above
+        if let Some(columns) = &view.node_columns {
+            if let Err(error) = ui.node_state.set_columns(columns) {
+                ui.show_error("Invalid node columns", error);
+            }
+        }
+
+        if let Some(columns) = &view.job_columns {
+            if let Err(error) = ui.job_state.set_columns(columns) {
+                ui.show_error("Invalid job columns", error);
+            }
+            // Errors are reported once for both job lists
+            let _ = ui.all_jobs.set_columns(columns);
+        }
+
+        // Text in the other tabs is shown from the top
+        for state in [
+            &mut ui.history,
+            &mut ui.reservations,
+            &mut ui.stats,
+            &mut ui.usage,
+        ] {
+            state.set_follow(false);
+        }
+
+        // Set initial focus, defaulting to the node list
+        if view.focus != Some(Pane::Jobs) {
+            ui.toggle_focus();
+        }
+
         // Fill out
         ui.update(app);
+
+        if let Some(partition) = &view.partition {
+            if ui.node_state.select_partition(partition) {
+                ui.scroll_node_selection(0);
+            } else {
+                ui.show_error(
+                    "Invalid partition",
+                    format!("partition {:?} not found", partition),
+                );
+            }
+        }
+
         ui
     }
 
-    pub fn update(&mut self, app: &App) {
-        self.node_state.update(app.cluster.clone());
-        self.scroll_node_selection(0);
-    }
+    pub fn update(&mut self, app: &App) {
+        // The amount of memory allocated per CPU by default and the limits of partitions
+        // are refreshed periodically (see `App::apply_partitions`)
+        self.node_state
+            .set_default_mem(app.default_mem, app.partition_default_mem.clone());
+        self.node_state
+            .set_partition_limits(app.partition_limits.clone());
+        self.node_state.set_maintenance(&app.reservations);
+        self.node_state.set_anomalies(&app.anomalies);
+        self.node_state.set_history(&app.node_history);
+        // Utilization is only tracked per partition, not per node group
+        if self.node_groups {
+            self.node_state
+                .set_partition_history(&PartitionHistory::default());
+        } else {
+            self.node_state
+                .set_partition_history(&app.partition_history);
+        }
+        self.job_state.set_watched(app.watchlist.ids());
+        self.all_jobs.set_watched(app.watchlist.ids());
+        self.update_tabs(app);
+        if self.node_groups {
+            self.node_state.update(app.grouped_cluster());
+        } else {
+            self.node_state.update(app.cluster.clone());
+        }
+
+        self.scroll_node_selection(0);
+        self.cluster = app.cluster.clone();
+        self.warnings.clone_from(&app.warnings);
+        self.updated = app.updated;
+        self.refreshing = None;
+        self.health = app.health.clone();
+        self.replay = app.replay.as_ref().map(|v| v.label());
+        self.status = None;
+
+        if let Some(error) = &app.error {
+            self.show_error("Error while updating Slurm state", error);
+            if let Some(popup) = &mut self.popup {
+                popup.update_error = true;
+            }
+        } else if self.popup.as_ref().is_some_and(|v| v.update_error) {
+            // Errors are resolved once an update succeeds
+            self.popup = None;
+        }
+    }
+
+    /// Indicates that the Slurm state is being updated, until the next call to `update`
+    pub fn set_refreshing(&mut self) {
+        self.refreshing.get_or_insert_with(Instant::now);
+    }
+
+    /// Indicates whether automatic updates are suspended
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Returns true if the description of when the Slurm state was last updated has
+    /// changed since it was drawn, e.g. from "updated 4s ago" to "updated 5s ago"
+    pub fn update_label_changed(&self) -> bool {
+        self.update_label().0 != self.update_label
+    }
+
+    /// Describes when the Slurm state was last updated, along with the color used to
+    /// draw the description. States older than two refresh intervals are highlighted
+    fn update_label(&self) -> (String, Color) {
+        if let Some(started) = self.refreshing {
+            let frame = (started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+            return (format!("{} updating", SPINNER[frame]), palette().text);
+        }
+
+        match self.updated {
+            // Snapshots are only updated on request when replaying
+            _ if self.replay.is_some() => (String::new(), palette().text),
+            Some(updated) if self.paused => (
+                format!("paused, updated {} ago", age_to_string(updated.elapsed())),
+                palette().warning,
+            ),
+            Some(updated) => {
+                let elapsed = updated.elapsed();
+                let color = if self.interval > 0 && elapsed.as_secs() >= 2 * self.interval {
+                    palette().warning
+                } else {
+                    palette().dimmed
+                };
+
+                (format!("updated {} ago", age_to_string(elapsed)), color)
+            }
+            None => (String::from("not updated"), palette().warning),
+        }
+    }
+
+    /// Returns true if a popup is currently being displayed
+    pub fn has_popup(&self) -> bool {
+        self.popup.is_some()
+    }
+
+    /// Closes the currently displayed popup, if any
+    pub fn close_popup(&mut self) {
+        self.popup = None;
+    }
+
+    /// Displays a message in a popup until dismissed
+    pub fn show_message<T: Into<Text<'static>>>(&mut self, title: &str, text: T) {
+        self.popup = Some(Message {
+            title: title.to_string(),
+            text: text.into(),
+            color: palette().text,
+            update_error: false,
+            chart: None,
+        });
+    }
+
+    /// Updates the contents of the tabs other than the cluster tab
+    fn update_tabs(&mut self, app: &App) {
+        // Pending jobs may be listed in multiple partitions
+        let mut seen = HashSet::new();
+        let jobs = app
+            .cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .filter(|v| seen.insert(v.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.all_jobs.update(&jobs);
+
+        let history = ledger_lines(
+            &app.ledger,
+            app.node_history.memory_usage()
+                + app.partition_history.memory_usage()
+                + app.load_history.memory_usage(),
+        );
+        self.history.set_text(&history.join("\n"), false);
+        let reservations = reservation_lines(&app.reservations);
+        self.reservations.set_text(&reservations.join("\n"), false);
+        let stats = stats_lines(&app.cluster, &app.partition_history);
+        self.stats.set_text(&stats.join("\n"), false);
+    }
+
+    /// Returns the tab currently shown
+    pub fn tab(&self) -> Tab {
+        self.tab
+    }
+
+    /// Returns the tabs that can be shown, in the order in which they are numbered
+    pub fn tabs(&self) -> impl Iterator<Item = Tab> + '_ {
+        Tab::ALL
+            .into_iter()
+            .filter(|&v| v != Tab::Usage || self.usage_tab)
+    }
+
+    /// Returns the period covered by the report shown in the usage tab
+    pub fn report_period(&self) -> ReportPeriod {
+        self.report_period
+    }
+
+    /// Selects the previous/next period of the report shown in the usage tab; the report
+    /// itself is shown using [`UI::show_usage`]
+    pub fn cycle_report_period(&mut self, delta: isize) {
+        let periods = ReportPeriod::ALL;
+        let idx = periods
+            .iter()
+            .position(|&v| v == self.report_period)
+            .unwrap_or_default();
+        let idx = (idx as isize + delta).rem_euclid(periods.len() as isize);
+        self.report_period = periods[idx as usize];
+    }
+
+    /// Shows a report of the historical utilization in the usage tab, or the error
+    /// encountered running `sreport`
+    pub fn show_usage(&mut self, report: Result<UsageReport>) {
+        let text = match report {
+            Ok(report) => usage_lines(&report).join("\n"),
+            Err(error) => format!(
+                "Could not collect usage for the last {}:\n\n{:#}\n\n\
+                 sreport requires the Slurm database daemon (slurmdbd)",
+                self.report_period, error
+            ),
+        };
+
+        self.usage.set_text(&text, false);
+    }
+
+    /// Indicates that the report of the selected period is being collected
+    pub fn collecting_usage(&mut self) {
+        let text = format!("Collecting usage for the last {} ...", self.report_period);
+        self.usage.set_text(&text, false);
+    }
+
+    /// Shows the given tab
+    pub fn select_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+        self.node_state
+            .focus(tab == Tab::Cluster && self.focus == Focus::Nodes);
+        self.job_state
+            .focus(tab == Tab::Cluster && self.focus == Focus::Jobs);
+        self.all_jobs.focus(tab == Tab::Jobs);
+    }
+
+    /// Returns the job list that has focus, if any
+    fn active_jobs(&self) -> Option<&JobTableState> {
+        match (self.tab, &self.focus) {
+            (Tab::Cluster, Focus::Jobs) => Some(&self.job_state),
+            (Tab::Jobs, _) => Some(&self.all_jobs),
+            _ => None,
+        }
+    }
+
+    fn active_jobs_mut(&mut self) -> Option<&mut JobTableState> {
+        match (self.tab, &self.focus) {
+            (Tab::Cluster, Focus::Jobs) => Some(&mut self.job_state),
+            (Tab::Jobs, _) => Some(&mut self.all_jobs),
+            _ => None,
+        }
+    }
+
+    /// Returns the text shown in the current tab, unless it shows a node or job list
+    fn active_text(&mut self) -> Option<&mut LogViewState> {
+        match self.tab {
+            Tab::Cluster | Tab::Jobs | Tab::Timeline => None,
+            Tab::History => Some(&mut self.history),
+            Tab::Reservations => Some(&mut self.reservations),
+            Tab::Stats => Some(&mut self.stats),
+            Tab::Usage => Some(&mut self.usage),
+        }
+    }
+
+    /// Displays details and the recent load of the selected node, or the limits of the
+    /// selected partition
+    pub fn show_info(
+        &mut self,
+        partition_limits: &HashMap<String, PartitionLimits>,
+        load_history: &LoadHistory,
+    ) {
+        if self.tab != Tab::Cluster {
+            return;
+        }
+
+        match self.node_state.selected() {
+            Some(Selection::Partition(partition)) => {
+                let name = partition.name.label.clone();
+                self.show_partition(&name, partition_limits);
+            }
+            Some(Selection::Node(node)) => {
+                let title = format!("Node {}", node.name);
+                let text = node_details(node);
+                let chart = load_history
+                    .samples(&node.name)
+                    .map(|v| LoadChart::new(v, node.cpus, node.mem))
+                    .filter(|v| !v.is_empty());
+                self.show_message(&title, text);
+                if let Some(message) = &mut self.popup {
+                    message.chart = chart;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Displays how the CPUs, memory, and GRES of a job are distributed across nodes
+    pub fn show_job_layout(&mut self, layout: &JobLayout) {
+        let mut lines = vec![
+            format!(
+                "{} task(s) on {} node(s), {} CPU(s) per task",
+                layout.tasks,
+                layout.nodes.len(),
+                layout.cpus_per_task
+            ),
+            String::new(),
+        ];
+
+        // Helps distinguish between jobs running similarly named scripts
+        let paths = [
+            ("Command", &layout.command),
+            ("WorkDir", &layout.work_dir),
+            ("StdOut", &layout.stdout),
+        ];
+
+        for (key, value) in paths {
+            if let Some(value) = value {
+                lines.push(format!("{:<8} {}", format!("{}:", key), value));
+            }
+        }
+
+        if paths.iter().any(|(_, v)| v.is_some()) {
+            lines.push(String::new());
+        }
+
+        lines.extend([format!(
+            "{:<12} {:>5} {:>5} {:<16} {:>9}  GRES",
+            "Node", "Tasks", "CPUs", "CPU IDs", "Memory"
+        )]);
+
+        for node in &layout.nodes {
+            lines.push(format!(
+                "{:<12} {:>5} {:>5} {:<16} {:>9}  {}",
+                node.node,
+                layout.tasks_on(node),
+                node.cpus,
+                node.cpu_ids,
+                node.mem.map(mb_to_string).unwrap_or_default(),
+                node.gres,
+            ));
+        }
+
+        if layout.is_unbalanced() {
+            lines.push(String::new());
+            lines.push(String::from(
+                "Warning: CPUs are unevenly distributed across nodes",
+            ));
+        }
+
+        self.show_message(&format!("Layout of job {}", layout.id), lines.join("\n"));
+    }
+
+    /// Displays the TRES minutes consumed by the associations of a user and their
+    /// accounts, compared to the limits of the associations (`GrpTRESMins`)
+    pub fn show_association_usage(&mut self, user: &str, associations: &[AssociationUsage]) {
+        let title = format!("Usage and limits of {}", user);
+        if associations.is_empty() {
+            let text = format!("No associations found for user {:?}", user);
+            self.show_message(&title, text);
+            return;
+        }
+
+        let palette = palette();
+        let mut lines = vec![Line::from(format!(
+            "{:<14} {:<11} {:<8} {:<10} {:>10} {:>10}",
+            "Account", "User", "TRES", "", "Used", "Limit"
+        ))];
+        for association in associations {
+            let user = match association.user.as_str() {
+                "" => "(all users)",
+                user => user,
+            };
+
+            if association.limits.is_empty() {
+                lines.push(Line::from(format!(
+                    "{:<14} {:<11} {:<8} {:<10} {:>10} {:>10}",
+                    association.account,
+                    user,
+                    "cpu",
+                    "",
+                    association.used("cpu"),
+                    "unlimited"
+                )));
+            }
+
+            for (tres, limit) in &association.limits {
+                let used = association.used(tres);
+                let bar = Utilization {
+                    allocated: used.min(*limit) as f64,
+                    capacity: *limit as f64,
+                    ..Default::default()
+                };
+                let percentage = used as f64 / (*limit).max(1) as f64 * 100.0;
+                let color = if used >= *limit {
+                    palette.error
+                } else if percentage >= 90.0 {
+                    palette.warning
+                } else {
+                    palette.text
+                };
+
+                let mut spans = vec![Span::raw(format!(
+                    "{:<14} {:<11} {:<8} ",
+                    association.account, user, tres
+                ))];
+                spans.extend(bar.to_line(10).spans);
+                spans.push(Span::raw(format!(" {:>10} {:>10} ", used, limit)));
+                spans.push(Span::raw(format!("{:>4.0}%", percentage)).fg(color));
+                lines.push(Line::from(spans));
+            }
+        }
+
+        lines.push(Line::default());
+        lines.push(Line::from(
+            "TRES minutes consumed, as reported by sshare, and GrpTRESMins limits",
+        ));
+        lines.push(Line::from(
+            "Jobs are held once a limit is reached; usage may decay over time",
+        ));
+        self.show_message(&title, lines);
+    }
+
+    /// Displays the limits of a partition
+    fn show_partition(&mut self, name: &str, partition_limits: &HashMap<String, PartitionLimits>) {
+        let text = match partition_limits.get(name) {
+            Some(limits) => {
+                let unlimited = || String::from("unlimited");
+                let mem = |v: Option<usize>| v.map(mb_to_string).unwrap_or_else(unlimited);
+                let count = |v: Option<usize>| v.map(|v| v.to_string()).unwrap_or_else(unlimited);
+
+                [
+                    ("MaxTime", limits.max_time.clone().unwrap_or_else(unlimited)),
+                    (
+                        "DefaultTime",
+                        limits.default_time.clone().unwrap_or_default(),
+                    ),
+                    ("MinNodes", count(limits.min_nodes)),
+                    ("MaxNodes", count(limits.max_nodes)),
+                    ("MaxCPUsPerNode", count(limits.max_cpus_per_node)),
+                    ("MaxCPUsPerUser", count(limits.max_cpus_per_user)),
+                    ("MaxMemPerNode", mem(limits.max_mem_per_node)),
+                    ("MaxMemPerCPU", mem(limits.max_mem_per_cpu)),
+                    ("QoS", limits.qos.clone().unwrap_or_default()),
+                ]
+                .into_iter()
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(key, value)| format!("{:<16}{}", key, value))
+                .collect::<Vec<_>>()
+                .join("\n")
+            }
+            None => String::from("Partition limits are not available"),
+        };
+
+        self.show_message(&format!("Partition {}", name), text);
+    }
+
+    /// Lists problems with the collected Slurm state, e.g. jobs in unknown partitions
+    pub fn show_warnings(&mut self) {
+        let text = if self.warnings.is_empty() {
+            String::from("No problems were found in the Slurm state")
+        } else {
+            self.warnings.join("\n")
+        };
+
+        self.show_message("Warnings", text);
+    }
+
+    /// Displays an error in a popup until dismissed
+    pub fn show_error<T: std::fmt::Display>(&mut self, title: &str, error: T) {
+        self.popup = Some(Message {
+            title: title.to_string(),
+            text: format!("{:#}", error).into(),
+            color: palette().error,
+            update_error: false,
+            chart: None,
+        });
+    }
+
+    /// Displays changes in the state of watched jobs, adding to those already shown
+    pub fn notify(&mut self, transitions: &[Transition]) {
+        let mut lines = match self.popup.take() {
+            Some(popup) if popup.title == WATCH_TITLE => popup.text,
+            popup => {
+                self.popup = popup;
+                Text::default()
+            }
+        };
+
+        for transition in transitions {
+            lines.lines.push(Line::from(transition.to_string()));
+        }
+
+        // Notifications are shown on top of other messages, which are then discarded
+        self.popup = Some(Message {
+            title: WATCH_TITLE.to_string(),
+            text: lines,
+            color: palette().warning,
+            update_error: false,
+            chart: None,
+        });
+    }
+
+    /// Displays alerts about nodes that became unavailable in the corner of the screen,
+    /// adding to those already shown. Unlike popups, alerts do not block input
+    pub fn alert(&mut self, alerts: &[NodeAlert]) {
+        let (time, lines) = self
+            .alert
+            .get_or_insert_with(|| (Instant::now(), Vec::new()));
+        *time = Instant::now();
+        lines.extend(alerts.iter().map(|v| v.to_string()));
+        if lines.len() > MAX_ALERT_LINES {
+            lines.drain(..lines.len() - MAX_ALERT_LINES);
+        }
+    }
+
+    /// Removes alerts shown for longer than [`ALERT_DURATION`]; returns true if the alerts
+    /// must be redrawn
+    pub fn alert_expired(&mut self) -> bool {
+        let expired = self
+            .alert
+            .as_ref()
+            .is_some_and(|(time, _)| time.elapsed() >= ALERT_DURATION);
+        if expired {
+            self.alert = None;
+        }
+
+        expired
+    }
+
+    /// Lists the nodes that became unavailable during this session, most recent first
+    pub fn show_node_alerts(&mut self, alerts: &NodeAlerts) {
+        let lines = alerts
+            .log()
+            .rev()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        let text = if lines.is_empty() {
+            String::from("No nodes have gone down, drained, or stopped responding")
+        } else {
+            lines.join("\n")
+        };
+
+        self.alert = None;
+        self.show_message("Node alerts", text);
+    }
+
+    /// Lists the nodes that are persistently overloaded or underutilized
+    pub fn show_anomalies(&mut self, anomalies: &Anomalies) {
+        let nodes = anomalies.active();
+        let mut lines = if nodes.is_empty() {
+            vec![String::from("No nodes are overloaded or underutilized")]
+        } else {
+            let mut lines = vec![format!(
+                "{:<16} {:<14} {:>8} {:>10}  Since",
+                "Node", "Anomaly", "CPU load", "Allocated"
+            )];
+            for (name, anomaly) in nodes {
+                lines.push(format!(
+                    "{:<16} {:<14} {:>8.2} {:>10}  {}",
+                    name,
+                    anomaly.kind,
+                    anomaly.cpu_load,
+                    anomaly.allocated,
+                    anomaly.since.format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+
+            lines
+        };
+
+        // Describes the thresholds, so that users know what is being flagged
+        let config = anomalies.config();
+        let after = |v: Option<std::time::Duration>| {
+            v.filter(|v| !v.is_zero())
+                .map(|v| format!(" for {}", duration_to_string(v)))
+                .unwrap_or_default()
+        };
+        lines.push(String::new());
+        if config.overload > 0.0 {
+            lines.push(format!(
+                "Overloaded: CPU load above {} times the allocated CPUs{}",
+                config.overload,
+                after(config.overload_after)
+            ));
+        }
+        if config.idle > 0.0 {
+            lines.push(format!(
+                "Underutilized: CPU load below {:.0}% of the allocated CPUs{}",
+                config.idle * 100.0,
+                after(config.idle_after)
+            ));
+        }
+
+        self.show_message("Anomalies", lines.join("\n"));
+    }
+
+    /// Displays a message in the status line until the next update
+    pub fn show_status<T: ToString>(&mut self, text: T) {
+        self.status = Some(Status {
+            text: text.to_string(),
+            error: false,
+        });
+    }
+
+    /// Removes the message shown in the status line, if any
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
+    /// Displays an error in the status line until the next update
+    pub fn show_status_error<T: std::fmt::Display>(&mut self, error: T) {
+        self.status = Some(Status {
+            text: format!("{:#}", error),
+            error: true,
+        });
+    }
+
+    /// Asks the user to confirm an action, which is returned by `take_confirmation`
+    pub fn confirm<T: ToString>(&mut self, title: &str, question: T, action: Action) {
+        self.confirmation = Some(Confirmation {
+            title: title.to_string(),
+            question: question.to_string(),
+            action,
+        });
+    }
+
+    /// Returns true if an action is awaiting confirmation
+    pub fn has_confirmation(&self) -> bool {
+        self.confirmation.is_some()
+    }
+
+    /// Closes the confirmation dialog and returns the confirmed action
+    pub fn take_confirmation(&mut self) -> Option<Action> {
+        self.confirmation.take().map(|v| v.action)
+    }
+
+    /// Closes the confirmation dialog without performing the action
+    pub fn close_confirmation(&mut self) {
+        self.confirmation = None;
+    }
+
+    /// Displays a list of scripts, from which the user can pick one to submit
+    pub fn show_scripts(&mut self, scripts: Vec<PathBuf>) {
+        let names = scripts
+            .iter()
+            .map(|v| {
+                v.file_name()
+                    .map(|v| v.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        self.scripts = Some(ScriptPicker {
+            scripts,
+            names,
+            selected: 0,
+        });
+    }
+
+    /// Returns true if the list of scripts is currently being displayed
+    pub fn has_scripts(&self) -> bool {
+        self.scripts.is_some()
+    }
+
+    /// Moves the selection in the list of scripts
+    pub fn scroll_scripts(&mut self, delta: isize) {
+        if let Some(picker) = &mut self.scripts {
+            picker.selected = (picker.selected as isize + delta)
+                .clamp(0, picker.scripts.len().saturating_sub(1) as isize)
+                as usize;
+        }
+    }
+
+    /// Closes the list of scripts and returns the script selected by the user
+    pub fn take_script(&mut self) -> Option<PathBuf> {
+        self.scripts.take().and_then(|mut v| {
+            (v.selected < v.scripts.len()).then(|| v.scripts.swap_remove(v.selected))
+        })
+    }
+
+    /// Closes the list of scripts without picking a script
+    pub fn close_scripts(&mut self) {
+        self.scripts = None;
+    }
+
+    /// Displays a list of partitions, from which the user can pick those to show
+    pub fn show_partitions(&mut self, app: &App) {
+        let hidden = &app.config.view.hidden_partitions;
+        let names = app
+            .cluster
+            .iter()
+            .map(|v| v.name.label.clone())
+            .filter(|v| !hidden.contains(v))
+            .collect::<Vec<_>>();
+
+        // Pre-select the current partitions, if any
+        let current = self.node_state.partitions();
+        let shown = names.iter().map(|v| current.contains(v)).collect();
+
+        self.partitions = Some(PartitionPicker {
+            names,
+            shown,
+            selected: 0,
+        });
+    }
+
+    /// Returns true if the list of partitions is currently being displayed
+    pub fn has_partitions(&self) -> bool {
+        self.partitions.is_some()
+    }
+
+    /// Moves the selection in the list of partitions
+    pub fn scroll_partitions(&mut self, delta: isize) {
+        if let Some(picker) = &mut self.partitions {
+            picker.selected = (picker.selected as isize + delta)
+                .clamp(0, picker.names.len().saturating_sub(1) as isize)
+                as usize;
+        }
+    }
+
+    /// Shows/hides the selected partition in the list of partitions
+    pub fn toggle_partition(&mut self) {
+        if let Some(picker) = &mut self.partitions {
+            if let Some(shown) = picker.shown.get_mut(picker.selected) {
+                *shown = !*shown;
+            }
+        }
+    }
+
+    /// Closes the list of partitions and only shows nodes in the picked partitions
+    pub fn select_partitions(&mut self) {
+        if let Some(picker) = self.partitions.take() {
+            let partitions = picker
+                .names
+                .into_iter()
+                .zip(picker.shown)
+                .filter_map(|(name, shown)| shown.then_some(name))
+                .collect();
+
+            self.node_state.set_partitions(partitions);
+            self.scroll_node_selection(0);
+        }
+    }
+
+    /// Closes the list of partitions without changing which partitions are shown
+    pub fn close_partitions(&mut self) {
+        self.partitions = None;
+    }
+
+    /// Displays a list of node features, from which the user can pick one to filter by
+    pub fn show_features(&mut self) {
+        let mut names = vec![String::from("(all nodes)")];
+        names.extend(self.node_state.features());
+
+        // Pre-select the current filter, if any
+        let selected = self
+            .node_state
+            .feature()
+            .and_then(|feature| names.iter().position(|v| v == feature))
+            .unwrap_or_default();
+
+        self.features = Some(FeaturePicker { names, selected });
+    }
+
+    /// Returns true if the list of features is currently being displayed
+    pub fn has_features(&self) -> bool {
+        self.features.is_some()
+    }
+
+    /// Moves the selection in the list of features
+    pub fn scroll_features(&mut self, delta: isize) {
+        if let Some(picker) = &mut self.features {
+            picker.selected = (picker.selected as isize + delta)
+                .clamp(0, picker.names.len().saturating_sub(1) as isize)
+                as usize;
+        }
+    }
+
+    /// Closes the list of features and filters nodes by the selected feature
+    pub fn select_feature(&mut self) {
+        if let Some(mut picker) = self.features.take() {
+            let feature = (picker.selected > 0 && picker.selected < picker.names.len())
+                .then(|| picker.names.swap_remove(picker.selected));
+
+            self.node_state.set_feature(feature);
+            self.scroll_node_selection(0);
+        }
+    }
+
+    /// Closes the list of features without changing the filter
+    pub fn close_features(&mut self) {
+        self.features = None;
+    }
+
+    /// Displays a dialog in which the user can enter a new time limit for a job
+    pub fn edit_time_limit(&mut self, id: usize) {
+        self.show_input(
+            "Time limit",
+            format!(
+                "New time limit of job {}, e.g. \"2-00:00:00\" or \"+60\" to add 60 minutes:",
+                id
+            ),
+            InputTarget::TimeLimit(id),
+        );
+    }
+
+    /// Displays a dialog in which the user can enter the reason for draining a node
+    pub fn edit_drain_reason(&mut self, node: &str) {
+        self.show_input(
+            "Drain node",
+            format!("Reason for draining node {}:", node),
+            InputTarget::DrainReason(node.to_string()),
+        );
+    }
+
+    fn show_input(&mut self, title: &str, prompt: String, target: InputTarget) {
+        self.input = Some(TextInput {
+            title: title.to_string(),
+            prompt,
+            value: String::new(),
+            error: None,
+            target,
+        });
+    }
+
+    pub fn has_input(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Appends a character to the text being entered
+    pub fn type_input(&mut self, c: char) {
+        if let Some(input) = &mut self.input {
+            input.value.push(c);
+            input.error = None;
+        }
+    }
+
+    /// Removes the last character of the text being entered
+    pub fn erase_input(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.value.pop();
+            input.error = None;
+        }
+    }
+
+    /// Closes the dialog and returns the action to perform using the text entered by
+    /// the user, if valid. Otherwise the dialog remains open and shows the error
+    pub fn take_input(&mut self) -> Option<Action> {
+        let input = self.input.as_mut()?;
+        match input.target.action(&input.value) {
+            Ok(action) => {
+                self.input = None;
+                Some(action)
+            }
+            Err(error) => {
+                input.error = Some(format!("{:#}", error));
+                None
+            }
+        }
+    }
+
+    pub fn close_input(&mut self) {
+        self.input = None;
+    }
+
+    /// Starts (or resumes) entering text used to filter the job list
+    pub fn start_job_filter(&mut self) {
+        match self.tab {
+            Tab::Cluster if self.focus != Focus::Jobs => self.toggle_focus(),
+            Tab::Cluster | Tab::Jobs => {}
+            _ => return,
+        }
+
+        match &mut self.job_filter {
+            Some(filter) => filter.editing = true,
+            None => {
+                self.job_filter = Some(JobFilter {
+                    text: String::new(),
+                    editing: true,
+                })
+            }
+        }
+    }
+
+    /// Is the user entering text used to filter the job list?
+    pub fn is_filtering_jobs(&self) -> bool {
+        self.job_filter.as_ref().is_some_and(|v| v.editing)
+    }
+
+    pub fn has_job_filter(&self) -> bool {
+        self.job_filter.is_some()
+    }
+
+    /// Appends a character to the job filter
+    pub fn type_job_filter(&mut self, c: char) {
+        if let Some(filter) = &mut self.job_filter {
+            filter.text.push(c);
+            self.apply_job_filter();
+        }
+    }
+
+    /// Removes the last character of the job filter
+    pub fn erase_job_filter(&mut self) {
+        if let Some(filter) = &mut self.job_filter {
+            filter.text.pop();
+            self.apply_job_filter();
+        }
+    }
+
+    /// Stops entering text, while keeping the job list filtered. An empty filter is
+    /// discarded
+    pub fn accept_job_filter(&mut self) {
+        match &mut self.job_filter {
+            Some(filter) if filter.text.is_empty() => self.clear_job_filter(),
+            Some(filter) => filter.editing = false,
+            None => {}
+        }
+    }
+
+    /// Removes the job filter, showing all jobs
+    pub fn clear_job_filter(&mut self) {
+        self.job_filter = None;
+        self.apply_job_filter();
+    }
+
+    /// Filters jobs using the text entered as a case-insensitive regular expression, or
+    /// as plain text if it is not a valid regular expression (e.g. while still typing)
+    fn apply_job_filter(&mut self) {
+        let filter = self
+            .job_filter
+            .as_ref()
+            .filter(|v| !v.text.is_empty())
+            .and_then(|v| {
+                RegexBuilder::new(&v.text)
+                    .case_insensitive(true)
+                    .build()
+                    .or_else(|_| {
+                        RegexBuilder::new(&regex::escape(&v.text))
+                            .case_insensitive(true)
+                            .build()
+                    })
+                    .ok()
+            });
+
+        self.job_state.set_filter(filter.clone());
+        self.all_jobs.set_filter(filter);
+    }
+
+    /// Displays the output of a job in the log viewer, following the end of the output
+    pub fn show_output(&mut self, output: JobOutput) {
+        let mut files = Vec::new();
+        match (output.stdout, output.stderr) {
+            (Some(stdout), None) => files.push(("stdout/stderr", stdout)),
+            (stdout, stderr) => {
+                files.extend(stdout.map(|v| ("stdout", v)));
+                files.extend(stderr.map(|v| ("stderr", v)));
+            }
+        }
+
+        self.output = Some(OutputViewer {
+            id: output.id,
+            files,
+            current: 0,
+            state: LogViewState::new(),
+            error: None,
+            last_read: None,
+        });
+    }
+
+    pub fn has_output(&self) -> bool {
+        self.output.is_some() || self.command_output.is_some()
+    }
+
+    /// Displays the output of a user-defined command in the log viewer
+    pub fn show_command_output(&mut self, command: &str, output: &str) {
+        // The output is shown from the top
+        let mut state = LogViewState::new();
+        state.set_follow(false);
+        state.set_text(output, false);
+
+        self.command_output = Some(CommandOutput {
+            command: command.to_string(),
+            state,
+        });
+    }
+
+    /// Returns the state of the log viewer on top, if shown
+    fn output_state(&mut self) -> Option<&mut LogViewState> {
+        match (&mut self.command_output, &mut self.output) {
+            (Some(output), _) => Some(&mut output.state),
+            (None, Some(viewer)) => Some(&mut viewer.state),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the location of the output file that is due to be (re-)read; files are
+    /// re-read every [`OUTPUT_INTERVAL`] while following the end of the output
+    pub fn output_due(&self) -> Option<String> {
+        let viewer = self.output.as_ref()?;
+        let due = match viewer.last_read {
+            Some(last_read) => viewer.state.following() && last_read.elapsed() >= OUTPUT_INTERVAL,
+            None => true,
+        };
+
+        due.then(|| viewer.files[viewer.current].1.clone())
+    }
+
+    /// Updates the output shown using the (partial) contents of the file at `path`,
+    /// unless the user switched to another file while it was being read
+    pub fn set_output(&mut self, path: &str, text: Result<String>) {
+        if let Some(viewer) = self
+            .output
+            .as_mut()
+            .filter(|v| v.files[v.current].1 == path)
+        {
+            match text {
+                Ok(text) => {
+                    viewer.state.set_text(&text, text.len() >= MAX_OUTPUT_BYTES);
+                    viewer.error = None;
+                }
+                Err(error) => viewer.error = Some(format!("{:#}", error)),
+            }
+
+            viewer.last_read = Some(Instant::now());
+        }
+    }
+
+    /// Switches between showing stdout and stderr, if written to different files
+    pub fn switch_output(&mut self) {
+        if self.command_output.is_some() {
+            return;
+        }
+
+        if let Some(viewer) = &mut self.output {
+            if viewer.files.len() > 1 {
+                viewer.current = (viewer.current + 1) % viewer.files.len();
+                viewer.state = LogViewState::new();
+                viewer.last_read = None;
+            }
+        }
+    }
+
+    pub fn scroll_output(&mut self, delta: isize) {
+        if let Some(state) = self.output_state() {
+            state.scroll(delta);
+        }
+    }
+
+    /// Starts or stops following the end of the output
+    pub fn toggle_follow_output(&mut self) {
+        if let Some(state) = self.output_state() {
+            let follow = !state.following();
+            state.set_follow(follow);
+        }
+    }
+
+    /// Displays a dialog in which the user can enter text to search for in the output
+    pub fn edit_output_search(&mut self) {
+        let current = self
+            .output_state()
+            .and_then(|v| v.search().map(String::from));
+        let current = current.unwrap_or_default();
+
+        self.show_input("Search", "Search for:".into(), InputTarget::OutputSearch);
+        if let Some(input) = &mut self.input {
+            input.value = current;
+        }
+    }
+
+    /// Highlights `text` in the output and scrolls to the first match
+    pub fn search_output(&mut self, text: String) {
+        if let Some(state) = self.output_state() {
+            if !state.set_search(text.clone()) && !text.is_empty() {
+                self.show_status_error(format!("{:?} not found", text));
+            }
+        }
+    }
+
+    /// Scrolls to the next (or previous) match of the text searched for
+    pub fn find_in_output(&mut self, forward: bool) {
+        if let Some(state) = self.output_state() {
+            state.find_next(forward);
+        }
+    }
+
+    /// Closes the output of a user-defined command, if shown, and otherwise the output
+    /// of the job
+    pub fn close_output(&mut self) {
+        if self.command_output.take().is_none() {
+            self.output = None;
+        }
+    }
+
+    pub fn scroll(&mut self, delta: isize) {
+        match (self.tab, &self.focus) {
+            (Tab::Cluster, Focus::Nodes) => self.scroll_node_selection(delta),
+            (Tab::Cluster, Focus::Jobs) => self.scroll_job_selection(delta),
+            (Tab::Jobs, _) => self.all_jobs.scroll(delta),
+            (Tab::Timeline, _) => {
+                self.timeline_offset = self.timeline_offset.saturating_add_signed(delta);
+            }
+            _ => {
+                if let Some(text) = self.active_text() {
+                    text.scroll(delta);
+                }
+            }
+        }
+    }
+
+    pub fn mouse_click(&mut self, column: u16, row: u16) {
+        if self.tab != Tab::Cluster {
+            return;
+        }
+
+        // Dragging the border between the node and job lists resizes the lists
+        if self.cluster_layout.is_some() && row == self.node_layout.bottom() {
+            self.dragging = true;
+            return;
+        }
+
+        if let Some(focus) = self.focus_at(column, row) {
+            if self.focus != focus {
+                self.toggle_focus();
+            }
+
+            match focus {
+                Focus::Nodes => {
+                    // -1 for border
+                    self.node_state
+                        .click(row.saturating_sub(self.node_layout.y + 1) as usize);
+                    self.scroll_node_selection(0)
+                }
+                Focus::Jobs => {
+                    self.job_state
+                        .click(row.saturating_sub(self.job_layout.y) as usize);
+                    self.scroll_node_selection(0)
+                }
+            }
+        }
+    }
+
+    /// Records a click on the node or job list, returning true if it is the second click
+    /// on the same row in quick succession
+    pub fn double_click(&mut self, column: u16, row: u16) -> bool {
+        let previous = self.last_click.take();
+        let focus = self.focus_at(column, row);
+        if self.tab != Tab::Cluster || self.dragging || focus.is_none() {
+            return false;
+        }
+
+        match previous {
+            Some((time, last_column, last_row))
+                if last_row == row
+                    && self.focus_at(last_column, last_row) == focus
+                    && time.elapsed() < DOUBLE_CLICK_INTERVAL =>
+            {
+                true
+            }
+            _ => {
+                self.last_click = Some((Instant::now(), column, row));
+                false
+            }
+        }
+    }
+
+    /// Moves the border between the node and job lists to the given row, if dragged
+    pub fn mouse_drag(&mut self, row: u16) {
+        if let Some(area) = self.cluster_layout.filter(|_| self.dragging) {
+            let height = row.saturating_sub(area.y);
+            let percentage = (100.0 * height as f64 / area.height as f64).round() as u16;
+            self.split = Some(percentage.clamp(MIN_SPLIT, MAX_SPLIT));
+        }
+    }
+
+    /// Stops dragging the border between the node and job lists, returning the final
+    /// percentage of the cluster tab used by the node list, if the border was dragged
+    pub fn mouse_release(&mut self) -> Option<u16> {
+        if std::mem::take(&mut self.dragging) {
+            self.split
+        } else {
+            None
+        }
+    }
+
+    pub fn mouse_wheel(&mut self, column: u16, row: u16, delta: isize) {
+        if self.tab != Tab::Cluster {
+            return self.scroll(delta);
+        }
+
+        match self.focus_at(column, row) {
+            Some(Focus::Jobs) => self.scroll_job_selection(delta),
+            Some(Focus::Nodes) => self.scroll_node_selection(delta),
+            None => {}
+        }
+    }
+
+    pub fn toggle_focus(&mut self) {
+        if self.tab != Tab::Cluster {
+            return;
+        }
+
+        self.focus = match self.focus {
+            Focus::Jobs => Focus::Nodes,
+            Focus::Nodes => Focus::Jobs,
+        };
+
+        self.node_state.focus(self.focus == Focus::Nodes);
+        self.job_state.focus(self.focus == Focus::Jobs);
+    }
+
+    /// Copies the ID of the selected or marked jobs, or the name of the selected node or
+    /// partition, to the clipboard; alternatively the text of the selected row is copied
+    pub fn copy_selection(&mut self) {
+        let text = match (self.copy_mode, self.active_jobs()) {
+            (CopyMode::Id, Some(_)) => {
+                let ids = self.marked_jobs();
+                let ids = ids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                Some(ids.join(" ")).filter(|v| !v.is_empty())
+            }
+            (CopyMode::Row, Some(jobs)) => jobs.selected_row_text(),
+            (_, None) if self.tab != Tab::Cluster => None,
+            (CopyMode::Id, None) => match self.node_state.selected() {
+                Some(Selection::Partition(partition)) => Some(partition.name.label.clone()),
+                Some(Selection::Node(node)) => Some(node.name.clone()),
+                None => None,
+            },
+            (CopyMode::Row, None) => self.node_state.selected_row_text(),
+        };
+
+        match text {
+            Some(text) => {
+                self.show_status(format!("Copied {:?} to the clipboard", text));
+                self.clipboard = Some(text);
+            }
+            None => self.show_status_error("Nothing selected to copy"),
+        }
+    }
+
+    /// Returns text to be copied to the clipboard, if any
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// Returns a summary of the cluster followed by the node and job lists as plain text,
+    /// e.g. for use in scripts (see `--once`)
+    pub fn to_plain_text(&self) -> String {
+        let summary = SummaryRow::new(&self.cluster)
+            .filters(self.filters())
+            .warnings(self.warnings.len());
+        format!(
+            "{}\n\n{}\n{}",
+            text_to_string(summary.to_line().into()),
+            self.node_state.to_plain_text(),
+            self.all_jobs.to_plain_text()
+        )
+    }
+
+    /// Switches between showing the utilization of nodes as bars and as numbers
+    pub fn toggle_numbers(&mut self) {
+        self.node_state.toggle_numbers();
+    }
+
+    /// Switches between showing the utilization of nodes as bars and as percentages
+    pub fn toggle_percentages(&mut self) {
+        self.node_state.toggle_percentages();
+    }
+
+    /// Switches between showing the node and job lists stacked and side by side
+    pub fn toggle_layout(&mut self) {
+        self.layout = match self.layout {
+            ListLayout::Vertical => ListLayout::Horizontal,
+            ListLayout::Horizontal => ListLayout::Vertical,
+        };
+    }
+
+    /// Switches between organizing nodes by partition and by node group
+    pub fn toggle_node_groups(&mut self, app: &App) {
+        if app.node_groups.is_empty() {
+            self.show_error(
+                "No node groups",
+                "Node groups must be defined in the configuration file",
+            );
+        } else {
+            self.node_groups = !self.node_groups;
+            self.update(app);
+        }
+    }
+
+    pub fn toggle_unavailable(&mut self) {
+        self.node_state.toggle_unavailable();
+    }
+
+    /// Returns true if the job list has focus
+    pub fn jobs_have_focus(&self) -> bool {
+        self.active_jobs().is_some()
+    }
+
+    /// Selects the previous/next column in the list that has focus; jobs are sorted by
+    /// the selected column
+    pub fn select_column(&mut self, delta: isize) {
+        match (self.tab, &self.focus) {
+            (Tab::Cluster, Focus::Nodes) => self.node_state.select_column(delta),
+            _ => {
+                if let Some(jobs) = self.active_jobs_mut() {
+                    jobs.set_sort_column(delta);
+                }
+            }
+        }
+    }
+
+    /// Moves the selected column in the list that has focus. Returns the name of the
+    /// corresponding setting in the configuration file and the new order of columns,
+    /// if the column was moved
+    pub fn move_column(&mut self, delta: isize) -> Option<(&'static str, Vec<String>)> {
+        // Columns are only saved from the cluster tab, as the jobs tab has extra columns
+        if self.tab != Tab::Cluster {
+            return None;
+        }
 
-    pub fn scroll(&mut self, delta: isize) {
         match self.focus {
-            Focus::Nodes => self.scroll_node_selection(delta),
-            Focus::Jobs => self.scroll_job_selection(delta),
+            Focus::Nodes if self.node_state.move_column(delta) => {
+                Some(("node_columns", self.node_state.column_names()))
+            }
+            Focus::Jobs if self.job_state.move_column(delta) => {
+                Some(("job_columns", self.job_state.column_names()))
+            }
+            _ => None,
         }
     }
 
-    pub fn mouse_click(&mut self, row: u16) {
-        if let Some(focus) = self.focus_at(row) {
-            if self.focus != focus {
-                self.toggle_focus();
+    /// Switches between sorting jobs in ascending and descending order
+    pub fn toggle_sort_order(&mut self) {
+        if let Some(jobs) = self.active_jobs_mut() {
+            jobs.toggle_sort_order();
+        }
+    }
+
+    /// Marks/unmarks the selected job if the job list has focus
+    pub fn toggle_mark(&mut self) {
+        if let Some(jobs) = self.active_jobs_mut() {
+            jobs.toggle_mark();
+        }
+    }
+
+    /// Returns the ID of the selected job if the job list has focus
+    pub fn selected_job(&self) -> Option<usize> {
+        self.active_jobs()
+            .and_then(|jobs| jobs.selected_job())
+            .map(|job| job.id)
+    }
+
+    /// Returns the values of the selected job, node, or partition that are substituted
+    /// into user-defined commands (see [`crate::commands::PLACEHOLDERS`])
+    pub fn selection_values(&self) -> Vec<(&'static str, String)> {
+        if let Some(jobs) = self.active_jobs() {
+            let Some(job) = jobs.selected_job() else {
+                return Vec::new();
+            };
+
+            let mut values = vec![
+                ("jobid", job.id.to_string()),
+                ("user", job.user.to_string()),
+                ("account", job.account.to_string()),
+                ("partition", job.partition.label.clone()),
+            ];
+            if let Some(node) = job.nodelist.first() {
+                values.push(("node", node.to_string()));
+                values.push(("nodelist", job.nodelist.join(",")));
             }
 
-            match focus {
-                Focus::Nodes => {
-                    // -1 for border
-                    self.node_state.click(row.saturating_sub(1) as usize);
-                    self.scroll_node_selection(0)
-                }
-                Focus::Jobs => {
-                    self.job_state
-                        .click(row.saturating_sub(self.node_layout.height) as usize);
-                    self.scroll_node_selection(0)
-                }
+            return values;
+        }
+
+        match (self.tab, self.node_state.selected()) {
+            (Tab::Cluster, Some(Selection::Node(node))) => vec![
+                ("node", node.name.clone()),
+                ("nodelist", node.name.clone()),
+                ("partition", node.partition.label.clone()),
+            ],
+            (Tab::Cluster, Some(Selection::Partition(partition))) => {
+                vec![("partition", partition.name.label.clone())]
             }
+            _ => Vec::new(),
         }
     }
 
-    pub fn mouse_wheel(&mut self, row: u16, delta: isize) {
-        match self.focus_at(row) {
-            Some(Focus::Jobs) => self.scroll_job_selection(delta),
-            Some(Focus::Nodes) => self.scroll_node_selection(delta),
-            None => {}
+    /// Returns the name of the selected node, if the node list has focus
+    pub fn selected_node(&self) -> Option<String> {
+        match (self.tab, &self.focus, self.node_state.selected()) {
+            (Tab::Cluster, Focus::Nodes, Some(Selection::Node(node))) => Some(node.name.clone()),
+            _ => None,
         }
     }
 
-    pub fn toggle_focus(&mut self) {
-        self.focus = match self.focus {
-            Focus::Jobs => Focus::Nodes,
-            Focus::Nodes => Focus::Jobs,
-        };
+    /// Sets the jobs that are highlighted as being watched
+    pub fn set_watched<I: IntoIterator<Item = usize>>(&mut self, ids: I) {
+        self.job_state.set_watched(ids);
+    }
 
-        self.node_state.focus(self.focus == Focus::Nodes);
-        self.job_state.focus(self.focus == Focus::Jobs);
+    /// Returns the IDs of marked jobs, or the ID of the selected job if no jobs are marked
+    pub fn marked_jobs(&self) -> Vec<usize> {
+        self.active_jobs()
+            .map(|jobs| jobs.marked_or_selected())
+            .unwrap_or_default()
     }
 
-    pub fn toggle_unavailable(&mut self) {
-        self.node_state.toggle_unavailable();
+    pub fn clear_marks(&mut self) {
+        self.job_state.clear_marks();
+        self.all_jobs.clear_marks();
     }
 
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
-        // Require space for at least 4 rows, 2 headers, and 3 borders before rendering both tables
-        if area.height >= 2 * (2 + 1) + 3 {
+        // The job table is not rendered if it is empty or if there is too little space
+        self.job_state.clear_hyperlinks();
+        self.all_jobs.clear_hyperlinks();
+
+        let full_area = area;
+        let area = match &self.health {
+            // The health row is only drawn if there is plenty of space for the tables
+            Some(health) if area.height > 2 * (2 + 1) + 3 => {
+                let layout = Layout::default()
+                    .direction(ratatui::layout::Direction::Vertical)
+                    .constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+                    .split(area);
+
+                HealthRow::new(health).render(layout[0], buf);
+                layout[1]
+            }
+            _ => area,
+        };
+
+        // Like the health row, cluster-wide gauges require plenty of space
+        let area = if self.gauges && area.height > 2 * (2 + 1) + 3 {
+            let layout = Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+
+            let (cpus, mem, gpus) = self.cluster_utilization();
+            GaugeRow::new(cpus, mem, gpus).render(layout[0], buf);
+            layout[1]
+        } else {
+            area
+        };
+
+        // The label is recorded even if not drawn, so that it is not redrawn needlessly
+        let (label, color) = self.update_label();
+        self.update_label.clone_from(&label);
+
+        // The cluster-wide summary is shown in a status bar at the bottom of the screen
+        let area = if area.height > 2 * (2 + 1) + 3 {
             let layout = Layout::default()
                 .direction(ratatui::layout::Direction::Vertical)
+                .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+                .split(area);
+
+            let bar = Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
                 .constraints(vec![
-                    // +2 for borders and an empty line to clearly indicate the end of the list
-                    Constraint::Max((self.node_state.height() + 2).max(5)),
-                    Constraint::Min(4),
+                    Constraint::Min(0),
+                    Constraint::Length(label.chars().count() as u16 + 1),
                 ])
+                .split(layout[1]);
+
+            SummaryRow::new(&self.cluster)
+                .filters(self.filters())
+                .warnings(self.warnings.len())
+                .render(bar[0], buf);
+            Line::from(label.fg(color)).render(bar[1], buf);
+
+            layout[0]
+        } else {
+            area
+        };
+
+        let area = match &self.status {
+            // Like the health row, the status line requires plenty of space
+            Some(status) if area.height > 2 * (2 + 1) + 3 => {
+                let layout = Layout::default()
+                    .direction(ratatui::layout::Direction::Vertical)
+                    .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+                    .split(area);
+
+                let text = format!(" {}", status.text.replace('\n', " "));
+                let line = if status.error {
+                    Line::from(text.fg(palette().error).bold())
+                } else {
+                    Line::from(text)
+                };
+
+                line.render(layout[1], buf);
+                layout[0]
+            }
+            _ => area,
+        };
+
+        let area = if area.height > 2 * (2 + 1) + 3 {
+            let layout = Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+
+            self.render_tabs(layout[0], buf);
+            layout[1]
+        } else {
+            area
+        };
+
+        match self.tab {
+            Tab::Cluster => self.render_cluster(area, buf),
+            Tab::Jobs => self.render_all_jobs(area, buf),
+            Tab::Timeline => self.render_timeline(area, buf),
+            Tab::History | Tab::Reservations | Tab::Stats | Tab::Usage => {
+                let title = self.tab.to_string();
+                let instructions = self.instructions();
+                if let Some(state) = self.active_text() {
+                    LogView::new(&title)
+                        .embedded()
+                        .instructions(instructions.content)
+                        .render(area, buf, state);
+                }
+            }
+        }
+
+        self.render_popups(full_area, buf);
+    }
+
+    /// Returns the utilization of CPUs, memory, and GPUs summed across all nodes; nodes
+    /// listed in multiple partitions are only counted once
+    fn cluster_utilization(&self) -> (Utilization, Utilization, Utilization) {
+        let mut seen = HashSet::new();
+        let nodes = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.nodes)
+            .filter(|v| seen.insert(v.name.as_str()))
+            .collect::<Vec<_>>();
+
+        let default_mem = |node: &Node| self.node_state.default_mem(&node.partition.label);
+        (
+            nodes
+                .iter()
+                .map(|v| v.cpu_utilization(default_mem(v)))
+                .sum(),
+            nodes.iter().map(|v| v.mem_utilization()).sum(),
+            nodes
+                .iter()
+                .map(|v| v.gpu_utilization(default_mem(v)))
+                .sum(),
+        )
+    }
+
+    /// Renders the numbered titles of the tabs, highlighting the current tab
+    fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
+        let titles = self
+            .tabs()
+            .enumerate()
+            .map(|(idx, tab)| format!("{} {}", idx + 1, tab));
+        let selected = self.tabs().position(|v| v == self.tab);
+
+        Tabs::new(titles)
+            .select(selected.unwrap_or_default())
+            .highlight_style(Style::default().bold().reversed())
+            .render(area, buf);
+    }
+
+    /// Renders the node list and the jobs of the selected partition or node
+    fn render_cluster(&mut self, area: Rect, buf: &mut Buffer) {
+        // Side by side, each table requires space for 2 rows, a header, and 2 borders
+        if self.layout == ListLayout::Horizontal
+            && area.width >= MIN_HORIZONTAL_WIDTH
+            && area.height >= 2 + 1 + 2
+        {
+            let layout = Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                .split(area);
+
+            // Join borders with the border-less right side of the nodes table
+            let border = symbols::border::Set {
+                top_left: symbols::line::NORMAL.horizontal_down,
+                bottom_left: symbols::line::NORMAL.horizontal_up,
+                ..symbols::border::PLAIN
+            };
+
+            let borders = Borders::TOP | Borders::LEFT | Borders::BOTTOM;
+            self.render_nodes(layout[0], buf, Title::default(), borders);
+            self.render_users(layout[1], buf, self.instructions(), border);
+            self.node_layout = layout[0];
+            self.job_layout = layout[1];
+            self.cluster_layout = None;
+        } else if area.height >= 2 * (2 + 1) + 3 {
+            // Require space for at least 4 rows, 2 headers, and 3 borders before rendering
+            // both tables
+            let nodes = match self.split {
+                Some(split) => {
+                    let height = (area.height as f64 * split as f64 / 100.0).round() as u16;
+                    Constraint::Length(height.clamp(5, area.height - 4))
+                }
+                // +2 for borders and an empty line to clearly indicate the end of the list
+                None => Constraint::Max((self.node_state.height() + 2).max(5)),
+            };
+
+            let layout = Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints(vec![nodes, Constraint::Min(4)])
                 .split(area);
 
-            self.render_nodes(layout[0], buf, Title::default());
-            self.render_users(layout[1], buf, UI::instructions());
+            // Join border with border-less bottom of nodes table
+            let border = symbols::border::Set {
+                top_left: symbols::line::NORMAL.vertical_right,
+                top_right: symbols::line::NORMAL.vertical_left,
+                ..symbols::border::PLAIN
+            };
+
+            let borders = Borders::TOP | Borders::LEFT | Borders::RIGHT;
+            self.render_nodes(layout[0], buf, Title::default(), borders);
+            self.render_users(layout[1], buf, self.instructions(), border);
             self.node_layout = layout[0];
+            self.job_layout = layout[1];
+            self.cluster_layout = Some(area);
         } else {
-            self.render_nodes(area, buf, UI::instructions());
+            let borders = Borders::TOP | Borders::LEFT | Borders::RIGHT;
+            self.render_nodes(area, buf, self.instructions(), borders);
             self.node_layout = area;
+            self.job_layout = Rect::default();
+            self.cluster_layout = None;
+        }
+    }
+
+    /// Renders the list of all jobs in the cluster
+    fn render_all_jobs(&mut self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .title(Title::from(" All jobs ".bold()).alignment(Alignment::Center))
+            .title(self.instructions())
+            .borders(Borders::ALL)
+            .border_set(border::PLAIN);
+
+        if let Some(title) = self.filter_title() {
+            block = block.title(title);
+        }
+
+        self.jobs
+            .render_ref(block.inner(area), buf, &mut self.all_jobs);
+        block.render(area, buf);
+    }
+
+    /// Renders the running jobs on the nodes of the selected partition, or of the first
+    /// partition if nothing is selected
+    fn render_timeline(&mut self, area: Rect, buf: &mut Buffer) {
+        let partition = self
+            .node_state
+            .selected_partition()
+            .or_else(|| self.cluster.first());
+        let title = match partition {
+            Some(partition) => format!(" Timeline of {} ", partition.name.label),
+            None => " Timeline ".to_string(),
+        };
+        let block = Block::default()
+            .title(Title::from(title.bold()).alignment(Alignment::Center))
+            .title(self.instructions())
+            .borders(Borders::ALL)
+            .border_set(border::PLAIN);
+
+        if let Some(partition) = partition {
+            let timeline = Timeline::new(partition);
+            // Scrolling stops once the last node is shown
+            let inner = block.inner(area);
+            let visible = inner.height.saturating_sub(1) as usize;
+            self.timeline_offset = self
+                .timeline_offset
+                .min(timeline.len().saturating_sub(visible));
+            timeline.offset(self.timeline_offset).render(inner, buf);
+        }
+
+        block.render(area, buf);
+    }
+
+    /// Renders pickers, dialogs, and popups on top of the current tab
+    fn render_popups(&mut self, full_area: Rect, buf: &mut Buffer) {
+        // Alerts are drawn below other popups, in the bottom right corner above the status bar
+        if let Some((_, lines)) = &self.alert {
+            let width = lines.iter().map(|v| v.chars().count()).max().unwrap_or(0) as u16 + 4;
+            let width = width.min(full_area.width);
+            let height = (lines.len() as u16 + 2).min(full_area.height.saturating_sub(1));
+            let area = Rect {
+                x: full_area.right() - width,
+                y: full_area
+                    .bottom()
+                    .saturating_sub(height + 1)
+                    .max(full_area.y),
+                width,
+                height,
+            };
+
+            let block = Block::default()
+                .title(Title::from(" Nodes unavailable ".bold()).alignment(Alignment::Center))
+                .title(
+                    Title::from(vec![" <A> ".bold(), "Log ".into()])
+                        .alignment(Alignment::Center)
+                        .position(Position::Bottom),
+                )
+                .borders(Borders::ALL)
+                .border_set(border::PLAIN)
+                .border_style(Style::default().fg(palette().warning));
+
+            Clear.render(area, buf);
+            Paragraph::new(
+                lines
+                    .iter()
+                    .map(|v| Line::from(format!(" {}", v)))
+                    .collect::<Vec<_>>(),
+            )
+            .block(block)
+            .render(area, buf);
+        }
+
+        if let Some(picker) = &self.scripts {
+            Picker::new("Submit job", &picker.names, picker.selected)
+                .instructions(vec![
+                    " <Enter> ".bold(),
+                    "Submit".into(),
+                    " <Esc> ".bold(),
+                    "Cancel ".into(),
+                ])
+                .render(full_area, buf);
+        }
+
+        if let Some(picker) = &self.partitions {
+            let items = picker
+                .names
+                .iter()
+                .zip(&picker.shown)
+                .map(|(name, &shown)| format!("[{}] {}", if shown { "x" } else { " " }, name))
+                .collect::<Vec<_>>();
+
+            Picker::new("Show partitions", &items, picker.selected)
+                .instructions(vec![
+                    " <Space> ".bold(),
+                    "Show/Hide".into(),
+                    " <Enter> ".bold(),
+                    "Apply".into(),
+                    " <Esc> ".bold(),
+                    "Cancel ".into(),
+                ])
+                .render(full_area, buf);
+        }
+
+        if let Some(picker) = &self.features {
+            Picker::new("Filter nodes by feature", &picker.names, picker.selected)
+                .instructions(vec![
+                    " <Enter> ".bold(),
+                    "Select".into(),
+                    " <Esc> ".bold(),
+                    "Cancel ".into(),
+                ])
+                .render(full_area, buf);
+        }
+
+        if let Some(viewer) = &mut self.output {
+            let (label, path) = &viewer.files[viewer.current];
+            let mut title = format!("Output of job {}: {} ({})", viewer.id, label, path);
+            if viewer.state.following() {
+                title.push_str(" [following]");
+            }
+
+            let mut instructions = Vec::new();
+            if viewer.files.len() > 1 {
+                instructions.extend([" <Tab> ".bold(), "stdout/stderr".into()]);
+            }
+            instructions.extend([
+                " <F> ".bold(),
+                "Follow".into(),
+                " </> ".bold(),
+                "Search".into(),
+                " <N/P> ".bold(),
+                "Next/Previous match".into(),
+                " <Esc> ".bold(),
+                "Close ".into(),
+            ]);
+
+            LogView::new(&title)
+                .error(viewer.error.as_deref())
+                .instructions(instructions)
+                .render(full_area, buf, &mut viewer.state);
+        }
+
+        if let Some(output) = &mut self.command_output {
+            LogView::new(&format!("Output of {}", output.command))
+                .instructions(vec![
+                    " </> ".bold(),
+                    "Search".into(),
+                    " <N/P> ".bold(),
+                    "Next/Previous match".into(),
+                    " <Esc> ".bold(),
+                    "Close ".into(),
+                ])
+                .render(full_area, buf, &mut output.state);
+        }
+
+        if let Some(input) = &self.input {
+            let mut text = Text::from(vec![
+                Line::from(input.prompt.clone()),
+                Line::from(vec!["> ".into(), input.value.clone().bold(), "█".into()]),
+            ]);
+            if let Some(error) = &input.error {
+                text.push_line(Line::from(error.clone().fg(palette().error)));
+            }
+
+            Popup::new(&input.title, text)
+                .instructions(vec![
+                    " <Enter> ".bold(),
+                    "Save".into(),
+                    " <Esc> ".bold(),
+                    "Cancel ".into(),
+                ])
+                .render(full_area, buf);
+        }
+
+        if let Some(confirmation) = &self.confirmation {
+            Popup::new(&confirmation.title, confirmation.question.as_str())
+                .color(palette().warning)
+                .instructions(vec![
+                    " <Y> ".bold(),
+                    "Yes".into(),
+                    " <N> ".bold(),
+                    "No ".into(),
+                ])
+                .render(full_area, buf);
+        }
+
+        if let Some(message) = &self.popup {
+            let chart_height = message.chart.as_ref().map_or(0, |_| LOAD_CHART_HEIGHT);
+            let area = Popup::new(&message.title, message.text.clone())
+                .color(message.color)
+                .instructions(vec![" <Esc> ".bold(), "Close ".into()])
+                .reserve(chart_height)
+                .render_reserved(full_area, buf);
+
+            if let (Some(chart), Some(area)) = (&message.chart, area) {
+                chart.clone().render(area, buf);
+            }
+        }
+    }
+
+    /// Returns hyperlinks to be drawn on top of the most recently rendered frame
+    pub fn hyperlinks(&self) -> Vec<Hyperlink> {
+        // Hyperlinks are not drawn over popups
+        if self.popup.is_some()
+            || self.confirmation.is_some()
+            || self.input.is_some()
+            || self.output.is_some()
+            || self.scripts.is_some()
+            || self.features.is_some()
+            || self.partitions.is_some()
+        {
+            return Vec::new();
+        }
+
+        match self.tab {
+            Tab::Cluster => {
+                let mut hyperlinks = self.node_state.hyperlinks().to_vec();
+                hyperlinks.extend_from_slice(self.job_state.hyperlinks());
+                hyperlinks
+            }
+            Tab::Jobs => self.all_jobs.hyperlinks().to_vec(),
+            _ => Vec::new(),
         }
     }
 
-    fn focus_at(&self, row: u16) -> Option<Focus> {
-        if row >= self.node_layout.height && !self.node_layout.is_empty() {
+    fn focus_at(&self, column: u16, row: u16) -> Option<Focus> {
+        let within = |area: Rect, bottom: u16| {
+            (area.x..area.right()).contains(&column) && (area.y..bottom).contains(&row)
+        };
+
+        if within(self.job_layout, self.job_layout.bottom()) {
             Some(Focus::Jobs)
-        } else if row < self.node_layout.height.saturating_sub(1) {
+        } else if within(
+            self.node_layout,
+            self.node_layout.bottom().saturating_sub(1),
+        ) {
             Some(Focus::Nodes)
         } else {
             None
@@ -155,14 +2304,37 @@ impl UI {
         self.job_state.scroll(delta)
     }
 
-    fn render_nodes(&mut self, area: Rect, buf: &mut Buffer, instructions: Title) {
-        let title = vec![" Partitions ".bold()];
+    fn render_nodes(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        instructions: Title,
+        borders: Borders,
+    ) {
+        let mut title = vec![if self.node_groups {
+            " Node groups ".bold()
+        } else {
+            " Partitions ".bold()
+        }];
+        if let Some(replay) = &self.replay {
+            title.push(format!("(replay {}) ", replay).into());
+        }
+
+        let partitions = self.node_state.partitions();
+        if !partitions.is_empty() {
+            title.push(format!("(only {}) ", partitions.join(",")).into());
+        }
+
+        if let Some(feature) = self.node_state.feature() {
+            title.push(format!("(feature {}) ", feature).into());
+        }
+
         let title = Title::from(Line::from(title));
 
         let block = Block::default()
             .title(title.clone().alignment(Alignment::Center))
             .title(instructions)
-            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .borders(borders)
             .border_set(border::PLAIN);
 
         self.nodes
@@ -170,41 +2342,405 @@ impl UI {
         block.render(area, buf);
     }
 
-    fn render_users(&mut self, area: Rect, buf: &mut Buffer, instructions: Title) {
+    fn render_users(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        instructions: Title,
+        border: symbols::border::Set,
+    ) {
         let title = match self.node_state.selected() {
             Some(Selection::Node(node)) => format!(" {} ", node.name),
             Some(Selection::Partition(partition)) => format!(" {} ", partition.name),
             None => String::default(),
         };
 
-        // Join border with border-less bottom of nodes table
-        let border = symbols::border::Set {
-            top_left: symbols::line::NORMAL.vertical_right,
-            top_right: symbols::line::NORMAL.vertical_left,
-            ..symbols::border::PLAIN
-        };
-
-        let block = Block::default()
+        let mut block = Block::default()
             .title(Title::from(title).alignment(Alignment::Center))
             .title(instructions)
             .borders(Borders::ALL)
             .border_set(border);
 
+        if let Some(title) = self.filter_title() {
+            block = block.title(title);
+        }
+
         self.jobs
             .render_ref(block.inner(area), buf, &mut self.job_state);
         block.render(area, buf);
     }
 
-    fn instructions() -> Title<'static> {
-        Title::from(Line::from(vec![
+    /// Describes the filters applied to the node and job lists
+    fn filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        let partitions = self.node_state.partitions();
+        if !partitions.is_empty() {
+            filters.push(format!("only {}", partitions.join(",")));
+        }
+
+        if let Some(feature) = self.node_state.feature() {
+            filters.push(format!("feature {}", feature));
+        }
+
+        if self.node_state.hides_unavailable() {
+            filters.push(String::from("unavailable hidden"));
+        }
+
+        if let Some(filter) = self.job_filter.as_ref().filter(|v| !v.text.is_empty()) {
+            filters.push(format!("jobs /{}", filter.text));
+        }
+
+        filters
+    }
+
+    /// Returns the title showing the job filter, if any
+    fn filter_title(&self) -> Option<Title<'static>> {
+        let filter = self.job_filter.as_ref()?;
+        let mut text = vec![" /".bold(), filter.text.clone().into()];
+        if filter.editing {
+            text.push("\u{2588}".into());
+        }
+        text.push(" ".into());
+
+        Some(Title::from(Line::from(text)).alignment(Alignment::Left))
+    }
+
+    fn instructions(&self) -> Title<'static> {
+        let mut instructions = Vec::new();
+        if self.replay.is_some() {
+            instructions.extend([" <[/]> ".bold(), "Previous/Next snapshot".into()]);
+        }
+
+        // Actions that modify jobs or nodes are not listed in read-only mode
+        if self.submit && !self.read_only {
+            instructions.extend([" <B> ".bold(), "Submit job".into()]);
+        }
+
+        if !self.read_only && self.jobs_have_focus() {
+            instructions.extend([
+                " <C> ".bold(),
+                "Cancel job".into(),
+                " <Z/U> ".bold(),
+                "Hold/Release".into(),
+                " <Ctrl-R> ".bold(),
+                "Requeue".into(),
+                " <T> ".bold(),
+                "Time limit".into(),
+            ]);
+        } else if !self.read_only && self.admin && self.tab == Tab::Cluster {
+            instructions.extend([" <D/O> ".bold(), "Drain/Resume node".into()]);
+        } else if self.tab == Tab::Usage {
+            instructions.extend([" <Left/Right> ".bold(), "Day/Week/Month".into()]);
+        }
+
+        instructions.extend([
             " <H> ".bold(),
             "Hide/Show unavailable".into(),
             " <R> ".bold(),
             "Refresh".into(),
+            " <P> ".bold(),
+            if self.paused { "Resume" } else { "Pause" }.into(),
             " <Q> ".bold(),
             "Quit ".into(),
-        ]))
-        .alignment(Alignment::Center)
-        .position(Position::Bottom)
+        ]);
+
+        Title::from(Line::from(instructions))
+            .alignment(Alignment::Center)
+            .position(Position::Bottom)
+    }
+}
+
+/// Describes the state transitions of the user's jobs, most recent first, as well as the
+/// memory used by histories
+fn ledger_lines(ledger: &Ledger, history_usage: usize) -> Vec<String> {
+    let mut lines = ledger
+        .transitions()
+        .rev()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        lines.push(format!("No jobs observed for user {:?}", ledger.user()));
+    }
+
+    // Memory used by per-session histories, which is limited via `[retention]`
+    let retention = ledger.retention();
+    let max_age = match retention.max_age {
+        Some(max_age) => format!(", at most {} old", duration_to_string(max_age)),
+        None => String::new(),
+    };
+    lines.push(String::new());
+    lines.push(format!(
+        "{} of at most {} transitions kept{}; history uses ~{}",
+        ledger.len(),
+        retention.max_entries,
+        max_age,
+        bytes_to_string(ledger.memory_usage() + history_usage)
+    ));
+
+    lines
+}
+
+/// Describes reservations, e.g. maintenance windows, in the order listed by Slurm
+fn reservation_lines(reservations: &[Reservation]) -> Vec<String> {
+    if reservations.is_empty() {
+        return vec![String::from("No reservations")];
+    }
+
+    let mut lines = vec![format!(
+        "{:<20} {:<16} {:<16} {:<24} Nodes",
+        "Name", "Start", "End", "Flags"
+    )];
+    for reservation in reservations {
+        lines.push(format!(
+            "{:<20} {:<16} {:<16} {:<24} {}",
+            reservation.name,
+            reservation.start.format("%Y-%m-%d %H:%M"),
+            reservation.end.format("%Y-%m-%d %H:%M"),
+            reservation.flags.join(","),
+            reservation.nodes.join(","),
+        ));
+    }
+
+    lines
+}
+
+/// Describes the utilization of each cluster and the users with the largest usage
+fn usage_lines(report: &UsageReport) -> Vec<String> {
+    let mut lines = vec![
+        format!("Utilization during the last {}", report.period),
+        String::new(),
+        format!(
+            "{:<16} {:>10} {:>8} {:>13} {:>8} {:>8}",
+            "Cluster", "Allocated", "Down", "Planned down", "Idle", "Planned"
+        ),
+    ];
+    for cluster in &report.clusters {
+        lines.push(format!(
+            "{:<16} {:>9.1}% {:>7.1}% {:>12.1}% {:>7.1}% {:>7.1}%",
+            cluster.cluster,
+            cluster.allocated,
+            cluster.down,
+            cluster.planned_down,
+            cluster.idle,
+            cluster.planned
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Top users during the last {}", report.period));
+    lines.push(String::new());
+    if report.top_users.is_empty() {
+        lines.push(String::from("No usage recorded"));
+        return lines;
+    }
+
+    lines.push(format!(
+        "{:<16} {:<16} {:<24} {:<16} {:>8}",
+        "Cluster", "Login", "Name", "Account", "Used"
+    ));
+    for user in &report.top_users {
+        lines.push(format!(
+            "{:<16} {:<16} {:<24} {:<16} {:>7.1}%",
+            user.cluster, user.login, user.name, user.account, user.used
+        ));
+    }
+
+    lines
+}
+
+/// Summarizes the utilization of the nodes and the number of jobs in each partition, as
+/// well as the recent utilization of CPUs and GPUs in each partition
+fn stats_lines(cluster: &[Partition], history: &PartitionHistory) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:<16} {:>11} {:>13} {:>21} {:>9} {:>8} {:>8}",
+        "Partition", "Nodes", "CPUs", "Memory", "GPUs", "Running", "Pending"
+    )];
+
+    for partition in cluster {
+        let nodes = &partition.nodes;
+        let sum = |f: fn(&Node) -> usize| nodes.iter().map(f).sum::<usize>();
+        let jobs = |state: JobState| partition.jobs.iter().filter(|v| v.state == state).count();
+
+        lines.push(format!(
+            "{:<16} {:>11} {:>13} {:>21} {:>9} {:>8} {:>8}",
+            partition.name.label,
+            format!(
+                "{}/{}",
+                sum(|v| v.state.is_available() as usize),
+                nodes.len()
+            ),
+            format!(
+                "{}/{}",
+                sum(|v| v.cpu_state.allocated),
+                sum(|v| v.cpu_state.total)
+            ),
+            format!(
+                "{}/{}",
+                mb_to_string(sum(|v| v.mem_alloc)),
+                mb_to_string(sum(|v| v.mem))
+            ),
+            format!("{}/{}", sum(|v| v.gpus_used), sum(|v| v.gpus)),
+            jobs(JobState::Running),
+            jobs(JobState::Pending),
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from(
+        "Nodes are available/total; CPUs, memory, and GPUs are allocated/total",
+    ));
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Allocated CPUs and GPUs during the last {} updates, oldest first",
+        MAX_PARTITION_SAMPLES
+    ));
+    for partition in cluster {
+        let Some(samples) = history.samples(&partition.name.label) else {
+            continue;
+        };
+
+        for (label, values) in [("CPUs", &samples.cpus), ("GPUs", &samples.gpus)] {
+            if let Some(last) = values.back() {
+                lines.push(format!(
+                    "{:<16} {} {:<width$} {:>3}%",
+                    if label == "CPUs" {
+                        partition.name.label.as_str()
+                    } else {
+                        ""
+                    },
+                    label,
+                    sparkline(values.iter().copied(), Some(100)),
+                    last,
+                    width = MAX_PARTITION_SAMPLES
+                ));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Describes the resources of a node, including warnings about how CPUs are counted
+fn node_details(node: &Node) -> String {
+    let cpus = &node.cpu_state;
+    let mut lines = vec![
+        format!("{:<12}{}", "Partition", node.partition),
+        format!("{:<12}{}", "State", node.state),
+        format!(
+            "{:<12}{} ({} allocated, {} idle, {} other)",
+            "CPUs", cpus.total, cpus.allocated, cpus.idle, cpus.other
+        ),
+    ];
+
+    if let Some(topology) = &node.topology {
+        lines.push(format!("{:<12}{}", "S:C:T", topology));
+    }
+
+    if let Some(load) = node.cpu_load {
+        lines.push(format!("{:<12}{:.2}", "CPU load", load));
+    }
+
+    lines.push(format!(
+        "{:<12}{} ({} allocated)",
+        "Memory",
+        mb_to_string(node.mem),
+        mb_to_string(node.mem_alloc)
+    ));
+
+    if let Some(free) = node.mem_free {
+        lines.push(format!("{:<12}{}", "Free memory", mb_to_string(free)));
+    }
+
+    if node.gpus > 0 {
+        lines.push(format!(
+            "{:<12}{} ({} allocated)",
+            "GPUs", node.gpus, node.gpus_used
+        ));
+    }
+
+    // Occupancy of individual GPUs, e.g. "[##..]" if GPUs 0 and 1 are in use
+    if node.gpus > 0 && !node.gpus_in_use.is_empty() {
+        let occupancy = (0..node
+            .gpus
+            .max(node.gpus_in_use.iter().max().map_or(0, |v| v + 1)))
+            .map(|idx| {
+                if node.gpus_in_use.contains(&idx) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        let in_use = node
+            .gpus_in_use
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+
+        lines.push(format!(
+            "{:<12}[{}] in use: {}",
+            "GPU indices",
+            occupancy,
+            in_use.join(",")
+        ));
+    }
+
+    // Utilization reported by the GPU probe, if any
+    for (idx, gpu) in node.gpu_usage.iter().enumerate() {
+        lines.push(format!(
+            "{:<12}{:>3.0}% utilized, {}/{} memory",
+            format!("GPU {}", idx),
+            gpu.utilization,
+            mb_to_string(gpu.mem_used),
+            mb_to_string(gpu.mem_total)
+        ));
+    }
+
+    if !node.features.is_empty() {
+        lines.push(format!("{:<12}{}", "Features", node.features.join(",")));
+    }
+
+    if let Some(warning) = node.topology_warning() {
+        lines.push(String::new());
+        lines.push(warning);
+    }
+
+    lines.join("\n")
+}
+
+/// Formats a duration using the largest whole unit, e.g. "7d" or "90m"
+fn duration_to_string(duration: std::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0 => String::from("0s"),
+        _ if seconds.is_multiple_of(86400) => format!("{}d", seconds / 86400),
+        _ if seconds.is_multiple_of(3600) => format!("{}h", seconds / 3600),
+        _ if seconds.is_multiple_of(60) => format!("{}m", seconds / 60),
+        _ => format!("{}s", seconds),
+    }
+}
+
+/// Formats the time elapsed since an event using the largest unit, e.g. "12s" or "3m"
+fn age_to_string(age: Duration) -> String {
+    let seconds = age.as_secs();
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}h", seconds / 3600)
+    }
+}
+
+/// Formats a number of bytes, e.g. "12.3 KB"
+fn bytes_to_string(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
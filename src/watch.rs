@@ -0,0 +1,78 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Local;
+
+use crate::{
+    ledger::Transition,
+    slurm::{Job, JobState, Partition},
+};
+
+/// Jobs pinned by the user, whose changes in state are reported as they are observed
+#[derive(Debug, Default)]
+pub struct Watchlist {
+    /// Most recently observed state and name of each watched job
+    jobs: BTreeMap<usize, (JobState, String)>,
+}
+
+impl Watchlist {
+    /// Starts or stops watching a job; returns true if the job is now being watched
+    pub fn toggle(&mut self, job: &Job) -> bool {
+        if self.jobs.remove(&job.id).is_some() {
+            false
+        } else {
+            self.jobs
+                .insert(job.id, (job.state.clone(), job.name.clone()));
+            true
+        }
+    }
+
+    /// Returns the IDs of watched jobs
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.jobs.keys().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Returns the changes in state of watched jobs since the previous update. Jobs
+    /// that are no longer listed by `squeue` are reported with no new state (see
+    /// [`Transition::to`]) and are no longer watched
+    pub fn update(&mut self, cluster: &[Partition]) -> Vec<Transition> {
+        if self.jobs.is_empty() {
+            return Vec::new();
+        }
+
+        let time = Local::now();
+        let current = cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .filter(|v| self.jobs.contains_key(&v.id))
+            .map(|v| (v.id, v.state.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let mut transitions = Vec::new();
+        self.jobs.retain(|&id, (state, name)| {
+            let to = current.get(&id);
+            if to != Some(state) {
+                transitions.push(Transition {
+                    time,
+                    id,
+                    name: name.clone(),
+                    from: Some(state.clone()),
+                    to: to.cloned(),
+                });
+            }
+
+            match to {
+                Some(to) => {
+                    *state = to.clone();
+                    true
+                }
+                None => false,
+            }
+        });
+
+        transitions
+    }
+}
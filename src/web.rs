@@ -0,0 +1,348 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::Local;
+use color_eyre::{eyre::Context, Result};
+use serde_json::Value;
+
+use crate::{
+    app::App,
+    export::cluster_to_json,
+    server::{serve, Request, Response},
+    slurm::{Job, JobState, Node, Time},
+    widgets::mb_to_string,
+};
+
+/// Style sheet of the dashboard; deliberately minimal, since the page is read-only
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 1em 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.2em 0.8em; text-align: left; border-bottom: 1px solid #ddd; }
+td.number { text-align: right; }
+tr.inactive { color: #888; }
+p.error { color: #b00; }
+p.warning { color: #a60; }
+";
+
+/// Pages served by the dashboard, rendered after every update
+#[derive(Debug)]
+struct Dashboard {
+    html: String,
+    json: Value,
+}
+
+impl Dashboard {
+    fn new(app: &App) -> Dashboard {
+        Dashboard {
+            html: render(app),
+            json: cluster_to_json(app),
+        }
+    }
+}
+
+/// Serves a read-only dashboard of partitions, nodes, and jobs as HTML (`/`) and as JSON
+/// (`/cluster.json`, see `--output json`) instead of running the user interface. The
+/// Slurm state is collected every `--interval` seconds, and the page reloads itself
+pub fn run(app: &mut App, addr: &str) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).wrap_err_with(|| format!("failed to bind {:?}", addr))?;
+    eprintln!("Serving dashboard at http://{}", listener.local_addr()?);
+
+    let dashboard = Arc::new(Mutex::new(Dashboard::new(app)));
+    {
+        let dashboard = dashboard.clone();
+        thread::spawn(move || {
+            serve(listener, move |request| {
+                let dashboard = dashboard.lock().unwrap_or_else(|v| v.into_inner());
+                handle(request, &dashboard)
+            })
+        });
+    }
+
+    let interval = app.args.interval.max(1);
+    while app.running {
+        thread::sleep(Duration::from_secs(interval));
+        if !app.update(interval)? {
+            continue;
+        }
+
+        // The last state collected successfully is still served, along with the error
+        if let Some(error) = &app.error {
+            eprintln!("Error while updating Slurm state: {}", error);
+        }
+
+        *dashboard.lock().unwrap_or_else(|v| v.into_inner()) = Dashboard::new(app);
+    }
+
+    Ok(())
+}
+
+fn handle(request: &Request, dashboard: &Dashboard) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => Response::html(&dashboard.html),
+        ("GET", "/cluster.json") => Response::json(&dashboard.json),
+        (_, "/" | "/cluster.json") => Response::text(405, "method not allowed"),
+        _ => Response::text(404, "not found"),
+    }
+}
+
+/// Renders the partition, node, and job lists as HTML tables
+fn render(app: &App) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"refresh\" content=\"{}\">\n<title>slurmboard</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>slurmboard</h1>\n",
+        app.args.interval.max(1),
+        STYLE
+    );
+
+    match app.updated {
+        Some(updated) => {
+            let time =
+                Local::now() - chrono::Duration::from_std(updated.elapsed()).unwrap_or_default();
+            let _ = writeln!(html, "<p>Updated {}</p>", time.format("%Y-%m-%d %H:%M:%S"));
+        }
+        None => html.push_str("<p>Not updated</p>\n"),
+    }
+
+    if let Some(error) = &app.error {
+        let _ = writeln!(
+            html,
+            "<p class=\"error\">Error while updating: {}</p>",
+            escape(error)
+        );
+    }
+
+    for warning in &app.warnings {
+        let _ = writeln!(
+            html,
+            "<p class=\"warning\">Warning: {}</p>",
+            escape(warning)
+        );
+    }
+
+    html.push_str("<h2>Partitions</h2>\n");
+    table(
+        &mut html,
+        &[
+            "Partition",
+            "Nodes",
+            "CPUs",
+            "Memory",
+            "GPUs",
+            "Running",
+            "Pending",
+        ],
+        app.cluster.iter().map(|partition| {
+            let nodes = &partition.nodes;
+            let sum = |f: fn(&Node) -> usize| nodes.iter().map(f).sum::<usize>();
+            let jobs = |state: JobState| partition.jobs.iter().filter(|v| v.state == state).count();
+
+            Row {
+                inactive: false,
+                cells: vec![
+                    Cell::text(&partition.name.to_string()),
+                    Cell::number(format!(
+                        "{}/{}",
+                        sum(|v| v.state.is_available() as usize),
+                        nodes.len()
+                    )),
+                    Cell::number(format!(
+                        "{}/{}",
+                        sum(|v| v.cpu_state.allocated),
+                        sum(|v| v.cpu_state.total)
+                    )),
+                    Cell::number(format!(
+                        "{}/{}",
+                        mb_to_string(sum(|v| v.mem_alloc)),
+                        mb_to_string(sum(|v| v.mem))
+                    )),
+                    Cell::number(format!("{}/{}", sum(|v| v.gpus_used), sum(|v| v.gpus))),
+                    Cell::number(jobs(JobState::Running).to_string()),
+                    Cell::number(jobs(JobState::Pending).to_string()),
+                ],
+            }
+        }),
+    );
+
+    // Nodes and pending jobs may be listed in multiple partitions, but are only listed once
+    let mut seen = HashSet::new();
+    html.push_str("<h2>Nodes</h2>\n");
+    table(
+        &mut html,
+        &[
+            "Node",
+            "Partition",
+            "State",
+            "CPUs",
+            "Load",
+            "Memory",
+            "GPUs",
+            "Jobs",
+        ],
+        app.cluster
+            .iter()
+            .flat_map(|v| &v.nodes)
+            .filter(|v| seen.insert(v.name.as_str()))
+            .map(|node| Row {
+                inactive: !node.state.is_available(),
+                cells: vec![
+                    Cell::text(&node.name),
+                    Cell::text(&node.partition.to_string()),
+                    Cell::text(&node.state.to_string()),
+                    Cell::number(format!(
+                        "{}/{}",
+                        node.cpu_state.allocated, node.cpu_state.total
+                    )),
+                    Cell::number(
+                        node.cpu_load
+                            .map_or_else(String::new, |v| format!("{:.1}", v)),
+                    ),
+                    Cell::number(format!(
+                        "{}/{}",
+                        mb_to_string(node.mem_alloc),
+                        mb_to_string(node.mem)
+                    )),
+                    Cell::number(format!("{}/{}", node.gpus_used, node.gpus)),
+                    Cell::number(node.jobs.len().to_string()),
+                ],
+            }),
+    );
+
+    let mut seen = HashSet::new();
+    html.push_str("<h2>Jobs</h2>\n");
+    table(
+        &mut html,
+        &[
+            "JobID",
+            "User",
+            "Account",
+            "Partition",
+            "State",
+            "Runtime",
+            "Time limit",
+            "Nodes",
+            "CPUs",
+            "GPUs",
+            "Memory",
+            "Nodelist",
+            "Name",
+        ],
+        app.cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .filter(|v| seen.insert(v.id))
+            .map(job_row),
+    );
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn job_row(job: &Job) -> Row {
+    let partition = if job.partitions.is_empty() {
+        job.partition.label.clone()
+    } else {
+        job.partitions.join(",")
+    };
+
+    Row {
+        inactive: job.state != JobState::Running,
+        cells: vec![
+            Cell::text(&job.display_id()),
+            Cell::text(&job.user),
+            Cell::text(&job.account),
+            Cell::text(&partition),
+            Cell::text(&job.state.to_string()),
+            Cell::number(job.time.to_string()),
+            // Time limits are missing from output captured by older versions of slurmboard
+            Cell::number(match job.time_limit {
+                Time::Invalid => String::new(),
+                ref limit => limit.to_string(),
+            }),
+            Cell::number(job.nodes.to_string()),
+            Cell::number(job.cpus.to_string()),
+            Cell::number(job.gpus.to_string()),
+            Cell::number(mb_to_string(job.mem)),
+            Cell::text(&job.nodelist.join(",")),
+            Cell::text(&job.name),
+        ],
+    }
+}
+
+/// A row of an HTML table; inactive rows (e.g. pending jobs) are dimmed
+struct Row {
+    inactive: bool,
+    cells: Vec<Cell>,
+}
+
+/// A cell of an HTML table, containing escaped text
+struct Cell {
+    text: String,
+    number: bool,
+}
+
+impl Cell {
+    fn text(value: &str) -> Cell {
+        Cell {
+            text: escape(value),
+            number: false,
+        }
+    }
+
+    fn number(value: String) -> Cell {
+        Cell {
+            text: escape(&value),
+            number: true,
+        }
+    }
+}
+
+fn table<I: Iterator<Item = Row>>(html: &mut String, header: &[&str], rows: I) {
+    html.push_str("<table>\n<tr>");
+    for column in header {
+        let _ = write!(html, "<th>{}</th>", escape(column));
+    }
+    html.push_str("</tr>\n");
+
+    for row in rows {
+        html.push_str(if row.inactive {
+            "<tr class=\"inactive\">"
+        } else {
+            "<tr>"
+        });
+
+        for cell in row.cells {
+            let class = if cell.number { " class=\"number\"" } else { "" };
+            let _ = write!(html, "<td{}>{}</td>", class, cell.text);
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n");
+}
+
+/// Escapes text for use in HTML elements and attributes
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
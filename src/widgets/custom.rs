@@ -0,0 +1,71 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use color_eyre::{eyre::bail, Result};
+use ratatui::{layout::Alignment as TextAlignment, text::Text};
+
+use crate::config::{Alignment, ColumnConfig, Pane};
+
+use super::{jobs, nodes};
+
+/// Checks that the custom columns defined in the configuration file have valid names and
+/// fields, and that their names do not clash with other columns of the same list
+pub fn validate_custom_columns(columns: &[ColumnConfig]) -> Result<()> {
+    let mut names = HashSet::new();
+    for column in columns {
+        let name = column.name.trim();
+        if name.is_empty() || name.contains(',') {
+            bail!("invalid name of custom column {:?}", column.name);
+        } else if column.field.is_empty()
+            || !column
+                .field
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "invalid field {:?} of custom column {:?}",
+                column.field,
+                column.name
+            );
+        }
+
+        let builtin = match column.list {
+            Pane::Jobs => jobs::is_builtin_column(name),
+            Pane::Nodes => nodes::is_builtin_column(name),
+        };
+
+        if builtin || !names.insert((column.list, name.to_ascii_lowercase())) {
+            bail!("custom column {:?} is already defined", column.name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the custom columns of the node or job list, in the order in which their
+/// values are stored in `custom` of each node or job
+pub fn custom_columns(columns: &[ColumnConfig], list: Pane) -> Vec<ColumnConfig> {
+    columns.iter().filter(|v| v.list == list).cloned().collect()
+}
+
+/// Returns the text of a custom column, aligned as configured
+pub fn custom_text<'a>(column: &ColumnConfig, values: &[String], idx: usize) -> Text<'a> {
+    let text = Text::from(values.get(idx).cloned().unwrap_or_default());
+    match column.align {
+        Alignment::Left => text,
+        Alignment::Right => text.alignment(TextAlignment::Right),
+    }
+}
+
+/// Compares the values of a custom column, numerically if so configured. Values that
+/// are not numbers (e.g. missing values) are ordered before numbers
+pub fn compare_custom(column: &ColumnConfig, a: Option<&String>, b: Option<&String>) -> Ordering {
+    if column.numeric {
+        let parse = |v: Option<&String>| v.and_then(|v| v.trim().parse::<f64>().ok());
+        match (parse(a), parse(b)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    } else {
+        a.cmp(&b)
+    }
+}
@@ -0,0 +1,65 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use super::palette;
+use crate::slurm::Utilization;
+
+/// Single line with wide gauges of the CPUs, memory, and GPUs allocated across the
+/// whole cluster, each followed by the percentage of allocated resources
+#[derive(Debug)]
+pub struct GaugeRow {
+    cpus: Utilization,
+    mem: Utilization,
+    gpus: Utilization,
+}
+
+impl GaugeRow {
+    pub fn new(cpus: Utilization, mem: Utilization, gpus: Utilization) -> Self {
+        Self { cpus, mem, gpus }
+    }
+}
+
+impl Widget for GaugeRow {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Clusters without GPUs only show CPUs and memory
+        let gauges = [
+            ("CPUs", self.cpus),
+            ("Memory", self.mem),
+            ("GPUs", self.gpus),
+        ]
+        .into_iter()
+        .filter(|(_, v)| v.capacity > 0.0)
+        .collect::<Vec<_>>();
+
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Ratio(1, gauges.len() as u32);
+                gauges.len()
+            ])
+            .split(area);
+
+        for ((label, utilization), area) in gauges.into_iter().zip(layout.iter()) {
+            let label = format!(" {} ", label);
+            let mut spans = vec![Span::from(format!("{:<8}", label)).bold()];
+
+            // Room for the label and a trailing margin
+            let width = area.width.saturating_sub(9);
+            if palette().monochrome {
+                // ASCII gauges include the percentage
+                spans.extend(utilization.to_ascii_line(width).spans);
+            } else {
+                let percentage = utilization.to_percentage();
+                spans.extend(utilization.to_line(width.saturating_sub(5)).spans);
+                spans.push(format!("{:>5}", percentage).into());
+            }
+
+            Line::from(spans).render(*area, buf);
+        }
+    }
+}
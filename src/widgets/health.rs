@@ -0,0 +1,67 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::slurm::Health;
+
+use super::palette;
+
+/// Single line summarizing the responsiveness of the Slurm daemons
+#[derive(Debug)]
+pub struct HealthRow<'a> {
+    health: &'a Health,
+}
+
+impl<'a> HealthRow<'a> {
+    pub fn new(health: &'a Health) -> Self {
+        Self { health }
+    }
+
+    /// Formats an optional `sdiag` statistic
+    fn statistic(label: &'static str, value: Option<usize>) -> Vec<Span<'a>> {
+        let value = match value {
+            Some(value) => value.to_string().into(),
+            None => "N/A".fg(palette().dimmed),
+        };
+
+        vec![" │ ".into(), label.into(), ": ".into(), value]
+    }
+}
+
+impl Widget for HealthRow<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![" Slurmctld: ".bold()];
+        if self.health.controllers.is_empty() {
+            spans.push("NOT RESPONDING".fg(palette().error).bold());
+        }
+
+        for (idx, controller) in self.health.controllers.iter().enumerate() {
+            if idx > 0 {
+                spans.push(", ".into());
+            }
+
+            spans.push(format!("{}@{} ", controller.role, controller.host).into());
+            spans.push(if controller.up {
+                "UP".fg(palette().ok)
+            } else {
+                "DOWN".fg(palette().error).bold()
+            });
+        }
+
+        spans.extend(Self::statistic(
+            "Server threads",
+            self.health.server_threads,
+        ));
+        spans.extend(Self::statistic("Agent queue", self.health.agent_queue));
+        spans.extend(Self::statistic(
+            "DBD agent queue",
+            self.health.dbd_agent_queue,
+        ));
+
+        Line::from(spans).render(area, buf);
+    }
+}
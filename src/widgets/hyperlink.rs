@@ -0,0 +1,84 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Attribute, Colors, Print, ResetColor, SetAttribute, SetColors},
+};
+use ratatui::{buffer::Buffer, layout::Rect, style::Modifier};
+
+/// Area of the screen that is rendered as an OSC 8 hyperlink
+#[derive(Clone, Debug)]
+pub struct Hyperlink {
+    pub area: Rect,
+    pub url: String,
+}
+
+/// Replaces `{key}` placeholders in a URL template with the corresponding values
+pub fn format_url(template: &str, values: &[(&str, &str)]) -> String {
+    let mut url = template.to_string();
+    for (key, value) in values {
+        url = url.replace(&format!("{{{}}}", key), value);
+    }
+
+    url
+}
+
+/// Re-draws the text of each hyperlink wrapped in OSC 8 escape sequences.
+///
+/// Escape sequences cannot be stored in the cells of a [`Buffer`], since the URL
+/// would count towards the width of the cell, so hyperlinks are instead drawn on
+/// top of cells that have already been drawn, using the same text and style.
+pub fn write_hyperlinks<W: Write>(
+    writer: &mut W,
+    buf: &Buffer,
+    hyperlinks: &[Hyperlink],
+) -> io::Result<()> {
+    for hyperlink in hyperlinks {
+        let area = hyperlink.area.intersection(buf.area);
+        if area.is_empty() {
+            continue;
+        }
+
+        let mut text = String::new();
+        for x in area.left()..area.right() {
+            text.push_str(buf.get(x, area.y).symbol());
+        }
+
+        let text = text.trim_end();
+        if text.is_empty() {
+            continue;
+        }
+
+        let cell = buf.get(area.x, area.y);
+        queue!(
+            writer,
+            MoveTo(area.x, area.y),
+            SetColors(Colors::new(cell.fg.into(), cell.bg.into()))
+        )?;
+
+        for (modifier, attribute) in [
+            (Modifier::BOLD, Attribute::Bold),
+            (Modifier::DIM, Attribute::Dim),
+            (Modifier::ITALIC, Attribute::Italic),
+            (Modifier::UNDERLINED, Attribute::Underlined),
+            (Modifier::REVERSED, Attribute::Reverse),
+        ] {
+            if cell.modifier.contains(modifier) {
+                queue!(writer, SetAttribute(attribute))?;
+            }
+        }
+
+        queue!(
+            writer,
+            Print(format!(
+                "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+                hyperlink.url, text
+            )),
+            SetAttribute(Attribute::Reset),
+            ResetColor
+        )?;
+    }
+
+    Ok(())
+}
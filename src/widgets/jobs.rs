@@ -1,36 +1,135 @@
-use std::{cmp::Reverse, fmt::Debug};
+use std::{cmp::Ordering, collections::BTreeSet, fmt::Debug, time::Duration};
 
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Color, Stylize},
     symbols::border,
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{Block, Borders, StatefulWidgetRef, TableState, Widget},
 };
 
-use crate::slurm::{Job, JobState};
+use color_eyre::Result;
+use regex::Regex;
+
+use crate::config::{ColumnConfig, Pane};
+use crate::slurm::{Job, JobState, Time};
 use crate::widgets::misc::scroll;
 
 use super::{
-    misc::{center_layout, mb_to_string, right_align_text},
-    table::{GenericTable, GenericTableState},
+    custom::{compare_custom, custom_columns, custom_text},
+    format_url,
+    misc::{
+        billing_text, center_layout, mb_to_string, move_column, parse_columns, right_align_text,
+    },
+    palette,
+    table::{plain_table, ColumnWidths, GenericTable, GenericTableState, RowStyle},
+    Hyperlink,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Column {
     JobID,
     JobArray,
     User,
+    Account,
+    Partition,
+    // Named like the Slurm option, since column names are derived from variant names
+    #[allow(clippy::upper_case_acronyms)]
+    QOS,
     State,
     Runtime,
+    TimeLimit,
+    Priority,
     Nodes,
     Tasks,
     CPUs,
     GPUs,
     Memory,
+    Billing,
     Nodelist,
     Name,
+    /// A column defined in the configuration file, by index into the custom columns of
+    /// the job list (see [`JobTableState::set_custom_columns`])
+    Custom(usize),
+}
+
+impl Column {
+    const ALL: [Column; 18] = [
+        Column::JobID,
+        Column::JobArray,
+        Column::User,
+        Column::Account,
+        Column::Partition,
+        Column::QOS,
+        Column::State,
+        Column::Runtime,
+        Column::TimeLimit,
+        Column::Priority,
+        Column::Nodes,
+        Column::Tasks,
+        Column::CPUs,
+        Column::GPUs,
+        Column::Memory,
+        Column::Billing,
+        Column::Nodelist,
+        Column::Name,
+    ];
+
+    /// Returns the built-in columns followed by the given custom columns
+    fn all(custom: &[ColumnConfig]) -> Vec<Column> {
+        Column::ALL
+            .into_iter()
+            .chain((0..custom.len()).map(Column::Custom))
+            .collect()
+    }
+
+    /// Returns true if jobs are initially sorted in descending order by this column,
+    /// i.e. if larger values are typically of more interest
+    fn descending(self, custom: &[ColumnConfig]) -> bool {
+        if let Column::Custom(idx) = self {
+            return custom[idx].numeric;
+        }
+
+        matches!(
+            self,
+            Column::Runtime
+                | Column::TimeLimit
+                | Column::Priority
+                | Column::CPUs
+                | Column::GPUs
+                | Column::Memory
+        )
+    }
+
+    /// Compares two jobs by the values shown in this column
+    fn compare(self, a: &Job, b: &Job, custom: &[ColumnConfig]) -> Ordering {
+        match self {
+            Column::JobID => a.id.cmp(&b.id),
+            Column::JobArray => {
+                (a.array_job_id, &a.array_task_id).cmp(&(b.array_job_id, &b.array_task_id))
+            }
+            Column::User => a.user.cmp(&b.user),
+            Column::Account => a.account.cmp(&b.account),
+            Column::QOS => a.qos.cmp(&b.qos),
+            Column::Partition => a.partition.label.cmp(&b.partition.label),
+            Column::State => a.state.to_string().cmp(&b.state.to_string()),
+            Column::Runtime => a.time.cmp(&b.time),
+            Column::TimeLimit => a.time_limit.cmp(&b.time_limit),
+            Column::Priority => a.priority.cmp(&b.priority),
+            Column::Nodes => a.nodes.cmp(&b.nodes),
+            Column::Tasks => a.tasks.cmp(&b.tasks),
+            Column::CPUs => a.cpus.cmp(&b.cpus),
+            Column::GPUs => a.gpus.cmp(&b.gpus),
+            Column::Memory => a.mem.cmp(&b.mem),
+            Column::Billing => a.billing.partial_cmp(&b.billing).unwrap_or(Ordering::Equal),
+            Column::Nodelist => a.nodelist.cmp(&b.nodelist),
+            Column::Name => a.name.cmp(&b.name),
+            Column::Custom(idx) => {
+                compare_custom(&custom[idx], a.custom.get(idx), b.custom.get(idx))
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for Column {
@@ -39,12 +138,47 @@ impl std::fmt::Display for Column {
     }
 }
 
+/// Returns true if `name` is the name of a built-in column
+pub(super) fn is_builtin_column(name: &str) -> bool {
+    Column::ALL
+        .iter()
+        .any(|v| v.to_string().eq_ignore_ascii_case(name))
+}
+
 #[derive(Debug)]
 pub struct JobTableState {
     focus: bool,
     table: TableState,
+    /// Jobs matching `filter`, sorted by `sort_column`
     jobs: Vec<Job>,
+    /// All jobs, regardless of `filter`
+    all_jobs: Vec<Job>,
+    /// Only show jobs whose ID, user, account, QOS, or name matches this expression, if
+    /// set
+    filter: Option<Regex>,
     columns: Vec<Column>,
+    /// Custom columns, in the order in which their values are stored in `custom` of
+    /// each job
+    custom: Vec<ColumnConfig>,
+    /// Column by which jobs are sorted
+    sort_column: Column,
+    /// Are jobs sorted in descending order?
+    descending: bool,
+    /// IDs of jobs marked by the user; marks are kept when the job list changes
+    marked: BTreeSet<usize>,
+    /// IDs of jobs watched for changes in state
+    watched: BTreeSet<usize>,
+    /// Template for job hyperlinks, if enabled
+    job_url: Option<String>,
+    /// Runtimes above which running jobs are highlighted as warnings and alerts
+    age_warning: Option<Duration>,
+    age_alert: Option<Duration>,
+    /// Remaining time below which running jobs are highlighted
+    time_left_alert: Option<Duration>,
+    /// Hyperlinks of the most recently rendered rows
+    hyperlinks: Vec<Hyperlink>,
+    /// Widths of fixed-width columns; cleared whenever the visible jobs change
+    widths: ColumnWidths<Column>,
 }
 
 impl JobTableState {
@@ -53,9 +187,100 @@ impl JobTableState {
     }
 
     pub fn update(&mut self, jobs: &[Job]) {
+        self.all_jobs.clear();
+        self.all_jobs.extend_from_slice(jobs);
+        self.sort_jobs();
+        self.apply_filter();
+    }
+
+    /// Sorts jobs by the previous (negative `delta`) or next (positive `delta`) visible
+    /// column
+    pub fn set_sort_column(&mut self, delta: isize) {
+        let current = self
+            .columns
+            .iter()
+            .position(|&v| v == self.sort_column)
+            .unwrap_or_default();
+        let next = (current as isize + delta).clamp(0, self.columns.len() as isize - 1) as usize;
+
+        if self.columns[next] != self.sort_column {
+            self.sort_column = self.columns[next];
+            self.descending = self.sort_column.descending(&self.custom);
+            self.resort();
+        }
+    }
+
+    /// Moves the column that jobs are sorted by to the left (negative `delta`) or to the
+    /// right (positive `delta`). Returns false if the column could not be moved
+    pub fn move_column(&mut self, delta: isize) -> bool {
+        let Some(current) = self.columns.iter().position(|&v| v == self.sort_column) else {
+            return false;
+        };
+
+        move_column(&mut self.columns, current, delta).is_some()
+    }
+
+    /// Returns the names of the visible columns, in order
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(|&v| self.name(v)).collect()
+    }
+
+    /// Switches between sorting jobs in ascending and descending order
+    pub fn toggle_sort_order(&mut self) {
+        self.descending = !self.descending;
+        self.resort();
+    }
+
+    /// Sorts jobs after the sort order has changed, keeping the selected job selected
+    fn resort(&mut self) {
+        let selected = self.selected_job().map(|job| job.id);
+        self.sort_jobs();
+        self.apply_filter();
+
+        if let Some(idx) = selected.and_then(|id| self.jobs.iter().position(|v| v.id == id)) {
+            self.table.select(Some(idx));
+        }
+    }
+
+    /// Sorts jobs by the current sort column, with ties broken by job ID
+    fn sort_jobs(&mut self) {
+        let (column, descending, custom) = (self.sort_column, self.descending, &self.custom);
+        self.all_jobs.sort_unstable_by(|a, b| {
+            let order = column.compare(a, b, custom).then_with(|| a.id.cmp(&b.id));
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    /// Only shows jobs whose ID, user, account, QOS, or name matches `filter`, or all
+    /// jobs if `None`
+    pub fn set_filter(&mut self, filter: Option<Regex>) {
+        self.filter = filter;
+        self.apply_filter();
+    }
+
+    fn apply_filter(&mut self) {
+        self.widths.clear();
         self.jobs.clear();
-        self.jobs.extend_from_slice(jobs);
-        self.jobs.sort_unstable_by_key(|j| Reverse(j.time.clone()));
+        match &self.filter {
+            Some(filter) => self.jobs.extend(
+                self.all_jobs
+                    .iter()
+                    .filter(|job| {
+                        filter.is_match(&job.id.to_string())
+                            || filter.is_match(&job.display_id())
+                            || filter.is_match(&job.user)
+                            || filter.is_match(&job.account)
+                            || filter.is_match(&job.qos)
+                            || filter.is_match(&job.name)
+                    })
+                    .cloned(),
+            ),
+            None => self.jobs.extend_from_slice(&self.all_jobs),
+        }
 
         // Update/clear job selection depending on the new contents
         self.scroll(0);
@@ -69,28 +294,170 @@ impl JobTableState {
         let offset = self.table.offset().saturating_add(row);
         self.table.select(Some(offset.saturating_sub(1)));
     }
+
+    /// Returns the plain text of the selected row, with columns separated by tabs
+    pub fn selected_row_text(&self) -> Option<String> {
+        self.table.selected().map(|row| self.row_text(row))
+    }
+
+    /// Returns all rows as a plain-text table
+    pub fn to_plain_text(&self) -> String {
+        plain_table(self)
+    }
+
+    /// Returns the currently selected job, if any
+    pub fn selected_job(&self) -> Option<&Job> {
+        self.table.selected().and_then(|idx| self.jobs.get(idx))
+    }
+
+    /// Marks or unmarks the currently selected job
+    pub fn toggle_mark(&mut self) {
+        if let Some(id) = self.selected_job().map(|job| job.id) {
+            if !self.marked.remove(&id) {
+                self.marked.insert(id);
+            }
+        }
+    }
+
+    /// Returns the IDs of marked jobs, or the ID of the selected job if no jobs are marked
+    pub fn marked_or_selected(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            self.selected_job().map(|job| job.id).into_iter().collect()
+        } else {
+            self.marked.iter().copied().collect()
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Sets the IDs of jobs that are highlighted as being watched
+    pub fn set_watched<I: IntoIterator<Item = usize>>(&mut self, ids: I) {
+        self.watched = ids.into_iter().collect();
+    }
+
+    /// Adds a column showing the billable TRES of jobs
+    pub fn show_billing(&mut self) {
+        if let Some(idx) = self
+            .columns
+            .iter()
+            .position(|v| matches!(v, Column::Memory))
+        {
+            self.columns.insert(idx + 1, Column::Billing);
+        }
+    }
+
+    /// Adds a column showing the partition of each job
+    pub fn show_partition(&mut self) {
+        if let Some(idx) = self.columns.iter().position(|v| matches!(v, Column::User)) {
+            self.columns.insert(idx + 1, Column::Partition);
+        }
+    }
+
+    /// Shows the named columns, in the order given, instead of the default columns
+    pub fn set_columns(&mut self, names: &[String]) -> Result<()> {
+        self.columns = parse_columns(&Column::all(&self.custom), |v| self.name(v), names)?;
+        Ok(())
+    }
+
+    /// Sets the custom columns defined in the configuration file. These are shown
+    /// before the name of jobs, which fills the remaining width
+    pub fn set_custom_columns(&mut self, columns: &[ColumnConfig]) {
+        self.custom = custom_columns(columns, Pane::Jobs);
+        self.columns.retain(|v| !matches!(v, Column::Custom(_)));
+        let idx = self
+            .columns
+            .iter()
+            .position(|v| matches!(v, Column::Name))
+            .unwrap_or(self.columns.len());
+        self.columns
+            .splice(idx..idx, (0..self.custom.len()).map(Column::Custom));
+        self.widths.clear();
+    }
+
+    /// Returns the color of jobs that are about to reach their time limit, or that have
+    /// been running longer than the age thresholds
+    fn runtime_color(&self, job: &Job) -> Option<Color> {
+        let time_left = job.time_left.as_duration();
+        if let (Some(time_left), Some(alert)) = (time_left, self.time_left_alert) {
+            if time_left < alert {
+                return Some(palette().alert);
+            }
+        }
+
+        let runtime = job.time.as_duration()?;
+        if self.age_alert.is_some_and(|v| runtime > v) {
+            Some(palette().error)
+        } else if self.age_warning.is_some_and(|v| runtime > v) {
+            Some(palette().warning)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the URL template used to make job IDs clickable
+    pub fn set_job_url(&mut self, job_url: Option<String>) {
+        self.job_url = job_url;
+    }
+
+    /// Sets the runtimes above which running jobs are highlighted, e.g. to spot
+    /// forgotten interactive jobs
+    pub fn set_age_thresholds(&mut self, warning: Option<Duration>, alert: Option<Duration>) {
+        self.age_warning = warning;
+        self.age_alert = alert;
+    }
+
+    /// Sets the remaining time below which running jobs are highlighted, so that users
+    /// notice jobs about to be killed at their time limit
+    pub fn set_time_left_alert(&mut self, alert: Option<Duration>) {
+        self.time_left_alert = alert;
+    }
+
+    /// Returns the hyperlinks of the most recently rendered rows
+    pub fn hyperlinks(&self) -> &[Hyperlink] {
+        &self.hyperlinks
+    }
+
+    pub fn clear_hyperlinks(&mut self) {
+        self.hyperlinks.clear();
+    }
 }
 
 impl Default for JobTableState {
     fn default() -> Self {
+        let columns = vec![
+            Column::JobID,
+            Column::User,
+            Column::State,
+            Column::Runtime,
+            Column::Nodes,
+            Column::Tasks,
+            Column::CPUs,
+            Column::GPUs,
+            Column::Memory,
+            Column::Nodelist,
+            Column::Name,
+        ];
+
         Self {
             focus: false,
-            columns: vec![
-                Column::JobID,
-                Column::JobArray,
-                Column::User,
-                Column::State,
-                Column::Runtime,
-                Column::Nodes,
-                Column::Tasks,
-                Column::CPUs,
-                Column::GPUs,
-                Column::Memory,
-                Column::Nodelist,
-                Column::Name,
-            ],
+            columns,
+            custom: Vec::new(),
+            sort_column: Column::Runtime,
+            descending: true,
             table: TableState::default(),
             jobs: Vec::default(),
+            all_jobs: Vec::default(),
+            filter: None,
+            marked: BTreeSet::default(),
+            watched: BTreeSet::default(),
+            job_url: None,
+            age_warning: None,
+            age_alert: None,
+            time_left_alert: None,
+            hyperlinks: Vec::default(),
+            widths: ColumnWidths::default(),
         }
     }
 }
@@ -108,6 +475,22 @@ impl GenericTableState<Column> for JobTableState {
         &self.columns
     }
 
+    fn name(&self, column: Column) -> String {
+        match column {
+            Column::Custom(idx) => self.custom[idx].name.trim().to_string(),
+            _ => column.to_string(),
+        }
+    }
+
+    fn header(&self, column: Column) -> String {
+        if column == self.sort_column {
+            let order = if self.descending { "▼" } else { "▲" };
+            format!("{}{}", self.name(column), order)
+        } else {
+            self.name(column)
+        }
+    }
+
     fn selected(&self) -> Option<usize> {
         self.table.selected()
     }
@@ -116,10 +499,17 @@ impl GenericTableState<Column> for JobTableState {
         matches!(column, Column::Name)
     }
 
+    fn fixed_width(&self, column: Column) -> Option<u16> {
+        match column {
+            Column::Custom(idx) => self.custom[idx].width,
+            _ => None,
+        }
+    }
+
     fn text<'a>(&self, _constraint: &Constraint, row: usize, column: Column) -> Text<'a> {
         let job = &self.jobs[row];
         let text = match column {
-            Column::JobID => job.id.to_string().into(),
+            Column::JobID => highlight(job.display_id(), self.filter.as_ref()),
             Column::JobArray => {
                 if job.array_task_id != "N/A" {
                     if job.array_job_id != job.id {
@@ -131,20 +521,50 @@ impl GenericTableState<Column> for JobTableState {
                     Text::default()
                 }
             }
-            Column::User => job.user.clone().into(),
+            Column::User => highlight(job.user.to_string(), self.filter.as_ref()),
+            Column::Account => highlight(job.account.to_string(), self.filter.as_ref()),
+            Column::QOS => highlight(job.qos.to_string(), self.filter.as_ref()),
+            // Jobs pending in multiple partitions are listed in each; this is indicated by
+            // the number of other partitions, e.g. "Pending (+1)"
+            Column::Partition if !job.partitions.is_empty() => job.partitions.join(",").into(),
+            Column::Partition => job.partition.label.clone().into(),
+            Column::State if job.partitions.len() > 1 => {
+                format!("{} (+{})", job.state, job.partitions.len() - 1).into()
+            }
             Column::State => job.state.to_string().into(),
             Column::Runtime => right_align_text(&job.time),
+            Column::TimeLimit => match job.time_limit {
+                // Not set or not captured
+                Time::Invalid => Text::default(),
+                ref limit => right_align_text(limit),
+            },
+            Column::Priority => match job.priority {
+                Some(priority) => right_align_text(priority),
+                None => Text::default(),
+            },
             Column::Nodes => right_align_text(job.nodes),
             Column::Tasks => right_align_text(job.tasks),
             Column::CPUs => right_align_text(job.cpus),
             Column::GPUs => right_align_text(job.gpus),
             Column::Memory => mb_to_string(job.mem).into(),
+            Column::Billing => billing_text(job.billing),
             Column::Nodelist => Text::from(job.nodelist.join(",")),
-            Column::Name => job.name.clone().into(),
+            Column::Name => highlight(job.name.clone(), self.filter.as_ref()),
+            Column::Custom(idx) => custom_text(&self.custom[idx], &job.custom, idx),
         };
 
-        if job.state != JobState::Running {
-            text.fg(Color::Gray)
+        let text = if job.state != JobState::Running {
+            text.fg(palette().inactive)
+        } else if let Some(color) = self.runtime_color(job) {
+            text.fg(color)
+        } else {
+            text
+        };
+
+        if self.marked.contains(&job.id) {
+            text.fg(palette().marked).bold()
+        } else if self.watched.contains(&job.id) {
+            text.fg(palette().warning)
         } else {
             text
         }
@@ -153,16 +573,46 @@ impl GenericTableState<Column> for JobTableState {
     fn inner_state(&mut self) -> &mut TableState {
         &mut self.table
     }
+
+    fn column_widths(&mut self) -> &mut ColumnWidths<Column> {
+        &mut self.widths
+    }
+
+    fn hyperlink(&self, row: usize, column: Column) -> Option<(u16, String)> {
+        match (column, &self.job_url) {
+            (Column::JobID, Some(template)) => {
+                let job = &self.jobs[row];
+                let url = format_url(
+                    template,
+                    &[("jobid", &job.id.to_string()), ("user", &job.user)],
+                );
+
+                Some((0, url))
+            }
+            _ => None,
+        }
+    }
+
+    fn set_hyperlinks(&mut self, hyperlinks: Vec<Hyperlink>) {
+        self.hyperlinks = hyperlinks;
+    }
 }
 
 #[derive(Debug, Default)]
-pub struct JobTable {}
+pub struct JobTable {
+    rows: RowStyle,
+}
 
 impl JobTable {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Sets the colors used to distinguish rows
+    pub fn set_row_style(&mut self, rows: RowStyle) {
+        self.rows = rows;
+    }
+
     // Renders a simple notification that there are no displayable jobs
     fn render_empty_table(area: Rect, buf: &mut Buffer) {
         let label = "No jobs found";
@@ -188,9 +638,28 @@ impl StatefulWidgetRef for JobTable {
         if state.jobs.is_empty() {
             Self::render_empty_table(area, buf)
         } else {
-            let table = GenericTable::<Column, JobTableState>::new();
+            let table = GenericTable::<Column, JobTableState>::new().row_style(self.rows);
 
             table.render_ref(area, buf, state);
         }
     }
 }
+
+/// Highlights the parts of a value matching the job filter, if any
+fn highlight<'a>(value: String, filter: Option<&Regex>) -> Text<'a> {
+    let Some(filter) = filter else {
+        return value.into();
+    };
+
+    let style = palette().matched();
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for m in filter.find_iter(&value).filter(|v| !v.is_empty()) {
+        spans.push(Span::from(value[end..m.start()].to_string()));
+        spans.push(Span::styled(m.as_str().to_string(), style));
+        end = m.end();
+    }
+    spans.push(Span::from(value[end..].to_string()));
+
+    Line::from(spans).into()
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::Marker,
+    text::Span,
+    widgets::{Axis, Block, Chart, Dataset, GraphType, Widget},
+};
+
+use crate::history::LoadSample;
+
+use super::{mb_to_string, palette};
+
+/// Number of lines needed to draw a [`LoadChart`]
+pub const LOAD_CHART_HEIGHT: u16 = 10;
+
+/// Charts of the CPU load and free memory of a node during recent updates, drawn side
+/// by side; updates for which Slurm did not report the load are skipped
+#[derive(Clone, Debug)]
+pub struct LoadChart {
+    samples: Vec<LoadSample>,
+    cpus: usize,
+    mem: usize,
+}
+
+impl LoadChart {
+    /// Creates a chart from samples ordered oldest first, given the number of CPUs and
+    /// the total memory (in MB) of the node
+    pub fn new(samples: &VecDeque<LoadSample>, cpus: usize, mem: usize) -> Self {
+        Self {
+            samples: samples.iter().copied().collect(),
+            cpus,
+            mem,
+        }
+    }
+
+    /// Returns true if there are no samples to draw
+    pub fn is_empty(&self) -> bool {
+        self.samples.iter().all(Option::is_none)
+    }
+
+    /// Returns the (x, y) points of a chart, where x is the number of updates ago
+    fn points<F: Fn(f64, usize) -> f64>(&self, f: F) -> Vec<(f64, f64)> {
+        let newest = self.samples.len().saturating_sub(1);
+        self.samples
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, v)| {
+                v.map(|(load, free)| (idx as f64 - newest as f64, f(load, free)))
+            })
+            .collect()
+    }
+
+    fn chart<'a>(
+        &self,
+        title: &'a str,
+        points: &'a [(f64, f64)],
+        max: f64,
+        labels: [String; 2],
+        color: Color,
+    ) -> Chart<'a> {
+        let oldest = self.samples.len().saturating_sub(1).max(1) as f64;
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(points);
+
+        Chart::new(vec![dataset])
+            .block(Block::default().title(title.bold()))
+            .x_axis(
+                Axis::default()
+                    .style(Style::default().fg(palette().dimmed))
+                    .bounds([-oldest, 0.0])
+                    .labels(vec![Span::from(format!("-{}", oldest)), Span::from("now")]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(palette().dimmed))
+                    .bounds([0.0, max])
+                    .labels(labels.into_iter().map(Span::from).collect()),
+            )
+    }
+}
+
+impl Widget for LoadChart {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Ratio(1, 2),
+                Constraint::Length(2),
+                Constraint::Ratio(1, 2),
+            ])
+            .split(area);
+
+        // The load may exceed the number of CPUs on overloaded nodes
+        let load = self.points(|load, _| load);
+        let max_load = load
+            .iter()
+            .map(|&(_, y)| y)
+            .fold(self.cpus as f64, f64::max)
+            .ceil()
+            .max(1.0);
+        self.chart(
+            "CPU load",
+            &load,
+            max_load,
+            ["0".into(), format!("{}", max_load)],
+            palette().allocated,
+        )
+        .render(layout[0], buf);
+
+        let free = self.points(|_, free| free as f64);
+        let max_free = free
+            .iter()
+            .map(|&(_, y)| y)
+            .fold(self.mem as f64, f64::max)
+            .max(1.0);
+        self.chart(
+            "Free memory",
+            &free,
+            max_free,
+            ["0".into(), mb_to_string(max_free as usize)],
+            palette().available,
+        )
+        .render(layout[2], buf);
+    }
+}
@@ -0,0 +1,245 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Stylize,
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph, StatefulWidget, Widget,
+    },
+};
+
+use super::palette;
+
+/// Lines of text shown in a [`LogView`], along with the scroll position
+#[derive(Debug)]
+pub struct LogViewState {
+    lines: Vec<String>,
+    /// Index of the first visible line, unless following the end of the text
+    offset: usize,
+    /// Keep the end of the text visible as lines are added?
+    follow: bool,
+    /// Text highlighted in the visible lines, if any
+    search: Option<String>,
+    /// Number of lines visible during the most recent render
+    height: usize,
+}
+
+impl LogViewState {
+    pub fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            offset: 0,
+            follow: true,
+            search: None,
+            height: 1,
+        }
+    }
+
+    /// Replaces the text shown. If `partial` is true, the first line is assumed to be
+    /// incomplete (e.g. if only the end of a file was read) and is therefore skipped
+    pub fn set_text(&mut self, text: &str, partial: bool) {
+        self.lines = text
+            .lines()
+            .skip(partial as usize)
+            .map(|line| {
+                // Only the final state of lines updated using carriage returns (e.g.
+                // progress bars) is shown, and tabs are expanded to keep columns aligned
+                let line = line.rsplit('\r').next().unwrap_or_default();
+                line.replace('\t', "    ")
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .collect()
+            })
+            .collect();
+    }
+
+    pub fn following(&self) -> bool {
+        self.follow
+    }
+
+    pub fn set_follow(&mut self, follow: bool) {
+        self.offset = self.start();
+        self.follow = follow;
+    }
+
+    /// Scrolls by the specified number of lines. Scrolling past the end of the text
+    /// resumes following the text
+    pub fn scroll(&mut self, delta: isize) {
+        let start = self.start().saturating_add_signed(delta);
+        self.follow = start >= self.max_start();
+        self.offset = start.min(self.max_start());
+    }
+
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    /// Highlights `text` and scrolls to the first match at or after the current position
+    pub fn set_search(&mut self, text: String) -> bool {
+        self.search = Some(text).filter(|v| !v.is_empty());
+        self.find(self.start(), true)
+    }
+
+    /// Scrolls to the next (or previous) line matching the search text, wrapping
+    /// around at the end (or start) of the text. Returns false if no lines match
+    pub fn find_next(&mut self, forward: bool) -> bool {
+        let start = if forward {
+            self.start() + 1
+        } else {
+            self.start() + self.lines.len().max(1) - 1
+        };
+
+        self.find(start, forward)
+    }
+
+    fn find(&mut self, start: usize, forward: bool) -> bool {
+        let (Some(search), len) = (&self.search, self.lines.len()) else {
+            return false;
+        };
+
+        let found = (0..len)
+            .map(|idx| {
+                if forward {
+                    (start + idx) % len
+                } else {
+                    (start + len - idx) % len
+                }
+            })
+            .find(|&idx| self.lines[idx].contains(search.as_str()));
+
+        if let Some(idx) = found {
+            self.offset = idx;
+            self.follow = false;
+        }
+
+        found.is_some()
+    }
+
+    fn max_start(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    /// Returns the index of the first visible line
+    fn start(&self) -> usize {
+        if self.follow {
+            self.max_start()
+        } else {
+            self.offset.min(self.max_start())
+        }
+    }
+}
+
+impl Default for LogViewState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A popup covering most of the screen that shows lines of text, e.g. the output of
+/// a job, without wrapping long lines
+#[derive(Debug)]
+pub struct LogView<'a> {
+    title: String,
+    /// Error shown above the text, e.g. if the text could not be updated
+    error: Option<&'a str>,
+    instructions: Option<Line<'a>>,
+    /// Is the text drawn as a popup on top of other widgets?
+    popup: bool,
+}
+
+impl<'a> LogView<'a> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: format!(" {} ", title),
+            error: None,
+            instructions: None,
+            popup: true,
+        }
+    }
+
+    /// Fills the whole area, instead of drawing a popup with margins
+    pub fn embedded(mut self) -> Self {
+        self.popup = false;
+        self
+    }
+
+    pub fn error(mut self, error: Option<&'a str>) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Sets the instructions shown in the bottom border of the popup
+    pub fn instructions<L: Into<Line<'a>>>(mut self, instructions: L) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+}
+
+impl StatefulWidget for LogView<'_> {
+    type State = LogViewState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // Margins of 2 characters / 1 line around the popup
+        let area = if self.popup {
+            Rect {
+                x: area.x + 2.min(area.width / 2),
+                y: area.y + 1.min(area.height / 2),
+                width: area.width.saturating_sub(4),
+                height: area.height.saturating_sub(2),
+            }
+        } else {
+            area
+        };
+
+        let mut block = Block::default()
+            .title(Title::from(self.title.bold()).alignment(Alignment::Center))
+            .borders(Borders::ALL)
+            .border_set(border::PLAIN);
+
+        if let Some(instructions) = self.instructions {
+            block = block.title(
+                Title::from(instructions)
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            );
+        }
+
+        Clear.render(area, buf);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let mut lines = Vec::new();
+        if let Some(error) = self.error {
+            lines.push(Line::from(error.to_string().fg(palette().error)));
+        }
+
+        state.height = (inner.height as usize).saturating_sub(lines.len()).max(1);
+        let start = state.start();
+        for line in state.lines.iter().skip(start).take(state.height) {
+            lines.push(highlight(line, state.search.as_deref()));
+        }
+
+        Paragraph::new(Text::from(lines)).render(inner, buf);
+    }
+}
+
+/// Highlights occurrences of `search` in a line
+fn highlight<'a>(line: &str, search: Option<&str>) -> Line<'a> {
+    let Some(search) = search else {
+        return Line::from(line.to_string());
+    };
+
+    let style = palette().matched();
+    let mut spans = Vec::new();
+    let mut rest = line;
+    while let Some(idx) = rest.find(search) {
+        spans.push(Span::from(rest[..idx].to_string()));
+        spans.push(Span::styled(search.to_string(), style));
+        rest = &rest[idx + search.len()..];
+    }
+    spans.push(Span::from(rest.to_string()));
+
+    Line::from(spans)
+}
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use color_eyre::{eyre::eyre, Result};
+
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::Text,
@@ -23,6 +25,50 @@ pub fn scroll(state: &mut TableState, items: usize, delta: isize) -> Option<usiz
 }
 
 /// Right aligns displayable value
+/// Looks up columns by (case-insensitive) name, in the order given
+pub fn parse_columns<C: Copy>(
+    all: &[C],
+    column_name: impl Fn(C) -> String,
+    names: &[String],
+) -> Result<Vec<C>> {
+    if names.is_empty() {
+        return Err(eyre!("no columns specified"));
+    }
+
+    names
+        .iter()
+        .map(|name| {
+            all.iter()
+                .find(|&&v| column_name(v).eq_ignore_ascii_case(name.trim()))
+                .copied()
+                .ok_or_else(|| {
+                    let all = all.iter().map(|&v| column_name(v)).collect::<Vec<_>>();
+                    eyre!(
+                        "unknown column {:?}; valid columns are {}",
+                        name,
+                        all.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Moves the column at index `idx` by `delta` positions, returning the new index unless
+/// the column could not be moved (e.g. because it is already the first column)
+pub fn move_column<C>(columns: &mut [C], idx: usize, delta: isize) -> Option<usize> {
+    let target = idx
+        .checked_add_signed(delta)
+        .filter(|&v| v < columns.len() && v != idx)?;
+
+    if target < idx {
+        columns[target..=idx].rotate_right(1);
+    } else {
+        columns[idx..=target].rotate_left(1);
+    }
+
+    Some(target)
+}
+
 pub fn right_align_text<'a, T: Display>(v: T) -> Text<'a> {
     Text::from(v.to_string()).alignment(Alignment::Right)
 }
@@ -61,3 +107,23 @@ pub fn mb_to_string(mb: usize) -> String {
         format!("{:.1}T", mb as f64 / 1048576.0)
     }
 }
+
+/// Right aligns billable TRES, rounded to the nearest whole number
+pub fn billing_text<'a>(billing: Option<f64>) -> Text<'a> {
+    match billing {
+        Some(billing) => right_align_text(format!("{:.0}", billing)),
+        None => Text::default(),
+    }
+}
+
+/// Draws values as a line of bars, e.g. "▁▃█▃▁", scaled to `max` or to the largest value
+pub fn sparkline<I: Iterator<Item = usize> + Clone>(values: I, max: Option<usize>) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = max
+        .unwrap_or_else(|| values.clone().max().unwrap_or_default())
+        .max(1);
+    values
+        .map(|v| BARS[(v.min(max) * (BARS.len() - 1)).div_ceil(max)])
+        .collect()
+}
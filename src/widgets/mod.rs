@@ -1,11 +1,35 @@
+mod custom;
+mod gauges;
+mod health;
+mod hyperlink;
 mod jobs;
+mod load;
+mod log;
 mod misc;
 mod nodes;
+mod picker;
+mod popup;
 mod scrollbar;
+mod summary;
 mod table;
+mod theme;
+mod timeline;
 mod utilization;
 
+pub use custom::validate_custom_columns;
+pub use gauges::GaugeRow;
+pub use health::HealthRow;
+pub use hyperlink::{format_url, write_hyperlinks, Hyperlink};
 pub use jobs::{JobTable, JobTableState};
+pub use load::{LoadChart, LOAD_CHART_HEIGHT};
+pub use log::{LogView, LogViewState};
+pub use misc::{mb_to_string, sparkline};
 pub use nodes::{NodeRow, NodeTable, NodeTableState, Selection};
+pub use picker::Picker;
+pub use popup::Popup;
 pub use scrollbar::RightScrollbar;
-pub use utilization::Utilization;
+pub use summary::SummaryRow;
+pub use table::{text_to_string, RowStyle};
+pub use theme::{palette, set_palette, Palette, PALETTES};
+pub use timeline::Timeline;
+pub use utilization::legend;
@@ -1,19 +1,28 @@
-use std::{fmt::Debug, rc::Rc};
+use std::{collections::HashMap, fmt::Debug, rc::Rc};
+
+use chrono::{Local, NaiveDateTime};
+use color_eyre::Result;
 
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Rect},
+    layout::{Alignment, Constraint, Rect},
     style::{Color, Stylize},
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{StatefulWidgetRef, TableState},
 };
 
-use crate::slurm::{Node, NodeState, Partition};
-use crate::widgets::{misc::scroll, Utilization};
+use crate::anomalies::{Anomalies, AnomalyKind};
+use crate::config::{ColumnConfig, Pane};
+use crate::history::{NodeHistory, PartitionHistory, MAX_SAMPLES};
+use crate::slurm::{
+    DefaultMem, Node, NodeState, Partition, PartitionLimits, Reservation, Utilization,
+};
+use crate::widgets::{format_url, mb_to_string, misc::scroll, palette, Hyperlink};
 
 use super::{
-    misc::right_align_text,
-    table::{GenericTable, GenericTableState},
+    custom::{custom_columns, custom_text},
+    misc::{billing_text, move_column, parse_columns, right_align_text, sparkline},
+    table::{plain_table, text_to_string, ColumnWidths, GenericTable, GenericTableState, RowStyle},
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -29,15 +38,62 @@ pub enum Selection<'a> {
     Node(&'a Node),
 }
 
-#[derive(Clone, Copy, Debug)]
+/// How the utilization of CPUs, memory, and GPUs is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum UtilizationMode {
+    /// Bars with segments for utilized, allocated, blocked, etc. resources
+    #[default]
+    Bars,
+    /// Allocated and total resources, as well as blocked resources, if any
+    Numbers,
+    /// Percentage of resources allocated
+    Percentages,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Column {
     Node,
     State,
     Users,
     Jobs,
+    Billing,
+    MaxTime,
+    MaxNodes,
+    MaxCPUsPerUser,
+    Features,
+    History,
     CPUs,
     Memory,
     GPUs,
+    /// A column defined in the configuration file, by index into the custom columns of
+    /// the node list (see [`NodeTableState::set_custom_columns`])
+    Custom(usize),
+}
+
+impl Column {
+    const ALL: [Column; 13] = [
+        Column::Node,
+        Column::State,
+        Column::Users,
+        Column::Jobs,
+        Column::Billing,
+        Column::MaxTime,
+        Column::MaxNodes,
+        Column::MaxCPUsPerUser,
+        Column::Features,
+        Column::History,
+        Column::CPUs,
+        Column::Memory,
+        Column::GPUs,
+    ];
+
+    /// Returns the built-in columns followed by the given custom columns
+    fn all(custom: &[ColumnConfig]) -> Vec<Column> {
+        Column::ALL
+            .into_iter()
+            .chain((0..custom.len()).map(Column::Custom))
+            .collect()
+    }
 }
 
 impl std::fmt::Display for Column {
@@ -46,21 +102,61 @@ impl std::fmt::Display for Column {
     }
 }
 
+/// Returns true if `name` is the name of a built-in column
+pub(super) fn is_builtin_column(name: &str) -> bool {
+    Column::ALL
+        .iter()
+        .any(|v| v.to_string().eq_ignore_ascii_case(name))
+}
+
 #[derive(Debug)]
 pub struct NodeTableState {
     /// Does this widget have focus?
     focus: bool,
+    /// Are CPUs, memory, and GPUs shown as bars or as numbers?
+    mode: UtilizationMode,
     /// Should unavailable nodes be hidden?
     hide_unavailable: bool,
+    /// Only show nodes with this feature, if set
+    feature: Option<String>,
+    /// Partitions that are not shown
+    hidden_partitions: Vec<String>,
+    /// Only show nodes in these partitions, unless empty
+    partitions: Vec<String>,
     /// Visible columns
     columns: Vec<Column>,
+    /// Custom columns, in the order in which their values are stored in `custom` of
+    /// each node
+    custom: Vec<ColumnConfig>,
+    /// Index of the column selected for moving, if any
+    selected_column: Option<usize>,
     table: TableState,
     cluster: Rc<Vec<Partition>>,
     /// Rows of nodes/partitions as indices into `cluster`, plus empty rows
     rows: Vec<NodeRow>,
 
-    /// Value of DefMemPerCPU from /etc/slurm/slurm.conf
-    def_mem_per_cpu: u64,
+    /// Value of DefMemPer* from /etc/slurm/slurm.conf
+    default_mem: DefaultMem,
+    /// Partition-specific values of DefMemPer*, overriding `default_mem`
+    partition_default_mem: HashMap<String, DefaultMem>,
+    /// Percentage of blocked CPUs above which partitions are highlighted
+    blocked_alert: Option<f64>,
+    /// Number of jobs on each node during recent updates, if shown
+    history: NodeHistory,
+    /// Utilization of each partition during recent updates, if shown
+    partition_history: PartitionHistory,
+    /// Limits on jobs submitted to each partition
+    partition_limits: HashMap<String, PartitionLimits>,
+    /// Start and end of the next maintenance reservation for each affected node
+    maintenance: HashMap<String, (NaiveDateTime, NaiveDateTime)>,
+    /// Nodes that are persistently overloaded or underutilized
+    anomalies: HashMap<String, AnomalyKind>,
+    /// Template for node hyperlinks, if enabled
+    node_url: Option<String>,
+    /// Hyperlinks of the most recently rendered rows
+    hyperlinks: Vec<Hyperlink>,
+    /// Widths of fixed-width columns; cleared whenever rows or their contents change
+    widths: ColumnWidths<Column>,
 }
 
 impl GenericTableState<Column> for NodeTableState {
@@ -76,12 +172,40 @@ impl GenericTableState<Column> for NodeTableState {
         &self.columns
     }
 
+    fn name(&self, column: Column) -> String {
+        match column {
+            Column::Custom(idx) => self.custom[idx].name.trim().to_string(),
+            _ => column.to_string(),
+        }
+    }
+
+    fn header(&self, column: Column) -> String {
+        let selected = self
+            .selected_column
+            .and_then(|idx| self.columns.get(idx))
+            .is_some_and(|&v| v == column);
+
+        // The selected column is only marked while the node list has focus
+        if selected && self.focus {
+            format!("▸{}", self.name(column))
+        } else {
+            self.name(column)
+        }
+    }
+
     fn selected(&self) -> Option<usize> {
         self.table.selected()
     }
 
     fn variable_width(&self, column: Column) -> bool {
-        matches!(column, Column::CPUs | Column::Memory)
+        self.mode == UtilizationMode::Bars && matches!(column, Column::CPUs | Column::Memory)
+    }
+
+    fn fixed_width(&self, column: Column) -> Option<u16> {
+        match column {
+            Column::Custom(idx) => self.custom[idx].width,
+            _ => None,
+        }
     }
 
     fn text<'a>(&self, constraint: &Constraint, row: usize, column: Column) -> Text<'a> {
@@ -102,18 +226,138 @@ impl GenericTableState<Column> for NodeTableState {
     fn inner_state(&mut self) -> &mut TableState {
         &mut self.table
     }
+
+    fn column_widths(&mut self) -> &mut ColumnWidths<Column> {
+        &mut self.widths
+    }
+
+    /// Utilization bars and sparklines are replaced with allocated/total resources
+    fn plain_text(&self, row: usize, column: Column) -> String {
+        let nodes = match self.rows[row] {
+            NodeRow::Partition(partition) => &self.cluster[partition].nodes[..],
+            NodeRow::Node(partition, node) => {
+                let node = &self.cluster[partition].nodes[node];
+                if column == Column::Node {
+                    return node.name.clone();
+                }
+
+                std::slice::from_ref(node)
+            }
+            NodeRow::Spacing => return String::new(),
+        };
+
+        let sum = |f: fn(&Node) -> usize| nodes.iter().map(f).sum::<usize>();
+        match column {
+            Column::CPUs => format!(
+                "{}/{}",
+                sum(|v| v.cpu_state.allocated),
+                sum(|v| v.cpu_state.total)
+            ),
+            Column::Memory => format!(
+                "{}/{}",
+                mb_to_string(sum(|v| v.mem_alloc)),
+                mb_to_string(sum(|v| v.mem))
+            ),
+            Column::GPUs if sum(|v| v.gpus) > 0 => {
+                format!("{}/{}", sum(|v| v.gpus_used), sum(|v| v.gpus))
+            }
+            Column::GPUs | Column::History => String::new(),
+            _ => text_to_string(self.text(&Constraint::Length(32), row, column)),
+        }
+    }
+
+    fn hyperlink(&self, row: usize, column: Column) -> Option<(u16, String)> {
+        match (self.rows[row], column, &self.node_url) {
+            (NodeRow::Node(partition, node), Column::Node, Some(template)) => {
+                // Rows may be organized by node group rather than by partition
+                let node = &self.cluster[partition].nodes[node];
+                let url = format_url(
+                    template,
+                    &[("node", &node.name), ("partition", &node.partition.label)],
+                );
+
+                // Skip the tree-drawing prefix
+                Some((3, url))
+            }
+            _ => None,
+        }
+    }
+
+    fn set_hyperlinks(&mut self, hyperlinks: Vec<Hyperlink>) {
+        self.hyperlinks = hyperlinks;
+    }
 }
 
 impl NodeTableState {
-    pub fn set_def_mem_per_cpu(&mut self, def_mem_per_cpu: u64) {
-        self.def_mem_per_cpu = def_mem_per_cpu;
+    pub fn set_default_mem(
+        &mut self,
+        default_mem: DefaultMem,
+        partition_default_mem: HashMap<String, DefaultMem>,
+    ) {
+        if (default_mem, &partition_default_mem) == (self.default_mem, &self.partition_default_mem)
+        {
+            return;
+        }
+
+        self.default_mem = default_mem;
+        self.partition_default_mem = partition_default_mem;
+        self.widths.clear();
+    }
+
+    /// Returns the value of DefMemPer* that applies to a partition
+    pub fn default_mem(&self, partition: &str) -> DefaultMem {
+        self.partition_default_mem
+            .get(partition)
+            .copied()
+            .unwrap_or(self.default_mem)
+    }
+
+    /// Sets the percentage of blocked CPUs above which partitions are highlighted
+    pub fn set_blocked_alert(&mut self, blocked_alert: Option<f64>) {
+        self.blocked_alert = blocked_alert;
+        self.widths.clear();
+    }
+
+    /// Returns the percentage of CPUs in a partition that are blocked, if that exceeds
+    /// the configured threshold
+    fn blocked_alert(&self, partition: &Partition) -> Option<f64> {
+        let threshold = self.blocked_alert?;
+        let cpus = self.cpu_utilization(partition);
+        if cpus.capacity > 0.0 {
+            // Blocked CPUs are drawn following allocated CPUs
+            let blocked = (cpus.blocked - cpus.allocated).max(0.0);
+            let percentage = 100.0 * blocked / cpus.capacity;
+            if percentage > threshold {
+                return Some(percentage);
+            }
+        }
+
+        None
+    }
+
+    fn cpu_utilization(&self, partition: &Partition) -> Utilization {
+        partition
+            .nodes
+            .iter()
+            .map(|v| v.cpu_utilization(self.default_mem(&v.partition.label)))
+            .sum()
+    }
+
+    /// Sets the URL template used to make node names clickable
+    pub fn set_node_url(&mut self, node_url: Option<String>) {
+        self.node_url = node_url;
+    }
+
+    /// Returns the hyperlinks of the most recently rendered rows
+    pub fn hyperlinks(&self) -> &[Hyperlink] {
+        &self.hyperlinks
     }
 
     pub fn focus(&mut self, focus: bool) {
         self.focus = focus;
     }
 
-    pub fn scroll(&mut self, delta: isize) -> Option<Selection> {
+    pub fn scroll(&mut self, delta: isize) -> Option<Selection<'_>> {
         // Skip across across spacing elements
         while let Some(idx) = scroll(&mut self.table, self.rows.len(), delta) {
             if !matches!(self.rows[idx], NodeRow::Spacing)
@@ -128,7 +372,36 @@ impl NodeTableState {
         self.selected()
     }
 
-    pub fn selected(&self) -> Option<Selection> {
+    /// Switches between showing CPUs, memory, and GPUs as bars and as numbers
+    pub fn toggle_numbers(&mut self) {
+        self.toggle_mode(UtilizationMode::Numbers);
+    }
+
+    /// Switches between showing CPUs, memory, and GPUs as bars and as percentages
+    pub fn toggle_percentages(&mut self) {
+        self.toggle_mode(UtilizationMode::Percentages);
+    }
+
+    fn toggle_mode(&mut self, mode: UtilizationMode) {
+        self.mode = if self.mode == mode {
+            UtilizationMode::Bars
+        } else {
+            mode
+        };
+        self.widths.clear();
+    }
+
+    /// Returns the plain text of the selected row, with columns separated by tabs
+    pub fn selected_row_text(&self) -> Option<String> {
+        self.table.selected().map(|row| self.row_text(row))
+    }
+
+    /// Returns all rows as a plain-text table
+    pub fn to_plain_text(&self) -> String {
+        plain_table(self)
+    }
+
+    pub fn selected(&self) -> Option<Selection<'_>> {
         if let Some(idx) = self.table.selected() {
             match self.rows[idx] {
                 NodeRow::Partition(partition) => {
@@ -144,6 +417,16 @@ impl NodeTableState {
         }
     }
 
+    /// Returns the selected partition, or the partition of the selected node
+    pub fn selected_partition(&self) -> Option<&Partition> {
+        match self.rows.get(self.table.selected()?)? {
+            NodeRow::Partition(partition) | NodeRow::Node(partition, _) => {
+                self.cluster.get(*partition)
+            }
+            NodeRow::Spacing => None,
+        }
+    }
+
     pub fn click(&mut self, row: usize) {
         let offset = self.table.offset().saturating_add(row).saturating_sub(1);
         if let Some(selection) = self.rows.get(offset) {
@@ -153,6 +436,203 @@ impl NodeTableState {
         }
     }
 
+    /// Records current and upcoming maintenance reservations for each node
+    pub fn set_maintenance(&mut self, reservations: &[Reservation]) {
+        let now = Local::now().naive_local();
+
+        self.maintenance.clear();
+        self.widths.clear();
+        for reservation in reservations {
+            if reservation.is_maintenance() && reservation.end > now {
+                for node in &reservation.nodes {
+                    let window = (reservation.start, reservation.end);
+                    self.maintenance
+                        .entry(node.clone())
+                        .and_modify(|v| *v = (*v).min(window))
+                        .or_insert(window);
+                }
+            }
+        }
+    }
+
+    /// Records the nodes that are persistently overloaded or underutilized
+    pub fn set_anomalies(&mut self, anomalies: &Anomalies) {
+        self.anomalies = anomalies
+            .active()
+            .into_iter()
+            .map(|(name, anomaly)| (name.to_string(), anomaly.kind))
+            .collect();
+        self.widths.clear();
+    }
+
+    /// Adds a column showing the billable TRES of running jobs in each partition
+    pub fn show_billing(&mut self) {
+        if let Some(idx) = self.columns.iter().position(|v| matches!(v, Column::Jobs)) {
+            self.columns.insert(idx + 1, Column::Billing);
+        }
+    }
+
+    /// Adds columns showing the time, node, and per-user CPU limits of each partition
+    pub fn show_limits(&mut self) {
+        if let Some(idx) = self.columns.iter().position(|v| matches!(v, Column::CPUs)) {
+            self.columns.insert(idx, Column::MaxCPUsPerUser);
+            self.columns.insert(idx, Column::MaxNodes);
+            self.columns.insert(idx, Column::MaxTime);
+        }
+    }
+
+    /// Selects the previous (negative `delta`) or next (positive `delta`) column
+    pub fn select_column(&mut self, delta: isize) {
+        let last = self.columns.len().saturating_sub(1);
+        self.selected_column = Some(match self.selected_column {
+            Some(idx) => idx.saturating_add_signed(delta).min(last),
+            None => 0,
+        });
+    }
+
+    /// Moves the selected column to the left (negative `delta`) or to the right (positive
+    /// `delta`). Returns false if no column is selected or if it could not be moved
+    pub fn move_column(&mut self, delta: isize) -> bool {
+        let Some(idx) = self.selected_column else {
+            return false;
+        };
+
+        match move_column(&mut self.columns, idx, delta) {
+            Some(idx) => {
+                self.selected_column = Some(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the names of the visible columns, in order
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(|&v| self.name(v)).collect()
+    }
+
+    /// Sets the limits of each partition, without adding columns
+    pub fn set_partition_limits(&mut self, partition_limits: HashMap<String, PartitionLimits>) {
+        if partition_limits == self.partition_limits {
+            return;
+        }
+
+        self.partition_limits = partition_limits;
+        self.widths.clear();
+    }
+
+    /// Shows the named columns, in the order given, instead of the default columns
+    pub fn set_columns(&mut self, names: &[String]) -> Result<()> {
+        self.columns = parse_columns(&Column::all(&self.custom), |v| self.name(v), names)?;
+        Ok(())
+    }
+
+    /// Sets the custom columns defined in the configuration file. These are shown
+    /// before the utilization of CPUs, memory, and GPUs
+    pub fn set_custom_columns(&mut self, columns: &[ColumnConfig]) {
+        self.custom = custom_columns(columns, Pane::Nodes);
+        self.columns.retain(|v| !matches!(v, Column::Custom(_)));
+        let idx = self
+            .columns
+            .iter()
+            .position(|v| matches!(v, Column::CPUs))
+            .unwrap_or(self.columns.len());
+        self.columns
+            .splice(idx..idx, (0..self.custom.len()).map(Column::Custom));
+        self.selected_column = None;
+        self.widths.clear();
+    }
+
+    /// Adds a column showing the features available on each node
+    pub fn show_features(&mut self) {
+        if let Some(idx) = self.columns.iter().position(|v| matches!(v, Column::CPUs)) {
+            self.columns.insert(idx, Column::Features);
+        }
+    }
+
+    /// Adds a column showing the number of jobs on each node during recent updates
+    pub fn show_history(&mut self) {
+        if let Some(idx) = self.columns.iter().position(|v| matches!(v, Column::CPUs)) {
+            self.columns.insert(idx, Column::History);
+        }
+    }
+
+    /// Sets the number of jobs on each node during recent updates
+    pub fn set_history(&mut self, history: &NodeHistory) {
+        if self.columns.iter().any(|v| matches!(v, Column::History)) {
+            self.history = history.clone();
+            self.widths.clear();
+        }
+    }
+
+    /// Sets the utilization of each partition during recent updates
+    pub fn set_partition_history(&mut self, history: &PartitionHistory) {
+        if self.columns.iter().any(|v| matches!(v, Column::History)) {
+            self.partition_history = history.clone();
+            self.widths.clear();
+        }
+    }
+
+    /// Returns the sorted, unique features available on nodes in the cluster
+    pub fn features(&self) -> Vec<String> {
+        let mut features = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.nodes)
+            .flat_map(|v| v.features.iter().cloned())
+            .collect::<Vec<_>>();
+        features.sort_unstable();
+        features.dedup();
+        features
+    }
+
+    /// Returns the feature that nodes are currently filtered by, if any
+    pub fn feature(&self) -> Option<&str> {
+        self.feature.as_deref()
+    }
+
+    /// Only show nodes with the given feature, or all nodes if `None`
+    pub fn set_feature(&mut self, feature: Option<String>) {
+        self.feature = feature;
+        self.update_selections();
+    }
+
+    /// Selects the partition with the given name, returning false if it was not found
+    pub fn select_partition(&mut self, name: &str) -> bool {
+        let row = self.rows.iter().position(|v| match v {
+            NodeRow::Partition(idx) => self.cluster[*idx].name.label == name,
+            _ => false,
+        });
+
+        if row.is_some() {
+            self.table.select(row);
+        }
+
+        row.is_some()
+    }
+
+    /// Hides the partitions with the given names
+    pub fn set_hidden_partitions(&mut self, partitions: Vec<String>) {
+        self.hidden_partitions = partitions;
+        self.update_selections();
+    }
+
+    /// Returns the partitions that nodes are currently restricted to, if any
+    pub fn partitions(&self) -> &[String] {
+        &self.partitions
+    }
+
+    /// Only show nodes in the given partitions, or nodes in all partitions if empty
+    pub fn set_partitions(&mut self, partitions: Vec<String>) {
+        self.partitions = partitions;
+        self.update_selections();
+    }
+
+    /// Are unavailable nodes hidden?
+    pub fn hides_unavailable(&self) -> bool {
+        self.hide_unavailable
+    }
+
     pub fn toggle_unavailable(&mut self) {
         self.hide_unavailable = !self.hide_unavailable;
         self.update_selections();
@@ -164,22 +644,53 @@ impl NodeTableState {
     }
 
     fn update_selections(&mut self) {
+        self.widths.clear();
         self.rows.clear();
 
         for (p_idx, partition) in self.cluster.iter().enumerate() {
-            self.rows.push(NodeRow::Partition(p_idx));
+            if self.hidden_partitions.contains(&partition.name.label) {
+                continue;
+            }
 
-            for (n_idx, node) in partition.nodes.iter().enumerate() {
-                if !self.hide_unavailable || node.state.is_available() {
-                    self.rows.push(NodeRow::Node(p_idx, n_idx));
-                }
+            // Nodes are matched by partition, so that node groups can also be restricted
+            let in_partitions = |node: &Node| {
+                self.partitions.is_empty() || self.partitions.contains(&node.partition.label)
+            };
+
+            if !partition.nodes.iter().any(in_partitions) {
+                continue;
+            }
+
+            let nodes = partition
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| !self.hide_unavailable || node.state.is_available())
+                .filter(|(_, node)| in_partitions(node))
+                .filter(|(_, node)| match &self.feature {
+                    Some(feature) => node.features.contains(feature),
+                    None => true,
+                })
+                .map(|(n_idx, _)| NodeRow::Node(p_idx, n_idx))
+                .collect::<Vec<_>>();
+
+            // Partitions without any nodes with the selected feature are skipped
+            if self.feature.is_some() && nodes.is_empty() {
+                continue;
             }
 
+            self.rows.push(NodeRow::Partition(p_idx));
+            self.rows.extend(nodes);
             self.rows.push(NodeRow::Spacing);
         }
 
         // Remove trailing spacing
         self.rows.pop();
+
+        // Filtering may leave fewer rows than before
+        if self.table.selected() >= Some(self.rows.len()) {
+            self.table.select(self.rows.len().checked_sub(1));
+        }
     }
 
     pub fn height(&self) -> u16 {
@@ -193,19 +704,71 @@ impl NodeTableState {
         column: Column,
     ) -> Text<'a> {
         match column {
-            Column::Node => partition.name.to_string().into(),
-            Column::State => Text::default(),
-            Column::Users => right_align_text(partition.users()),
+            Column::Node => match self.blocked_alert(partition) {
+                Some(_) => Text::from(partition.name.to_string())
+                    .fg(palette().alert)
+                    .bold(),
+                None => partition.name.to_string().into(),
+            },
+            Column::State => {
+                let mut text = node_states_text(partition);
+                if let Some(percentage) = self.blocked_alert(partition) {
+                    if let Some(line) = text.lines.first_mut() {
+                        line.spans.push(Span::from(" · ").fg(palette().dimmed));
+                        line.spans.push(
+                            Span::from(format!("{:.0}% blocked", percentage))
+                                .fg(palette().alert)
+                                .bold(),
+                        );
+                    }
+                }
+
+                text
+            }
+            Column::Users => right_align_text(partition.users),
             Column::Jobs => right_align_text(partition.jobs.len()),
-            Column::CPUs => partition
-                .nodes
-                .iter()
-                .map(|v| v.cpu_utilization(self.def_mem_per_cpu))
-                .sum::<Utilization>()
-                .to_line(constraint_length(*constraint))
-                .into(),
+            Column::Billing => billing_text(partition.billing()),
+            Column::MaxTime => {
+                let limits = self.partition_limits.get(&partition.name.label);
+                right_align_text(limits.and_then(|v| v.max_time.clone()).unwrap_or_default())
+            }
+            // Values of custom fields are only shown for individual nodes
+            Column::Features | Column::Custom(_) => Text::default(),
+            // Partitions show the percentage of allocated CPUs during recent updates
+            Column::History => match self.partition_history.samples(&partition.name.label) {
+                Some(samples) => {
+                    let cpus = samples
+                        .cpus
+                        .iter()
+                        .skip(samples.cpus.len().saturating_sub(MAX_SAMPLES));
+                    format!(
+                        "{:>width$}",
+                        sparkline(cpus.copied(), Some(100)),
+                        width = MAX_SAMPLES
+                    )
+                    .into()
+                }
+                None => Text::default(),
+            },
+            Column::MaxNodes => {
+                let limits = self.partition_limits.get(&partition.name.label);
+                match limits.and_then(|v| v.max_nodes) {
+                    Some(nodes) => right_align_text(nodes),
+                    None => Text::default(),
+                }
+            }
+            Column::MaxCPUsPerUser => {
+                let limits = self.partition_limits.get(&partition.name.label);
+                match limits.and_then(|v| v.max_cpus_per_user) {
+                    Some(cpus) => right_align_text(cpus),
+                    None => Text::default(),
+                }
+            }
+            Column::CPUs => {
+                self.utilization_text(self.cpu_utilization(partition), column, constraint)
+            }
             Column::Memory => {
-                partition
+                let mem = partition
                     .nodes
                     .iter()
                     .map(|v| {
@@ -220,26 +783,50 @@ impl NodeTableState {
                         }
                         mem
                     })
-                    .sum::<Utilization>()
-                    .to_line(constraint_length(*constraint))
-                    .into()
+                    .sum::<Utilization>();
+                self.utilization_text(mem, column, constraint)
+            }
+            Column::GPUs => {
+                let gpus = partition
+                    .nodes
+                    .iter()
+                    .map(|v| {
+                        let mut gpus = v.gpu_utilization(self.default_mem(&v.partition.label));
+                        if !v.state.is_available() {
+                            gpus.allocated = 0.0;
+                            gpus.utilized = 0.0;
+                            gpus.blocked = 0.0;
+                            gpus.unavailable = gpus.capacity;
+                        }
+                        gpus
+                    })
+                    .sum::<Utilization>();
+                self.utilization_text(gpus, column, constraint)
+            }
+        }
+    }
+
+    /// Renders the utilization of CPUs, memory, or GPUs as a bar, numbers, or a percentage
+    fn utilization_text<'a>(
+        &self,
+        utilization: Utilization,
+        column: Column,
+        constraint: &Constraint,
+    ) -> Text<'a> {
+        match self.mode {
+            UtilizationMode::Bars => utilization.to_line(constraint_length(*constraint)).into(),
+            UtilizationMode::Numbers => {
+                let text = if column == Column::Memory {
+                    utilization.to_numbers(|v| mb_to_string(v as usize))
+                } else {
+                    utilization.to_numbers(|v| format!("{:.0}", v))
+                };
+
+                Text::from(text).alignment(Alignment::Right)
+            }
+            UtilizationMode::Percentages => {
+                Text::from(utilization.to_percentage()).alignment(Alignment::Right)
             }
-            Column::GPUs => partition
-                .nodes
-                .iter()
-                .map(|v| {
-                    let mut gpus = v.gpu_utilization(self.def_mem_per_cpu);
-                    if !v.state.is_available() {
-                        gpus.allocated = 0.0;
-                        gpus.utilized = 0.0;
-                        gpus.blocked = 0.0;
-                        gpus.unavailable = gpus.capacity;
-                    }
-                    gpus
-                })
-                .sum::<Utilization>()
-                .to_line(constraint_length(*constraint))
-                .into(),
         }
     }
 
@@ -251,77 +838,198 @@ impl NodeTableState {
         last: bool,
     ) -> Text<'a> {
         match column {
-            Column::Node => Text::from(format!(" {} {}", if last { "┕" } else { "┝" }, node.name)),
-            Column::State => color_state_text(&node.state),
-            Column::Users => right_align_text(node.users()),
-            Column::Jobs => right_align_text(node.jobs.len()),
-            Column::CPUs => node
-                .cpu_utilization(self.def_mem_per_cpu)
-                .to_line(constraint_length(*constraint))
-                .into(),
+            Column::Node => {
+                let text = Text::from(format!(" {} {}", if last { "┕" } else { "┝" }, node.name));
+                match self.anomalies.get(&node.name) {
+                    Some(&kind) => text.fg(anomaly_color(kind)),
+                    None => text,
+                }
+            }
+            Column::State => {
+                let mut text = match self.maintenance.get(&node.name) {
+                    Some(&(start, _)) => maintenance_text(&node.state, start),
+                    None => color_state_text(&node.state),
+                };
 
-            Column::Memory => node
-                .mem_utilization()
-                .to_line(constraint_length(*constraint))
-                .into(),
-            Column::GPUs => node
-                .gpu_utilization(self.def_mem_per_cpu)
-                .to_line(constraint_length(*constraint))
+                if let (Some(&kind), Some(line)) =
+                    (self.anomalies.get(&node.name), text.lines.first_mut())
+                {
+                    line.spans.push(Span::from(" · ").fg(palette().dimmed));
+                    line.spans
+                        .push(Span::from(kind.to_string()).fg(anomaly_color(kind)));
+                }
+
+                text
+            }
+            Column::Users => right_align_text(node.users),
+            Column::Jobs => right_align_text(node.jobs.len()),
+            // Billing is not split across the nodes allocated to a job
+            Column::Billing => Text::default(),
+            // Limits apply to partitions as a whole
+            Column::MaxTime | Column::MaxNodes | Column::MaxCPUsPerUser => Text::default(),
+            Column::Features => node.features.join(",").into(),
+            Column::Custom(idx) => custom_text(&self.custom[idx], &node.custom, idx),
+            Column::History => match self.history.jobs(&node.name) {
+                // Padded so that the column does not grow as samples are collected
+                Some(jobs) => format!(
+                    "{:>width$}",
+                    sparkline(jobs.iter().copied(), None),
+                    width = MAX_SAMPLES
+                )
                 .into(),
+                None => Text::default(),
+            },
+            Column::CPUs => self.utilization_text(
+                node.cpu_utilization(self.default_mem(&node.partition.label)),
+                column,
+                constraint,
+            ),
+            Column::Memory => self.utilization_text(node.mem_utilization(), column, constraint),
+            Column::GPUs => self.utilization_text(
+                node.gpu_utilization(self.default_mem(&node.partition.label)),
+                column,
+                constraint,
+            ),
         }
     }
 }
 
 impl Default for NodeTableState {
     fn default() -> Self {
+        let columns = vec![
+            Column::Node,
+            Column::State,
+            Column::Users,
+            Column::Jobs,
+            Column::CPUs,
+            Column::Memory,
+            Column::GPUs,
+        ];
+
         Self {
             focus: false,
+            mode: UtilizationMode::default(),
             hide_unavailable: false,
-            columns: vec![
-                Column::Node,
-                Column::State,
-                Column::Users,
-                Column::Jobs,
-                Column::CPUs,
-                Column::Memory,
-                Column::GPUs,
-            ],
+            feature: None,
+            hidden_partitions: Vec::new(),
+            partitions: Vec::new(),
+            selected_column: None,
+            columns,
+            custom: Vec::new(),
             table: TableState::default(),
             cluster: Rc::default(),
             rows: Vec::default(),
-            def_mem_per_cpu: 0,
+            default_mem: DefaultMem::None,
+            partition_default_mem: HashMap::new(),
+            blocked_alert: None,
+            history: NodeHistory::default(),
+            partition_history: PartitionHistory::default(),
+            partition_limits: HashMap::new(),
+            maintenance: HashMap::new(),
+            anomalies: HashMap::new(),
+            node_url: None,
+            hyperlinks: Vec::default(),
+            widths: ColumnWidths::default(),
         }
     }
 }
 
 #[derive(Debug, Default)]
-pub struct NodeTable {}
+pub struct NodeTable {
+    rows: RowStyle,
+}
 
 impl NodeTable {
     pub fn new() -> NodeTable {
         NodeTable::default()
     }
+
+    /// Sets the colors used to distinguish rows
+    pub fn set_row_style(&mut self, rows: RowStyle) {
+        self.rows = rows;
+    }
 }
 
 impl StatefulWidgetRef for NodeTable {
     type State = NodeTableState;
 
     fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let table = GenericTable::<Column, NodeTableState>::new();
+        let table = GenericTable::<Column, NodeTableState>::new().row_style(self.rows);
 
         table.render_ref(area, buf, state);
     }
 }
 
-/// Colorize a Node state based on availability
-fn color_state_text<'a>(state: &NodeState) -> Text<'a> {
-    let color = if state.is_available() {
-        Color::White
+/// Summarizes the number of nodes in each state, e.g. "idle 12 · mix 30 · down 2"
+fn node_states_text<'a>(partition: &Partition) -> Text<'a> {
+    let mut spans = Vec::new();
+    for (label, count) in partition.node_states() {
+        if !spans.is_empty() {
+            spans.push(Span::from(" · ").fg(palette().dimmed));
+        }
+
+        let span = Span::from(format!("{} {}", label, count));
+        spans.push(match label {
+            "drain" | "down" => span.fg(palette().error),
+            _ => span,
+        });
+    }
+
+    Line::from(spans).into()
+}
+
+/// Node state followed by a countdown to an upcoming maintenance window, or by
+/// "MAINT" if the node is currently reserved for maintenance
+fn maintenance_text<'a>(state: &NodeState, start: NaiveDateTime) -> Text<'a> {
+    let remaining = start - Local::now().naive_local();
+    let (label, color) = if remaining.num_seconds() <= 0 {
+        (String::from("MAINT"), palette().alert)
+    } else if remaining.num_hours() < 1 {
+        (
+            format!("maint in {}m", remaining.num_minutes().max(1)),
+            palette().warning,
+        )
+    } else if remaining.num_days() < 1 {
+        let minutes = remaining.num_minutes();
+        (
+            format!("maint in {}h{:02}m", minutes / 60, minutes % 60),
+            palette().warning,
+        )
     } else {
-        Color::Red
+        let hours = remaining.num_hours();
+        (
+            format!("maint in {}d{:02}h", hours / 24, hours % 24),
+            palette().warning,
+        )
     };
 
-    Text::from(state.to_string()).fg(color)
+    Line::from(vec![
+        Span::from(state.to_string()).fg(state_color(state)),
+        Span::from(" "),
+        Span::from(label).fg(color),
+    ])
+    .into()
+}
+
+/// Returns the color of nodes that are overloaded or underutilized
+fn anomaly_color(kind: AnomalyKind) -> Color {
+    match kind {
+        AnomalyKind::Overloaded => palette().alert,
+        AnomalyKind::Underutilized => palette().warning,
+    }
+}
+
+/// Colorize a Node state based on availability
+fn color_state_text<'a>(state: &NodeState) -> Text<'a> {
+    Text::from(state.to_string()).fg(state_color(state))
+}
+
+fn state_color(state: &NodeState) -> Color {
+    if state.is_available() {
+        palette().text
+    } else {
+        palette().error
+    }
 }
 
 fn constraint_length(c: Constraint) -> u16 {
@@ -0,0 +1,65 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    text::{Line, Text},
+    widgets::Widget,
+};
+
+use super::{palette, Popup};
+
+/// A popup listing a number of items, one of which is selected
+#[derive(Debug)]
+pub struct Picker<'a> {
+    title: &'a str,
+    items: &'a [String],
+    selected: usize,
+    instructions: Option<Line<'a>>,
+}
+
+impl<'a> Picker<'a> {
+    pub fn new(title: &'a str, items: &'a [String], selected: usize) -> Self {
+        Self {
+            title,
+            items,
+            selected,
+            instructions: None,
+        }
+    }
+
+    /// Sets the instructions shown in the bottom border of the popup
+    pub fn instructions<L: Into<Line<'a>>>(mut self, instructions: L) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+}
+
+impl Widget for Picker<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Only as many items as fit in the popup (excluding borders and margins) are shown,
+        // scrolling the list as needed to keep the selected item visible
+        let visible = (area.height.saturating_sub(4) as usize).max(1);
+        let start = self.selected.saturating_sub(visible - 1);
+
+        let lines = self
+            .items
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(visible)
+            .map(|(idx, item)| {
+                if idx == self.selected {
+                    Line::from(item.as_str()).style(palette().selected(palette().selection, true))
+                } else {
+                    Line::from(item.as_str())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut popup = Popup::new(self.title, Text::from(lines));
+        if let Some(instructions) = self.instructions {
+            popup = popup.instructions(instructions);
+        }
+
+        popup.render(area, buf);
+    }
+}
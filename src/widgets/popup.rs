@@ -0,0 +1,132 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Style, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph, Widget, Wrap,
+    },
+};
+
+use super::{misc::center_layout, palette};
+
+/// Maximum width of popups, including borders
+const MAX_WIDTH: u16 = 80;
+
+/// A popup displaying a (word-wrapped) message centered on top of other widgets
+#[derive(Debug, Default)]
+pub struct Popup<'a> {
+    title: String,
+    text: Text<'a>,
+    color: Color,
+    instructions: Option<Line<'a>>,
+    /// Lines reserved below the text for other widgets
+    reserved: u16,
+}
+
+impl<'a> Popup<'a> {
+    pub fn new<T: Into<Text<'a>>>(title: &str, text: T) -> Self {
+        Self {
+            title: format!(" {} ", title),
+            text: text.into(),
+            color: palette().text,
+            instructions: None,
+            reserved: 0,
+        }
+    }
+
+    /// Sets the color of the border and the title
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the instructions shown in the bottom border of the popup
+    pub fn instructions<L: Into<Line<'a>>>(mut self, instructions: L) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Reserves lines below the text for another widget, to be drawn in the area returned
+    /// by [`Popup::render_reserved`]. Popups with reserved lines use the full width
+    pub fn reserve(mut self, height: u16) -> Self {
+        self.reserved = height;
+        self
+    }
+
+    /// Renders the popup, returning the area reserved below the text, if any lines were
+    /// reserved and there is room for all of them
+    pub fn render_reserved(self, area: Rect, buf: &mut Buffer) -> Option<Rect> {
+        let title_width = self.title.chars().count() as u16;
+        let instructions_width = self.instructions.as_ref().map_or(0, |v| v.width() as u16);
+        let mut block = Block::default()
+            .title(
+                Title::from(self.title.bold())
+                    .alignment(Alignment::Center)
+                    .position(Position::Top),
+            )
+            .borders(Borders::ALL)
+            .border_set(border::PLAIN)
+            .border_style(Style::default().fg(self.color));
+
+        if let Some(instructions) = self.instructions {
+            block = block.title(
+                Title::from(instructions)
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            );
+        }
+
+        // Margins of 1 character on each side of the text
+        let max_width = area.width.saturating_sub(2).min(MAX_WIDTH);
+        let text_width = self.text.width() as u16 + 4;
+        let width = text_width.max(title_width.max(instructions_width) + 4);
+        let width = if self.reserved > 0 {
+            max_width
+        } else {
+            width.min(max_width)
+        };
+
+        let paragraph = Paragraph::new(self.text).wrap(Wrap { trim: false });
+        let text_height = paragraph.line_count(width.saturating_sub(4)) as u16;
+        // Reserved lines are separated from the text by an empty line
+        let reserved = if self.reserved > 0 {
+            self.reserved + 1
+        } else {
+            0
+        };
+        let height = (text_height + reserved + 2).min(area.height);
+
+        let area = center_layout(area, width, height)?;
+        Clear.render(area, buf);
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let inner = Rect {
+            x: inner.x + 1,
+            width: inner.width.saturating_sub(2),
+            ..inner
+        };
+        paragraph.render(inner, buf);
+
+        // The text takes precedence if the popup does not fit on screen
+        if reserved > 0 && inner.height >= text_height + reserved {
+            Some(Rect {
+                y: inner.bottom() - self.reserved,
+                height: self.reserved,
+                ..inner
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Widget for Popup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.render_reserved(area, buf);
+    }
+}
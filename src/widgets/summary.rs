@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::slurm::{count_node_states, JobState, Node, Partition};
+
+use super::{mb_to_string, palette};
+
+/// Single line summarizing the resources, nodes, and jobs of the whole cluster, as well
+/// as the filters currently applied to the node and job lists and the number of problems
+/// found in the Slurm state
+#[derive(Debug)]
+pub struct SummaryRow<'a> {
+    cluster: &'a [Partition],
+    filters: Vec<String>,
+    warnings: usize,
+}
+
+impl<'a> SummaryRow<'a> {
+    pub fn new(cluster: &'a [Partition]) -> Self {
+        Self {
+            cluster,
+            filters: Vec::new(),
+            warnings: 0,
+        }
+    }
+
+    /// Sets descriptions of the active filters, e.g. "feature avx512"
+    pub fn filters(mut self, filters: Vec<String>) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Sets the number of problems found in the Slurm state, e.g. jobs in unknown partitions
+    pub fn warnings(mut self, warnings: usize) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    fn separator() -> Span<'a> {
+        " │ ".fg(palette().dimmed)
+    }
+
+    pub fn to_line(&self) -> Line<'a> {
+        // Nodes may belong to multiple partitions and pending jobs may be listed in
+        // multiple partitions, but each is only counted once
+        let mut seen = HashSet::new();
+        let nodes = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.nodes)
+            .filter(|v| seen.insert(v.name.as_str()))
+            .collect::<Vec<_>>();
+
+        let mut seen = HashSet::new();
+        let jobs = self
+            .cluster
+            .iter()
+            .flat_map(|v| &v.jobs)
+            .filter(|v| seen.insert(v.id))
+            .collect::<Vec<_>>();
+
+        let sum = |f: fn(&Node) -> usize| nodes.iter().map(|v| f(v)).sum::<usize>();
+        let mut spans = Vec::new();
+        // Warnings are shown first, so that they are not cut off on narrow terminals
+        if self.warnings > 0 {
+            let label = if self.warnings == 1 {
+                "warning"
+            } else {
+                "warnings"
+            };
+            spans.extend([
+                format!(" ⚠ {} {} (!)", self.warnings, label).fg(palette().warning),
+                " │".fg(palette().dimmed),
+            ]);
+        }
+
+        spans.extend([
+            " CPUs ".bold(),
+            format!(
+                "{}/{}",
+                sum(|v| v.cpu_state.allocated),
+                sum(|v| v.cpu_state.total)
+            )
+            .into(),
+            Self::separator(),
+            "Memory ".bold(),
+            format!(
+                "{}/{}",
+                mb_to_string(sum(|v| v.mem_alloc)),
+                mb_to_string(sum(|v| v.mem))
+            )
+            .into(),
+            Self::separator(),
+            "GPUs ".bold(),
+            format!("{}/{}", sum(|v| v.gpus_used), sum(|v| v.gpus)).into(),
+            Self::separator(),
+            "Nodes ".bold(),
+        ]);
+
+        for (idx, (label, count)) in count_node_states(nodes.iter().copied())
+            .into_iter()
+            .enumerate()
+        {
+            if idx > 0 {
+                spans.push(" · ".fg(palette().dimmed));
+            }
+
+            let span = Span::from(format!("{} {}", label, count));
+            spans.push(match label {
+                "drain" | "down" => span.fg(palette().error),
+                _ => span,
+            });
+        }
+
+        let count = |state: JobState| jobs.iter().filter(|v| v.state == state).count();
+        spans.extend([
+            Self::separator(),
+            "Jobs ".bold(),
+            format!(
+                "{} running, {} pending",
+                count(JobState::Running),
+                count(JobState::Pending)
+            )
+            .into(),
+        ]);
+
+        if !self.filters.is_empty() {
+            spans.extend([
+                Self::separator(),
+                "Filters ".bold(),
+                self.filters.join(", ").fg(palette().warning),
+            ]);
+        }
+
+        Line::from(spans)
+    }
+}
+
+impl Widget for SummaryRow<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.to_line().render(area, buf);
+    }
+}
@@ -3,77 +3,247 @@ use std::{fmt::Display, marker::PhantomData};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::Text,
     widgets::{Row, StatefulWidgetRef, Table, TableState},
 };
 
-use super::{misc::COLUMN_SPACING, RightScrollbar};
+use super::{misc::COLUMN_SPACING, palette, Hyperlink, RightScrollbar};
 
 pub trait GenericTableState<C>
 where
-    C: Copy + Display + Sized,
+    C: Copy + Display + PartialEq + Sized,
 {
     fn focus(&self) -> bool;
 
     fn nrows(&self) -> usize;
     // FIXME: Should return slice
     fn columns(&self) -> &[C];
+    /// Returns the name of a column, as used in the configuration file
+    fn name(&self, column: C) -> String {
+        column.to_string()
+    }
+    /// Returns the header of a column, e.g. including an indicator of the sort order
+    fn header(&self, column: C) -> String {
+        self.name(column)
+    }
 
     /// Returns the text object for a given row and column. The `constraint` value
     /// will either be a constant
     fn text<'a>(&self, constraint: &Constraint, row: usize, column: C) -> Text<'a>;
     /// Returns true if a column should grow to consume available space
     fn variable_width(&self, column: C) -> bool;
+    /// Returns the width of a column with a configured width, rather than one fitted to
+    /// the text of its rows
+    fn fixed_width(&self, _column: C) -> Option<u16> {
+        None
+    }
 
     /// Returns TableState object used by the actual table
     fn inner_state(&mut self) -> &mut TableState;
+    /// Returns the cached widths of fixed-width columns; these must be cleared by the
+    /// state whenever the text of its rows may have changed
+    fn column_widths(&mut self) -> &mut ColumnWidths<C>;
     /// Returns the currently selected item
     fn selected(&self) -> Option<usize>;
+
+    /// Returns the URL of the hyperlink for a given row and column, if any, along
+    /// with the offset (in characters) at which the link starts in the cell
+    fn hyperlink(&self, _row: usize, _column: C) -> Option<(u16, String)> {
+        None
+    }
+    /// Stores the hyperlinks of the cells that were rendered
+    fn set_hyperlinks(&mut self, _hyperlinks: Vec<Hyperlink>) {}
+
+    /// Returns the text of a cell without styling, e.g. for copying it to the clipboard
+    fn plain_text(&self, row: usize, column: C) -> String {
+        text_to_string(self.text(&Constraint::Length(32), row, column))
+    }
+
+    /// Returns the plain text of each column of a row, separated by tabs
+    fn row_text(&self, row: usize) -> String {
+        self.columns()
+            .iter()
+            .map(|&column| self.plain_text(row, column))
+            .collect::<Vec<_>>()
+            .join("\t")
+    }
+}
+
+/// Widths of the rows of fixed-width columns, excluding the header. These are cached
+/// between renders, since measuring a column requires the text of every row
+#[derive(Debug)]
+pub struct ColumnWidths<C>(Vec<(C, u16)>);
+
+impl<C: Copy + PartialEq> ColumnWidths<C> {
+    pub fn get(&self, column: C) -> Option<u16> {
+        self.0.iter().find(|v| v.0 == column).map(|v| v.1)
+    }
+
+    pub fn insert(&mut self, column: C, width: u16) {
+        self.0.retain(|v| v.0 != column);
+        self.0.push((column, width));
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<C> Default for ColumnWidths<C> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+/// Formats a table as plain text, with columns padded to the width of their widest cell
+pub fn plain_table<C, S>(state: &S) -> String
+where
+    C: Copy + Display + PartialEq + Sized,
+    S: GenericTableState<C>,
+{
+    let mut rows = vec![state
+        .columns()
+        .iter()
+        .map(|&v| state.name(v))
+        .collect::<Vec<_>>()];
+    for row in 0..state.nrows() {
+        rows.push(
+            state
+                .columns()
+                .iter()
+                .map(|&column| state.plain_text(row, column))
+                .collect(),
+        );
+    }
+
+    let mut widths = vec![0; state.columns().len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = cell.chars().count().max(*width);
+        }
+    }
+
+    let spacing = " ".repeat(COLUMN_SPACING as usize);
+    let mut text = String::new();
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell))
+            .collect::<Vec<_>>();
+
+        text.push_str(cells.join(&spacing).trim_end());
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Concatenates the content of the spans and lines of a text, trimming whitespace
+pub fn text_to_string(text: Text) -> String {
+    let lines = text
+        .lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|v| v.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    lines.join(" ").trim().to_string()
+}
+
+/// Background colors used to distinguish rows in tables
+#[derive(Clone, Copy, Debug)]
+pub struct RowStyle {
+    /// Background color of every other row, if any
+    pub stripes: Option<Color>,
+    /// Background color of the selected row when the table has focus
+    pub selection: Color,
+    /// Background color of the selected row when the table does not have focus
+    pub inactive_selection: Color,
+    /// Draw the text of the selected row in bold
+    pub bold_selection: bool,
+}
+
+impl Default for RowStyle {
+    fn default() -> Self {
+        Self {
+            stripes: None,
+            selection: palette().selection,
+            inactive_selection: palette().inactive_selection,
+            bold_selection: false,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct GenericTable<C, S>
 where
-    C: Copy + Display + Sized,
+    C: Copy + Display + PartialEq + Sized,
     S: GenericTableState<C>,
 {
+    rows: RowStyle,
     c: PhantomData<C>,
     s: PhantomData<S>,
 }
 
 impl<C, S> GenericTable<C, S>
 where
-    C: Copy + Display + Sized,
+    C: Copy + Display + PartialEq + Sized,
     S: GenericTableState<C>,
 {
     pub fn new() -> Self {
         Self {
+            rows: RowStyle::default(),
             c: PhantomData,
             s: PhantomData,
         }
     }
 
-    fn width(state: &S, column: C) -> Option<Constraint> {
+    pub fn row_style(mut self, rows: RowStyle) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    fn width(state: &mut S, column: C) -> Option<Constraint> {
         if state.variable_width(column) {
-            None
-        } else {
-            // Dummy value
-            let constraint = Constraint::Length(32);
-            let mut width = column.to_string().chars().count();
-            for row in 0..state.nrows() {
-                width = state.text(&constraint, row, column).width().max(width);
-            }
+            return None;
+        }
 
-            Some(Constraint::Length(width as u16))
+        if let Some(width) = state.fixed_width(column) {
+            return Some(Constraint::Length(width));
         }
+
+        let width = match state.column_widths().get(column) {
+            Some(width) => width,
+            None => {
+                // Dummy value
+                let constraint = Constraint::Length(32);
+                let width = (0..state.nrows())
+                    .map(|row| state.text(&constraint, row, column).width())
+                    .max()
+                    .unwrap_or_default() as u16;
+
+                state.column_widths().insert(column, width);
+                width
+            }
+        };
+
+        // Headers are measured separately, as these may change (e.g. sort indicators)
+        let header = state.header(column).chars().count() as u16;
+        Some(Constraint::Length(width.max(header)))
     }
 
-    fn constraints(state: &S, area: Rect) -> Vec<Constraint> {
+    fn constraints(state: &mut S, area: Rect) -> Vec<Constraint> {
         let widths = state
             .columns()
-            .iter()
-            .map(|c| Self::width(state, *c))
+            .to_vec()
+            .into_iter()
+            .map(|c| Self::width(state, c))
             .collect::<Vec<_>>();
 
         let variable_length_columns = widths.iter().filter(|v| v.is_none()).count() as u16;
@@ -96,7 +266,7 @@ where
 
 impl<C, S> StatefulWidgetRef for GenericTable<C, S>
 where
-    C: Copy + Display + Sized,
+    C: Copy + Display + PartialEq + Sized,
     S: GenericTableState<C>,
 {
     type State = S;
@@ -104,7 +274,6 @@ where
     #[doc = " Draws the current state of the widget in the given buffer. That is the only method required"]
     #[doc = " to implement a custom stateful widget."]
     fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let columns = state.columns();
         let area = RightScrollbar::default()
             .header(1)
             .items(state.nrows())
@@ -112,9 +281,14 @@ where
             .render(area, buf);
 
         let constraints = GenericTable::<C, S>::constraints(state, area);
+        // Only rows in the visible window are generated, which therefore needs to be
+        // scrolled to the selected row here rather than by the table itself
+        let offset = GenericTable::<C, S>::scroll_offset(state, area);
+        let visible_rows = area.height.saturating_sub(1) as usize;
+        let columns = state.columns();
 
         let mut rows = Vec::new();
-        for idx in 0..state.nrows() {
+        for idx in offset..(offset + visible_rows).min(state.nrows()) {
             let mut row = Row::new(
                 columns
                     .iter()
@@ -125,23 +299,90 @@ where
             // Used instead of Table::highlight_style so that it doesn't override the style of individual
             // cells; this is required since Utilization bars use both fg and bg colors to draw fractions.
             if state.selected() == Some(idx) {
-                row = row.style(Style::default().bg(if state.focus() {
-                    Color::Blue
+                let style = palette().selected(
+                    if state.focus() {
+                        self.rows.selection
+                    } else {
+                        self.rows.inactive_selection
+                    },
+                    state.focus(),
+                );
+
+                row = row.style(if self.rows.bold_selection {
+                    style.add_modifier(Modifier::BOLD)
                 } else {
-                    Color::LightBlue
-                }));
+                    style
+                });
+            } else if let Some(color) = self.rows.stripes.filter(|_| idx % 2 == 1) {
+                row = row.style(Style::default().bg(color));
             }
 
             rows.push(row);
         }
 
-        let table = Table::new(rows, constraints)
+        let table = Table::new(rows, constraints.clone())
             .column_spacing(COLUMN_SPACING)
             .header(Row::new(
-                state.columns().iter().map(C::to_string).collect::<Vec<_>>(),
+                columns.iter().map(|&c| state.header(c)).collect::<Vec<_>>(),
             ));
 
-        StatefulWidgetRef::render_ref(&table, area, buf, state.inner_state());
+        let mut window = TableState::default()
+            .with_selected(state.selected().and_then(|v| v.checked_sub(offset)));
+        StatefulWidgetRef::render_ref(&table, area, buf, &mut window);
+        *state.inner_state().offset_mut() = offset;
+
+        let hyperlinks = GenericTable::<C, S>::hyperlinks(state, area, &constraints);
+        state.set_hyperlinks(hyperlinks);
+    }
+}
+
+impl<C, S> GenericTable<C, S>
+where
+    C: Copy + Display + PartialEq + Sized,
+    S: GenericTableState<C>,
+{
+    /// Returns the index of the first visible row, scrolled as little as possible from
+    /// the previous offset to keep the selected row visible (as done by [`Table`])
+    fn scroll_offset(state: &mut S, area: Rect) -> usize {
+        let visible_rows = (area.height.saturating_sub(1) as usize).max(1);
+        let last = state.nrows().saturating_sub(1);
+        let offset = state.inner_state().offset().min(last);
+        let selected = state.selected().unwrap_or_default().min(last);
+
+        if selected >= offset + visible_rows {
+            selected + 1 - visible_rows
+        } else {
+            offset.min(selected)
+        }
+    }
+
+    /// Collects hyperlinks for the visible rows, excluding the header
+    fn hyperlinks(state: &mut S, area: Rect, constraints: &[Constraint]) -> Vec<Hyperlink> {
+        let offset = state.inner_state().offset();
+        let visible_rows = area.height.saturating_sub(1) as usize;
+
+        let mut hyperlinks = Vec::new();
+        for idx in offset..(offset + visible_rows).min(state.nrows()) {
+            let y = area.y + 1 + (idx - offset) as u16;
+            let mut x = area.x;
+
+            for (&column, &constraint) in state.columns().iter().zip(constraints) {
+                let width = constraint_length(constraint);
+                if let Some((start, url)) = state.hyperlink(idx, column) {
+                    let start = start.min(width);
+                    let link = Rect::new(x + start, y, width - start, 1);
+
+                    hyperlinks.push(Hyperlink {
+                        area: link.intersection(area),
+                        url,
+                    });
+                }
+
+                x = x.saturating_add(width + COLUMN_SPACING);
+            }
+        }
+
+        hyperlinks
     }
 }
 
@@ -0,0 +1,317 @@
+use std::sync::OnceLock;
+
+use color_eyre::{eyre::eyre, Result};
+use ratatui::style::{Color, Modifier, Style};
+
+/// Names of the built-in palettes
+pub const PALETTES: [&str; 4] = ["dark", "light", "solarized", "colorblind"];
+
+/// Palette used by all widgets; set once at startup (see [`set_palette`])
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Colors used throughout the user interface
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    /// Regular text, e.g. the state of available nodes and the borders of popups
+    pub text: Color,
+    /// Separators and missing values
+    pub dimmed: Color,
+    /// Jobs that are not running
+    pub inactive: Color,
+    /// Errors and unavailable nodes
+    pub error: Color,
+    /// Responsive Slurm controllers
+    pub ok: Color,
+    /// Upcoming maintenance, watched jobs, and confirmations
+    pub warning: Color,
+    /// Ongoing maintenance and partitions with many blocked CPUs
+    pub alert: Color,
+    /// Jobs marked by the user
+    pub marked: Color,
+    /// Text and background of search/filter matches
+    pub match_fg: Color,
+    pub match_bg: Color,
+    /// Background of the selected row in the list that has focus
+    pub selection: Color,
+    /// Background of the selected row in the list that does not have focus
+    pub inactive_selection: Color,
+    /// Segments of utilization bars (see [`crate::slurm::Utilization`])
+    pub utilized: Color,
+    pub allocated: Color,
+    pub blocked: Color,
+    pub cached: Color,
+    pub available: Color,
+    pub unavailable: Color,
+    /// Bars of the job timeline, picked by user so that the jobs of a user share a color
+    pub timeline: [Color; 6],
+    /// Job IDs drawn on the bars of the job timeline
+    pub timeline_text: Color,
+    /// Draw without colors, e.g. for terminals that do not support colors; selected
+    /// rows are reversed and utilization is drawn using ASCII gauges
+    pub monochrome: bool,
+}
+
+impl Palette {
+    /// Colors suitable for terminals with a dark background
+    pub const fn dark() -> Palette {
+        Palette {
+            text: Color::White,
+            dimmed: Color::DarkGray,
+            inactive: Color::Gray,
+            error: Color::Red,
+            ok: Color::Green,
+            warning: Color::Yellow,
+            alert: Color::LightMagenta,
+            marked: Color::Cyan,
+            match_fg: Color::Black,
+            match_bg: Color::Yellow,
+            selection: Color::Blue,
+            inactive_selection: Color::LightBlue,
+            utilized: Color::Green,
+            allocated: Color::Yellow,
+            blocked: Color::LightMagenta,
+            cached: Color::Blue,
+            available: Color::DarkGray,
+            unavailable: Color::Black,
+            timeline: [
+                Color::Blue,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Green,
+                Color::Yellow,
+                Color::LightRed,
+            ],
+            timeline_text: Color::Black,
+            monochrome: false,
+        }
+    }
+
+    /// Colors suitable for terminals with a light background
+    pub const fn light() -> Palette {
+        Palette {
+            text: Color::Black,
+            dimmed: Color::Indexed(245),
+            inactive: Color::Indexed(240),
+            error: Color::Indexed(160),
+            ok: Color::Indexed(28),
+            warning: Color::Indexed(130),
+            alert: Color::Indexed(127),
+            marked: Color::Indexed(25),
+            match_fg: Color::Black,
+            match_bg: Color::Indexed(228),
+            selection: Color::Indexed(153),
+            inactive_selection: Color::Indexed(252),
+            utilized: Color::Indexed(34),
+            allocated: Color::Indexed(214),
+            blocked: Color::Indexed(170),
+            cached: Color::Indexed(75),
+            available: Color::Indexed(252),
+            unavailable: Color::Indexed(255),
+            timeline: [
+                Color::Indexed(75),
+                Color::Indexed(176),
+                Color::Indexed(80),
+                Color::Indexed(114),
+                Color::Indexed(221),
+                Color::Indexed(210),
+            ],
+            timeline_text: Color::Black,
+            monochrome: false,
+        }
+    }
+
+    /// The Solarized (dark) palette; see <https://ethanschoonover.com/solarized/>
+    pub const fn solarized() -> Palette {
+        const BASE03: Color = Color::Rgb(0x00, 0x2b, 0x36);
+        const BASE02: Color = Color::Rgb(0x07, 0x36, 0x42);
+        const BASE01: Color = Color::Rgb(0x58, 0x6e, 0x75);
+        const BASE0: Color = Color::Rgb(0x83, 0x94, 0x96);
+        const BASE1: Color = Color::Rgb(0x93, 0xa1, 0xa1);
+        const YELLOW: Color = Color::Rgb(0xb5, 0x89, 0x00);
+        const RED: Color = Color::Rgb(0xdc, 0x32, 0x2f);
+        const MAGENTA: Color = Color::Rgb(0xd3, 0x36, 0x82);
+        const VIOLET: Color = Color::Rgb(0x6c, 0x71, 0xc4);
+        const BLUE: Color = Color::Rgb(0x26, 0x8b, 0xd2);
+        const CYAN: Color = Color::Rgb(0x2a, 0xa1, 0x98);
+        const GREEN: Color = Color::Rgb(0x85, 0x99, 0x00);
+        const ORANGE: Color = Color::Rgb(0xcb, 0x4b, 0x16);
+
+        Palette {
+            text: BASE1,
+            dimmed: BASE01,
+            inactive: BASE0,
+            error: RED,
+            ok: GREEN,
+            warning: YELLOW,
+            alert: MAGENTA,
+            marked: CYAN,
+            match_fg: BASE03,
+            match_bg: YELLOW,
+            selection: BLUE,
+            inactive_selection: VIOLET,
+            utilized: GREEN,
+            allocated: YELLOW,
+            blocked: MAGENTA,
+            cached: BLUE,
+            available: BASE02,
+            unavailable: BASE03,
+            timeline: [BLUE, MAGENTA, CYAN, GREEN, YELLOW, ORANGE],
+            timeline_text: BASE03,
+            monochrome: false,
+        }
+    }
+
+    /// Colors distinguishable with common forms of color blindness, based on the
+    /// Okabe-Ito palette; red/green distinctions (e.g. green vs. yellow segments of
+    /// utilization bars) are replaced by blue/orange distinctions
+    pub const fn colorblind() -> Palette {
+        const ORANGE: Color = Color::Rgb(0xe6, 0x9f, 0x00);
+        const SKY_BLUE: Color = Color::Rgb(0x56, 0xb4, 0xe9);
+        const BLUISH_GREEN: Color = Color::Rgb(0x00, 0x9e, 0x73);
+        const YELLOW: Color = Color::Rgb(0xf0, 0xe4, 0x42);
+        const BLUE: Color = Color::Rgb(0x00, 0x72, 0xb2);
+        const VERMILLION: Color = Color::Rgb(0xd5, 0x5e, 0x00);
+        const REDDISH_PURPLE: Color = Color::Rgb(0xcc, 0x79, 0xa7);
+
+        Palette {
+            error: VERMILLION,
+            ok: BLUISH_GREEN,
+            warning: ORANGE,
+            alert: REDDISH_PURPLE,
+            marked: SKY_BLUE,
+            match_bg: YELLOW,
+            utilized: SKY_BLUE,
+            allocated: ORANGE,
+            blocked: REDDISH_PURPLE,
+            cached: BLUE,
+            timeline: [
+                SKY_BLUE,
+                REDDISH_PURPLE,
+                BLUISH_GREEN,
+                ORANGE,
+                YELLOW,
+                VERMILLION,
+            ],
+            timeline_text: Color::Black,
+            ..Palette::dark()
+        }
+    }
+
+    /// The default colors of the terminal, used if `--no-color` or `NO_COLOR` is set
+    pub const fn monochrome() -> Palette {
+        Palette {
+            text: Color::Reset,
+            dimmed: Color::Reset,
+            inactive: Color::Reset,
+            error: Color::Reset,
+            ok: Color::Reset,
+            warning: Color::Reset,
+            alert: Color::Reset,
+            marked: Color::Reset,
+            match_fg: Color::Reset,
+            match_bg: Color::Reset,
+            selection: Color::Reset,
+            inactive_selection: Color::Reset,
+            utilized: Color::Reset,
+            allocated: Color::Reset,
+            blocked: Color::Reset,
+            cached: Color::Reset,
+            available: Color::Reset,
+            unavailable: Color::Reset,
+            timeline: [Color::Reset; 6],
+            timeline_text: Color::Reset,
+            monochrome: true,
+        }
+    }
+
+    /// Returns the style of a selected row using the given background color. Without
+    /// colors, selected rows are reversed if the list has focus and underlined if not
+    pub fn selected(&self, background: Color, focus: bool) -> Style {
+        match (self.monochrome, focus) {
+            (false, _) => Style::default().bg(background),
+            (true, true) => Style::default().add_modifier(Modifier::REVERSED),
+            (true, false) => Style::default().add_modifier(Modifier::UNDERLINED),
+        }
+    }
+
+    /// Returns the style of text matching a search or filter
+    pub fn matched(&self) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.match_fg).bg(self.match_bg)
+        }
+    }
+
+    /// Returns the built-in palette with the given name (see [`PALETTES`])
+    pub fn named(name: &str) -> Result<Palette> {
+        match name {
+            "dark" => Ok(Palette::dark()),
+            "light" => Ok(Palette::light()),
+            "solarized" => Ok(Palette::solarized()),
+            "colorblind" => Ok(Palette::colorblind()),
+            _ => Err(eyre!(
+                "unknown theme {:?}; expected one of {}",
+                name,
+                PALETTES.join(", ")
+            )),
+        }
+    }
+
+    /// Replaces a single color, identified by the name of the field in this struct
+    pub fn set(&mut self, name: &str, color: Color) -> Result<()> {
+        let field = match name {
+            "text" => &mut self.text,
+            "dimmed" => &mut self.dimmed,
+            "inactive" => &mut self.inactive,
+            "error" => &mut self.error,
+            "ok" => &mut self.ok,
+            "warning" => &mut self.warning,
+            "alert" => &mut self.alert,
+            "marked" => &mut self.marked,
+            "match_fg" => &mut self.match_fg,
+            "match_bg" => &mut self.match_bg,
+            "selection" => &mut self.selection,
+            "inactive_selection" => &mut self.inactive_selection,
+            "utilized" => &mut self.utilized,
+            "allocated" => &mut self.allocated,
+            "blocked" => &mut self.blocked,
+            "cached" => &mut self.cached,
+            "available" => &mut self.available,
+            "unavailable" => &mut self.unavailable,
+            "timeline_text" => &mut self.timeline_text,
+            _ => match name
+                .strip_prefix("timeline")
+                .and_then(|v| v.parse::<usize>().ok())
+            {
+                // Colors of the timeline are numbered from 1, e.g. "timeline1"
+                Some(index @ 1..=6) => &mut self.timeline[index - 1],
+                _ => return Err(eyre!("unknown color {:?} in [theme.colors]", name)),
+            },
+        };
+
+        *field = color;
+        Ok(())
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::dark()
+    }
+}
+
+/// Sets the palette used by all widgets. Must be called before the first draw, as the
+/// palette can only be set once; setting a different palette afterwards is an error
+pub fn set_palette(palette: Palette) -> Result<()> {
+    if *PALETTE.get_or_init(|| palette) != palette {
+        return Err(eyre!("palette has already been set"));
+    }
+
+    Ok(())
+}
+
+/// Returns the palette used by all widgets
+pub fn palette() -> &'static Palette {
+    PALETTE.get_or_init(Palette::default)
+}
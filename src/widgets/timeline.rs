@@ -0,0 +1,234 @@
+use std::hash::{Hash, Hasher};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::Widget,
+};
+
+use crate::slurm::{JobState, Partition};
+
+use super::palette;
+
+/// Maximum number of jobs drawn for each node; further jobs are counted on the last line
+const MAX_JOBS_PER_NODE: usize = 4;
+/// Time spans (in seconds) that may be covered on either side of the current time; the
+/// shortest span covering the elapsed and remaining time of all jobs is used
+const SPANS: [u64; 10] = [
+    3600,
+    2 * 3600,
+    6 * 3600,
+    12 * 3600,
+    86400,
+    2 * 86400,
+    3 * 86400,
+    7 * 86400,
+    14 * 86400,
+    30 * 86400,
+];
+
+/// A running job, drawn from its start to the end of its time limit
+#[derive(Clone, Debug)]
+struct Bar {
+    id: String,
+    /// Index into the timeline colors of the palette (see [`super::Palette::timeline`])
+    color: usize,
+    /// Seconds since the job started
+    elapsed: u64,
+    /// Seconds until the job reaches its time limit, if limited
+    remaining: Option<u64>,
+}
+
+/// A line of the timeline; the first line of each node is labelled with its name
+#[derive(Clone, Debug)]
+struct Row {
+    node: Option<String>,
+    bar: Option<Bar>,
+    /// Number of jobs on the node that are not drawn
+    more: usize,
+}
+
+/// Running jobs on each node of a partition, drawn as bars spanning their elapsed and
+/// remaining time, centered on the current time. This shows when capacity frees up and
+/// how jobs are spread across nodes
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    rows: Vec<Row>,
+    /// Seconds covered on either side of the current time
+    span: u64,
+    /// Index of the first row drawn
+    offset: usize,
+}
+
+impl Timeline {
+    /// Creates a timeline of the running jobs on the nodes of a partition (or node group)
+    pub fn new(partition: &Partition) -> Self {
+        let mut rows = Vec::new();
+        let mut longest = 0;
+        for node in &partition.nodes {
+            let mut jobs = node
+                .jobs
+                .iter()
+                .filter(|v| v.state == JobState::Running)
+                .map(|job| Bar {
+                    id: job.display_id(),
+                    color: color_index(&job.user),
+                    elapsed: job.time.as_duration().map_or(0, |v| v.as_secs()),
+                    remaining: job.time_left.as_duration().map(|v| v.as_secs()),
+                })
+                .collect::<Vec<_>>();
+            // Jobs ending first are drawn first, as they free up capacity first
+            jobs.sort_by_key(|v| (v.remaining.unwrap_or(u64::MAX), v.elapsed));
+
+            for job in &jobs {
+                longest = longest.max(job.elapsed).max(job.remaining.unwrap_or(0));
+            }
+
+            let more = jobs.len().saturating_sub(MAX_JOBS_PER_NODE);
+            jobs.truncate(MAX_JOBS_PER_NODE);
+            if jobs.is_empty() {
+                rows.push(Row {
+                    node: Some(node.name.clone()),
+                    bar: None,
+                    more: 0,
+                });
+            }
+
+            let count = jobs.len();
+            for (idx, bar) in jobs.into_iter().enumerate() {
+                rows.push(Row {
+                    node: (idx == 0).then(|| node.name.clone()),
+                    bar: Some(bar),
+                    more: if idx + 1 == count { more } else { 0 },
+                });
+            }
+        }
+
+        let span = SPANS
+            .iter()
+            .copied()
+            .find(|&v| v >= longest)
+            .unwrap_or(SPANS[SPANS.len() - 1]);
+
+        Self {
+            rows,
+            span,
+            offset: 0,
+        }
+    }
+
+    /// Sets the index of the first line drawn, e.g. when scrolling
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset.min(self.len().saturating_sub(1));
+        self
+    }
+
+    /// Returns the number of lines of the timeline, excluding the time axis
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns true if the partition has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Draws the labels of the time axis, e.g. "-1d", "now", and "+1d"
+    fn render_axis(&self, area: Rect, now: u16, buf: &mut Buffer) {
+        let style = Style::default().fg(palette().dimmed);
+        let span = span_to_string(self.span);
+        let left = format!("-{}", span);
+        let right = format!("+{}", span);
+
+        buf.set_string(area.x, area.y, &left, style);
+        buf.set_string(now.saturating_sub(1), area.y, "now", style.bold());
+        let x = area.right().saturating_sub(right.len() as u16);
+        buf.set_string(x, area.y, &right, style);
+    }
+}
+
+impl Widget for Timeline {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 2 || area.width < 16 {
+            return;
+        }
+
+        let label_width = self
+            .rows
+            .iter()
+            .filter_map(|v| v.node.as_ref())
+            .map(|v| v.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(area.width as usize / 3) as u16
+            + 1;
+        let chart = Rect {
+            x: area.x + label_width,
+            y: area.y,
+            width: area.width - label_width,
+            height: area.height,
+        };
+        // Columns per second on either side of the current time
+        let half = chart.width / 2;
+        let scale = half as f64 / self.span as f64;
+        let now = chart.x + half;
+
+        self.render_axis(chart, now, buf);
+
+        let palette = palette();
+        let lines = self.rows.iter().skip(self.offset);
+        for (y, row) in (area.y + 1..area.bottom()).zip(lines) {
+            if let Some(node) = &row.node {
+                buf.set_stringn(area.x, y, node, label_width as usize - 1, Style::default());
+            }
+
+            buf.set_string(now, y, "│", Style::default().fg(palette.dimmed));
+            let Some(bar) = &row.bar else {
+                continue;
+            };
+
+            // Bars are clipped to the chart; jobs without a time limit extend to the end
+            let start = now.saturating_sub((bar.elapsed as f64 * scale).round() as u16);
+            let end = match bar.remaining {
+                Some(remaining) => now + ((remaining as f64 * scale).round() as u16).min(half),
+                None => chart.right(),
+            };
+            let start = start.max(chart.x);
+            let end = end.max(start + 1).min(chart.right());
+
+            let style = if palette.monochrome {
+                Style::default().reversed()
+            } else {
+                Style::default()
+                    .bg(palette.timeline[bar.color])
+                    .fg(palette.timeline_text)
+            };
+            let width = (end - start) as usize;
+            let text = format!("{:<width$.width$}", bar.id, width = width);
+            buf.set_string(start, y, text, style);
+
+            if row.more > 0 {
+                let more = format!(" +{} more", row.more);
+                let x = end.min(chart.right().saturating_sub(more.len() as u16));
+                buf.set_string(x, y, more, Style::default().fg(palette.dimmed));
+            }
+        }
+    }
+}
+
+/// Picks the color of the jobs of a user
+fn color_index(user: &str) -> usize {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    user.hash(&mut hasher);
+    hasher.finish() as usize % palette().timeline.len()
+}
+
+/// Formats a span of time using the largest whole unit, e.g. "12h" or "7d"
+fn span_to_string(seconds: u64) -> String {
+    if seconds.is_multiple_of(86400) {
+        format!("{}d", seconds / 86400)
+    } else {
+        format!("{}h", seconds / 3600)
+    }
+}
@@ -1,35 +1,49 @@
-use std::iter::Sum;
-
 use ratatui::{
-    style::{Color, Style},
-    text::{Line, Span},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
 };
 
-const BARS: [&str; 8] = ["█", "▉", "▊", "▋", "▌", "▍", "▎", "▏"];
+use super::palette;
+use crate::slurm::Utilization;
 
-#[derive(Debug, Default)]
-pub struct Utilization {
-    /// Actual utilization; should normally be less than reserved, but may exceed it
-    /// due to resource usage by system processes
-    pub utilized: f64,
-    /// Amount of resources allocated to users. Must be less or equal to capacity
-    pub allocated: f64,
-    /// Amount of resources "blocked" due to (over)allocation of linked resources;
-    /// CPUs may for example be "blocked" due to (over)allocation of RAM, unless a
-    /// user explicitly asks for less than the default amount of RAM per CPU.
-    pub blocked: f64,
-    /// Amount of resources unavailable for other reasons (node down)
-    pub unavailable: f64,
-    /// Total amount of resources available
-    pub capacity: f64,
-}
+const BARS: [&str; 8] = ["█", "▉", "▊", "▋", "▌", "▍", "▎", "▏"];
+/// Characters used for utilized, allocated, blocked, cached, available, and
+/// unavailable resources in ASCII gauges (see [`Utilization::to_ascii_line`])
+const GAUGE: [&str; 6] = ["#", "=", "+", "~", ".", "x"];
+/// Names and definitions of the segments of utilization bars, in the order drawn; see
+/// the fields of [`Utilization`] and the `*_utilization` functions of [`crate::slurm::Node`]
+const LEGEND: [(&str, &str); 6] = [
+    (
+        "Utilized",
+        "CPU load, memory, or GPU utilization of jobs, up to the amount allocated. CPU \
+         load is only refreshed periodically by Slurm, and GPU utilization is only shown \
+         if a GPU probe is configured",
+    ),
+    ("Allocated", "Allocated to jobs, but not (yet) utilized"),
+    (
+        "Blocked",
+        "Not allocated, but unusable by jobs requesting the default amount of memory \
+         (DefMemPerCPU, DefMemPerNode, or DefMemPerGPU): CPUs lacking memory, memory \
+         on nodes without free CPUs, and GPUs lacking CPUs or memory",
+    ),
+    (
+        "Cached",
+        "Memory not allocated to jobs, but used by the page cache or by system processes",
+    ),
+    ("Available", "Neither allocated nor blocked"),
+    (
+        "Unavailable",
+        "CPUs that are down, drained, or otherwise unusable, and memory on nodes \
+         without usable CPUs",
+    ),
+];
 
 impl Utilization {
-    pub fn available(&self) -> f64 {
-        self.capacity - (self.allocated + self.blocked + self.unavailable)
-    }
-
     pub fn to_line<'a>(self, length: u16) -> Line<'a> {
+        if palette().monochrome {
+            return self.to_ascii_line(length);
+        }
+
         assert!(self.allocated + self.unavailable <= self.capacity);
 
         let mut spans = Vec::new();
@@ -40,23 +54,29 @@ impl Utilization {
             // CPUs available to the user
             let available = self.capacity - self.unavailable;
             // List of segments by their end-point and their colors
+            let palette = palette();
             let segments = [
                 // Utilization may spike above resources available to users/Slurm,
                 // but it doesn't make sense to show utilization beyond the resources
                 // actually available to the users
-                (self.utilized.min(available), Color::Green),
+                (self.utilized.min(available), palette.utilized),
                 // Allocated but unutilized resources
-                (self.allocated, Color::Yellow),
+                (self.allocated, palette.allocated),
                 // Resources blocked to to allocation of linked resources
-                (self.blocked, Color::LightMagenta),
+                (self.blocked, palette.blocked),
+                // Unallocated resources used for caching
+                (
+                    (self.allocated.max(self.blocked) + self.cached).min(available),
+                    palette.cached,
+                ),
                 // Unblocked, unallocated resources
-                (available, Color::DarkGray),
+                (available, palette.available),
                 // Unavailable resources
-                (self.capacity, Color::Black),
+                (self.capacity, palette.unavailable),
             ];
 
             let mut last_end = 0.0;
-            let mut last_color = Color::Green;
+            let mut last_color = palette.utilized;
 
             for (end, color) in segments {
                 let end = (end / self.capacity) * length as f64;
@@ -102,24 +122,84 @@ impl Utilization {
 
         Line::from(spans)
     }
-}
 
-/// Implements the sum operator for Utilization objects
-/// This is used for generating partition overviews
-impl Sum for Utilization {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut result = Utilization::default();
-        for it in iter {
-            result.utilized += it.utilized;
-            result.allocated += it.allocated;
-            result.blocked += it.blocked;
-            result.unavailable += it.unavailable;
-            result.capacity += it.capacity;
+    /// Renders utilization as an ASCII gauge followed by the percentage of allocated
+    /// resources, e.g. `[##==+..] 57%`, for use without colors
+    pub fn to_ascii_line<'a>(self, length: u16) -> Line<'a> {
+        if self.capacity <= 0.0 {
+            return Line::default();
+        }
+
+        let length = length as usize;
+        let percentage = self.to_percentage();
+
+        // Room for brackets, a space, and the percentage (up to "100%")
+        let width = length.saturating_sub(7);
+        if width == 0 {
+            return Line::from(format!("{:>length$}", percentage));
+        }
+
+        let available = self.capacity - self.unavailable;
+        let ends = [
+            self.utilized.min(available),
+            self.allocated,
+            self.blocked,
+            (self.allocated.max(self.blocked) + self.cached).min(available),
+            available,
+            self.capacity,
+        ];
+
+        let mut gauge = String::from("[");
+        let mut last_end = 0;
+        for (end, c) in ends.into_iter().zip(GAUGE) {
+            let end = ((end / self.capacity) * width as f64).round() as usize;
+            if end > last_end {
+                gauge.push_str(&c.repeat(end - last_end));
+                last_end = end;
+            }
         }
-        result
+
+        gauge.push_str(&" ".repeat(width.saturating_sub(last_end)));
+        Line::from(format!("{}] {:>4}", gauge, percentage))
     }
 }
 
+/// Describes the segments of utilization bars, each preceded by a sample of the color
+/// (or the ASCII character, without colors) used to draw it
+pub fn legend<'a>() -> Text<'a> {
+    let palette = palette();
+    let colors = [
+        palette.utilized,
+        palette.allocated,
+        palette.blocked,
+        palette.cached,
+        palette.available,
+        palette.unavailable,
+    ];
+
+    let mut lines = vec![
+        Line::from("Utilization bars are drawn using the following segments, in order:"),
+        Line::default(),
+    ];
+
+    for (((name, definition), color), c) in LEGEND.into_iter().zip(colors).zip(GAUGE) {
+        let sample = if palette.monochrome {
+            Span::from(format!("[{}]", c.repeat(3)))
+        } else {
+            Span::styled(BARS[0].repeat(5), style(color, color))
+        };
+
+        lines.push(Line::from(vec![
+            sample,
+            " ".into(),
+            name.bold(),
+            format!(": {}", definition).into(),
+        ]));
+    }
+
+    Text::from(lines)
+}
+
 fn style(fg: Color, bg: Color) -> Style {
     Style::default().fg(fg).bg(bg)
 }
@@ -0,0 +1,464 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::{eyre::eyre, Report, Result};
+use tracing::{debug, warn};
+
+use crate::config::SlurmOptions;
+use crate::replay::create_snapshot;
+use crate::slurm::{
+    final_state, AssociationUsage, CustomFields, GpuUsage, Health, Job, JobLayout, JobOutput,
+    JobState, Partition, PartitionConfig, ReportPeriod, Reservation, Slurm, SlurmVersion, Source,
+    Timeout, UsageReport, Watchdog,
+};
+
+/// Maximum number of GPU probes run concurrently
+const MAX_GPU_PROBES: usize = 16;
+/// Time after which no further GPU probes are started during an update, so that slow
+/// probes (e.g. of unreachable nodes) cannot stall updates on clusters with many GPUs
+const GPU_PROBE_DEADLINE: Duration = Duration::from_secs(20);
+
+/// Parameters of an update of the Slurm state
+#[derive(Debug)]
+pub struct UpdateRequest {
+    /// Source of Slurm command output
+    pub source: Source,
+    /// Directory in which the output is recorded in a new snapshot (see `--record`); the
+    /// snapshot is only created once the request is run
+    pub record: Option<PathBuf>,
+    /// Locations of Slurm executables
+    pub slurm: SlurmOptions,
+    /// Use the JSON output of sinfo/squeue, falling back to delimited output
+    pub json: bool,
+    /// Version of Slurm, if known; used to select the fields of the delimited output
+    pub version: Option<SlurmVersion>,
+    /// Additional fields requested for custom columns
+    pub custom: CustomFields,
+    /// Collect the responsiveness of the Slurm daemons
+    pub health: bool,
+    /// Watched jobs, whose final state is looked up if no longer listed by squeue
+    pub watched: Vec<usize>,
+    /// Collect the configuration and limits of partitions, which change rarely
+    pub partitions: bool,
+}
+
+/// Slurm state collected by an [`UpdateRequest`]
+#[derive(Debug)]
+pub struct Snapshot {
+    pub health: Option<Health>,
+    pub reservations: Vec<Reservation>,
+    /// Nodes and jobs organized by partition, along with jobs in partitions not listed by
+    /// sinfo, or the error encountered running sinfo/squeue
+    pub cluster: Result<(Vec<Partition>, Vec<Job>)>,
+    /// False if the JSON output was requested but is not supported by Slurm
+    pub json: bool,
+    /// Final states of watched jobs no longer listed by squeue, as reported by sacct
+    pub final_states: HashMap<usize, JobState>,
+    /// Configuration of partitions, if requested
+    pub partitions: Option<Result<Vec<PartitionConfig>>>,
+}
+
+impl UpdateRequest {
+    /// Runs the Slurm commands needed to collect the current state
+    pub fn run(&self) -> Snapshot {
+        let snapshot = match &self.record {
+            Some(root) => match create_snapshot(root) {
+                Ok(path) => path,
+                Err(error) => return Snapshot::failed(error, self.json),
+            },
+            None => return self.collect(&self.source),
+        };
+
+        let result = self.collect(&Source::Record(
+            snapshot.clone(),
+            Box::new(self.source.clone()),
+        ));
+
+        // Snapshots in which no output was recorded (e.g. because the controller could not
+        // be reached) are removed, since they cannot be replayed
+        let _ = fs::remove_dir(&snapshot);
+        result
+    }
+
+    fn collect(&self, source: &Source) -> Snapshot {
+        let slurm = &self.slurm;
+        let started = Instant::now();
+
+        // Health is collected first, so that it reflects the state when sinfo/squeue fails.
+        // Failure to run `scontrol` is reported as a non-responsive controller
+        let health = self
+            .health
+            .then(|| Health::collect(source, &slurm.scontrol, &slurm.sdiag).unwrap_or_default());
+        // Reservations are informative only and may not be available when reading from files
+        let reservations = Reservation::collect(source, &slurm.scontrol).unwrap_or_default();
+
+        let mut json = self.json;
+        let (sinfo, squeue) = (&slurm.sinfo, &slurm.squeue);
+        let mut cluster =
+            match Slurm::collect(source, sinfo, squeue, json, self.version, &self.custom) {
+                // Older versions of Slurm do not support `--json`, in which case the delimited
+                // output is used instead. JSON is retried if both fail, as the failure may be
+                // unrelated to the format (e.g. the controller being unreachable). Commands that
+                // were killed by the watchdog are not retried, to avoid stalling twice as long
+                Err(error) if json && !error.chain().any(|v| v.is::<Timeout>()) => {
+                    match Slurm::collect(source, sinfo, squeue, false, self.version, &self.custom) {
+                        Ok(cluster) => {
+                            warn!(
+                                "JSON output not supported; using delimited output: {:#}",
+                                error
+                            );
+                            json = false;
+                            Ok(cluster)
+                        }
+                        Err(_) => Err(error),
+                    }
+                }
+                result => result,
+            };
+
+        if let (Ok((cluster, _)), Some(probe)) = (&mut cluster, &slurm.gpu_probe) {
+            probe_gpus(source, probe, cluster);
+        }
+
+        // Finished jobs are only briefly listed by squeue, so the final state is
+        // looked up using sacct, if possible
+        let mut final_states = HashMap::new();
+        if let Ok((cluster, _)) = &cluster {
+            for &id in &self.watched {
+                if !cluster.iter().flat_map(|v| &v.jobs).any(|v| v.id == id) {
+                    if let Ok(state) = final_state(source, &slurm.sacct, id) {
+                        final_states.insert(id, state);
+                    }
+                }
+            }
+        }
+
+        let partitions = self
+            .partitions
+            .then(|| PartitionConfig::collect(source, &slurm.scontrol, &slurm.sacctmgr));
+
+        debug!(
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            ok = cluster.is_ok(),
+            "collected Slurm state"
+        );
+
+        Snapshot {
+            health,
+            reservations,
+            cluster,
+            json,
+            final_states,
+            partitions,
+        }
+    }
+}
+
+impl Snapshot {
+    /// Returns a snapshot without any state, e.g. because the state could not be recorded
+    fn failed(error: Report, json: bool) -> Snapshot {
+        Snapshot {
+            health: None,
+            reservations: Vec::new(),
+            cluster: Err(error),
+            json,
+            final_states: HashMap::new(),
+            partitions: None,
+        }
+    }
+}
+
+/// Slurm commands run on behalf of the user, e.g. to cancel jobs or to look up the layout
+/// of a job; see [`Worker::spawn`]
+pub type Task = Box<dyn FnOnce() -> Outcome + Send>;
+
+/// Builds the outcome reported for a task that was abandoned before it finished, given
+/// the reason why; see [`Worker::restart`]
+pub type Abandoned = Box<dyn FnOnce(Report) -> Outcome>;
+
+/// Kinds of commands that modify jobs or nodes, whose outcomes are reported differently
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modification {
+    /// Jobs were cancelled using `scancel`
+    Cancel,
+    /// A batch script was submitted using `sbatch`
+    Submit,
+    /// Jobs or nodes were modified using `scontrol`, e.g. a job was held
+    Control,
+}
+
+/// Result of a [`Task`], reported to the user once the task has finished
+#[derive(Debug)]
+pub enum Outcome {
+    /// Jobs or nodes were modified, and the Slurm state was collected again so that the
+    /// change is shown along with the message describing it, unless the command failed
+    Modified(Modification, Result<String>, Option<Box<Snapshot>>),
+    /// Details of jobs were exported to a file
+    Exported(Result<String>),
+    /// Distribution of the CPUs allocated to a job across nodes
+    JobLayout(Result<JobLayout>),
+    /// Locations of the files to which a job writes its output
+    JobOutput(Result<JobOutput>),
+    /// (Partial) contents of the output file at the given location
+    Output(String, Result<String>),
+    /// Output of the given user-defined command
+    Command(String, Result<String>),
+    /// Utilization of the cluster during the given period
+    UsageReport(ReportPeriod, Result<UsageReport>),
+    /// Usage and limits of the associations of the given user
+    AssociationUsage(String, Result<Vec<AssociationUsage>>),
+}
+
+/// Collects the utilization of GPUs on nodes with GPUs allocated to jobs, running up to
+/// [`MAX_GPU_PROBES`] probes at a time. Each probe is started as soon as another probe
+/// has finished, until [`GPU_PROBE_DEADLINE`] has passed. Nodes whose probe fails or was
+/// not started in time are left without usage
+fn probe_gpus(source: &Source, probe: &str, cluster: &mut [Partition]) {
+    let mut names = cluster
+        .iter()
+        .flat_map(|v| &v.nodes)
+        .filter(|v| v.gpus_used > 0)
+        .map(|v| v.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    let deadline = Instant::now() + GPU_PROBE_DEADLINE;
+    let queue = Mutex::new(names.iter());
+    let usage = Mutex::new(HashMap::new());
+    thread::scope(|scope| {
+        let probes = (0..MAX_GPU_PROBES.min(names.len())).map(|_| {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap_or_else(|v| v.into_inner()).next();
+                let Some(name) = next else {
+                    break;
+                };
+
+                if Instant::now() >= deadline {
+                    debug!(node = name, "GPU probe skipped after deadline");
+                    continue;
+                }
+
+                match GpuUsage::probe(source, probe, name) {
+                    Ok(gpus) => {
+                        let mut usage = usage.lock().unwrap_or_else(|v| v.into_inner());
+                        usage.insert(name.as_str(), gpus);
+                    }
+                    Err(error) => debug!(node = name, "GPU probe failed: {:#}", error),
+                }
+            })
+        });
+
+        for probe in probes.collect::<Vec<_>>() {
+            if probe.join().is_err() {
+                warn!("GPU probe panicked");
+            }
+        }
+    });
+    let usage = usage.into_inner().unwrap_or_else(|v| v.into_inner());
+
+    // Nodes in multiple partitions are listed once per partition
+    for node in cluster.iter_mut().flat_map(|v| &mut v.nodes) {
+        if let Some(gpus) = usage.get(node.name.as_str()) {
+            node.gpu_usage.clone_from(gpus);
+        }
+    }
+}
+
+/// Performs updates of the Slurm state on a background thread, so that the user
+/// interface remains responsive while Slurm commands are running. Tasks run on behalf
+/// of the user are performed on a second thread, so that they are not delayed by slow
+/// updates
+pub struct Worker {
+    /// Requests sent to the worker thread
+    requests: Option<mpsc::Sender<UpdateRequest>>,
+    /// Results of finished updates
+    results: mpsc::Receiver<Snapshot>,
+    /// Worker thread; joined when the worker is dropped
+    handle: Option<thread::JoinHandle<()>>,
+    /// Time at which the current update was started, if any
+    started: Option<Instant>,
+    /// Most recent request submitted while busy, performed once the current update has
+    /// finished; overlapping requests (e.g. the user holding `r`) are thus coalesced
+    queued: Option<UpdateRequest>,
+    /// Tasks sent to the task thread
+    tasks: Option<mpsc::Sender<Task>>,
+    /// Outcomes of finished tasks
+    outcomes: mpsc::Receiver<Outcome>,
+    /// Builds the outcomes of tasks that have not yet finished, in the order in which
+    /// the tasks were spawned
+    in_flight: VecDeque<Abandoned>,
+    /// Outcomes of tasks abandoned by [`Worker::restart`], not yet returned
+    abandoned: VecDeque<Outcome>,
+    /// Task thread; joined when the worker is dropped
+    task_handle: Option<thread::JoinHandle<()>>,
+    /// Kills commands still running when the worker is dropped
+    watchdog: Watchdog,
+    /// Called whenever an update or a task has finished
+    notify: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl std::fmt::Debug for Worker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Worker")
+            .field("started", &self.started)
+            .field("queued", &self.queued)
+            .field("in_flight", &self.in_flight.len())
+            .field("watchdog", &self.watchdog)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Worker {
+    /// Starts the worker threads; `notify` is called whenever an update or a task has
+    /// finished. The sources of requests and tasks are expected to be watched by
+    /// `watchdog`, which is cancelled when the worker is dropped (see
+    /// [`Source::with_watchdog`])
+    pub fn new<F: Fn() + Send + Sync + 'static>(watchdog: Watchdog, notify: F) -> Self {
+        Self::start(watchdog, Arc::new(notify))
+    }
+
+    fn start(watchdog: Watchdog, notify: Arc<dyn Fn() + Send + Sync>) -> Self {
+        let (requests, receiver) = mpsc::channel::<UpdateRequest>();
+        let (sender, results) = mpsc::channel();
+        let notify_update = notify.clone();
+        let handle = thread::spawn(move || {
+            for request in receiver {
+                if sender.send(request.run()).is_err() {
+                    break;
+                }
+
+                notify_update();
+            }
+        });
+
+        let (tasks, receiver) = mpsc::channel::<Task>();
+        let (sender, outcomes) = mpsc::channel();
+        let notify_task = notify.clone();
+        let task_handle = thread::spawn(move || {
+            for task in receiver {
+                if sender.send(task()).is_err() {
+                    break;
+                }
+
+                notify_task();
+            }
+        });
+
+        Self {
+            requests: Some(requests),
+            results,
+            handle: Some(handle),
+            started: None,
+            queued: None,
+            tasks: Some(tasks),
+            outcomes,
+            in_flight: VecDeque::new(),
+            abandoned: VecDeque::new(),
+            task_handle: Some(task_handle),
+            watchdog,
+            notify,
+        }
+    }
+
+    /// Returns true if an update is being performed or queued
+    pub fn busy(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Returns the time for which the current update has been running, if any
+    pub fn busy_for(&self) -> Option<Duration> {
+        self.started.map(|v| v.elapsed())
+    }
+
+    /// Replaces the worker threads with new threads, e.g. because an update is stuck,
+    /// and returns the watchdog of the new threads, which has the same timeout as the
+    /// current watchdog. Commands run by the current threads are killed, but the threads
+    /// are not waited for. Queued requests are discarded, while tasks not yet finished
+    /// are returned by [`Worker::try_outcome`] as having been abandoned
+    pub fn restart(&mut self) -> Watchdog {
+        let watchdog = Watchdog::new(self.watchdog.timeout());
+        let mut previous =
+            std::mem::replace(self, Self::start(watchdog.clone(), self.notify.clone()));
+        self.abandoned
+            .extend(std::iter::from_fn(|| previous.try_outcome()));
+        self.abandoned.extend(
+            previous
+                .in_flight
+                .drain(..)
+                .map(|abandoned| abandoned(eyre!("task abandoned when the worker was restarted"))),
+        );
+
+        // Dropping the previous worker cancels its watchdog, killing running commands
+        previous.handle = None;
+        previous.task_handle = None;
+        drop(previous);
+
+        watchdog
+    }
+
+    /// Starts an update, or queues it if another update is in progress. A previously
+    /// queued request is replaced, since only the latest state is of interest
+    pub fn submit(&mut self, request: UpdateRequest) {
+        if self.started.is_some() {
+            self.queued = Some(request);
+        } else if let Some(requests) = &self.requests {
+            if requests.send(request).is_ok() {
+                self.started = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Returns the result of a finished update, if any, and starts the queued update
+    pub fn try_recv(&mut self) -> Option<Snapshot> {
+        let result = self.results.try_recv().ok()?;
+        self.started = None;
+        if let Some(request) = self.queued.take() {
+            self.submit(request);
+        }
+
+        Some(result)
+    }
+
+    /// Starts a task once previously spawned tasks have finished. If the task is
+    /// abandoned, e.g. because the worker is restarted, `abandoned` builds its outcome
+    pub fn spawn(&mut self, task: Task, abandoned: Abandoned) {
+        if let Some(tasks) = &self.tasks {
+            if tasks.send(task).is_ok() {
+                self.in_flight.push_back(abandoned);
+            }
+        }
+    }
+
+    /// Returns the outcome of a finished or abandoned task, if any
+    pub fn try_outcome(&mut self) -> Option<Outcome> {
+        if let Some(outcome) = self.abandoned.pop_front() {
+            return Some(outcome);
+        }
+
+        let outcome = self.outcomes.try_recv().ok()?;
+        // Tasks are run one at a time, in the order in which they were spawned
+        self.in_flight.pop_front();
+        Some(outcome)
+    }
+}
+
+impl Drop for Worker {
+    /// Kills any running Slurm commands and waits for the worker threads to exit, unless
+    /// abandoned by [`Worker::restart`]
+    fn drop(&mut self) {
+        self.watchdog.cancel();
+        self.requests = None;
+        self.tasks = None;
+        for handle in [self.handle.take(), self.task_handle.take()]
+            .into_iter()
+            .flatten()
+        {
+            let _ = handle.join();
+        }
+    }
+}
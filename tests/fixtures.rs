@@ -0,0 +1,612 @@
+//! Tests of the collection of Slurm state using canned output of Slurm commands, covering
+//! the parsing of that output, the organization of nodes and jobs by partition, and the
+//! state of the user interface. Fixtures are found in `tests/fixtures/<cluster>`, where
+//! files are named as when recording the output of commands (e.g. `sinfo.txt`), except
+//! for `sinfo --version`, whose output is stored in `sinfo_version.txt`.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use color_eyre::{eyre::bail, Result};
+use slurmboard::slurm::{
+    file_name, AssociationUsage, Backend, CustomFields, DefaultMem, GpuUsage, Job, JobDetails,
+    JobState, Node, PartitionConfig, ReportPeriod, Slurm, SlurmConfig, SlurmState, SlurmVersion,
+    Source, Time, UsageReport,
+};
+
+/// Serves the output of Slurm commands from the files of a fixture
+#[derive(Debug)]
+struct Fixture {
+    root: PathBuf,
+    /// Commands run so far, including their arguments
+    commands: Mutex<Vec<String>>,
+}
+
+impl Fixture {
+    fn new(cluster: &str) -> Arc<Self> {
+        Arc::new(Self {
+            root: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(cluster),
+            commands: Mutex::default(),
+        })
+    }
+
+    fn source(self: &Arc<Self>) -> Source {
+        Source::Backend(self.clone())
+    }
+
+    /// Returns the commands run so far that start with `exe`
+    fn commands(&self, exe: &str) -> Vec<String> {
+        let commands = self.commands.lock().expect("poisoned lock");
+        commands
+            .iter()
+            .filter(|v| v.split(' ').next() == Some(exe))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Backend for Fixture {
+    fn run(&self, exe: &str, args: &[&str]) -> Result<Vec<u8>> {
+        let mut command = exe.to_string();
+        for arg in args {
+            command.push(' ');
+            command.push_str(arg);
+        }
+        self.commands.lock().expect("poisoned lock").push(command);
+
+        // JSON output is not captured, as with clusters running older versions of Slurm
+        let name = if args.contains(&"--json") {
+            bail!("{} --json: unrecognized option", exe);
+        } else if args.contains(&"--version") {
+            format!("{}_version.txt", exe)
+        } else {
+            file_name(exe, args)
+        };
+
+        match fs::read(self.root.join(&name)) {
+            Ok(stdout) => Ok(stdout),
+            Err(error) => bail!("{}: {}", name, error),
+        }
+    }
+}
+
+/// Returns the version of Slurm of a fixture
+fn version(fixture: &Arc<Fixture>) -> Option<SlurmVersion> {
+    Some(SlurmVersion::collect(&fixture.source(), "sinfo").expect("failed to collect version"))
+}
+
+fn node<'a>(nodes: &'a [Node], name: &str, partition: &str) -> &'a Node {
+    nodes
+        .iter()
+        .find(|v| v.name == name && v.partition.label == partition)
+        .expect("node not found")
+}
+
+fn job(jobs: &[Job], id: usize) -> &Job {
+    jobs.iter().find(|v| v.id == id).expect("job not found")
+}
+
+#[test]
+fn collect_version() {
+    let fixture = Fixture::new("standard");
+    assert_eq!(version(&fixture), Some(SlurmVersion::new(23, 2, 7)));
+}
+
+#[test]
+fn collect_nodes() {
+    let fixture = Fixture::new("standard");
+    let nodes = Node::collect(&fixture.source(), "sinfo", false, version(&fixture), &[])
+        .expect("failed to collect nodes");
+    assert_eq!(nodes.len(), 5);
+
+    let gpu01 = node(&nodes, "gpu01", "gpu");
+    assert!(matches!(gpu01.state.state, SlurmState::Mixed));
+    assert_eq!(
+        (gpu01.cpus, gpu01.mem, gpu01.mem_alloc),
+        (32, 256000, 64000)
+    );
+    assert_eq!((gpu01.gpus, gpu01.gpus_used), (4, 2));
+    assert_eq!(gpu01.gpus_in_use, [0, 1]);
+    assert_eq!(gpu01.cpu_load, Some(12.5));
+
+    let node01 = node(&nodes, "node01", "standard");
+    assert!(node01.partition.default);
+    assert_eq!(node01.cpu_state.allocated, 64);
+
+    let node03 = node(&nodes, "node03", "standard");
+    assert!(matches!(node03.state.state, SlurmState::Down));
+    assert!(!node03.state.responds);
+    assert_eq!((node03.cpu_load, node03.mem_free), (None, None));
+}
+
+#[test]
+fn collect_jobs() {
+    let fixture = Fixture::new("standard");
+    let jobs = Job::collect(&fixture.source(), "squeue", false, version(&fixture), &[])
+        .expect("failed to collect jobs");
+    assert_eq!(jobs.len(), 5);
+
+    let job101 = job(&jobs, 101);
+    assert_eq!(job101.state, JobState::Running);
+    assert_eq!((&*job101.user, &*job101.account), ("alice", "proj_a"));
+    assert_eq!((job101.cpus, job101.mem, job101.gpus), (16, 64000, 2));
+    assert_eq!(job101.nodelist, [Arc::from("gpu01")]);
+
+    let job102 = job(&jobs, 102);
+    assert_eq!((job102.nodes, job102.cpus), (2, 72));
+    assert_eq!(job102.nodelist.len(), 2);
+
+    let job103 = job(&jobs, 103);
+    assert_eq!(job103.state, JobState::Pending);
+    assert!(job103.account.is_empty());
+    assert!(job103.nodelist.is_empty());
+
+    let task = job(&jobs, 217);
+    assert_eq!(
+        (task.array_job_id, task.array_task_id.as_str()),
+        (200, "17")
+    );
+}
+
+#[test]
+fn parse_job_names() {
+    let fixture = Fixture::new("standard");
+    let squeue = fs::read_to_string(fixture.root.join("squeue.txt")).expect("failed to read");
+    let squeue = squeue
+        .replace("|train|", "|a|b|")
+        .replace("|bigjob|", "| a |");
+    let jobs = Job::from_reader(squeue.as_bytes(), Some(SlurmVersion::new(23, 2, 7)), &[])
+        .expect("failed to parse");
+
+    // Names are kept as is, while other fields are trimmed
+    assert_eq!(jobs.len(), 5);
+    assert_eq!(
+        (job(&jobs, 101).name.as_str(), job(&jobs, 101).cpus),
+        ("a|b", 16)
+    );
+    assert_eq!(job(&jobs, 102).name, " a ");
+}
+
+#[test]
+fn parse_custom_values_containing_delimiter() {
+    let fixture = Fixture::new("standard");
+    let squeue = fs::read_to_string(fixture.root.join("squeue.txt")).expect("failed to read");
+    let version = Some(SlurmVersion::new(23, 2, 7));
+    let custom = ["Comment".to_string()];
+
+    // Appends a Comment column to the fixture, using `delimiter` to separate fields
+    let output = |delimiter: &str| {
+        squeue
+            .lines()
+            .map(|line| {
+                let comment = match line.split('|').nth(3) {
+                    Some("JOBID") => "COMMENT",
+                    Some("101") => "x|y",
+                    _ => "none",
+                };
+                let line = line.replace('|', delimiter).replace("train", "a|b");
+                format!("{}{}{}\n", line, delimiter, comment)
+            })
+            .collect::<String>()
+    };
+
+    let jobs =
+        Job::from_reader(output("\x1f").as_bytes(), version, &custom).expect("failed to parse");
+    assert_eq!(jobs.len(), 5);
+    let job101 = job(&jobs, 101);
+    assert_eq!(
+        (job101.name.as_str(), job101.custom.as_slice()),
+        ("a|b", &["x|y".to_string()][..])
+    );
+    assert_eq!(job(&jobs, 102).custom, ["none"]);
+
+    // Values cannot be told apart in output delimited by `|`, so such jobs are skipped
+    let jobs = Job::from_reader(output("|").as_bytes(), version, &custom).expect("failed to parse");
+    assert_eq!(jobs.len(), 4);
+    assert!(jobs.iter().all(|v| v.id != 101 && v.custom == ["none"]));
+}
+
+#[test]
+fn collect_nodes_json() {
+    let fixture = Fixture::new("json");
+    let nodes = Node::collect(&fixture.source(), "sinfo", false, None, &[])
+        .expect("failed to collect nodes");
+    assert_eq!(nodes.len(), 4);
+
+    let gpu01 = node(&nodes, "gpu01", "gpu");
+    assert!(matches!(gpu01.state.state, SlurmState::Mixed));
+    assert_eq!(
+        (gpu01.cpus, gpu01.mem, gpu01.mem_alloc, gpu01.mem_free),
+        (32, 256000, 64000, Some(100000))
+    );
+    assert_eq!((gpu01.gpus, gpu01.gpus_used), (4, 2));
+    assert_eq!(gpu01.gpus_in_use, [0, 1]);
+    assert_eq!(gpu01.cpu_load, Some(12.5));
+    assert_eq!(gpu01.features, ["avx512", "ib"]);
+    assert_eq!(gpu01.topology.map(|v| v.to_string()), Some("2:16:1".into()));
+
+    // Entries covering multiple nodes are expanded
+    let node02 = node(&nodes, "node02", "standard");
+    assert!(node02.partition.default);
+    assert_eq!(
+        (node02.cpu_state.allocated, node02.cpu_load),
+        (64, Some(64.0))
+    );
+    assert!(node02.features.is_empty());
+
+    let node03 = node(&nodes, "node03", "standard");
+    assert!(matches!(node03.state.state, SlurmState::Drained));
+    assert!(!node03.state.responds);
+    assert_eq!(
+        (node03.cpu_load, node03.mem_free, node03.topology),
+        (None, None, None)
+    );
+}
+
+#[test]
+fn collect_jobs_json() {
+    let fixture = Fixture::new("json");
+    let jobs = Job::collect(&fixture.source(), "squeue", false, None, &[])
+        .expect("failed to collect jobs");
+    assert_eq!(jobs.len(), 5);
+
+    let job101 = job(&jobs, 101);
+    assert_eq!(job101.state, JobState::Running);
+    assert_eq!((&*job101.user, &*job101.account), ("alice", "proj_a"));
+    assert_eq!((job101.cpus, job101.mem, job101.gpus), (16, 64000, 2));
+    assert_eq!(job101.priority, Some(4294901700));
+    assert_eq!(job101.display_id(), "101");
+    assert_eq!(
+        (job101.time.to_string(), job101.time_limit.to_string()),
+        ("6-22:40:00".into(), "7-00:00:00".into())
+    );
+
+    let job102 = job(&jobs, 102);
+    assert_eq!((job102.nodes, job102.cpus, job102.tasks), (2, 72, 2));
+    assert_eq!(job102.nodelist, [Arc::from("node01"), Arc::from("node02")]);
+    assert_eq!(job102.name, "big job");
+    assert_eq!(job102.time_limit, Time::Unlimited);
+
+    let job103 = job(&jobs, 103);
+    assert_eq!(job103.state, JobState::Pending);
+    assert_eq!(job103.partitions, ["standard", "gpu"]);
+    assert_eq!(job103.partition.label, "standard");
+    assert_eq!(job103.time_limit, Time::Invalid);
+
+    let task = job(&jobs, 217);
+    assert_eq!(task.display_id(), "200_17");
+    assert_eq!(task.time_left.to_string(), "50:00");
+    assert_eq!(job(&jobs, 200).display_id(), "200_[18-100]");
+}
+
+#[test]
+fn collect_job_details() {
+    let fixture = Fixture::new("standard");
+    let details = JobDetails::collect(&fixture.source(), "scontrol", "sacct", 102)
+        .expect("failed to collect job details");
+    assert_eq!(details.id, 102);
+    assert!(details
+        .fields
+        .contains(&("JobName".into(), "bigjob".into())));
+}
+
+#[test]
+fn collect_config() {
+    let fixture = Fixture::new("standard");
+    let config = SlurmConfig::collect(&fixture.source(), "scontrol").expect("failed to collect");
+    assert_eq!(config.get("ClusterName"), Some("standard"));
+    assert_eq!(config.default_mem(), DefaultMem::PerCPU(2000));
+
+    let partitions = PartitionConfig::collect(&fixture.source(), "scontrol", "sacctmgr")
+        .expect("failed to collect partitions");
+    let names = partitions
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["gpu", "standard"]);
+    assert_eq!(partitions[0].default_mem, Some(DefaultMem::PerGPU(64000)));
+    assert_eq!(partitions[1].default_mem, None);
+
+    let weights = partitions[0].billing_weights.as_ref().expect("no weights");
+    assert_eq!((weights.cpu, weights.gpu), (1.0, 8.0));
+    assert_eq!(weights.mem, 0.25 / 1024.0);
+
+    // Per-user limits are configured via the QOS of the partition
+    assert_eq!(partitions[0].limits.qos.as_deref(), Some("gpu"));
+    assert_eq!(partitions[0].limits.max_cpus_per_user, Some(32));
+    assert_eq!(partitions[1].limits.max_cpus_per_user, None);
+}
+
+#[test]
+fn collect_usage() {
+    let fixture = Fixture::new("standard");
+    let report = UsageReport::collect(&fixture.source(), "sreport", ReportPeriod::Week)
+        .expect("failed to collect usage");
+    assert_eq!(report.clusters.len(), 1);
+    let cluster = &report.clusters[0];
+    assert_eq!((cluster.allocated, cluster.idle), (71.42, 18.36));
+    assert_eq!(cluster.planned, 7.12);
+
+    // Users are ordered by descending usage
+    let users = report
+        .top_users
+        .iter()
+        .map(|v| (v.login.as_str(), v.account.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        users,
+        [("alice", "proj_a"), ("bob", "proj_b"), ("alice", "proj_c")]
+    );
+    assert_eq!(report.top_users[1].name, "Bob Builder");
+
+    // Legacy clusters without slurmdbd cannot report usage
+    let fixture = Fixture::new("legacy");
+    assert!(UsageReport::collect(&fixture.source(), "sreport", ReportPeriod::Day).is_err());
+}
+
+#[test]
+fn collect_association_usage() {
+    let fixture = Fixture::new("standard");
+    let associations = AssociationUsage::collect(&fixture.source(), "sshare", "sacctmgr", "alice")
+        .expect("failed to collect associations");
+
+    // The root account and associations of other users are not listed
+    let names = associations
+        .iter()
+        .map(|v| (v.account.as_str(), v.user.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            ("proj_a", ""),
+            ("proj_a", "alice"),
+            ("proj_c", ""),
+            ("proj_c", "alice")
+        ]
+    );
+
+    let account = &associations[0];
+    assert_eq!(account.used("cpu"), 910000);
+    assert_eq!(account.used("gres/gpu"), 0);
+    assert_eq!(
+        account.limits,
+        [("cpu".into(), 1000000), ("gres/gpu".into(), 20000)]
+    );
+    assert_eq!(associations[1].limits, [("cpu".into(), 500000)]);
+    assert!(associations[3].limits.is_empty());
+}
+
+#[test]
+fn parse_gpu_usage() {
+    let gpus = GpuUsage::parse("95, 40000, 81920\n5, 1000, 81920\n0, 0, 81920\n")
+        .expect("failed to parse nvidia-smi output");
+    assert_eq!(gpus.len(), 3);
+    assert_eq!((gpus[0].utilization, gpus[0].mem_used), (95.0, 40000));
+    assert!(GpuUsage::parse("[N/A], 0, 81920").is_err());
+
+    let dcgm = "\
+# HELP DCGM_FI_DEV_GPU_UTIL GPU utilization (in %).
+# TYPE DCGM_FI_DEV_GPU_UTIL gauge
+DCGM_FI_DEV_GPU_UTIL{gpu=\"0\",UUID=\"GPU-1\",Hostname=\"gpu01\"} 95
+DCGM_FI_DEV_GPU_UTIL{gpu=\"1\",UUID=\"GPU-2\",Hostname=\"gpu01\"} 5
+DCGM_FI_DEV_FB_USED{gpu=\"0\",UUID=\"GPU-1\",Hostname=\"gpu01\"} 40000
+DCGM_FI_DEV_FB_FREE{gpu=\"0\",UUID=\"GPU-1\",Hostname=\"gpu01\"} 41920
+";
+    let dcgm = GpuUsage::parse(dcgm).expect("failed to parse DCGM metrics");
+    assert_eq!(dcgm.len(), 2);
+    assert_eq!(dcgm[0], gpus[0]);
+    assert_eq!(dcgm[1].utilization, 5.0);
+
+    // GPUs are only considered utilized up to the number allocated
+    let fixture = Fixture::new("standard");
+    let nodes = Node::collect(&fixture.source(), "sinfo", false, version(&fixture), &[])
+        .expect("failed to collect nodes");
+    let mut gpu01 = node(&nodes, "gpu01", "gpu").clone();
+    assert_eq!(gpu01.gpu_utilization(DefaultMem::None).utilized, 0.0);
+    gpu01.gpu_usage = gpus;
+    assert_eq!(gpu01.gpu_utilization(DefaultMem::None).utilized, 1.0);
+    gpu01.gpu_usage[1].utilization = 100.0;
+    assert_eq!(gpu01.gpu_utilization(DefaultMem::None).utilized, 1.95);
+    gpu01.gpu_usage[2].utilization = 100.0;
+    assert_eq!(gpu01.gpu_utilization(DefaultMem::None).utilized, 2.0);
+}
+
+#[test]
+fn collect_cluster() {
+    let fixture = Fixture::new("standard");
+    let (partitions, unassigned) = Slurm::collect(
+        &fixture.source(),
+        "sinfo",
+        "squeue",
+        false,
+        version(&fixture),
+        &CustomFields::default(),
+    )
+    .expect("failed to collect cluster");
+    assert!(unassigned.is_empty());
+
+    // Partitions are ordered by descending number of nodes
+    let names = partitions
+        .iter()
+        .map(|v| v.name.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["standard", "gpu"]);
+
+    let standard = &partitions[0];
+    let ids = standard.jobs.iter().map(|v| v.id).collect::<Vec<_>>();
+    assert_eq!(ids.len(), 4);
+    assert!([102, 103, 200, 217].iter().all(|v| ids.contains(v)));
+
+    let jobs = |name: &str| {
+        let node = node(&standard.nodes, name, "standard");
+        node.jobs.iter().map(|v| v.id).collect::<Vec<_>>()
+    };
+    assert_eq!(jobs("node01"), [102]);
+    assert_eq!(jobs("node02").len(), 2);
+    assert!(jobs("node03").is_empty());
+}
+
+#[test]
+fn collect_legacy_cluster() {
+    let fixture = Fixture::new("legacy");
+    let version = version(&fixture);
+    let (partitions, unassigned) = Slurm::collect(
+        &fixture.source(),
+        "sinfo",
+        "squeue",
+        false,
+        version,
+        &CustomFields::default(),
+    )
+    .expect("failed to collect cluster");
+
+    // Fields introduced in later versions of Slurm are not requested
+    let commands = fixture.commands("squeue").join(" ");
+    assert!(!commands.contains("PriorityLong") && !commands.contains("Tres-Alloc"));
+    let commands = fixture.commands("sinfo").join(" ");
+    assert!(!commands.contains("AllocMem") && !commands.contains("GresUsed"));
+
+    let batch = partitions
+        .iter()
+        .find(|v| v.name.label == "batch")
+        .expect("partition not found");
+    assert_eq!(batch.nodes.len(), 4);
+    let c04 = node(&batch.nodes, "c04", "batch");
+    assert!(matches!(c04.state.state, SlurmState::Drained));
+    assert_eq!(c04.mem_free, None);
+
+    // Pending jobs submitted to multiple partitions are listed in each of them
+    for partition in &partitions {
+        assert!(partition.jobs.iter().any(|v| v.id == 12));
+    }
+
+    // Jobs in partitions not listed by sinfo cannot be assigned
+    let ids = unassigned.iter().map(|v| v.id).collect::<Vec<_>>();
+    assert_eq!(ids, [13]);
+}
+
+#[test]
+fn parse_output() {
+    let fixture = Fixture::new("legacy");
+    let version = Some(SlurmVersion::new(14, 3, 9));
+    let sinfo = fs::read(fixture.root.join("sinfo.txt")).expect("failed to read sinfo output");
+    let squeue = fs::read_to_string(fixture.root.join("squeue.txt")).expect("failed to read");
+
+    let nodes = Node::from_reader(sinfo.as_slice(), version, &[]).expect("failed to parse");
+    let jobs = Job::from_reader(squeue.as_bytes(), version, &[]).expect("failed to parse");
+    let (partitions, unassigned) = Slurm::organize(nodes, jobs);
+    assert_eq!(partitions.len(), 2);
+    assert_eq!(unassigned.len(), 1);
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn user_interface() {
+    use argh::FromArgs;
+    use slurmboard::{app::App, args::Args, ui::UI};
+
+    let fixture = Fixture::new("standard");
+    let config = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config.toml");
+    let args = Args::from_args(
+        &["slurmboard"],
+        &[
+            "--config",
+            &config.to_string_lossy(),
+            "--no-color",
+            "--json",
+        ],
+    )
+    .expect("invalid arguments");
+    let app = App::with_source(args, fixture.source()).expect("failed to collect state");
+
+    // The JSON output requested using `--json` is not available, in which case the
+    // delimited output is used
+    assert_eq!(fixture.commands("sinfo").len(), 3);
+    assert!(app.error.is_none());
+    // Jobs of the user running the Slurm commands are tracked by default
+    assert_eq!(app.tracked_user(), "alice");
+    assert!(app.warnings.is_empty());
+
+    let health = app.health.as_ref().expect("health not collected");
+    assert_eq!(health.controllers.len(), 2);
+    assert!(!health.is_up());
+    assert_eq!(health.dbd_agent_queue, Some(12));
+    assert_eq!(app.reservations.len(), 1);
+    assert_eq!(app.default_mem("standard"), DefaultMem::PerCPU(2000));
+    assert_eq!(app.default_mem("gpu"), DefaultMem::PerGPU(64000));
+
+    let text = UI::new(&app).to_plain_text();
+    for node in ["gpu01", "gpu02", "node01", "node02", "node03"] {
+        assert!(text.contains(node), "{} not listed:\n{}", node, text);
+    }
+    for job in ["101", "102", "103", "200_17", "200_[18-100]"] {
+        assert!(text.contains(job), "job {} not listed:\n{}", job, text);
+    }
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn export_job_details() {
+    use slurmboard::export::export_jobs;
+
+    let fixture = Fixture::new("standard");
+    let details = JobDetails::collect(&fixture.source(), "scontrol", "sacct", 102)
+        .expect("failed to collect job details");
+    let root = std::env::temp_dir().join(format!("slurmboard-export-{}", std::process::id()));
+    fs::create_dir_all(&root).expect("failed to create directory");
+
+    // Existing files are kept by adding a suffix, unless overwriting is requested
+    let path = root.join("jobs.csv");
+    let jobs = [details];
+    let paths = [false, false, true]
+        .map(|overwrite| export_jobs(&path, &jobs, overwrite).expect("export failed"));
+    assert_eq!(paths, [path.clone(), root.join("jobs-1.csv"), path]);
+
+    let text = fs::read_to_string(&paths[1]).expect("failed to read export");
+    assert!(text.contains("bigjob"));
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn record_snapshots() {
+    use slurmboard::replay::{create_snapshot, Replay};
+
+    let root = std::env::temp_dir().join(format!("slurmboard-record-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    // Snapshots recorded in quick succession are kept in separate directories
+    let paths = [(); 3].map(|_| create_snapshot(&root).expect("failed to create snapshot"));
+    assert!(paths[0] != paths[1] && paths[1] != paths[2]);
+
+    // Directories not named like snapshots are skipped
+    fs::create_dir(root.join("notes")).expect("failed to create directory");
+    let mut replay = Replay::open(&root).expect("failed to open snapshots");
+    assert_eq!(replay.current(), paths[0]);
+    assert!(replay.seek(2));
+    assert_eq!(replay.current(), paths[2]);
+    assert!(!replay.seek(1));
+    let _ = fs::remove_dir_all(&root);
+
+    // Snapshots are ordered by time and then by the counter added to existing names
+    let names = [
+        "20240501-030000.000-10",
+        "20240501-030000.000-2",
+        "20240501-030000",
+    ];
+    for name in names {
+        fs::create_dir_all(root.join(name)).expect("failed to create directory");
+    }
+    let mut replay = Replay::open(&root).expect("failed to open snapshots");
+    let mut order = vec![replay.current().to_owned()];
+    while replay.seek(1) {
+        order.push(replay.current().to_owned());
+    }
+    assert_eq!(order, [2, 1, 0].map(|idx| root.join(names[idx])));
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,50 @@
+#!/bin/bash
+# Runs the integration tests in tests/slurm.rs against a miniature Slurm cluster
+# provided by https://github.com/giovtorres/slurm-docker-cluster
+#
+# Usage: tests/slurm-docker/run.sh [cargo test arguments]
+#
+# Requires git, docker, and docker compose. The cluster is left running if
+# SLURMBOARD_KEEP_CLUSTER is set, and is otherwise removed once the tests finish.
+set -euo pipefail
+
+REPOSITORY=${SLURM_DOCKER_REPOSITORY:-https://github.com/giovtorres/slurm-docker-cluster.git}
+REVISION=${SLURM_DOCKER_REVISION:-master}
+CONTAINER=${SLURM_DOCKER_CONTAINER:-slurmctld}
+
+ROOT=$(cd "$(dirname "$0")/../.." && pwd)
+WORKDIR=$(mktemp -d)
+
+cleanup() {
+    if [ -z "${SLURMBOARD_KEEP_CLUSTER:-}" ]; then
+        (cd "${WORKDIR}/cluster" && docker compose down --volumes) || true
+    fi
+
+    rm -rf "${WORKDIR}"
+}
+trap cleanup EXIT
+
+git clone --quiet --depth 1 --branch "${REVISION}" "${REPOSITORY}" "${WORKDIR}/cluster"
+(cd "${WORKDIR}/cluster" && docker compose up --detach)
+
+# Slurm commands are run in the controller container via wrapper scripts
+mkdir "${WORKDIR}/bin"
+for command in sacct sbatch scancel scontrol sdiag sinfo squeue; do
+    cat > "${WORKDIR}/bin/${command}" <<WRAPPER
+#!/bin/sh
+exec docker exec -i "${CONTAINER}" ${command} "\$@"
+WRAPPER
+    chmod +x "${WORKDIR}/bin/${command}"
+done
+
+# The cluster needs to be registered with slurmdbd and all nodes must be up
+for _ in $(seq 60); do
+    if "${WORKDIR}/bin/sinfo" --noheader --states=idle 2>/dev/null | grep -q .; then
+        break
+    fi
+
+    sleep 5
+done
+
+cd "${ROOT}"
+SLURMBOARD_TEST_BIN="${WORKDIR}/bin" cargo test --features integration-tests --test slurm "$@"
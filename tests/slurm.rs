@@ -0,0 +1,247 @@
+//! Integration tests running the collection of Slurm state against a live cluster,
+//! in order to catch changes to the output of Slurm commands that fixtures cannot.
+//!
+//! These tests are only built with `--features integration-tests`. Slurm commands are
+//! looked up in `$SLURMBOARD_TEST_BIN` if set, and otherwise in `$PATH`; see
+//! `tests/slurm-docker/run.sh` for running the tests against a containerized cluster.
+#![cfg(feature = "integration-tests")]
+
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use slurmboard::slurm::{
+    CustomFields, Health, Job, JobDetails, JobState, Node, PartitionConfig, Reservation, Slurm,
+    SlurmConfig, SlurmVersion, Source,
+};
+
+/// Returns the path of a Slurm command
+fn exe(name: &str) -> String {
+    match std::env::var_os("SLURMBOARD_TEST_BIN") {
+        Some(root) => PathBuf::from(root).join(name).to_string_lossy().to_string(),
+        None => name.to_string(),
+    }
+}
+
+/// Submits a job that sleeps for `seconds` and returns its ID
+fn submit_job(name: &str, seconds: usize) -> usize {
+    let stdout = Source::Local
+        .run_action(
+            &exe("sbatch"),
+            &[
+                "--parsable",
+                "--job-name",
+                name,
+                "--wrap",
+                &format!("sleep {}", seconds),
+            ],
+        )
+        .expect("sbatch failed");
+
+    // Output is either "<jobid>" or "<jobid>;<cluster>"
+    String::from_utf8_lossy(&stdout)
+        .trim()
+        .split(';')
+        .next()
+        .and_then(|v| v.parse().ok())
+        .expect("invalid sbatch output")
+}
+
+/// Cancels a job, ignoring failures
+fn cancel_job(id: usize) {
+    let _ = Source::Local.run_action(&exe("scancel"), &[id.to_string()]);
+}
+
+/// Waits for a job to appear in the output of `squeue`
+fn wait_for_job(id: usize) -> Job {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(60) {
+        let jobs =
+            Job::collect(&Source::Local, &exe("squeue"), false, None, &[]).expect("squeue failed");
+        if let Some(job) = jobs.into_iter().find(|v| v.id == id) {
+            if job.state == JobState::Running {
+                return job;
+            }
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    panic!("job {} did not start within 60 seconds", id);
+}
+
+#[test]
+fn collect_version() {
+    let version = SlurmVersion::collect(&Source::Local, &exe("sinfo")).expect("sinfo failed");
+    assert!(version >= SlurmVersion::new(14, 11, 0));
+
+    // The fields requested for this version must be accepted by sinfo and squeue
+    Node::collect(&Source::Local, &exe("sinfo"), false, Some(version), &[]).expect("sinfo failed");
+    Job::collect(&Source::Local, &exe("squeue"), false, Some(version), &[]).expect("squeue failed");
+}
+
+#[test]
+fn collect_nodes() {
+    let nodes =
+        Node::collect(&Source::Local, &exe("sinfo"), false, None, &[]).expect("sinfo failed");
+    assert!(!nodes.is_empty());
+
+    for node in &nodes {
+        assert!(!node.name.is_empty());
+        assert!(node.cpus > 0);
+        assert_eq!(node.cpus, node.cpu_state.total);
+        assert!(node.mem > 0);
+    }
+}
+
+#[test]
+fn collect_nodes_json() {
+    let delimited =
+        Node::collect(&Source::Local, &exe("sinfo"), false, None, &[]).expect("sinfo failed");
+    let json = Node::collect(&Source::Local, &exe("sinfo"), true, None, &[]).expect("sinfo failed");
+    assert_eq!(delimited.len(), json.len());
+
+    for (a, b) in delimited.iter().zip(&json) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.partition.to_string(), b.partition.to_string());
+        assert_eq!(a.state.to_string(), b.state.to_string());
+        assert_eq!(a.cpus, b.cpus);
+        assert_eq!(a.mem, b.mem);
+        assert_eq!(a.gpus, b.gpus);
+        assert_eq!(a.features, b.features);
+        assert_eq!(a.topology, b.topology);
+    }
+}
+
+#[test]
+fn collect_cluster() {
+    let id = submit_job("slurmboard-cluster", 300);
+    let job = wait_for_job(id);
+    let version = SlurmVersion::collect(&Source::Local, &exe("sinfo")).ok();
+    let partitions = Slurm::collect(
+        &Source::Local,
+        &exe("sinfo"),
+        &exe("squeue"),
+        false,
+        version,
+        &CustomFields::default(),
+    );
+    let json = Slurm::collect(
+        &Source::Local,
+        &exe("sinfo"),
+        &exe("squeue"),
+        true,
+        version,
+        &CustomFields::default(),
+    );
+    cancel_job(id);
+
+    let (partitions, _) = partitions.expect("failed to collect cluster");
+    let (json, _) = json.expect("failed to collect cluster using JSON");
+    assert_eq!(partitions.len(), json.len());
+    let partition = partitions
+        .iter()
+        .find(|v| v.name.same(&job.partition))
+        .expect("partition of job not found");
+
+    assert!(partition.jobs.iter().any(|v| v.id == id));
+    assert!(partition
+        .nodes
+        .iter()
+        .filter(|v| job.nodelist.iter().any(|n| **n == v.name))
+        .all(|v| v.jobs.iter().any(|v| v.id == id)));
+}
+
+#[test]
+fn collect_jobs() {
+    let id = submit_job("slurmboard-jobs", 300);
+    let job = wait_for_job(id);
+    cancel_job(id);
+
+    assert_eq!(job.name, "slurmboard-jobs");
+    assert_eq!(job.array_job_id, id);
+    assert!(!job.nodelist.is_empty());
+    assert!(job.cpus > 0);
+    assert!(job.nodes > 0);
+}
+
+#[test]
+fn collect_custom_fields() {
+    let id = submit_job("slurmboard-custom", 300);
+    wait_for_job(id);
+    let custom = ["JobID".to_string(), "Name".to_string()];
+    let jobs = Job::collect(&Source::Local, &exe("squeue"), false, None, &custom);
+    let custom = ["NodeHost".to_string()];
+    let nodes = Node::collect(&Source::Local, &exe("sinfo"), false, None, &custom);
+    cancel_job(id);
+
+    let jobs = jobs.expect("squeue failed");
+    let job = jobs.iter().find(|v| v.id == id).expect("job not found");
+    assert_eq!(
+        job.custom,
+        [id.to_string(), "slurmboard-custom".to_string()]
+    );
+    assert!(nodes
+        .expect("sinfo failed")
+        .iter()
+        .all(|v| v.custom.len() == 1));
+}
+
+#[test]
+fn collect_jobs_json() {
+    let id = submit_job("slurmboard-json", 300);
+    let job = wait_for_job(id);
+    let jobs = Job::collect(&Source::Local, &exe("squeue"), true, None, &[]);
+    cancel_job(id);
+
+    let jobs = jobs.expect("failed to collect jobs using JSON");
+    let other = jobs.iter().find(|v| v.id == id).expect("job not found");
+    assert_eq!(job.name, other.name);
+    assert_eq!(job.user, other.user);
+    assert_eq!(job.state, other.state);
+    assert_eq!(job.nodelist, other.nodelist);
+    assert_eq!(job.array_job_id, other.array_job_id);
+    assert_eq!(job.array_task_id, other.array_task_id);
+    assert_eq!(job.cpus, other.cpus);
+    assert_eq!(job.mem, other.mem);
+    assert_eq!(job.gpus, other.gpus);
+}
+
+#[test]
+fn collect_job_details() {
+    let id = submit_job("slurmboard-details", 300);
+    wait_for_job(id);
+    let details = JobDetails::collect(&Source::Local, &exe("scontrol"), &exe("sacct"), id);
+    cancel_job(id);
+
+    let details = details.expect("failed to collect job details");
+    assert_eq!(details.id, id);
+    assert!(details.fields.iter().any(|(key, _)| key == "JobName"));
+}
+
+#[test]
+fn collect_health() {
+    let health = Health::collect(&Source::Local, &exe("scontrol"), &exe("sdiag"))
+        .expect("failed to collect health");
+
+    assert!(health.is_up());
+    assert!(health.server_threads.is_some());
+}
+
+#[test]
+fn collect_config() {
+    let config = SlurmConfig::collect(&Source::Local, &exe("scontrol")).expect("scontrol failed");
+    assert!(config.get("ClusterName").is_some());
+
+    let partitions = PartitionConfig::collect(&Source::Local, &exe("scontrol"), &exe("sacctmgr"))
+        .expect("scontrol failed");
+    assert!(!partitions.is_empty());
+    assert!(partitions.iter().all(|v| !v.name.is_empty()));
+}
+
+#[test]
+fn collect_reservations() {
+    Reservation::collect(&Source::Local, &exe("scontrol")).expect("scontrol failed");
+}